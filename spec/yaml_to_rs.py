@@ -723,6 +723,52 @@ def gen_file(category: str, commands: list[Command], output_dir: Path) -> None:
     with open(file_path, 'w') as f:
         _ = f.write('\n'.join(lines))
 
+def gen_opcode_file(commands: list[tuple[str, Command]], output_dir: Path) -> None:
+    """Generate the catalog of all documented opcodes"""
+    file_path = output_dir / "opcode.rs"
+
+    lines = ["// Opcode catalog\n"]
+    cmds = [(category, cmd) for category, cmd in commands if cmd.opcode >= 0]
+
+    lines.append("/// Opcode of all documented commands")
+    lines.append("#[derive(Debug, Clone, Copy, PartialEq, Eq)]")
+    lines.append("#[cfg_attr(feature = \"defmt\", derive(defmt::Format))]")
+    lines.append("pub enum Opcode {")
+    for _, cmd in cmds:
+        desc = cmd.description.replace('\n', ' ').split('. ')[0].strip().rstrip('.')
+        if desc:
+            lines.append(f"    /// {desc}")
+        lines.append(f"    {cmd.name},")
+    lines.append("}")
+    lines.append("")
+
+    lines.append("impl Opcode {")
+    lines.append("    /// Opcode value")
+    lines.append("    pub const fn value(&self) -> u16 {")
+    lines.append("        match self {")
+    for _, cmd in cmds:
+        lines.append(f"            Opcode::{cmd.name} => 0x{cmd.opcode:04X},")
+    lines.append("        }")
+    lines.append("    }")
+    lines.append("")
+    lines.append("    /// Opcode as sent on the SPI (MSB first)")
+    lines.append("    pub const fn to_bytes(&self) -> [u8; 2] {")
+    lines.append("        self.value().to_be_bytes()")
+    lines.append("    }")
+    lines.append("")
+    lines.append("    /// Command category (name of the module defining the command API)")
+    lines.append("    pub const fn category(&self) -> &'static str {")
+    lines.append("        match self {")
+    for category, cmd in cmds:
+        lines.append(f"            Opcode::{cmd.name} => \"{category}\",")
+    lines.append("        }")
+    lines.append("    }")
+    lines.append("}")
+    lines.append("")
+
+    with open(file_path, 'w') as f:
+        _ = f.write('\n'.join(lines))
+
 def main():
     
     yaml_path = Path(sys.argv[1]) if len(sys.argv) > 1 else Path("./commands.yaml")
@@ -738,6 +784,7 @@ def main():
                 data = yaml.safe_load(f)
             
             # Parse commands
+            all_commands : list[tuple[str, Command]] = []
             for category, category_data in data.get('categories', {}).items():
                 print(f'Category {category}')
                 commands : list[Command] = []
@@ -750,6 +797,9 @@ def main():
                         sys.exit(1)
             
                 gen_file(category, commands, output_dir)
+                all_commands.extend((category, cmd) for cmd in commands)
+
+            gen_opcode_file(all_commands, output_dir)
 
         else:
             print(f"Error: {yaml_path} is not a file or directory", file=sys.stderr)
@@ -0,0 +1,145 @@
+//! # Antenna tuning
+//!
+//! This module provides a generic optimization loop for products with a tunable matching network
+//! (varactor, digital tuner, ...) controlled by the MCU. The loop applies a tuner setting, measures
+//! the RSSI of a known signal (CW or continuous beacon) and converges on the setting maximizing the RSSI.
+//!
+//! The search is done in two steps: a coarse sweep of the full range followed by a refinement around the best
+//! setting, halving the step until it reaches 1. Each measurement can be reported through a callback,
+//! allowing to log tuning data at manufacturing or in the field.
+//!
+//! ## Quick Start
+//!
+//! ```rust,no_run
+//! use lr2021::antenna::{AntennaTuner, TuneCfg};
+//!
+//! struct MyTuner { /* DAC driving a varactor */ }
+//! impl AntennaTuner for MyTuner {
+//!     async fn apply(&mut self, setting: u16) -> Result<(), Lr2021Error> {
+//!         // Set DAC value
+//!         Ok(())
+//!     }
+//! }
+//!
+//! // Chip must be in RX on the frequency of the reference signal
+//! lr2021.set_rx(0xFFFFFF, true).await.expect("SetRx");
+//! let cfg = TuneCfg::new(0, 255, 16);
+//! let res = lr2021.tune_antenna(&mut tuner, &cfg, |setting, rssi| info!("{} : -{}dBm", setting, rssi>>1)).await.expect("Tune");
+//! info!("Best setting {} : -{}dBm", res.setting, res.rssi>>1);
+//! ```
+//!
+//! ## Available Methods
+//! - [`tune_antenna`](Lr2021::tune_antenna) - Search the tuner setting maximizing the RSSI
+
+use embassy_time::{Duration, Timer};
+use embedded_hal::digital::OutputPin;
+use embedded_hal_async::spi::SpiBus;
+
+use super::{BusyPin, Lr2021, Lr2021Error};
+
+/// Tunable matching network controlled by the host
+pub trait AntennaTuner {
+    /// Apply a tuner setting
+    #[allow(async_fn_in_trait)]
+    async fn apply(&mut self, setting: u16) -> Result<(), Lr2021Error>;
+}
+
+/// Antenna tuning configuration
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct TuneCfg {
+    /// Minimum tuner setting
+    pub min: u16,
+    /// Maximum tuner setting
+    pub max: u16,
+    /// Step of the coarse sweep
+    pub step: u16,
+    /// Number of RSSI measurements averaged for each setting
+    pub nb_meas: u16,
+    /// Settling time after applying a setting
+    pub settle: Duration,
+}
+
+impl TuneCfg {
+    /// Tuning configuration with 8 measurements per setting and a settling time of 100us
+    pub fn new(min: u16, max: u16, step: u16) -> Self {
+        Self { min, max: max.max(min), step: step.max(1), nb_meas: 8, settle: Duration::from_micros(100) }
+    }
+
+    /// Set the number of RSSI measurements averaged for each setting
+    pub fn with_nb_meas(self, nb_meas: u16) -> Self {
+        Self { nb_meas: nb_meas.max(1), ..self }
+    }
+
+    /// Set the settling time after applying a setting
+    pub fn with_settle(self, settle: Duration) -> Self {
+        Self { settle, ..self }
+    }
+}
+
+/// Result of the antenna tuning
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct TuneResult {
+    /// Best tuner setting (applied at the end of the tuning)
+    pub setting: u16,
+    /// RSSI measured with the best setting (in -0.5dBm)
+    pub rssi: u16,
+    /// Number of settings evaluated
+    pub nb_eval: u16,
+}
+
+impl<O,SPI, M> Lr2021<O,SPI, M> where
+    O: OutputPin, SPI: SpiBus<u8>, M: BusyPin
+{
+    /// Apply a tuner setting and return the average RSSI
+    async fn tune_eval<T: AntennaTuner>(&mut self, tuner: &mut T, cfg: &TuneCfg, setting: u16) -> Result<u16, Lr2021Error> {
+        tuner.apply(setting).await?;
+        Timer::after(cfg.settle).await;
+        self.get_rssi_avg(cfg.nb_meas).await
+    }
+
+    /// Search the tuner setting maximizing the RSSI of a reference signal (CW or continuous beacon)
+    /// The chip must be in RX on the frequency of the reference signal.
+    /// The callback is called after each measurement with the setting and the RSSI (in -0.5dBm)
+    pub async fn tune_antenna<T, F>(&mut self, tuner: &mut T, cfg: &TuneCfg, mut report: F) -> Result<TuneResult, Lr2021Error>
+        where T: AntennaTuner, F: FnMut(u16, u16)
+    {
+        let mut nb_eval = 0;
+        // Coarse sweep
+        let mut best = (cfg.min, u16::MAX);
+        let mut setting = cfg.min;
+        loop {
+            let rssi = self.tune_eval(tuner, cfg, setting).await?;
+            report(setting, rssi);
+            nb_eval += 1;
+            // RSSI is in -0.5dBm unit: lower is better
+            if rssi < best.1 {
+                best = (setting, rssi);
+            }
+            if setting >= cfg.max {
+                break;
+            }
+            setting = setting.saturating_add(cfg.step).min(cfg.max);
+        }
+        // Refinement around best setting
+        let mut step = cfg.step / 2;
+        while step > 0 {
+            let center = best.0;
+            for cand in [center.saturating_sub(step).max(cfg.min), center.saturating_add(step).min(cfg.max)] {
+                if cand == center {
+                    continue;
+                }
+                let rssi = self.tune_eval(tuner, cfg, cand).await?;
+                report(cand, rssi);
+                nb_eval += 1;
+                if rssi < best.1 {
+                    best = (cand, rssi);
+                }
+            }
+            step /= 2;
+        }
+        tuner.apply(best.0).await?;
+        Ok(TuneResult { setting: best.0, rssi: best.1, nb_eval })
+    }
+}
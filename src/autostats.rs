@@ -0,0 +1,223 @@
+//! # Automatic refresh of the packet status and RX statistics
+//!
+//! This module caches the status of the last packet received and the RX statistics of the current packet type,
+//! refreshed automatically on RxDone, so that UI or telemetry code can read them synchronously
+//! with [`last_packet_status`](Lr2021::last_packet_status) and [`stats`](Lr2021::stats)
+//! without issuing redundant SPI commands. The cached packet status is read on the RxDone of the packet,
+//! so it is consistent with the packet read from the FIFO.
+//!
+//! The refresh is done by [`rx_fifo_push_pkt`](Lr2021::rx_fifo_push_pkt), or by [`auto_stats_update`](Lr2021::auto_stats_update)
+//! for applications reading packets directly. To limit the SPI traffic, the RX statistics can be refreshed only
+//! every N packets and no more often than a minimum interval.
+//!
+//! ## Quick Start
+//!
+//! ```rust,no_run
+//! use lr2021::autostats::AutoStatsCfg;
+//!
+//! // Packet status on each packet, statistics every 10 packets and at most once per second
+//! lr2021.set_auto_stats(Some(AutoStatsCfg::new(10).with_min_interval(Duration::from_secs(1))));
+//! // On RxDone
+//! lr2021.auto_stats_update().await.expect("AutoStats");
+//! // Anywhere
+//! if let Some(status) = lr2021.last_packet_status() {
+//!     info!("RSSI {}dBm", status.rssi_avg_dbm());
+//! }
+//! ```
+//!
+//! ## Available Methods
+//! - [`set_auto_stats`](Lr2021::set_auto_stats) - Enable/disable the automatic refresh and clear the cached values
+//! - [`auto_stats_update`](Lr2021::auto_stats_update) - Refresh the cached values after a RxDone
+//! - [`get_rx_stats`](Lr2021::get_rx_stats) - Read the RX statistics in a format common to all packet types
+//! - [`last_packet_status`](Lr2021::last_packet_status) - Cached status of the last packet received
+//! - [`stats`](Lr2021::stats) - Cached RX statistics
+
+use embassy_time::{Duration, Instant};
+use embedded_hal::digital::OutputPin;
+use embedded_hal_async::spi::SpiBus;
+
+use super::ble::BleRxStatsRsp;
+use super::flrc::FlrcRxStatsRsp;
+use super::fsk::FskRxStatsRsp;
+use super::lora::LoraRxStatsRsp;
+use super::ook::OokRxStatsRsp;
+use super::radio::{PacketType, PktStatus};
+use super::wisun::WisunRxStatsRsp;
+use super::wmbus::WmbusRxStatsRsp;
+use super::zigbee::ZigbeeRxStatsRsp;
+use super::zwave::ZwaveRxStatsRsp;
+use super::{BusyPin, Lr2021, Lr2021Error};
+
+/// RX statistics common to all packet types, built from the protocol specific statistics
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct RxStats {
+    /// Packets received
+    pub pkt_rx: u16,
+    /// Packets received with a CRC error
+    pub crc_error: u16,
+    /// Packets received with a length error (header error for LoRa)
+    pub len_error: u16,
+}
+
+impl From<&LoraRxStatsRsp> for RxStats {
+    fn from(rsp: &LoraRxStatsRsp) -> Self {
+        Self { pkt_rx: rsp.pkt_rx(), crc_error: rsp.crc_error(), len_error: rsp.header_error() }
+    }
+}
+
+impl From<&FskRxStatsRsp> for RxStats {
+    fn from(rsp: &FskRxStatsRsp) -> Self {
+        Self { pkt_rx: rsp.pkt_rx(), crc_error: rsp.crc_error(), len_error: rsp.len_error() }
+    }
+}
+
+impl From<&FlrcRxStatsRsp> for RxStats {
+    fn from(rsp: &FlrcRxStatsRsp) -> Self {
+        Self { pkt_rx: rsp.pkt_rx(), crc_error: rsp.crc_error(), len_error: rsp.len_error() }
+    }
+}
+
+impl From<&BleRxStatsRsp> for RxStats {
+    fn from(rsp: &BleRxStatsRsp) -> Self {
+        Self { pkt_rx: rsp.pkt_rx(), crc_error: rsp.crc_error(), len_error: rsp.len_error() }
+    }
+}
+
+impl From<&OokRxStatsRsp> for RxStats {
+    fn from(rsp: &OokRxStatsRsp) -> Self {
+        Self { pkt_rx: rsp.pkt_rx(), crc_error: rsp.crc_error(), len_error: rsp.len_error() }
+    }
+}
+
+impl From<&ZigbeeRxStatsRsp> for RxStats {
+    fn from(rsp: &ZigbeeRxStatsRsp) -> Self {
+        Self { pkt_rx: rsp.pkt_rx(), crc_error: rsp.crc_error(), len_error: rsp.len_error() }
+    }
+}
+
+impl From<&ZwaveRxStatsRsp> for RxStats {
+    fn from(rsp: &ZwaveRxStatsRsp) -> Self {
+        Self { pkt_rx: rsp.pkt_rx(), crc_error: rsp.crc_error(), len_error: rsp.len_error() }
+    }
+}
+
+impl From<&WmbusRxStatsRsp> for RxStats {
+    fn from(rsp: &WmbusRxStatsRsp) -> Self {
+        Self { pkt_rx: rsp.pkt_rx(), crc_error: rsp.crc_error(), len_error: rsp.len_error() }
+    }
+}
+
+impl From<&WisunRxStatsRsp> for RxStats {
+    fn from(rsp: &WisunRxStatsRsp) -> Self {
+        Self { pkt_rx: rsp.pkt_rx(), crc_error: rsp.crc_error(), len_error: rsp.len_error() }
+    }
+}
+
+/// Configuration of the automatic refresh
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct AutoStatsCfg {
+    /// Refresh the packet status on each packet
+    pub pkt_status: bool,
+    /// Refresh the RX statistics every N packets (0 to disable)
+    pub stats_every: u16,
+    /// Minimum interval between two refreshes of the RX statistics
+    pub min_interval: Duration,
+}
+
+impl AutoStatsCfg {
+    /// Refresh the packet status on each packet and the RX statistics every stats_every packets (0 to disable)
+    pub fn new(stats_every: u16) -> Self {
+        Self { pkt_status: true, stats_every, min_interval: Duration::from_ticks(0) }
+    }
+
+    /// Enable/disable the refresh of the packet status
+    pub fn with_pkt_status(self, pkt_status: bool) -> Self {
+        Self { pkt_status, ..self }
+    }
+
+    /// Set the minimum interval between two refreshes of the RX statistics
+    pub fn with_min_interval(self, min_interval: Duration) -> Self {
+        Self { min_interval, ..self }
+    }
+}
+
+/// State of the automatic refresh
+#[derive(Debug, Default, Clone, Copy)]
+pub(crate) struct AutoStats {
+    /// Configuration (None when disabled)
+    cfg: Option<AutoStatsCfg>,
+    /// Packets received since the last refresh of the RX statistics
+    nb_pkt: u16,
+    /// Time of the last refresh of the RX statistics
+    last_refresh: Option<Instant>,
+    /// Cached packet status
+    pkt_status: Option<PktStatus>,
+    /// Cached RX statistics
+    stats: Option<RxStats>,
+}
+
+impl<O,SPI, M> Lr2021<O,SPI, M> where
+    O: OutputPin, SPI: SpiBus<u8>, M: BusyPin
+{
+    /// Enable (Some) or disable (None) the automatic refresh of the packet status and RX statistics
+    /// The cached values are cleared
+    pub fn set_auto_stats(&mut self, cfg: Option<AutoStatsCfg>) {
+        self.auto_stats = AutoStats { cfg, ..AutoStats::default() };
+    }
+
+    /// Refresh the cached packet status and RX statistics according to the configuration: must be called on each RxDone
+    /// (already done by `rx_fifo_push_pkt`). No SPI access when disabled or when nothing needs to be refreshed
+    pub async fn auto_stats_update(&mut self) -> Result<(), Lr2021Error> {
+        let Some(cfg) = self.auto_stats.cfg else {
+            return Ok(());
+        };
+        if cfg.pkt_status {
+            self.auto_stats.pkt_status = Some(self.get_packet_status().await?);
+        }
+        if cfg.stats_every == 0 {
+            return Ok(());
+        }
+        self.auto_stats.nb_pkt = self.auto_stats.nb_pkt.saturating_add(1);
+        let too_soon = self.auto_stats.last_refresh.is_some_and(|t| t.elapsed() < cfg.min_interval);
+        if self.auto_stats.nb_pkt < cfg.stats_every || too_soon {
+            return Ok(());
+        }
+        self.auto_stats.stats = Some(self.get_rx_stats().await?);
+        self.auto_stats.nb_pkt = 0;
+        self.auto_stats.last_refresh = Some(Instant::now());
+        Ok(())
+    }
+
+    /// Read the RX statistics of the current packet type in a format common to all packet types
+    pub async fn get_rx_stats(&mut self) -> Result<RxStats, Lr2021Error> {
+        let packet_type = match self.cache.packet_type {
+            Some(t) => t,
+            None => self.get_packet_type().await?,
+        };
+        let stats = match packet_type {
+            PacketType::Lora => RxStats::from(&self.get_lora_rx_stats().await?),
+            PacketType::FskGeneric | PacketType::FskLegacy => RxStats::from(&self.get_fsk_rx_stats().await?),
+            PacketType::Flrc => RxStats::from(&self.get_flrc_rx_stats().await?),
+            PacketType::Ble => RxStats::from(&self.get_ble_rx_stats().await?),
+            PacketType::Ook => RxStats::from(&self.get_ook_rx_stats().await?),
+            PacketType::Zigbee => RxStats::from(&self.get_zigbee_rx_stats().await?),
+            PacketType::Zwave => RxStats::from(&self.get_zwave_rx_stats().await?),
+            PacketType::Wmbus => RxStats::from(&self.get_wmbus_rx_stats().await?),
+            PacketType::Wisun => RxStats::from(&self.get_wisun_rx_stats().await?),
+            _ => return Err(Lr2021Error::InvalidParam),
+        };
+        Ok(stats)
+    }
+
+    /// Cached status of the last packet received (None until refreshed, see `set_auto_stats`)
+    pub fn last_packet_status(&self) -> Option<PktStatus> {
+        self.auto_stats.pkt_status
+    }
+
+    /// Cached RX statistics (None until refreshed, see `set_auto_stats`)
+    pub fn stats(&self) -> Option<RxStats> {
+        self.auto_stats.stats
+    }
+}
@@ -0,0 +1,182 @@
+//! # Battery monitoring
+//!
+//! This module wraps the End-of-Life (EOL) detector and the battery voltage measurement into a [`BatteryMonitor`]:
+//!  - the EOL threshold is configured in millivolts, converted to the closest [`EolTrim`] at or above it
+//!  - battery voltage samples are smoothed with an exponential moving average
+//!  - low battery / recovery events are generated with an hysteresis to avoid toggling around the threshold
+//!
+//! The EOL interrupt is only used as a trigger to confirm the low battery with a measurement:
+//! add `IRQ_MASK_EOL` to the interrupts routed to a DIO to be notified without polling.
+//!
+//! ## Quick Start
+//!
+//! ```rust,no_run
+//! use lr2021::battery::{BatteryMonitor, BatteryEvent};
+//!
+//! // Low battery below 1.9V, recovered above 2.0V
+//! let mut mon = BatteryMonitor::new(1900, 100).with_smoothing(2);
+//! lr2021.battery_monitor_start(&mon).await.expect("BatteryStart");
+//!
+//! // Dedicated task: wait for a low battery, sampling the voltage every minute
+//! let mv = lr2021.on_low_battery(&mut mon, Duration::from_secs(60)).await.expect("LowBattery");
+//! warn!("Low battery: {}mV", mv);
+//! ```
+//!
+//! ## Available Methods
+//! - [`battery_monitor_start`](Lr2021::battery_monitor_start) - Configure and enable the EOL detector
+//! - [`battery_sample`](Lr2021::battery_sample) - Measure the battery voltage and update the monitor
+//! - [`battery_check_eol`](Lr2021::battery_check_eol) - Confirm an EOL interrupt with a measurement
+//! - [`on_low_battery`](Lr2021::on_low_battery) - Wait until a low battery is detected
+
+use embassy_time::{Duration, Timer};
+use embedded_hal::digital::OutputPin;
+use embedded_hal_async::spi::SpiBus;
+
+use super::status::{Intr, IRQ_MASK_EOL};
+use super::system::{AdcRes, EolTrim};
+use super::{BusyPin, Lr2021, Lr2021Error};
+
+/// EOL thresholds in mV
+const EOL_TRIMS: [(u16, EolTrim); 8] = [
+    (1600, EolTrim::Eol1p60),
+    (1670, EolTrim::Eol1p67),
+    (1740, EolTrim::Eol1p74),
+    (1800, EolTrim::Eol1p80),
+    (1880, EolTrim::Eol1p88),
+    (1950, EolTrim::Eol1p95),
+    (2000, EolTrim::Eol2p00),
+    (2100, EolTrim::Eol2p10),
+];
+
+impl EolTrim {
+    /// Lowest EOL threshold at or above a voltage in mV (saturate to 2.1V)
+    pub fn from_mv(mv: u16) -> Self {
+        EOL_TRIMS.iter()
+            .find(|(thr, _)| *thr >= mv)
+            .map(|(_, trim)| *trim)
+            .unwrap_or(EolTrim::Eol2p10)
+    }
+
+    /// EOL threshold in mV
+    pub fn to_mv(&self) -> u16 {
+        EOL_TRIMS[*self as usize].0
+    }
+}
+
+/// Battery event
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum BatteryEvent {
+    /// Battery voltage dropped below the low threshold
+    Low,
+    /// Battery voltage went back above the low threshold plus hysteresis
+    Recovered,
+}
+
+/// Battery monitor with smoothing and hysteresis
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct BatteryMonitor {
+    /// Low battery threshold in mV
+    low_mv: u16,
+    /// Hysteresis in mV
+    hysteresis_mv: u16,
+    /// Smoothing factor: each new sample has a weight of 1/2^shift
+    shift: u8,
+    /// Average voltage in mV with 8 fractional bits
+    avg: Option<u32>,
+    /// Low battery state
+    is_low: bool,
+}
+
+impl BatteryMonitor {
+    /// Create a monitor with a low battery threshold and an hysteresis in mV, without smoothing
+    pub fn new(low_mv: u16, hysteresis_mv: u16) -> Self {
+        Self { low_mv, hysteresis_mv, shift: 0, avg: None, is_low: false }
+    }
+
+    /// Set the smoothing factor: each new sample has a weight of 1/2^shift (max 7)
+    pub fn with_smoothing(self, shift: u8) -> Self {
+        Self { shift: shift.min(7), ..self }
+    }
+
+    /// Low battery threshold in mV
+    pub fn low_mv(&self) -> u16 {
+        self.low_mv
+    }
+
+    /// Smoothed battery voltage in mV
+    pub fn avg_mv(&self) -> Option<u16> {
+        self.avg.map(|a| ((a + 128) >> 8) as u16)
+    }
+
+    /// Flag if the battery is low
+    pub fn is_low(&self) -> bool {
+        self.is_low
+    }
+
+    /// Update the monitor with a new voltage sample in mV
+    /// Return an event when the low battery state changes
+    pub fn update(&mut self, mv: u16) -> Option<BatteryEvent> {
+        let sample = (mv as u32) << 8;
+        let avg = match self.avg {
+            Some(a) => a - (a >> self.shift) + (sample >> self.shift),
+            None => sample,
+        };
+        self.avg = Some(avg);
+        let avg_mv = self.avg_mv().unwrap_or(mv);
+        if !self.is_low && avg_mv < self.low_mv {
+            self.is_low = true;
+            Some(BatteryEvent::Low)
+        } else if self.is_low && avg_mv > self.low_mv.saturating_add(self.hysteresis_mv) {
+            self.is_low = false;
+            Some(BatteryEvent::Recovered)
+        } else {
+            None
+        }
+    }
+}
+
+impl<O,SPI, M> Lr2021<O,SPI, M> where
+    O: OutputPin, SPI: SpiBus<u8>, M: BusyPin
+{
+    /// Configure and enable the EOL detector with the monitor threshold
+    pub async fn battery_monitor_start(&mut self, mon: &BatteryMonitor) -> Result<(), Lr2021Error> {
+        self.set_eol_config(EolTrim::from_mv(mon.low_mv), true).await?;
+        self.clear_irqs(Intr::new(IRQ_MASK_EOL)).await
+    }
+
+    /// Measure the battery voltage and update the monitor
+    pub async fn battery_sample(&mut self, mon: &mut BatteryMonitor) -> Result<Option<BatteryEvent>, Lr2021Error> {
+        let mv = self.get_vbat(AdcRes::Res10bit).await?;
+        Ok(mon.update(mv))
+    }
+
+    /// Confirm an EOL interrupt with a battery measurement (the EOL interrupt is cleared)
+    /// Return None if the interrupt is not an EOL or no state change occurred
+    pub async fn battery_check_eol(&mut self, mon: &mut BatteryMonitor, intr: Intr) -> Result<Option<BatteryEvent>, Lr2021Error> {
+        if !intr.eol() {
+            return Ok(None);
+        }
+        self.clear_irqs(Intr::new(IRQ_MASK_EOL)).await?;
+        self.battery_sample(mon).await
+    }
+
+    /// Wait until a low battery is detected, sampling the voltage periodically and on EOL interrupt
+    /// Return immediately if the battery is already low
+    /// Return the smoothed battery voltage in mV
+    pub async fn on_low_battery(&mut self, mon: &mut BatteryMonitor, period: Duration) -> Result<u16, Lr2021Error> {
+        loop {
+            let (_, intr) = self.get_status().await?;
+            if intr.eol() {
+                self.battery_check_eol(mon, intr).await?;
+            } else {
+                self.battery_sample(mon).await?;
+            }
+            if mon.is_low() {
+                return Ok(mon.avg_mv().unwrap_or(0));
+            }
+            Timer::after(period).await;
+        }
+    }
+}
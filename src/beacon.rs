@@ -0,0 +1,177 @@
+//! # Periodic beacon transmitter
+//!
+//! This module provides a simple beacon transmitter sending a frame every period with a random jitter,
+//! typically used by OOK sensors (but any packet type configured before can be used).
+//!
+//! The time on air of each transmission is accounted in a [`DutyCycleBudget`] (e.g. 1% over one hour in the EU 868MHz band):
+//! a transmission is skipped when the budget left in the current observation window is not enough to send the frame.
+//! Between two transmissions the chip is set in sleep with retention, so the configuration is kept
+//! and the chip only needs to be woken up before the next transmission.
+//!
+//! ## Quick Start
+//!
+//! ```rust,no_run
+//! use lr2021::radio::PacketType;
+//! use lr2021::ook::{AddrComp, PktFormat, Crc, Encoding};
+//! use lr2021::beacon::{Beacon, DutyCycleBudget};
+//! use lr2021::{RxBw, PulseShape};
+//!
+//! lr2021.set_packet_type(PacketType::Ook).await.expect("SetPacketType");
+//! lr2021.set_rf(868_300_000).await.expect("SetRF");
+//! lr2021.set_ook_modulation(4_800, RxBw::Bw20, PulseShape::None).await.expect("SetOokModulation");
+//! lr2021.set_ook_packet(16, AddrComp::Off, PktFormat::FixedLength, 6, Crc::Crc1Byte, Encoding::Manchester).await.expect("SetOokPacket");
+//!
+//! // Send a 6 bytes frame every 10s +/- 1s, with a duty-cycle of 1% over one hour
+//! let frame = [0x12, 0x34, 0x56, 0x78, 0x9A, 0xBC];
+//! let beacon = Beacon::new(&frame, Duration::from_secs(10)).with_jitter(Duration::from_secs(1));
+//! let mut budget = DutyCycleBudget::new(10, Duration::from_secs(3600));
+//! lr2021.beacon_run(&beacon, &mut budget).await.expect("Beacon");
+//! ```
+//!
+//! ## Available Methods
+//! - [`beacon_send`](Lr2021::beacon_send) - Send the beacon frame once (if allowed by the budget) and set the chip in sleep
+//! - [`beacon_run`](Lr2021::beacon_run) - Send the beacon frame periodically
+//! - [`beacon_run_with_rng`](Lr2021::beacon_run_with_rng) - Send the beacon frame periodically with a custom random source for the jitter
+
+use embassy_time::{Duration, Instant, Timer};
+use embedded_hal::digital::OutputPin;
+use embedded_hal_async::spi::SpiBus;
+
+use super::rng::RandomSource;
+use super::system::ChipMode;
+use super::{BusyPin, Lr2021, Lr2021Error};
+
+/// Duty-cycle budget: maximum time on air over an observation window
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct DutyCycleBudget {
+    /// Maximum time on air allowed in a window
+    limit: Duration,
+    /// Duration of the observation window
+    window: Duration,
+    /// Time on air used in the current window
+    used: Duration,
+    /// Start of the current window
+    start: Instant,
+}
+
+impl DutyCycleBudget {
+    /// Create a budget with a duty-cycle given in per-mille (e.g. 10 for 1%) over an observation window
+    pub fn new(duty_permille: u16, window: Duration) -> Self {
+        let limit = Duration::from_ticks(window.as_ticks() * duty_permille.min(1000) as u64 / 1000);
+        Self { limit, window, used: Duration::from_ticks(0), start: Instant::now() }
+    }
+
+    /// Start a new window if the current one is over
+    fn update(&mut self) {
+        let elapsed = self.start.elapsed();
+        if elapsed >= self.window {
+            // Keep windows aligned on the first one
+            let nb = elapsed.as_ticks() / self.window.as_ticks();
+            self.start += Duration::from_ticks(nb * self.window.as_ticks());
+            self.used = Duration::from_ticks(0);
+        }
+    }
+
+    /// Time on air left in the current window
+    pub fn remaining(&mut self) -> Duration {
+        self.update();
+        self.limit.checked_sub(self.used).unwrap_or(Duration::from_ticks(0))
+    }
+
+    /// Time until the start of the next window (i.e. when the budget is refilled)
+    pub fn time_to_refill(&mut self) -> Duration {
+        self.update();
+        self.window.checked_sub(self.start.elapsed()).unwrap_or(Duration::from_ticks(0))
+    }
+
+    /// Check if a transmission with a given time on air is allowed
+    pub fn allows(&mut self, airtime: Duration) -> bool {
+        self.remaining() >= airtime
+    }
+
+    /// Account a transmission in the current window
+    pub fn consume(&mut self, airtime: Duration) {
+        self.update();
+        self.used += airtime;
+    }
+}
+
+/// Beacon configuration
+#[derive(Debug, Clone, Copy)]
+pub struct Beacon<'a> {
+    /// Frame transmitted
+    pub frame: &'a [u8],
+    /// Nominal period between two transmissions
+    pub period: Duration,
+    /// Maximum random deviation from the period (uniform in +/- jitter)
+    pub jitter: Duration,
+}
+
+impl<'a> Beacon<'a> {
+    /// Beacon sending a frame every period, without jitter
+    pub fn new(frame: &'a [u8], period: Duration) -> Self {
+        Self { frame, period, jitter: Duration::from_ticks(0) }
+    }
+
+    /// Add a random jitter on the period (clamped to the period)
+    pub fn with_jitter(self, jitter: Duration) -> Self {
+        Self { jitter: jitter.min(self.period), ..self }
+    }
+}
+
+/// Result of a beacon transmission attempt
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum BeaconTx {
+    /// Frame sent with the measured time on air
+    Sent(Duration),
+    /// Transmission skipped since the duty-cycle budget was exhausted
+    Skipped,
+}
+
+impl<O,SPI, M> Lr2021<O,SPI, M> where
+    O: OutputPin, SPI: SpiBus<u8>, M: BusyPin
+{
+    /// Send the beacon frame if the duty-cycle budget allows it, then set the chip in sleep with retention.
+    /// The chip is woken up first, so it can be called directly after a previous `beacon_send`.
+    /// The time on air of the previous transmission is used to check the budget (`last_airtime`, 0 for the first one)
+    /// The packet type and parameters must be configured before, with a payload length matching the frame
+    /// for protocols where it is not updated by the driver (see [`tx_payload`](Lr2021::tx_payload))
+    pub async fn beacon_send(&mut self, beacon: &Beacon<'_>, budget: &mut DutyCycleBudget, last_airtime: Duration) -> Result<BeaconTx, Lr2021Error> {
+        if !budget.allows(last_airtime) || budget.remaining() == Duration::from_ticks(0) {
+            return Ok(BeaconTx::Skipped);
+        }
+        self.wake_up().await?;
+        let start = Instant::now();
+        self.tx_wait_done(beacon.frame, beacon.period).await?;
+        let airtime = start.elapsed();
+        budget.consume(airtime);
+        self.set_chip_mode(ChipMode::DeepRetention).await?;
+        Ok(BeaconTx::Sent(airtime))
+    }
+
+    /// Send the beacon frame periodically (only returns on error)
+    /// Each period is randomly offset by up to +/- jitter using a PRNG seeded once from the chip,
+    /// and the chip is kept in sleep with retention between transmissions
+    pub async fn beacon_run(&mut self, beacon: &Beacon<'_>, budget: &mut DutyCycleBudget) -> Result<(), Lr2021Error> {
+        self.wake_up().await?;
+        let mut rng = self.chip_rng().await?;
+        self.beacon_run_with_rng(beacon, budget, &mut rng).await
+    }
+
+    /// Send the beacon frame periodically (only returns on error), using a custom random source for the jitter
+    pub async fn beacon_run_with_rng(&mut self, beacon: &Beacon<'_>, budget: &mut DutyCycleBudget, rng: &mut impl RandomSource) -> Result<(), Lr2021Error> {
+        let mut airtime = Duration::from_ticks(0);
+        loop {
+            let next = Instant::now() + rng.jitter(beacon.period, beacon.jitter);
+            match self.beacon_send(beacon, budget, airtime).await? {
+                BeaconTx::Sent(t) => airtime = t,
+                BeaconTx::Skipped => {
+                    #[cfg(feature = "defmt")]{defmt::warn!("Beacon skipped: duty-cycle budget exhausted");}
+                }
+            }
+            Timer::at(next).await;
+        }
+    }
+}
@@ -0,0 +1,155 @@
+//! # Bitrate error measurement
+//!
+//! Long FSK/OOK packets can fail when the bitrate of the transmitter differs from the nominal one
+//! (crystal error, legacy devices using RC oscillators, ...): the receiver slowly loses the bit synchronization.
+//! The chip has no datarate trimming, so this module measures the actual bitrate from the chip timestamps
+//! of packets with a known length and computes the bitrate to program for compensation.
+//!
+//! The duration between the syncword detection and the end of reception is measured with a resolution of one HF clock tick (31.25ns).
+//! This duration includes a fixed processing latency: the bitrate is estimated as the slope of the duration
+//! against the number of bits, using packets with at least two different lengths.
+//!
+//! Timestamps [`TimestampIndex::Ts1`] and [`TimestampIndex::Ts2`] are used by the measurement.
+//!
+//! ## Quick Start
+//!
+//! ```rust,no_run
+//! use lr2021::bitrate::BitrateMeas;
+//!
+//! let mut meas = BitrateMeas::new(4800);
+//! lr2021.bitrate_meas_start().await.expect("BitrateMeasStart");
+//! lr2021.set_rx_continous().await.expect("SetRx");
+//! while meas.nb_samples() < 16 {
+//!     // Wait for RxDone interrupt ...
+//!     let len = lr2021.get_rx_pkt_len().await.expect("RxLen") as u32;
+//!     // Payload and 2B CRC, Manchester encoded
+//!     lr2021.bitrate_meas_packet(&mut meas, (len + 2) * 16).await.expect("BitrateMeasPkt");
+//! }
+//! info!("Bitrate error = {}ppm", meas.error_ppm().unwrap_or(0));
+//! if let Some(bitrate) = meas.bitrate_frac() {
+//!     lr2021.set_ook_modulation(bitrate, RxBw::Bw7p4, PulseShape::None).await.expect("SetModulation");
+//! }
+//! ```
+//!
+//! ## Available Methods
+//! - [`bitrate_meas_start`](Lr2021::bitrate_meas_start) - Configure the timestamps used by the measurement
+//! - [`bitrate_meas_packet`](Lr2021::bitrate_meas_packet) - Measure the duration of the last packet received and update the estimation
+
+use embedded_hal::digital::OutputPin;
+use embedded_hal_async::spi::SpiBus;
+
+use super::radio::{TimestampIndex, TimestampSource};
+use super::{BusyPin, Lr2021, Lr2021Error};
+
+/// HF clock frequency used by the timestamps
+const HF_CLK_HZ: i128 = 32_000_000;
+
+/// Bitrate estimation from packet durations
+#[derive(Debug, Default, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct BitrateMeas {
+    /// Nominal bitrate (in bit/s)
+    nominal: u32,
+    /// Number of samples
+    n: u32,
+    /// Sum of the number of bits
+    sx: i64,
+    /// Sum of the durations (in HF ticks)
+    sy: i64,
+    /// Sum of the squared number of bits
+    sxx: i64,
+    /// Sum of the product number of bits by duration
+    sxy: i64,
+}
+
+impl BitrateMeas {
+    /// Create an estimation for a nominal bitrate in bit/s
+    pub fn new(nominal: u32) -> Self {
+        Self { nominal, ..Self::default() }
+    }
+
+    /// Add a sample: number of bits after the syncword and duration in HF ticks
+    pub fn add(&mut self, nb_bits: u32, ticks: u32) {
+        let (x, y) = (nb_bits as i64, ticks as i64);
+        self.n += 1;
+        self.sx += x;
+        self.sy += y;
+        self.sxx += x * x;
+        self.sxy += x * y;
+    }
+
+    /// Remove all samples
+    pub fn clear(&mut self) {
+        *self = Self::new(self.nominal);
+    }
+
+    /// Number of samples
+    pub fn nb_samples(&self) -> u32 {
+        self.n
+    }
+
+    /// Nominal bitrate (in bit/s)
+    pub fn nominal(&self) -> u32 {
+        self.nominal
+    }
+
+    /// Measured bitrate with 8 fractional bits
+    /// Return None if the samples do not contain at least two different packet lengths
+    fn bitrate_x256(&self) -> Option<i128> {
+        let n = self.n as i128;
+        let num = n * self.sxy as i128 - self.sx as i128 * self.sy as i128;
+        let den = n * self.sxx as i128 - self.sx as i128 * self.sx as i128;
+        if num <= 0 || den <= 0 {
+            return None;
+        }
+        // Slope is the number of ticks per bit: bitrate = HF_CLK / slope
+        Some((HF_CLK_HZ * 256 * den + num / 2) / num)
+    }
+
+    /// Measured bitrate (in bit/s)
+    pub fn bitrate(&self) -> Option<u32> {
+        self.bitrate_x256().map(|b| ((b + 128) >> 8) as u32)
+    }
+
+    /// Measured bitrate in the fractional format of the modulation commands (bit 31 set, bitrate*256)
+    /// This value can be used directly to program the FSK/OOK modulation for compensation
+    pub fn bitrate_frac(&self) -> Option<u32> {
+        self.bitrate_x256()
+            .filter(|&b| b < (1 << 31))
+            .map(|b| (1 << 31) | b as u32)
+    }
+
+    /// Bitrate error relative to the nominal bitrate (in ppm)
+    pub fn error_ppm(&self) -> Option<i32> {
+        let nominal = (self.nominal as i128) << 8;
+        if nominal == 0 {
+            return None;
+        }
+        self.bitrate_x256().map(|b| ((b - nominal) * 1_000_000 / nominal) as i32)
+    }
+}
+
+impl<O,SPI, M> Lr2021<O,SPI, M> where
+    O: OutputPin, SPI: SpiBus<u8>, M: BusyPin
+{
+    /// Configure the timestamps used by the measurement: Ts1 on syncword detection, Ts2 on RxDone
+    pub async fn bitrate_meas_start(&mut self) -> Result<(), Lr2021Error> {
+        self.set_timestamp_source(TimestampIndex::Ts1, TimestampSource::Sync).await?;
+        self.set_timestamp_source(TimestampIndex::Ts2, TimestampSource::RxDone).await
+    }
+
+    /// Measure the duration between syncword and end of the last packet received, and add it to the estimation
+    /// Must be called after RxDone with the number of bits sent over the air after the syncword (including encoding and CRC)
+    /// Return the duration in HF ticks
+    pub async fn bitrate_meas_packet(&mut self, meas: &mut BitrateMeas, nb_bits: u32) -> Result<u32, Lr2021Error> {
+        // Timestamps are relative to each request: read the sync one before and after the end one
+        // to compensate the delay between requests
+        let sync_a = self.get_timestamp(TimestampIndex::Ts1).await?;
+        let end = self.get_timestamp(TimestampIndex::Ts2).await?;
+        let sync_b = self.get_timestamp(TimestampIndex::Ts1).await?;
+        let sync = sync_a + sync_b.saturating_sub(sync_a) / 2;
+        let ticks = sync.checked_sub(end).ok_or(Lr2021Error::InvalidParam)?;
+        meas.add(nb_bits, ticks);
+        Ok(ticks)
+    }
+}
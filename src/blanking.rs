@@ -0,0 +1,265 @@
+//! # LoRa blanking auto-tuning
+//!
+//! The LoRa blanking (see [`set_lora_blanking`](Lr2021::set_lora_blanking)) reduces the impact of interferers
+//! at the cost of a small sensitivity loss on a clean channel. This module provides [`BlankingTuner`],
+//! stepping the blanking through the [`BlankingLevel`]s (off → symbol → time-domain & symbol → full)
+//! based on the packet error rate and the SNR margin observed:
+//!  - errors while the SNR margin is comfortable are caused by interference: the blanking is increased
+//!  - after several clean observation windows the blanking is decreased
+//!
+//! The statistics are evaluated over windows of a minimum number of packets,
+//! and decreasing requires consecutive clean windows to avoid oscillations.
+//!
+//! ## Quick Start
+//!
+//! ```rust,no_run
+//! use lr2021::blanking::{BlankingTuneCfg, BlankingTuner};
+//!
+//! let mut tuner = BlankingTuner::new(BlankingTuneCfg::default());
+//! lr2021.set_lora_blanking_level(tuner.level()).await.expect("Blanking");
+//! // On each RxDone (with or without CRC error)
+//! let level = lr2021.lora_blanking_tune(&mut tuner).await.expect("BlankingTune");
+//! info!("Blanking {}", level);
+//! ```
+//!
+//! ## Available Methods
+//! - [`set_lora_blanking_level`](Lr2021::set_lora_blanking_level) - Configure the blanking for a level
+//! - [`lora_blanking_tune`](Lr2021::lora_blanking_tune) - Update a tuner with the last packet status and RX statistics and apply its level
+//! - [`BlankingTuner::update_snr`] - Add the SNR of a received packet
+//! - [`BlankingTuner::update_stats`] - Add RX statistics and evaluate the level at the end of a window
+
+use embedded_hal::digital::OutputPin;
+use embedded_hal_async::spi::SpiBus;
+
+use super::autostats::RxStats;
+use super::lora::{BlankingCfg, Sf};
+use super::{BusyPin, Lr2021, Lr2021Error};
+
+/// Blanking level, from no blanking to full blanking
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum BlankingLevel {
+    /// Blanking disabled
+    #[default]
+    Off,
+    /// Blanking in symbol domain
+    Symbol,
+    /// Blanking in time domain and symbol domain
+    TdSymb,
+    /// Blanking in time domain and symbol domain, including during detection
+    Full,
+}
+
+impl BlankingLevel {
+    /// Blanking configuration of the level
+    pub fn cfg(&self) -> BlankingCfg {
+        match self {
+            BlankingLevel::Off => BlankingCfg::off(),
+            BlankingLevel::Symbol => BlankingCfg::symbol(),
+            BlankingLevel::TdSymb => BlankingCfg::td_symb(),
+            BlankingLevel::Full => BlankingCfg::full(),
+        }
+    }
+
+    /// Next stronger level (saturating)
+    pub fn up(&self) -> Self {
+        match self {
+            BlankingLevel::Off => BlankingLevel::Symbol,
+            BlankingLevel::Symbol => BlankingLevel::TdSymb,
+            _ => BlankingLevel::Full,
+        }
+    }
+
+    /// Next weaker level (saturating)
+    pub fn down(&self) -> Self {
+        match self {
+            BlankingLevel::Full => BlankingLevel::TdSymb,
+            BlankingLevel::TdSymb => BlankingLevel::Symbol,
+            _ => BlankingLevel::Off,
+        }
+    }
+}
+
+/// Thresholds of the blanking auto-tuning
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct BlankingTuneCfg {
+    /// Minimum number of packets in an observation window
+    pub window: u16,
+    /// Packet error rate (in percent) increasing the blanking
+    pub per_up: u8,
+    /// Packet error rate (in percent) below which a window is clean
+    pub per_down: u8,
+    /// Minimum SNR margin above the demodulation floor (in 0.25dB) to attribute errors to interference
+    pub snr_margin: i16,
+    /// Number of consecutive clean windows needed to decrease the blanking
+    pub hold: u8,
+    /// Strongest level allowed
+    pub max_level: BlankingLevel,
+}
+
+impl Default for BlankingTuneCfg {
+    /// Windows of 20 packets, increase above 10% PER with 3dB margin, decrease after 3 windows below 2% PER
+    fn default() -> Self {
+        Self { window: 20, per_up: 10, per_down: 2, snr_margin: 12, hold: 3, max_level: BlankingLevel::Full }
+    }
+}
+
+impl BlankingTuneCfg {
+    /// Set the packet error rates (in percent) increasing and decreasing the blanking
+    pub fn with_per(self, per_up: u8, per_down: u8) -> Self {
+        Self { per_up, per_down, ..self }
+    }
+
+    /// Set the minimum SNR margin (in 0.25dB) to attribute errors to interference
+    pub fn with_snr_margin(self, snr_margin: i16) -> Self {
+        Self { snr_margin, ..self }
+    }
+
+    /// Set the size of the window (in packets) and the number of clean windows to decrease the blanking
+    pub fn with_window(self, window: u16, hold: u8) -> Self {
+        Self { window, hold, ..self }
+    }
+
+    /// Set the strongest level allowed
+    pub fn with_max_level(self, max_level: BlankingLevel) -> Self {
+        Self { max_level, ..self }
+    }
+}
+
+/// Approximate SNR (in 0.25dB) required to demodulate a spreading factor: -2.5dB per SF above SF4
+pub fn lora_snr_floor(sf: Sf) -> i16 {
+    -10 * (sf as i16 - 4)
+}
+
+/// Blanking auto-tuning state
+#[derive(Debug, Default, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct BlankingTuner {
+    cfg: BlankingTuneCfg,
+    /// Current level
+    level: BlankingLevel,
+    /// Statistics at the start of the window
+    ref_stats: Option<RxStats>,
+    /// Sum of the SNR in the window
+    snr_sum: i32,
+    /// Number of SNR in the window
+    snr_nb: u16,
+    /// Consecutive clean windows
+    clean: u8,
+    /// Number of level changes
+    changes: u32,
+}
+
+impl BlankingTuner {
+    /// Create a tuner starting with blanking disabled
+    pub fn new(cfg: BlankingTuneCfg) -> Self {
+        Self { cfg, ..Self::default() }
+    }
+
+    /// Start from a given level
+    pub fn with_level(self, level: BlankingLevel) -> Self {
+        Self { level: level.min(self.cfg.max_level), ..self }
+    }
+
+    /// Current level
+    pub fn level(&self) -> BlankingLevel {
+        self.level
+    }
+
+    /// Number of level changes
+    pub fn changes(&self) -> u32 {
+        self.changes
+    }
+
+    /// Add the SNR (in 0.25dB) of a packet received
+    pub fn update_snr(&mut self, snr: i16) {
+        self.snr_sum += snr as i32;
+        self.snr_nb = self.snr_nb.saturating_add(1);
+    }
+
+    /// Start a new observation window from some statistics
+    fn restart(&mut self, stats: &RxStats) {
+        self.ref_stats = Some(*stats);
+        self.snr_sum = 0;
+        self.snr_nb = 0;
+    }
+
+    /// Add RX statistics and evaluate the level when the window is complete.
+    /// The SNR margin is computed against the demodulation floor of the spreading factor (ignored if None).
+    /// Return the new level if it changed
+    pub fn update_stats(&mut self, stats: &RxStats, sf: Option<Sf>) -> Option<BlankingLevel> {
+        let Some(prev) = self.ref_stats else {
+            self.restart(stats);
+            return None;
+        };
+        // Statistics cleared in between: restart the window
+        if stats.pkt_rx < prev.pkt_rx {
+            self.restart(stats);
+            return None;
+        }
+        let total = (stats.pkt_rx - prev.pkt_rx) as u32;
+        if total < self.cfg.window.max(1) as u32 {
+            return None;
+        }
+        let errors = (stats.crc_error.wrapping_sub(prev.crc_error) as u32 + stats.len_error.wrapping_sub(prev.len_error) as u32).min(total);
+        let per = errors * 100 / total;
+        let snr_ok = match (sf, self.snr_nb) {
+            (Some(sf), nb) if nb > 0 => self.snr_sum / nb as i32 - lora_snr_floor(sf) as i32 >= self.cfg.snr_margin as i32,
+            _ => true,
+        };
+        self.restart(stats);
+        let level = if per >= self.cfg.per_up as u32 && snr_ok {
+            self.clean = 0;
+            self.level.up().min(self.cfg.max_level)
+        } else if per <= self.cfg.per_down as u32 {
+            self.clean = self.clean.saturating_add(1);
+            if self.clean < self.cfg.hold {
+                return None;
+            }
+            self.clean = 0;
+            self.level.down()
+        } else {
+            self.clean = 0;
+            return None;
+        };
+        if level == self.level {
+            return None;
+        }
+        self.level = level;
+        self.changes = self.changes.saturating_add(1);
+        Some(level)
+    }
+}
+
+impl<O,SPI, M> Lr2021<O,SPI, M> where
+    O: OutputPin, SPI: SpiBus<u8>, M: BusyPin
+{
+    /// Configure the blanking for a level
+    pub async fn set_lora_blanking_level(&mut self, level: BlankingLevel) -> Result<(), Lr2021Error> {
+        self.set_lora_blanking(level.cfg()).await
+    }
+
+    /// Update a blanking tuner with the SNR of the last packet and the LoRa RX statistics,
+    /// and apply the blanking when the level changes. Must be called on each RxDone.
+    /// The values cached by the automatic refresh are used when available (see [`set_auto_stats`](Lr2021::set_auto_stats))
+    /// Return the level in use
+    pub async fn lora_blanking_tune(&mut self, tuner: &mut BlankingTuner) -> Result<BlankingLevel, Lr2021Error> {
+        let status = match self.last_packet_status() {
+            Some(status) => status,
+            None => self.get_packet_status().await?,
+        };
+        if let Some(snr) = status.quality {
+            tuner.update_snr(snr);
+        }
+        let stats = match self.stats() {
+            Some(stats) => stats,
+            None => self.get_rx_stats().await?,
+        };
+        let sf = self.cache.lora_modulation.map(|m| m.sf);
+        if let Some(level) = tuner.update_stats(&stats, sf) {
+            self.set_lora_blanking_level(level).await?;
+        }
+        Ok(tuner.level())
+    }
+}
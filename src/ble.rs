@@ -36,6 +36,12 @@
 //! - [`set_ble_tx_pdu_len`](Lr2021::set_ble_tx_pdu_len) - Set PDU length for pin-triggered transmission
 //! - [`patch_ble_coded`](Lr2021::patch_ble_coded) - Patch some settings when BLE Coded is used
 //!
+//! ### Raw PDU (CRC in FIFO)
+//! - [`ble_crc24`] - Compute the 24-bit CRC of a PDU
+//! - [`ble_whit_init`] - Whitening initial value for a channel index
+//! - [`ble_whiten`] - Apply (or remove) the whitening on a buffer
+//! - [`ble_tx_raw`](Lr2021::ble_tx_raw) - Send a raw PDU followed by a CRC provided by the host
+//!
 //! ### Status and Statistics
 //! - [`get_ble_packet_status`](Lr2021::get_ble_packet_status) - Get status of last received packet
 //! - [`get_ble_rx_stats`](Lr2021::get_ble_rx_stats) - Get basic reception statistics
@@ -48,6 +54,52 @@ use crate::constants::*;
 pub use super::cmd::cmd_ble::*;
 use super::{BusyPin, Lr2021, Lr2021Error};
 
+/// CRC-24 polynomial (x^24 + x^10 + x^9 + x^6 + x^4 + x^3 + x + 1) in the LSB-first shift register
+const CRC24_LFSR_MASK: u32 = 0x5A6000;
+
+/// Reverse the 24 LSB of a value
+fn reverse24(v: u32) -> u32 {
+    (v & 0xFF_FFFF).reverse_bits() >> 8
+}
+
+/// BLE CRC-24 of a PDU (header and payload) with the CRC init (0x555555 for advertising channels)
+/// The result is in transmission order: append its 3 LSB in little-endian (`crc.to_le_bytes()[..3]`) after the PDU
+pub fn ble_crc24(init: u32, pdu: &[u8]) -> u32 {
+    let mut state = reverse24(init);
+    for &byte in pdu {
+        let mut cur = byte;
+        for _ in 0..8 {
+            let fb = (state ^ cur as u32) & 1;
+            cur >>= 1;
+            state >>= 1;
+            if fb != 0 {
+                state = (state | (1 << 23)) ^ CRC24_LFSR_MASK;
+            }
+        }
+    }
+    state
+}
+
+/// Whitening initial value for a channel index (0-39), as used by [`set_ble_params`](Lr2021::set_ble_params) (0x53 for channel 37)
+pub fn ble_whit_init(channel: u8) -> u8 {
+    (0x40 | (channel & 0x3F)).reverse_bits() >> 1
+}
+
+/// Apply the BLE whitening (x^7 + x^4 + 1) on a buffer (PDU and CRC) starting from a whitening initial value
+/// Whitening is its own inverse: the same function de-whitens a received buffer
+pub fn ble_whiten(whit_init: u8, data: &mut [u8]) {
+    let mut lfsr = whit_init << 1;
+    for byte in data.iter_mut() {
+        for bit in 0..8 {
+            if lfsr & 0x80 != 0 {
+                lfsr ^= 0x11;
+                *byte ^= 1 << bit;
+            }
+            lfsr <<= 1;
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 /// Sampling period of Angle of Arrival data
@@ -106,6 +158,7 @@ impl<O,SPI, M> Lr2021<O,SPI, M> where
     }
 
     /// Set BLE parameters: Channel type (advertising, Header16/24b), Whitening & CRC init, SyncWord/AccessCode
+    /// The access code follows the BLE convention (LSB first): use [`Syncword::lsb_bits`](crate::syncword::Syncword::lsb_bits) to convert from a [`Syncword`](crate::syncword::Syncword)
     /// Call before `set_ble_modulation` to ensure BLE coded settings are not overwritten
     pub async fn set_ble_params(&mut self, crc_in_fifo: bool, channel_type: ChannelType, whit_init: u8, crc_init: u32, syncword: u32) -> Result<(), Lr2021Error> {
         let req = set_ble_channel_params_cmd(crc_in_fifo, channel_type, whit_init, crc_init, syncword);
@@ -126,7 +179,22 @@ impl<O,SPI, M> Lr2021<O,SPI, M> where
         self.cmd_wr(&req).await
     }
 
+    /// Send a raw PDU (header and payload written as-is) followed by a 24-bit CRC provided by the host
+    /// The CRC is typically computed with [`ble_crc24`] and can be corrupted on purpose for test tooling
+    /// BLE parameters must have been set with `crc_in_fifo` enabled: the packet length includes the 3 CRC bytes
+    pub async fn ble_tx_raw(&mut self, pdu: &[u8], crc: u32) -> Result<(), Lr2021Error> {
+        let len = pdu.len() + 3;
+        if len > u8::MAX as usize {
+            return Err(Lr2021Error::InvalidSize);
+        }
+        self.clear_tx_fifo().await?;
+        self.wr_tx_fifo_from(pdu).await?;
+        self.wr_tx_fifo_from(&crc.to_le_bytes()[..3]).await?;
+        self.set_ble_tx(len as u8).await
+    }
+
     /// Return length of last packet received
+    /// RSSI values are raw: use [`get_packet_status`](Lr2021::get_packet_status) or [`rssi_calibrate`](Lr2021::rssi_calibrate) to apply the RSSI calibration
     pub async fn get_ble_packet_status(&mut self) -> Result<BlePacketStatusRsp, Lr2021Error> {
         let req = get_ble_packet_status_req();
         let mut rsp = BlePacketStatusRsp::new();
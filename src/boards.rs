@@ -0,0 +1,150 @@
+//! # Board support presets
+//!
+//! This module describes the hardware around the LR2021 on known modules and evaluation kits:
+//! DIOs driving the RF switches, TCXO presence and voltage, crystal trimming and maximum TX power
+//! allowed by the PA matching of each path.
+//!
+//! A [`BoardPreset`] is attached to the driver with [`with_board`](Lr2021::with_board) and applied
+//! by [`init_board`](Lr2021::init_board) (reset, clock setup, RF switch configuration and calibration).
+//! The PA limits are then checked by [`set_tx_params`](Lr2021::set_tx_params), which returns InvalidParam
+//! when the power requested exceeds the limit of the PA selected.
+//!
+//! Available presets:
+//!  - [`BOARD_SEMTECH_EVK`]: Semtech LR2021 evaluation kit (Nucleo shield) with a 32MHz crystal
+//!  - [`BOARD_SEMTECH_EVK_TCXO`]: Semtech LR2021 evaluation kit, TCXO variant
+//!  - [`BOARD_GENERIC`]: bare chip with a crystal, no RF switch and no PA limits
+//!
+//! Custom boards can be described with a `const` [`BoardPreset`] literal.
+//!
+//! ## Quick Start
+//!
+//! ```rust,no_run
+//! use lr2021::boards::BOARD_SEMTECH_EVK;
+//!
+//! let mut lr2021 = Lr2021::new(nreset, busy, spi, nss).with_board(BOARD_SEMTECH_EVK);
+//! lr2021.init_board().await.expect("InitBoard");
+//! ```
+//!
+//! ## Available Methods
+//! - [`with_board`](Lr2021::with_board) - Attach a board preset to the driver
+//! - [`init_board`](Lr2021::init_board) - Reset the chip and apply the board preset
+//! - [`board`](Lr2021::board) - Return the board preset attached
+
+use embedded_hal::digital::OutputPin;
+use embedded_hal_async::spi::SpiBus;
+
+use super::radio::PaSel;
+use super::system::{DioNum, TcxoVoltage};
+use super::{BusyPin, Lr2021, Lr2021Error};
+
+/// DIO driving an RF switch: each flag indicates when the DIO is high
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct RfSwitchPin {
+    pub dio: DioNum,
+    pub tx_hf: bool,
+    pub rx_hf: bool,
+    pub tx_lf: bool,
+    pub rx_lf: bool,
+    pub standby: bool,
+}
+
+/// Hardware description of a board
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct BoardPreset {
+    /// Board name
+    pub name: &'static str,
+    /// DIOs driving the RF switches
+    pub rf_switch: &'static [RfSwitchPin],
+    /// TCXO voltage and start time (in 32MHz steps), None when using a crystal
+    pub tcxo: Option<(TcxoVoltage, u32)>,
+    /// Crystal foot capacitor trimming (XTA, XTB), None to keep the chip default
+    pub xosc_trim: Option<(u8, u8)>,
+    /// Maximum TX power (in half-dB) on the LF PA
+    pub pa_lf_max: i8,
+    /// Maximum TX power (in half-dB) on the HF PA
+    pub pa_hf_max: i8,
+}
+
+impl BoardPreset {
+    /// Maximum TX power (in half-dB) allowed for a PA
+    pub fn pa_max(&self, pa: PaSel) -> i8 {
+        match pa {
+            PaSel::LfPa => self.pa_lf_max,
+            PaSel::HfPa => self.pa_hf_max,
+        }
+    }
+}
+
+/// Semtech LR2021 evaluation kit: sub-GHz and 2.4GHz paths selected by DIO5/DIO6, 32MHz crystal
+pub const BOARD_SEMTECH_EVK: BoardPreset = BoardPreset {
+    name: "Semtech LR2021 EVK",
+    rf_switch: &[
+        RfSwitchPin { dio: DioNum::Dio5, tx_hf: false, rx_hf: false, tx_lf: true, rx_lf: true, standby: false },
+        RfSwitchPin { dio: DioNum::Dio6, tx_hf: true, rx_hf: true, tx_lf: false, rx_lf: false, standby: false },
+    ],
+    tcxo: None,
+    xosc_trim: Some((16, 16)),
+    pa_lf_max: 44,
+    pa_hf_max: 24,
+};
+
+/// Semtech LR2021 evaluation kit with a 1.8V TCXO (5ms start time)
+pub const BOARD_SEMTECH_EVK_TCXO: BoardPreset = BoardPreset {
+    name: "Semtech LR2021 EVK (TCXO)",
+    tcxo: Some((TcxoVoltage::Tcxo1v8, 160_000)),
+    xosc_trim: None,
+    ..BOARD_SEMTECH_EVK
+};
+
+/// Bare chip with a crystal: no RF switch, default trimming and full PA range
+pub const BOARD_GENERIC: BoardPreset = BoardPreset {
+    name: "Generic",
+    rf_switch: &[],
+    tcxo: None,
+    xosc_trim: None,
+    pa_lf_max: 44,
+    pa_hf_max: 24,
+};
+
+impl<O,SPI, M> Lr2021<O,SPI, M> where
+    O: OutputPin, SPI: SpiBus<u8>, M: BusyPin
+{
+    /// Attach a board preset to the driver: applied by [`init_board`](Lr2021::init_board)
+    /// and used to check the TX power in `set_tx_params`
+    pub fn with_board(mut self, board: BoardPreset) -> Self {
+        self.cache.board = Some(board);
+        self
+    }
+
+    /// Board preset attached to the driver
+    pub fn board(&self) -> Option<&BoardPreset> {
+        self.cache.board.as_ref()
+    }
+
+    /// Reset the chip and apply the board preset: TCXO (see [`init_with_tcxo`](Lr2021::init_with_tcxo))
+    /// or crystal trimming, calibration and RF switch DIOs.
+    /// Return InvalidParam if no board is attached or if the TCXO does not start
+    pub async fn init_board(&mut self) -> Result<(), Lr2021Error> {
+        let Some(board) = self.cache.board else {
+            return Err(Lr2021Error::InvalidParam);
+        };
+        match board.tcxo {
+            Some((volt, start_time)) => {
+                self.init_with_tcxo(volt, start_time).await?;
+            }
+            None => {
+                self.reset().await?;
+                if let Some((xta, xtb)) = board.xosc_trim {
+                    self.set_xosc_trim(xta, xtb, None).await?;
+                }
+                self.calibrate(true, true, true, true, true, true).await?;
+            }
+        }
+        for pin in board.rf_switch {
+            self.set_dio_rf_switch(pin.dio, pin.tx_hf, pin.rx_hf, pin.tx_lf, pin.rx_lf, pin.standby).await?;
+        }
+        Ok(())
+    }
+}
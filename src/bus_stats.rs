@@ -0,0 +1,91 @@
+//! # SPI bus statistics
+//!
+//! With the `bus-stats` feature, the driver counts every command sent, the bytes transferred on the SPI
+//! and the time spent waiting for the busy pin. This allows to quantify how much time is spent
+//! in the radio driver and which optimization matters (fewer commands, larger transfers, shorter busy waits).
+//!
+//! ## Quick Start
+//!
+//! ```rust,no_run
+//! lr2021.reset_bus_stats();
+//! // ... run the application for a while
+//! let stats = lr2021.bus_stats();
+//! info!("{} commands, {} bytes, busy {}us over {}ms",
+//!     stats.cmds, stats.bytes, stats.busy_time.as_micros(), stats.since.elapsed().as_millis());
+//! ```
+//!
+//! ## Available Methods
+//! - [`bus_stats`](Lr2021::bus_stats) - Return the statistics accumulated since the last reset
+//! - [`reset_bus_stats`](Lr2021::reset_bus_stats) - Reset all counters
+
+use embassy_time::{Duration, Instant};
+use embedded_hal::digital::OutputPin;
+use embedded_hal_async::spi::SpiBus;
+
+use super::{BusyPin, Lr2021};
+
+/// SPI bus statistics
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct BusStats {
+    /// Number of commands issued
+    pub cmds: u32,
+    /// Number of bytes transferred (commands, responses and payloads)
+    pub bytes: u32,
+    /// Time spent waiting for the busy pin
+    pub busy_time: Duration,
+    /// Number of busy timeouts
+    pub busy_timeouts: u32,
+    /// Start of the statistics
+    pub since: Instant,
+}
+
+impl Default for BusStats {
+    fn default() -> Self {
+        Self { cmds: 0, bytes: 0, busy_time: Duration::from_ticks(0), busy_timeouts: 0, since: Instant::now() }
+    }
+}
+
+impl BusStats {
+    /// Count a command and its bytes
+    pub(crate) fn add_cmd(&mut self, len: usize) {
+        self.cmds = self.cmds.saturating_add(1);
+        self.add_bytes(len);
+    }
+
+    /// Count bytes transferred in an existing command (response, payload)
+    pub(crate) fn add_bytes(&mut self, len: usize) {
+        self.bytes = self.bytes.saturating_add(len as u32);
+    }
+
+    /// Accumulate a busy wait
+    pub(crate) fn add_busy(&mut self, start: Instant, timeout: bool) {
+        self.busy_time += start.elapsed();
+        if timeout {
+            self.busy_timeouts = self.busy_timeouts.saturating_add(1);
+        }
+    }
+
+    /// Ratio of time spent waiting on busy since the start of the statistics (0..1)
+    pub fn busy_ratio(&self) -> f32 {
+        let total = self.since.elapsed().as_ticks();
+        if total == 0 {
+            return 0.0;
+        }
+        self.busy_time.as_ticks() as f32 / total as f32
+    }
+}
+
+impl<O,SPI, M> Lr2021<O,SPI, M> where
+    O: OutputPin, SPI: SpiBus<u8>, M: BusyPin
+{
+    /// Return the statistics accumulated since the last reset
+    pub fn bus_stats(&self) -> BusStats {
+        self.bus_stats
+    }
+
+    /// Reset all counters
+    pub fn reset_bus_stats(&mut self) {
+        self.bus_stats = BusStats::default();
+    }
+}
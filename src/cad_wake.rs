@@ -0,0 +1,139 @@
+//! # CAD wake-up transmitter
+//!
+//! Duty-cycled LoRa receivers often sleep most of the time and run a short Channel Activity Detection (CAD)
+//! periodically: a transmission is only noticed if it is on air during one of these CADs.
+//! This module sends a wake-up sequence lasting at least one CAD cycle of the peer, so that the downlink
+//! reliably wakes it up:
+//!  - [`WakeSeq::LongPreamble`]: a single packet with an extended preamble (the peer detects the preamble,
+//!    then stays in RX to receive the packet)
+//!  - [`WakeSeq::PacketTrain`]: a train of short packets sent back-to-back (the peer receives one of them)
+//!
+//! Both sequences are long transmissions: the time on air is checked against a [`DutyCycleBudget`]
+//! before sending and accounted after.
+//!
+//! ## Quick Start
+//!
+//! ```rust,no_run
+//! use lr2021::beacon::DutyCycleBudget;
+//! use lr2021::cad_wake::{CadWake, WakeSeq};
+//!
+//! // Peer runs a 2 symbols CAD every second
+//! let wake = CadWake::new(Duration::from_secs(1), 2, WakeSeq::LongPreamble);
+//! let mut budget = DutyCycleBudget::new(10, Duration::from_secs(3600));
+//! // LoRa modulation and packet parameters configured before
+//! lr2021.cad_wake_send(&wake, b"wake", &mut budget).await.expect("CadWake");
+//! ```
+//!
+//! ## Available Methods
+//! - [`cad_wake_send`](Lr2021::cad_wake_send) - Send a wake-up sequence covering the peer CAD cycle
+//! - [`CadWake::airtime_us`] - Time on air of the wake-up sequence
+
+use embassy_time::Duration;
+use embedded_hal::digital::OutputPin;
+use embedded_hal_async::spi::SpiBus;
+
+use super::beacon::DutyCycleBudget;
+use super::lora::{LoraModulationParams, LoraPacketParams};
+use super::{BusyPin, Lr2021, Lr2021Error};
+
+/// Margin (in symbols) added to the preamble on top of the CAD cycle and CAD duration
+const CAD_WAKE_MARGIN_SYMB: u32 = 4;
+
+/// Kind of wake-up sequence
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum WakeSeq {
+    /// Single packet with a preamble longer than the CAD cycle
+    LongPreamble,
+    /// Packets sent back-to-back during the CAD cycle
+    PacketTrain,
+}
+
+/// Wake-up sequence parameters
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct CadWake {
+    /// CAD period of the peer
+    pub cycle: Duration,
+    /// Duration of the peer CAD (in symbols)
+    pub cad_symbols: u8,
+    /// Kind of sequence
+    pub seq: WakeSeq,
+}
+
+/// Result of a wake-up sequence
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum CadWakeTx {
+    /// Sequence sent with its time on air
+    Sent(Duration),
+    /// Sequence skipped since the duty-cycle budget was exhausted
+    Skipped,
+}
+
+impl CadWake {
+    /// Wake-up sequence for a peer running a CAD of cad_symbols every cycle
+    pub fn new(cycle: Duration, cad_symbols: u8, seq: WakeSeq) -> Self {
+        Self { cycle, cad_symbols, seq }
+    }
+
+    /// Minimum preamble length (in symbols) to be detected by the peer wherever its CAD falls
+    pub fn preamble_len(&self, modulation: &LoraModulationParams) -> u16 {
+        let symb = modulation.symb_time_us().max(1) as u64;
+        let nb = self.cycle.as_micros().div_ceil(symb) + self.cad_symbols as u64 + CAD_WAKE_MARGIN_SYMB as u64;
+        nb.min(u16::MAX as u64) as u16
+    }
+
+    /// Number of packets in a train covering the CAD cycle
+    pub fn nb_packets(&self, modulation: &LoraModulationParams, packet: &LoraPacketParams) -> u32 {
+        let toa = packet.time_on_air_us(modulation).max(1) as u64;
+        let cad = self.cad_symbols as u64 * modulation.symb_time_us() as u64;
+        ((self.cycle.as_micros() + cad).div_ceil(toa) + 1) as u32
+    }
+
+    /// Time on air (in us) of the wake-up sequence for a packet
+    pub fn airtime_us(&self, modulation: &LoraModulationParams, packet: &LoraPacketParams) -> u64 {
+        match self.seq {
+            WakeSeq::LongPreamble => {
+                let packet = LoraPacketParams { pbl_len: self.preamble_len(modulation), ..*packet };
+                packet.time_on_air_us(modulation) as u64
+            }
+            WakeSeq::PacketTrain => self.nb_packets(modulation, packet) as u64 * packet.time_on_air_us(modulation) as u64,
+        }
+    }
+}
+
+impl<O,SPI, M> Lr2021<O,SPI, M> where
+    O: OutputPin, SPI: SpiBus<u8>, M: BusyPin
+{
+    /// Send a wake-up sequence covering the CAD cycle of the peer, if the duty-cycle budget allows it.
+    /// LoRa modulation and packet parameters must have been set (InvalidParam otherwise):
+    /// the packet parameters are restored after a long preamble sequence
+    pub async fn cad_wake_send(&mut self, wake: &CadWake, payload: &[u8], budget: &mut DutyCycleBudget) -> Result<CadWakeTx, Lr2021Error> {
+        let modulation = self.cache.lora_modulation.ok_or(Lr2021Error::InvalidParam)?;
+        let packet = self.cache.lora_packet.ok_or(Lr2021Error::InvalidParam)?;
+        let packet_pld = LoraPacketParams { payload_len: payload.len() as u8, ..packet };
+        let airtime = Duration::from_micros(wake.airtime_us(&modulation, &packet_pld));
+        if !budget.allows(airtime) {
+            #[cfg(feature = "defmt")]{defmt::warn!("Wake-up skipped: duty-cycle budget exhausted");}
+            return Ok(CadWakeTx::Skipped);
+        }
+        match wake.seq {
+            WakeSeq::LongPreamble => {
+                let long = LoraPacketParams { pbl_len: wake.preamble_len(&modulation), ..packet_pld };
+                self.set_lora_packet(&long).await?;
+                let res = self.tx_wait_done(payload, airtime * 2 + Duration::from_millis(10)).await;
+                self.set_lora_packet(&packet).await?;
+                res?;
+            }
+            WakeSeq::PacketTrain => {
+                let toa = Duration::from_micros(packet_pld.time_on_air_us(&modulation) as u64);
+                for _ in 0..wake.nb_packets(&modulation, &packet_pld) {
+                    self.tx_wait_done(payload, toa * 2 + Duration::from_millis(10)).await?;
+                }
+            }
+        }
+        budget.consume(airtime);
+        Ok(CadWakeTx::Sent(airtime))
+    }
+}
@@ -0,0 +1,215 @@
+//! # Packet timing capture
+//!
+//! This module provides a capture mode recording the time of the main events of each received packet
+//! (preamble detection, sync word / header, end of packet), for example to measure the real preamble duration
+//! and the inter-frame spacing of third-party devices when adding a new protocol preset.
+//!
+//! The three chip timestamps are used to get the precise time (HF clock, 32MHz) of the Sync, Header and RxDone events.
+//! The preamble detection is not available as timestamp source: its time is the one at which the
+//! PreambleDetected interrupt is seen by [`capture_timing_poll`](Lr2021::capture_timing_poll),
+//! so its precision depends on the polling rate.
+//!
+//! The capture windows can be started by the host (e.g. with `set_rx`) or by an external event:
+//! with [`CaptureTrigger::External`] a DIO is configured as RX trigger, so that each edge on this DIO
+//! (e.g. from a sensor or a logic analyzer) opens an RX window of a fixed duration,
+//! and [`capture_complete`](Lr2021::capture_complete) waits for the end of the window.
+//!
+//! ## Quick Start
+//!
+//! ```rust,no_run
+//! use lr2021::capture::TimingCapture;
+//!
+//! // Packet type, modulation and packet parameters configured before
+//! let mut capture = TimingCapture::new();
+//! lr2021.capture_timing_start(&mut capture).await.expect("CaptureStart");
+//! lr2021.set_rx_continous().await.expect("SetRx");
+//! loop {
+//!     if let Some(timing) = lr2021.capture_timing_poll(&mut capture).await.expect("CapturePoll") {
+//!         info!("Preamble to sync = {}us, gap = {}us",
+//!             timing.preamble_to_sync().map(|d| d.as_micros()),
+//!             timing.gap.map(|d| d.as_micros()));
+//!         lr2021.clear_rx_fifo().await.expect("ClearFifo");
+//!     }
+//!     Timer::after_micros(100).await;
+//! }
+//! ```
+//!
+//! With an external trigger:
+//!
+//! ```rust,no_run
+//! use lr2021::capture::{CaptureTrigger, TimingCapture};
+//!
+//! // Each rising edge on DIO8 opens a 100ms RX window (3277 LF clock steps)
+//! let mut capture = TimingCapture::new();
+//! lr2021.capture_arm(&mut capture, CaptureTrigger::External(DioNum::Dio8, 3277)).await.expect("CaptureArm");
+//! loop {
+//!     match lr2021.capture_complete(&mut capture, Duration::from_secs(10)).await {
+//!         Ok(Some(timing)) => info!("Packet {}us after sync", timing.sync_to_end().map(|d| d.as_micros())),
+//!         Ok(None) => info!("Window closed without packet"),
+//!         Err(_) => info!("No trigger"),
+//!     }
+//! }
+//! ```
+//!
+//! ## Available Methods
+//! - [`capture_timing_start`](Lr2021::capture_timing_start) - Configure the timestamp sources for the capture
+//! - [`capture_timing_poll`](Lr2021::capture_timing_poll) - Check the interrupts and return the timing of each packet received
+//! - [`capture_arm`](Lr2021::capture_arm) - Configure the capture and the trigger of the RX windows (host or DIO)
+//! - [`capture_complete`](Lr2021::capture_complete) - Wait for a packet or for the end of the RX window
+
+use embassy_time::{Duration, Instant, Timer};
+use embedded_hal::digital::OutputPin;
+use embedded_hal_async::spi::SpiBus;
+
+use super::radio::{TimestampIndex, TimestampSource};
+use super::status::{Intr, IRQ_MASK_PREAMBLE_DETECTED, IRQ_MASK_RX_DONE, IRQ_MASK_TIMEOUT};
+use super::system::{DioFunc, DioNum, PullDrive};
+use super::{BusyPin, Lr2021, Lr2021Error};
+
+/// Timing of the events of a received packet
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct PacketTiming {
+    /// Time at which the preamble detection was seen (precision depends on the polling rate)
+    pub preamble: Option<Instant>,
+    /// Sync word detected (or end of LoRa preamble)
+    pub sync: Option<Instant>,
+    /// Header received (LoRa explicit header)
+    pub header: Option<Instant>,
+    /// End of packet
+    pub rx_done: Instant,
+    /// Time between the end of the previous packet and the start of this one (preamble or sync)
+    pub gap: Option<Duration>,
+}
+
+impl PacketTiming {
+    /// Duration between the preamble detection and the sync word
+    pub fn preamble_to_sync(&self) -> Option<Duration> {
+        self.sync?.checked_duration_since(self.preamble?)
+    }
+
+    /// Duration between the sync word and the end of packet
+    pub fn sync_to_end(&self) -> Option<Duration> {
+        self.rx_done.checked_duration_since(self.sync?)
+    }
+
+    /// Start of the packet: preamble detection if seen, sync otherwise
+    pub fn start(&self) -> Option<Instant> {
+        self.preamble.or(self.sync)
+    }
+}
+
+/// Start of the capture RX windows
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum CaptureTrigger {
+    /// RX started by the host (`set_rx`, `set_rx_continous`, ...)
+    Host,
+    /// RX window opened by an edge on a DIO, lasting the RX timeout given (in LF clock steps, 0 for continuous RX)
+    External(DioNum, u32),
+}
+
+/// State of the timing capture
+#[derive(Debug, Default, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct TimingCapture {
+    /// Preamble detection seen for the packet being received
+    preamble: Option<Instant>,
+    /// End of the previous packet
+    last_end: Option<Instant>,
+}
+
+impl TimingCapture {
+    /// Create a timing capture
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Forget the previous packet (the next packet will have no gap)
+    pub fn restart(&mut self) {
+        *self = Self::default();
+    }
+}
+
+impl<O,SPI, M> Lr2021<O,SPI, M> where
+    O: OutputPin, SPI: SpiBus<u8>, M: BusyPin
+{
+    /// Configure the three timestamp sources for the capture (Ts0: Sync, Ts1: Header, Ts2: RxDone)
+    /// and clear the interrupts monitored. The timestamps must not be used for anything else during the capture
+    pub async fn capture_timing_start(&mut self, capture: &mut TimingCapture) -> Result<(), Lr2021Error> {
+        self.set_timestamp_source(TimestampIndex::Ts0, TimestampSource::Sync).await?;
+        self.set_timestamp_source(TimestampIndex::Ts1, TimestampSource::Header).await?;
+        self.set_timestamp_source(TimestampIndex::Ts2, TimestampSource::RxDone).await?;
+        self.clear_irqs(Intr::new(IRQ_MASK_PREAMBLE_DETECTED | IRQ_MASK_RX_DONE)).await?;
+        capture.restart();
+        Ok(())
+    }
+
+    /// Check the interrupts: record the preamble detection and return the timing of the packet on RxDone.
+    /// The PreambleDetected and RxDone interrupts are cleared, the packet is left in the RX FIFO.
+    /// Events whose timestamp was not updated for this packet (e.g. no header) are reported as None
+    pub async fn capture_timing_poll(&mut self, capture: &mut TimingCapture) -> Result<Option<PacketTiming>, Lr2021Error> {
+        let (_, intr) = self.get_status().await?;
+        self.capture_timing_process(capture, intr).await
+    }
+
+    /// Configure the capture (see [`capture_timing_start`](Lr2021::capture_timing_start)) and the trigger of the RX windows.
+    /// With an external trigger, the DIO is configured as RX trigger (pulled down in sleep) with the RX window duration
+    /// as default RX timeout: the chip must then stay in standby, waiting for the DIO edge.
+    /// Return InvalidParam if the DIO is already assigned to another function
+    pub async fn capture_arm(&mut self, capture: &mut TimingCapture, trigger: CaptureTrigger) -> Result<(), Lr2021Error> {
+        self.capture_timing_start(capture).await?;
+        self.clear_irqs(Intr::new(IRQ_MASK_TIMEOUT)).await?;
+        if let CaptureTrigger::External(dio, rx_timeout) = trigger {
+            self.set_default_timeout(0, rx_timeout).await?;
+            self.set_dio_function(dio, DioFunc::RxTrigger, PullDrive::PullDown).await?;
+        }
+        Ok(())
+    }
+
+    /// Wait for a packet or for the end of the current RX window (RX timeout).
+    /// Return the timing of the packet, None if the window closed without packet,
+    /// and Timeout if nothing happened before the timeout (e.g. no trigger)
+    pub async fn capture_complete(&mut self, capture: &mut TimingCapture, timeout: Duration) -> Result<Option<PacketTiming>, Lr2021Error> {
+        let start = Instant::now();
+        loop {
+            let (_, intr) = self.get_status().await?;
+            if let Some(timing) = self.capture_timing_process(capture, intr).await? {
+                return Ok(Some(timing));
+            }
+            if intr.timeout() {
+                self.clear_irqs(Intr::new(IRQ_MASK_TIMEOUT)).await?;
+                capture.preamble = None;
+                return Ok(None);
+            }
+            if start.elapsed() >= timeout {
+                return Err(Lr2021Error::Timeout);
+            }
+            Timer::after_micros(100).await;
+        }
+    }
+
+    /// Record the preamble detection and return the timing of the packet on RxDone
+    async fn capture_timing_process(&mut self, capture: &mut TimingCapture, intr: Intr) -> Result<Option<PacketTiming>, Lr2021Error> {
+        if intr.preamble_detected() {
+            capture.preamble = Some(Instant::now());
+            self.clear_irqs(Intr::new(IRQ_MASK_PREAMBLE_DETECTED)).await?;
+        }
+        if !intr.rx_done() {
+            return Ok(None);
+        }
+        let rx_done = self.get_timestamp_instant(TimestampIndex::Ts2).await?;
+        let sync = self.get_timestamp_instant(TimestampIndex::Ts0).await?;
+        let header = self.get_timestamp_instant(TimestampIndex::Ts1).await?;
+        self.clear_irqs(Intr::new(IRQ_MASK_RX_DONE)).await?;
+        // Timestamps older than the previous packet were not updated
+        let is_new = |t: Instant| t <= rx_done && capture.last_end.is_none_or(|end| t > end);
+        let sync = Some(sync).filter(|t| is_new(*t));
+        let header = Some(header).filter(|t| is_new(*t));
+        let preamble = capture.preamble.take().filter(|t| is_new(*t));
+        let start = preamble.or(sync);
+        let gap = capture.last_end.zip(start).and_then(|(end, start)| start.checked_duration_since(end));
+        capture.last_end = Some(rx_done);
+        Ok(Some(PacketTiming { preamble, sync, header, rx_done, gap }))
+    }
+}
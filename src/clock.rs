@@ -0,0 +1,175 @@
+//! # Clock source management
+//!
+//! Each standby mode is a trade-off between current consumption and latency:
+//!  - Standby RC: lowest current, but the crystal must start before any radio operation and timings are based on the RC oscillator
+//!  - Standby XOSC: crystal running, accurate timing and faster start of radio operations
+//!  - FS: PLL locked, immediate start of TX/RX
+//!
+//! The [`ClockManager`] keeps track of the mode set by the driver and moves the chip to the minimal mode
+//! sufficient for an operation ([`clock_require`](Lr2021::clock_require)), then back to a lower mode once done,
+//! depending on the [`ClockPolicy`] ([`clock_release`](Lr2021::clock_release) and [`clock_idle`](Lr2021::clock_idle)).
+//! For a scheduled operation, [`clock_prewarm`](Lr2021::clock_prewarm) starts the crystal just in time.
+//!
+//! ## Quick Start
+//!
+//! ```rust,no_run
+//! use lr2021::clock::{ClockManager, ClockNeed, ClockPolicy};
+//!
+//! // Keep the crystal running up to 50ms after the last operation
+//! let mut clk = ClockManager::new(ClockPolicy::Balanced(Duration::from_millis(50)));
+//!
+//! // Scheduled TX: start the crystal just before the TX time, then lock the PLL
+//! lr2021.clock_prewarm(&mut clk, tx_time).await.expect("Prewarm");
+//! lr2021.clock_require(&mut clk, ClockNeed::Fs).await.expect("Require");
+//! lr2021.tx_payload(&payload, 0).await.expect("Tx");
+//! // Wait for TxDone ...
+//! lr2021.clock_release(&mut clk).await.expect("Release");
+//!
+//! // Periodically: go back to Standby RC once idle for too long
+//! lr2021.clock_idle(&mut clk).await.expect("Idle");
+//! ```
+//!
+//! ## Available Methods
+//! - [`clock_require`](Lr2021::clock_require) - Move the chip to the minimal mode sufficient for an operation
+//! - [`clock_release`](Lr2021::clock_release) - Signal the end of an operation and lower the mode according to the policy
+//! - [`clock_idle`](Lr2021::clock_idle) - Move back to Standby RC once the idle timeout has elapsed
+//! - [`clock_prewarm`](Lr2021::clock_prewarm) - Start the crystal just in time for a scheduled operation
+
+use embassy_time::{Duration, Instant, Timer};
+use embedded_hal::digital::OutputPin;
+use embedded_hal_async::spi::SpiBus;
+
+use super::system::ChipMode;
+use super::{BusyPin, Lr2021, Lr2021Error};
+
+/// Clock requirement of an operation, from the lowest to the highest
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ClockNeed {
+    /// RC oscillator is enough (configuration commands)
+    Rc,
+    /// Crystal oscillator running (accurate timing, measurements)
+    Xosc,
+    /// PLL locked (immediate TX/RX)
+    Fs,
+}
+
+impl ClockNeed {
+    fn chip_mode(&self) -> ChipMode {
+        match self {
+            ClockNeed::Rc => ChipMode::StandbyRc,
+            ClockNeed::Xosc => ChipMode::StandbyXosc,
+            ClockNeed::Fs => ChipMode::Fs,
+        }
+    }
+}
+
+/// Mode selected at the end of an operation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ClockPolicy {
+    /// Always go back to Standby RC
+    PowerSaving,
+    /// Keep the crystal running, and go back to Standby RC after an idle timeout
+    Balanced(Duration),
+    /// Keep the PLL locked
+    LowLatency,
+}
+
+/// Clock manager: track the mode set and apply the policy
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ClockManager {
+    /// Policy applied at the end of an operation
+    policy: ClockPolicy,
+    /// Mode set by the manager (None if unknown)
+    current: Option<ClockNeed>,
+    /// End of the last operation
+    last_use: Instant,
+    /// Crystal start-up time
+    xosc_startup: Duration,
+}
+
+impl ClockManager {
+    /// Create a clock manager with a policy, and a default crystal start-up time of 1ms
+    pub fn new(policy: ClockPolicy) -> Self {
+        Self { policy, current: None, last_use: Instant::now(), xosc_startup: Duration::from_millis(1) }
+    }
+
+    /// Set the crystal start-up time used for pre-warming (TCXO can require a few ms)
+    pub fn with_xosc_startup(self, xosc_startup: Duration) -> Self {
+        Self { xosc_startup, ..self }
+    }
+
+    /// Change the policy
+    pub fn set_policy(&mut self, policy: ClockPolicy) {
+        self.policy = policy;
+    }
+
+    /// Mode set by the manager (None if unknown)
+    pub fn current(&self) -> Option<ClockNeed> {
+        self.current
+    }
+
+    /// Forget the mode set: must be called when the mode is changed outside of the manager (sleep, reset, ...)
+    pub fn invalidate(&mut self) {
+        self.current = None;
+    }
+}
+
+impl<O,SPI, M> Lr2021<O,SPI, M> where
+    O: OutputPin, SPI: SpiBus<u8>, M: BusyPin
+{
+    /// Set the mode of the manager
+    async fn clock_set(&mut self, mgr: &mut ClockManager, need: ClockNeed) -> Result<(), Lr2021Error> {
+        self.set_chip_mode(need.chip_mode()).await?;
+        mgr.current = Some(need);
+        Ok(())
+    }
+
+    /// Move the chip to the minimal mode sufficient for an operation
+    /// Nothing is done if the current mode is already sufficient
+    pub async fn clock_require(&mut self, mgr: &mut ClockManager, need: ClockNeed) -> Result<(), Lr2021Error> {
+        if mgr.current.is_none_or(|c| c < need) {
+            self.clock_set(mgr, need).await?;
+        }
+        Ok(())
+    }
+
+    /// Signal the end of an operation and set the mode according to the policy
+    pub async fn clock_release(&mut self, mgr: &mut ClockManager) -> Result<(), Lr2021Error> {
+        let need = match mgr.policy {
+            ClockPolicy::PowerSaving => ClockNeed::Rc,
+            ClockPolicy::Balanced(_) => ClockNeed::Xosc,
+            ClockPolicy::LowLatency => ClockNeed::Fs,
+        };
+        self.clock_set(mgr, need).await?;
+        mgr.last_use = Instant::now();
+        Ok(())
+    }
+
+    /// Move back to Standby RC if the idle timeout of the balanced policy has elapsed
+    /// Return true if the mode was changed
+    pub async fn clock_idle(&mut self, mgr: &mut ClockManager) -> Result<bool, Lr2021Error> {
+        let ClockPolicy::Balanced(timeout) = mgr.policy else {
+            return Ok(false);
+        };
+        if mgr.current.is_some_and(|c| c > ClockNeed::Rc) && mgr.last_use.elapsed() >= timeout {
+            self.clock_set(mgr, ClockNeed::Rc).await?;
+            return Ok(true);
+        }
+        Ok(false)
+    }
+
+    /// Start the crystal just in time for an operation scheduled at a given instant
+    /// Return immediately if the crystal is already running
+    pub async fn clock_prewarm(&mut self, mgr: &mut ClockManager, at: Instant) -> Result<(), Lr2021Error> {
+        if mgr.current.is_some_and(|c| c >= ClockNeed::Xosc) {
+            return Ok(());
+        }
+        if let Some(start) = at.checked_sub(mgr.xosc_startup) {
+            Timer::at(start).await;
+        }
+        self.clock_set(mgr, ClockNeed::Xosc).await
+    }
+}
@@ -13,6 +13,7 @@ pub mod cmd_wisun;
 pub mod cmd_wmbus;
 pub mod cmd_zigbee;
 pub mod cmd_zwave;
+pub mod opcode;
 
 // Re-export Bandwidth as it is shared amongst multple commands
 pub use cmd_fsk::{RxBw, PulseShape};
\ No newline at end of file
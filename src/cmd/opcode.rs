@@ -0,0 +1,574 @@
+// Opcode catalog
+
+/// Opcode of all documented commands
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Opcode {
+    /// Configure the modulation parameters for BLE packets
+    SetBleModulationParams,
+    /// Sets the BLE channel/packet dependent parameters
+    SetBleChannelParams,
+    /// Configure PDU length to transmit and send a BLE packet
+    SetBleTx,
+    /// Gets the status of the last received packet
+    GetBlePacketStatus,
+    /// Gets the internal statistics of the received packets
+    GetBleRxStats,
+    /// Sets PDU length for TX
+    SetBleTxPduLen,
+    /// Sets the modulation parameters for BPSK packets
+    SetBpskModulationParams,
+    /// Sets the packet parameters for BPSK packets
+    SetBpskPacketParams,
+    /// Sets the RF frequency for subsequent radio operations
+    SetRfFrequency,
+    /// Sets the RX path and boost configuration
+    SetRxPath,
+    /// Chooses which PA to use and sets the parameters of the PA
+    SetPaConfig,
+    /// Sets the TX power and ramp time of the PA
+    SetTxParams,
+    /// Sets the RSSI calibration table for the requested RX path(s)
+    SetRssiCalibration,
+    /// Configures the fallback mode after a RX or TX operation (after transmission/reception or timeout)
+    SetRxTxFallbackMode,
+    /// Sets the current packet type
+    SetPacketType,
+    /// Returns the current packet type of the radio
+    GetPacketType,
+    /// Defines if the RX timeout should be stopped on Syncword/Header detection or on Preamble detection
+    SetStopTimeout,
+    /// Reset Rx Statistics
+    ResetRxStats,
+    /// Gets the instantaneous RSSI value during reception of the packet
+    GetRssiInst,
+    /// Sets the device into RX mode
+    SetRx,
+    /// Sets the device into TX mode
+    SetTx,
+    /// Sets the device into TX test mode
+    SetTxTestMode,
+    /// Select which PA to use
+    SelPA,
+    /// Start reception every cycle_time and listen for rx_max_time
+    SetRxDutyCycle,
+    /// Activate or deactivate the auto TX/auto RX mode
+    SetAutoRxTx,
+    /// Get the length of the last received packet
+    GetRxPktLength,
+    /// Set the global value of the power offset
+    SetPowerOffset,
+    /// Sets the default RX and TX timeouts to be used for DIO RX/TX triggers, or if the timeout parameters are not sent in the SetRx and SetTx commands
+    SetDefaultRxTxTimeout,
+    /// Sets the source event for time-stamping different radio events
+    SetTimestampSource,
+    /// Get the delay in HF clk tick between the event and the SPI NSS falling edge of the request
+    GetTimestampValue,
+    /// Set the radio into RX mode for Clear Channel Assessment measurements
+    SetCca,
+    /// Get the RSSI statistics for the CCA measurement
+    GetCcaResult,
+    /// Set the manual gain of the AGC
+    SetAgcGainManual,
+    /// Set the CAD parameters for the Channel Activity Detect for packet types other than LoRa
+    SetCadParams,
+    /// Set device into RX CAD mode (not LoRa)
+    SetCad,
+    /// Sets the modulation parameters for FLRC packets
+    SetFlrcModulationParams,
+    /// Sets the packet parameters for FLRC packets
+    SetFlrcPacketParams,
+    /// Gets the internal statistics of the received packets
+    GetFlrcRxStats,
+    /// Gets the status of the last received packet
+    GetFlrcPacketStatus,
+    /// Sets the syncword for FLRC packets
+    SetFlrcSyncword,
+    /// Sets the modulation parameters for FSK packets
+    SetFskModulationParams,
+    /// Sets the packet parameters for FSK packets
+    SetFskPacketParams,
+    /// Configure the whitening params for FSK packets, SX126x/LR11xx or SX128x compatible
+    SetFskWhiteningParams,
+    /// Configure the CRC params for FSK packets
+    SetFskCrcParams,
+    /// Configure the syncword for FSK packets
+    SetFskSyncWord,
+    /// Configure the addresses for filtering for FSK packets
+    SetFskAddress,
+    /// Get FSK RX stats
+    GetFskRxStats,
+    /// Gets the status of the last received packet
+    GetFskPacketStatus,
+    /// Sets the LoRa modulation parameters
+    SetLoraModulationParams,
+    /// Sets the packet parameters for the LoRa packets
+    SetLoraPacketParams,
+    /// Configure LoRa modem to search for a detect for N symbols
+    SetLoraSynchTimeout,
+    /// Sets the LoRa syncword
+    SetLoraSyncword,
+    /// Configures the LoRa phase modulation demodulation during preamble feature
+    ConfigLoraPreambleModulation,
+    /// Configure LoRa CAD mode parameters
+    SetLoraCadParams,
+    /// Set device into RX CAD mode (LoRa)
+    SetLoraCad,
+    /// Gets the internal statistics of the received packets
+    GetLoraRxStats,
+    /// Gets the status of the last received packet
+    GetLoraPacketStatus,
+    /// Sets the address for LoRa RX address filtering
+    SetLoraAddress,
+    /// Extended version of the SetLoraSyncword command to set all 10bits of the syncword
+    SetLoraSyncwordExtended,
+    /// Extended version of the SetLoraSideDetSyncword command to set all 10bits of the syncwords
+    SetLoraSideDetSyncwordExtended,
+    /// Configures the LoRa Tx synchronization using dio
+    SetLoraTxSync,
+    /// Configures the LoRa Tx synchronization using dio
+    SetLoraBlanking,
+    /// Prepare LR-FHSS packet in the FIFO without sending it
+    LrFhssBuildFrame,
+    /// Sets the LR-FHSS syncword
+    SetLrFhssSyncWord,
+    /// Writes the LR-FHSS hopping table
+    WriteLrFhssHoppingTable,
+    /// Sets the OOK modulation parameters
+    SetOokModulationParams,
+    /// Sets the OOK packet parameters
+    SetOokPacketParams,
+    /// Sets the OOK CRC parameters (same as for FSK)
+    SetOokCrcParams,
+    /// Sets the OOK syncword
+    SetOokSyncWord,
+    /// Sets the OOK address (same as for FSK)
+    SetOokAddress,
+    /// Gets the internal statistics of the received packets
+    GetOokRxStats,
+    /// Gets the status of the last received OOK packet
+    GetOokPacketStatus,
+    /// Configures the OOK detection
+    SetOokDetector,
+    /// Configure the whitening for OOK packets
+    SetOokWhiteningParams,
+    /// Sets the ranging Id for this device (used by the responder)
+    SetRangingAddr,
+    /// Sets the ranging Id for the requests (used by the initiator)
+    SetRangingReqAddr,
+    /// Gets the ranging result (For Master or spy only)
+    GetRangingResult,
+    /// Sets the Tx->Rx delay for the ranging calibration
+    SetRangingTxRxDelay,
+    /// Sets the ranging specific parameters
+    SetRangingParams,
+    /// Gets the ranging counters for ranging exchanges
+    GetRangingStats,
+    /// Writes a block of 32-bit words in register/memory space starting at a specific address
+    WriteRegMem32,
+    /// Read-Modify-Writes data at given register/memory address
+    WriteRegMemMask32,
+    /// Reads a block of 32-bit words in register/memory space starting at a specific address
+    ReadRegMem32,
+    /// Returns status of device
+    GetStatus,
+    /// Returns version of firmware
+    GetVersion,
+    /// Returns current pending errors that occurred since the last ClearErrors() call, or the startup of the circuit
+    GetErrors,
+    /// Clears all error flags in the status
+    ClearErrors,
+    /// Configure the functionality of the freely configurable DIOs, as well as the pull-up/down configuration for sleep modes
+    SetDioFunction,
+    /// Configure the value of the specified DIO pin when configured as RF switch with the SetDioFunction command
+    SetDioRfSwitchConfig,
+    /// Clears specific fifo irq flags
+    ClearFifoIrqFlags,
+    /// Configure IRQs which assert DIO pin
+    SetDioIrqConfig,
+    /// Clear pending irqs
+    ClearIrq,
+    /// Read out the pending IRQs and clear all pending IRQs
+    GetAndClearIrq,
+    /// Configures the used Low Frequency clock
+    ConfigLfClock,
+    /// Configure LF and HF clock configuration for DIO output
+    ConfigClkOutputs,
+    /// Configure which FIFO status flags generate FIFO IRQs as well as the threshold levels
+    ConfigFifoIrq,
+    /// Returns all FIFO flags that have been triggered since the last flags clear
+    GetFifoIrqFlags,
+    /// Read out the RX FIFO level in bytes
+    GetRxFifoLevel,
+    /// Read out the TX FIFO level in bytes
+    GetTxFifoLevel,
+    /// Clears the RX FIFO
+    ClearRxFifo,
+    /// Clears the TX FIFO
+    ClearTxFifo,
+    /// Configures the SIMO mode and ramp times
+    SetRegMode,
+    /// Calibrates the requested blocks defined by the blocks_to_calibrate parameter
+    Calibrate,
+    /// Launches all Front End calibrations (ADC Offset, PPF, Image) at the given frequencies
+    CalibFe,
+    /// Measure and return current VBAT value
+    GetVBat,
+    /// Measure and return temperature value
+    GetTemp,
+    /// Return a 32-bit random number
+    GetRandomNumber,
+    /// Put device in sleep mode
+    SetSleep,
+    /// Put device in standby mode (XOSC or RC)
+    SetStandby,
+    /// Put device in Frequency Synthesis Mode
+    SetFs,
+    /// Specify address of additional register to save for sleep modes with retention
+    SetAdditionalRegToRetain,
+    /// Read out and clear FIFO flags that generated FIFO IRQs
+    GetAndClearFifoIrqFlags,
+    /// Enable/disable end of life detection and setup trigger voltage
+    SetEolConfig,
+    /// Configure the chip to use a TCXO
+    SetTcxoMode,
+    /// Configure XOSC foot capacitor trim
+    SetXoscCpTrim,
+    /// Configure temperature compensation mode for TX and RX
+    SetTempCompCfg,
+    /// Configure NTC parameters
+    SetNtcParams,
+    /// Configure the wisun mode (1a, 1b, 2a, 2b, 3, 4a, 4b, 5)
+    SetWisunMode,
+    /// Configure the wisun packet parameters
+    SetWisunPacketParams,
+    /// Gets the status of the last received packet
+    GetWisunPacketStatus,
+    /// Sets length of frame for TX for normal packets, or header value for mode_switch packets
+    SetWisunPacketLen,
+    /// Get the Rx statistics for WiSUN packets
+    GetWisunRxStats,
+    /// Sets the parameters for Zigbee packets
+    SetZigbeeParams,
+    /// Gets the status of the last received packet
+    GetZigbeePacketStatus,
+    /// Sets the zigbee packet length without the need to call the full SetZigbeeParams command (slower to run)
+    SetZigbeePacketLen,
+    /// Sets the Zigbee addresses for filtering in Rx
+    SetZigbeeAddress,
+    /// Gets the internal statistics of the received packets
+    GetZigbeeRxStats,
+    /// Sets the parameters for Z-Wave packets
+    SetZwaveParams,
+    /// Sets the HomeID address to use as a filter in Rx
+    SetZwaveHomeIdFiltering,
+    /// Gets the status of the last received packet
+    GetZwavePacketStatus,
+    /// Settings for filtering incoming beam frames in Rx
+    SetZwaveBeamFiltering,
+    /// This command is used to enter the Zwave RX scan mode
+    SetZwaveScan,
+    /// Gets the internal statistics of the received packets
+    GetZwaveRxStats,
+    /// Configure the wm-bus mode according to EN13757-4 2019
+    SetWmbusParams,
+    /// Get the Rx statistics for wmbus packets
+    GetWmbusRxStats,
+    /// Get Rx packet status informations for wmbus packets
+    GetWmbusPacketStatus,
+    /// Set the WM-Bus address for RX filtering (A-field)
+    SetWmbusAddress,
+}
+
+impl Opcode {
+    /// Opcode value
+    pub const fn value(&self) -> u16 {
+        match self {
+            Opcode::SetBleModulationParams => 0x0260,
+            Opcode::SetBleChannelParams => 0x0261,
+            Opcode::SetBleTx => 0x0262,
+            Opcode::GetBlePacketStatus => 0x0265,
+            Opcode::GetBleRxStats => 0x0264,
+            Opcode::SetBleTxPduLen => 0x0266,
+            Opcode::SetBpskModulationParams => 0x0250,
+            Opcode::SetBpskPacketParams => 0x0251,
+            Opcode::SetRfFrequency => 0x0200,
+            Opcode::SetRxPath => 0x0201,
+            Opcode::SetPaConfig => 0x0202,
+            Opcode::SetTxParams => 0x0203,
+            Opcode::SetRssiCalibration => 0x0205,
+            Opcode::SetRxTxFallbackMode => 0x0206,
+            Opcode::SetPacketType => 0x0207,
+            Opcode::GetPacketType => 0x0208,
+            Opcode::SetStopTimeout => 0x0209,
+            Opcode::ResetRxStats => 0x020A,
+            Opcode::GetRssiInst => 0x020B,
+            Opcode::SetRx => 0x020C,
+            Opcode::SetTx => 0x020D,
+            Opcode::SetTxTestMode => 0x020E,
+            Opcode::SelPA => 0x020F,
+            Opcode::SetRxDutyCycle => 0x0210,
+            Opcode::SetAutoRxTx => 0x0211,
+            Opcode::GetRxPktLength => 0x0212,
+            Opcode::SetPowerOffset => 0x0214,
+            Opcode::SetDefaultRxTxTimeout => 0x0215,
+            Opcode::SetTimestampSource => 0x0216,
+            Opcode::GetTimestampValue => 0x0217,
+            Opcode::SetCca => 0x0218,
+            Opcode::GetCcaResult => 0x0219,
+            Opcode::SetAgcGainManual => 0x021A,
+            Opcode::SetCadParams => 0x021B,
+            Opcode::SetCad => 0x021C,
+            Opcode::SetFlrcModulationParams => 0x0248,
+            Opcode::SetFlrcPacketParams => 0x0249,
+            Opcode::GetFlrcRxStats => 0x024A,
+            Opcode::GetFlrcPacketStatus => 0x024B,
+            Opcode::SetFlrcSyncword => 0x024C,
+            Opcode::SetFskModulationParams => 0x0240,
+            Opcode::SetFskPacketParams => 0x0241,
+            Opcode::SetFskWhiteningParams => 0x0242,
+            Opcode::SetFskCrcParams => 0x0243,
+            Opcode::SetFskSyncWord => 0x0244,
+            Opcode::SetFskAddress => 0x0245,
+            Opcode::GetFskRxStats => 0x0246,
+            Opcode::GetFskPacketStatus => 0x0247,
+            Opcode::SetLoraModulationParams => 0x0220,
+            Opcode::SetLoraPacketParams => 0x0221,
+            Opcode::SetLoraSynchTimeout => 0x0222,
+            Opcode::SetLoraSyncword => 0x0223,
+            Opcode::ConfigLoraPreambleModulation => 0x0226,
+            Opcode::SetLoraCadParams => 0x0227,
+            Opcode::SetLoraCad => 0x0228,
+            Opcode::GetLoraRxStats => 0x0229,
+            Opcode::GetLoraPacketStatus => 0x022A,
+            Opcode::SetLoraAddress => 0x022B,
+            Opcode::SetLoraSyncwordExtended => 0x022D,
+            Opcode::SetLoraSideDetSyncwordExtended => 0x022E,
+            Opcode::SetLoraTxSync => 0x021D,
+            Opcode::SetLoraBlanking => 0x021D,
+            Opcode::LrFhssBuildFrame => 0x0256,
+            Opcode::SetLrFhssSyncWord => 0x0257,
+            Opcode::WriteLrFhssHoppingTable => 0x0259,
+            Opcode::SetOokModulationParams => 0x0281,
+            Opcode::SetOokPacketParams => 0x0282,
+            Opcode::SetOokCrcParams => 0x0283,
+            Opcode::SetOokSyncWord => 0x0284,
+            Opcode::SetOokAddress => 0x0285,
+            Opcode::GetOokRxStats => 0x0286,
+            Opcode::GetOokPacketStatus => 0x0287,
+            Opcode::SetOokDetector => 0x0288,
+            Opcode::SetOokWhiteningParams => 0x0289,
+            Opcode::SetRangingAddr => 0x0278,
+            Opcode::SetRangingReqAddr => 0x0279,
+            Opcode::GetRangingResult => 0x027A,
+            Opcode::SetRangingTxRxDelay => 0x027B,
+            Opcode::SetRangingParams => 0x027C,
+            Opcode::GetRangingStats => 0x027D,
+            Opcode::WriteRegMem32 => 0x0104,
+            Opcode::WriteRegMemMask32 => 0x0105,
+            Opcode::ReadRegMem32 => 0x0106,
+            Opcode::GetStatus => 0x0100,
+            Opcode::GetVersion => 0x0101,
+            Opcode::GetErrors => 0x0110,
+            Opcode::ClearErrors => 0x0111,
+            Opcode::SetDioFunction => 0x0112,
+            Opcode::SetDioRfSwitchConfig => 0x0113,
+            Opcode::ClearFifoIrqFlags => 0x0114,
+            Opcode::SetDioIrqConfig => 0x0115,
+            Opcode::ClearIrq => 0x0116,
+            Opcode::GetAndClearIrq => 0x0117,
+            Opcode::ConfigLfClock => 0x0118,
+            Opcode::ConfigClkOutputs => 0x0119,
+            Opcode::ConfigFifoIrq => 0x011A,
+            Opcode::GetFifoIrqFlags => 0x011B,
+            Opcode::GetRxFifoLevel => 0x011C,
+            Opcode::GetTxFifoLevel => 0x011D,
+            Opcode::ClearRxFifo => 0x011E,
+            Opcode::ClearTxFifo => 0x011F,
+            Opcode::SetRegMode => 0x0121,
+            Opcode::Calibrate => 0x0122,
+            Opcode::CalibFe => 0x0123,
+            Opcode::GetVBat => 0x0124,
+            Opcode::GetTemp => 0x0125,
+            Opcode::GetRandomNumber => 0x0126,
+            Opcode::SetSleep => 0x0127,
+            Opcode::SetStandby => 0x0128,
+            Opcode::SetFs => 0x0129,
+            Opcode::SetAdditionalRegToRetain => 0x012A,
+            Opcode::GetAndClearFifoIrqFlags => 0x012E,
+            Opcode::SetEolConfig => 0x0130,
+            Opcode::SetTcxoMode => 0x0120,
+            Opcode::SetXoscCpTrim => 0x0131,
+            Opcode::SetTempCompCfg => 0x0132,
+            Opcode::SetNtcParams => 0x0133,
+            Opcode::SetWisunMode => 0x0270,
+            Opcode::SetWisunPacketParams => 0x0271,
+            Opcode::GetWisunPacketStatus => 0x0273,
+            Opcode::SetWisunPacketLen => 0x0274,
+            Opcode::GetWisunRxStats => 0x026C,
+            Opcode::SetZigbeeParams => 0x029F,
+            Opcode::GetZigbeePacketStatus => 0x02A1,
+            Opcode::SetZigbeePacketLen => 0x02A2,
+            Opcode::SetZigbeeAddress => 0x02A3,
+            Opcode::GetZigbeeRxStats => 0x02A0,
+            Opcode::SetZwaveParams => 0x0297,
+            Opcode::SetZwaveHomeIdFiltering => 0x0298,
+            Opcode::GetZwavePacketStatus => 0x029A,
+            Opcode::SetZwaveBeamFiltering => 0x029B,
+            Opcode::SetZwaveScan => 0x029D,
+            Opcode::GetZwaveRxStats => 0x0299,
+            Opcode::SetWmbusParams => 0x026A,
+            Opcode::GetWmbusRxStats => 0x026C,
+            Opcode::GetWmbusPacketStatus => 0x026D,
+            Opcode::SetWmbusAddress => 0x026E,
+        }
+    }
+
+    /// Opcode as sent on the SPI (MSB first)
+    pub const fn to_bytes(&self) -> [u8; 2] {
+        self.value().to_be_bytes()
+    }
+
+    /// Command category (name of the module defining the command API)
+    pub const fn category(&self) -> &'static str {
+        match self {
+            Opcode::SetBleModulationParams => "ble",
+            Opcode::SetBleChannelParams => "ble",
+            Opcode::SetBleTx => "ble",
+            Opcode::GetBlePacketStatus => "ble",
+            Opcode::GetBleRxStats => "ble",
+            Opcode::SetBleTxPduLen => "ble",
+            Opcode::SetBpskModulationParams => "bpsk",
+            Opcode::SetBpskPacketParams => "bpsk",
+            Opcode::SetRfFrequency => "common",
+            Opcode::SetRxPath => "common",
+            Opcode::SetPaConfig => "common",
+            Opcode::SetTxParams => "common",
+            Opcode::SetRssiCalibration => "common",
+            Opcode::SetRxTxFallbackMode => "common",
+            Opcode::SetPacketType => "common",
+            Opcode::GetPacketType => "common",
+            Opcode::SetStopTimeout => "common",
+            Opcode::ResetRxStats => "common",
+            Opcode::GetRssiInst => "common",
+            Opcode::SetRx => "common",
+            Opcode::SetTx => "common",
+            Opcode::SetTxTestMode => "common",
+            Opcode::SelPA => "common",
+            Opcode::SetRxDutyCycle => "common",
+            Opcode::SetAutoRxTx => "common",
+            Opcode::GetRxPktLength => "common",
+            Opcode::SetPowerOffset => "common",
+            Opcode::SetDefaultRxTxTimeout => "common",
+            Opcode::SetTimestampSource => "common",
+            Opcode::GetTimestampValue => "common",
+            Opcode::SetCca => "common",
+            Opcode::GetCcaResult => "common",
+            Opcode::SetAgcGainManual => "common",
+            Opcode::SetCadParams => "common",
+            Opcode::SetCad => "common",
+            Opcode::SetFlrcModulationParams => "flrc",
+            Opcode::SetFlrcPacketParams => "flrc",
+            Opcode::GetFlrcRxStats => "flrc",
+            Opcode::GetFlrcPacketStatus => "flrc",
+            Opcode::SetFlrcSyncword => "flrc",
+            Opcode::SetFskModulationParams => "fsk",
+            Opcode::SetFskPacketParams => "fsk",
+            Opcode::SetFskWhiteningParams => "fsk",
+            Opcode::SetFskCrcParams => "fsk",
+            Opcode::SetFskSyncWord => "fsk",
+            Opcode::SetFskAddress => "fsk",
+            Opcode::GetFskRxStats => "fsk",
+            Opcode::GetFskPacketStatus => "fsk",
+            Opcode::SetLoraModulationParams => "lora",
+            Opcode::SetLoraPacketParams => "lora",
+            Opcode::SetLoraSynchTimeout => "lora",
+            Opcode::SetLoraSyncword => "lora",
+            Opcode::ConfigLoraPreambleModulation => "lora",
+            Opcode::SetLoraCadParams => "lora",
+            Opcode::SetLoraCad => "lora",
+            Opcode::GetLoraRxStats => "lora",
+            Opcode::GetLoraPacketStatus => "lora",
+            Opcode::SetLoraAddress => "lora",
+            Opcode::SetLoraSyncwordExtended => "lora",
+            Opcode::SetLoraSideDetSyncwordExtended => "lora",
+            Opcode::SetLoraTxSync => "lora",
+            Opcode::SetLoraBlanking => "lora",
+            Opcode::LrFhssBuildFrame => "lrfhss",
+            Opcode::SetLrFhssSyncWord => "lrfhss",
+            Opcode::WriteLrFhssHoppingTable => "lrfhss",
+            Opcode::SetOokModulationParams => "ook",
+            Opcode::SetOokPacketParams => "ook",
+            Opcode::SetOokCrcParams => "ook",
+            Opcode::SetOokSyncWord => "ook",
+            Opcode::SetOokAddress => "ook",
+            Opcode::GetOokRxStats => "ook",
+            Opcode::GetOokPacketStatus => "ook",
+            Opcode::SetOokDetector => "ook",
+            Opcode::SetOokWhiteningParams => "ook",
+            Opcode::SetRangingAddr => "ranging",
+            Opcode::SetRangingReqAddr => "ranging",
+            Opcode::GetRangingResult => "ranging",
+            Opcode::SetRangingTxRxDelay => "ranging",
+            Opcode::SetRangingParams => "ranging",
+            Opcode::GetRangingStats => "ranging",
+            Opcode::WriteRegMem32 => "regmem",
+            Opcode::WriteRegMemMask32 => "regmem",
+            Opcode::ReadRegMem32 => "regmem",
+            Opcode::GetStatus => "system",
+            Opcode::GetVersion => "system",
+            Opcode::GetErrors => "system",
+            Opcode::ClearErrors => "system",
+            Opcode::SetDioFunction => "system",
+            Opcode::SetDioRfSwitchConfig => "system",
+            Opcode::ClearFifoIrqFlags => "system",
+            Opcode::SetDioIrqConfig => "system",
+            Opcode::ClearIrq => "system",
+            Opcode::GetAndClearIrq => "system",
+            Opcode::ConfigLfClock => "system",
+            Opcode::ConfigClkOutputs => "system",
+            Opcode::ConfigFifoIrq => "system",
+            Opcode::GetFifoIrqFlags => "system",
+            Opcode::GetRxFifoLevel => "system",
+            Opcode::GetTxFifoLevel => "system",
+            Opcode::ClearRxFifo => "system",
+            Opcode::ClearTxFifo => "system",
+            Opcode::SetRegMode => "system",
+            Opcode::Calibrate => "system",
+            Opcode::CalibFe => "system",
+            Opcode::GetVBat => "system",
+            Opcode::GetTemp => "system",
+            Opcode::GetRandomNumber => "system",
+            Opcode::SetSleep => "system",
+            Opcode::SetStandby => "system",
+            Opcode::SetFs => "system",
+            Opcode::SetAdditionalRegToRetain => "system",
+            Opcode::GetAndClearFifoIrqFlags => "system",
+            Opcode::SetEolConfig => "system",
+            Opcode::SetTcxoMode => "system",
+            Opcode::SetXoscCpTrim => "system",
+            Opcode::SetTempCompCfg => "system",
+            Opcode::SetNtcParams => "system",
+            Opcode::SetWisunMode => "wisun",
+            Opcode::SetWisunPacketParams => "wisun",
+            Opcode::GetWisunPacketStatus => "wisun",
+            Opcode::SetWisunPacketLen => "wisun",
+            Opcode::GetWisunRxStats => "wisun",
+            Opcode::SetZigbeeParams => "zigbee",
+            Opcode::GetZigbeePacketStatus => "zigbee",
+            Opcode::SetZigbeePacketLen => "zigbee",
+            Opcode::SetZigbeeAddress => "zigbee",
+            Opcode::GetZigbeeRxStats => "zigbee",
+            Opcode::SetZwaveParams => "zwave",
+            Opcode::SetZwaveHomeIdFiltering => "zwave",
+            Opcode::GetZwavePacketStatus => "zwave",
+            Opcode::SetZwaveBeamFiltering => "zwave",
+            Opcode::SetZwaveScan => "zwave",
+            Opcode::GetZwaveRxStats => "zwave",
+            Opcode::SetWmbusParams => "wmbus",
+            Opcode::GetWmbusRxStats => "wmbus",
+            Opcode::GetWmbusPacketStatus => "wmbus",
+            Opcode::SetWmbusAddress => "wmbus",
+        }
+    }
+}
@@ -0,0 +1,500 @@
+//! # Radio configuration export/import
+//!
+//! This module defines a [`RadioConfig`] gathering the full radio configuration (protocol, modulation,
+//! packet parameters, syncword, RF channel, RX path, PA and TX power) with a compact binary representation,
+//! so that devices can receive configuration updates over the air or store them in flash/EEPROM.
+//!
+//! The current configuration is rebuilt from the settings cached by the driver with
+//! [`current_config`](Lr2021::current_config) and applied with [`apply_config`](Lr2021::apply_config):
+//! applying an exported configuration on another device gives back the same configuration.
+//!
+//! Supported protocols are LoRa and FLRC.
+//!
+//! Protocols alternating configurations between packets (e.g. an ACK on a different data rate) can describe
+//! the change with a [`ConfigDelta`] applied with [`apply_delta`](Lr2021::apply_delta): only the settings
+//! differing from the cached ones are sent, in a fixed order, and the number of commands and the reconfiguration
+//! latency are reported. The commands are sent one by one (the chip has no command batching): the delta is
+//! validated before the first command, and the previous settings are restored if a command fails on the way.
+//!
+//! ## Binary format
+//!
+//! All multi-byte fields are little-endian and enumerations use the chip encoding:
+//!
+//! | Offset | Size | Field |
+//! |--------|------|-------|
+//! | 0 | 1 | Format version ([`CONFIG_VERSION`]) |
+//! | 1 | 1 | Protocol (0: LoRa, 1: FLRC) |
+//! | 2 | 4 | RF channel (Hz) |
+//! | 6 | 1 | RX path (bit 0) and RX boost (bits 3:1) |
+//! | 7 | 3 | PA: LF mode (0xFF for HF PA), duty-cycle, slices |
+//! | 10 | 1 | TX power (half-dB) |
+//! | 11 | 1 | Ramp time (0xFF for automatic) |
+//! | 12 | 10 | LoRa: SF, BW, CR, LDRO, preamble (2B), payload length, header type, CRC/IQ flags, syncword |
+//! | 12 | 16 | FLRC: bitrate, CR, pulse shape, AGC preamble, syncword length/TX/match, format, CRC, payload length (2B), syncword length (bits) and value (4B) |
+//!
+//! ## Quick Start
+//!
+//! ```rust,no_run
+//! use lr2021::config::{RadioConfig, CONFIG_MAX_LEN};
+//!
+//! // Export the configuration to store it in flash
+//! let mut buf = [0u8; CONFIG_MAX_LEN];
+//! let len = lr2021.current_config().expect("CurrentConfig").to_bytes(&mut buf).expect("ToBytes");
+//! flash.write(&buf[..len]);
+//!
+//! // Apply a configuration received over the air
+//! let cfg = RadioConfig::from_bytes(&payload).expect("FromBytes");
+//! lr2021.apply_config(&cfg).await.expect("ApplyConfig");
+//! ```
+//!
+//! ## Available Methods
+//! - [`current_config`](Lr2021::current_config) - Rebuild the configuration from the settings cached by the driver
+//! - [`apply_config`](Lr2021::apply_config) - Apply a full configuration
+//! - [`apply_delta`](Lr2021::apply_delta) - Apply a configuration change between packets with the minimal set of commands
+//! - [`RadioConfig::to_bytes`] / [`RadioConfig::from_bytes`] - Binary representation
+
+use embassy_time::{Duration, Instant};
+use embedded_hal::digital::OutputPin;
+use embedded_hal_async::spi::SpiBus;
+
+use super::failover::LinkPa;
+use super::flrc::{AgcPblLen, Crc, FlrcBitrate, FlrcCr, FlrcPacketParams, PktFormat, SwLen, SwMatch, SwTx};
+use super::lora::{HeaderType, Ldro, LoraBw, LoraCr, LoraModulationParams, LoraPacketParams, Sf};
+use super::radio::{PaLfMode, PaSel, PacketType, RampSel, RampTime, RxBoost, RxPath};
+use super::syncword::Syncword;
+use super::{BusyPin, Lr2021, Lr2021Error, PulseShape};
+
+/// Version of the binary format
+pub const CONFIG_VERSION: u8 = 1;
+
+/// Length of the common part of the binary format
+const CONFIG_COMMON_LEN: usize = 12;
+
+/// Maximum length of the binary representation of a configuration
+pub const CONFIG_MAX_LEN: usize = CONFIG_COMMON_LEN + 16;
+
+/// Decode a byte into one of the enumeration variants listed (using the chip encoding)
+macro_rules! decode {
+    ($v:expr, $($var:path),+ $(,)?) => {
+        match $v {
+            $(x if x == $var as u8 => Ok($var),)+
+            _ => Err(Lr2021Error::InvalidParam),
+        }
+    };
+}
+
+/// Protocol part of the configuration
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ProtocolConfig {
+    /// LoRa modulation, packet parameters and syncword (1B notation)
+    Lora(LoraModulationParams, LoraPacketParams, u8),
+    /// FLRC bitrate, coding rate, pulse shape, packet parameters and syncword 1
+    Flrc(FlrcBitrate, FlrcCr, PulseShape, FlrcPacketParams, Syncword),
+}
+
+/// Full radio configuration
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct RadioConfig {
+    /// RF channel (in Hz)
+    pub rf: u32,
+    /// RX path
+    pub rx_path: RxPath,
+    /// RX boost
+    pub rx_boost: RxBoost,
+    /// PA configuration
+    pub pa: LinkPa,
+    /// TX power (in half-dB)
+    pub tx_power: i8,
+    /// PA ramp time selection
+    pub ramp_time: RampSel,
+    /// Protocol configuration
+    pub protocol: ProtocolConfig,
+}
+
+impl RadioConfig {
+    /// Packet type of the configuration
+    pub fn packet_type(&self) -> PacketType {
+        match self.protocol {
+            ProtocolConfig::Lora(..) => PacketType::Lora,
+            ProtocolConfig::Flrc(..) => PacketType::Flrc,
+        }
+    }
+
+    /// Write the binary representation in a buffer and return its length
+    /// Return InvalidSize if the buffer is too small (see [`CONFIG_MAX_LEN`])
+    pub fn to_bytes(&self, buf: &mut [u8]) -> Result<usize, Lr2021Error> {
+        let len = CONFIG_COMMON_LEN + match self.protocol {
+            ProtocolConfig::Lora(..) => 10,
+            ProtocolConfig::Flrc(..) => 16,
+        };
+        let Some(buf) = buf.get_mut(..len) else {
+            return Err(Lr2021Error::InvalidSize);
+        };
+        buf[0] = CONFIG_VERSION;
+        buf[1] = match self.protocol {
+            ProtocolConfig::Lora(..) => 0,
+            ProtocolConfig::Flrc(..) => 1,
+        };
+        buf[2..6].copy_from_slice(&self.rf.to_le_bytes());
+        buf[6] = self.rx_path as u8 | ((self.rx_boost as u8) << 1);
+        buf[7..10].copy_from_slice(&match self.pa {
+            LinkPa::Lf(mode, duty_cycle, slices) => [mode as u8, duty_cycle, slices],
+            LinkPa::Hf => [0xFF, 0, 0],
+        });
+        buf[10] = self.tx_power as u8;
+        buf[11] = match self.ramp_time {
+            RampSel::Auto => 0xFF,
+            RampSel::Fixed(ramp_time) => ramp_time as u8,
+        };
+        let p = &mut buf[CONFIG_COMMON_LEN..];
+        match self.protocol {
+            ProtocolConfig::Lora(modulation, packet, syncword) => {
+                p[0] = modulation.sf as u8;
+                p[1] = modulation.bw as u8;
+                p[2] = modulation.cr as u8;
+                p[3] = modulation.ldro as u8;
+                p[4..6].copy_from_slice(&packet.pbl_len.to_le_bytes());
+                p[6] = packet.payload_len;
+                p[7] = packet.header_type as u8;
+                p[8] = packet.crc_en as u8 | ((packet.invert_iq as u8) << 1);
+                p[9] = syncword;
+            }
+            ProtocolConfig::Flrc(bitrate, cr, pulse_shape, packet, syncword) => {
+                p[0] = bitrate as u8;
+                p[1] = cr as u8;
+                p[2] = pulse_shape as u8;
+                p[3] = packet.agc_pbl_len as u8;
+                p[4] = packet.sw_len as u8;
+                p[5] = packet.sw_tx as u8;
+                p[6] = packet.sw_match as u8;
+                p[7] = packet.hdr_format as u8;
+                p[8] = packet.crc as u8;
+                p[9..11].copy_from_slice(&packet.pld_len.to_le_bytes());
+                p[11] = syncword.nb_bits();
+                p[12..16].copy_from_slice(&(syncword.msb_bits() as u32).to_le_bytes());
+            }
+        }
+        Ok(len)
+    }
+
+    /// Parse a binary representation
+    /// Return InvalidSize if the data is truncated and InvalidParam on an unknown version or an invalid field
+    pub fn from_bytes(data: &[u8]) -> Result<Self, Lr2021Error> {
+        if data.len() < CONFIG_COMMON_LEN {
+            return Err(Lr2021Error::InvalidSize);
+        }
+        if data[0] != CONFIG_VERSION {
+            return Err(Lr2021Error::InvalidParam);
+        }
+        let rf = u32::from_le_bytes([data[2], data[3], data[4], data[5]]);
+        let rx_path = decode!(data[6] & 1, RxPath::LfPath, RxPath::HfPath)?;
+        let rx_boost = decode!(data[6] >> 1, RxBoost::Off, RxBoost::B1, RxBoost::B2, RxBoost::B3, RxBoost::B4, RxBoost::B5, RxBoost::B6, RxBoost::Max)?;
+        let pa = match data[7] {
+            0xFF => LinkPa::Hf,
+            mode => LinkPa::Lf(decode!(mode, PaLfMode::LfPaFsm, PaLfMode::LfPaFdm, PaLfMode::LfPaHsmRfo1, PaLfMode::LfPaHsmRfo2)?, data[8], data[9]),
+        };
+        let tx_power = data[10] as i8;
+        let ramp_time = match data[11] {
+            0xFF => RampSel::Auto,
+            ramp => RampSel::Fixed(decode!(ramp,
+                RampTime::Ramp2u, RampTime::Ramp4u, RampTime::Ramp8u, RampTime::Ramp16u,
+                RampTime::Ramp32u, RampTime::Ramp48u, RampTime::Ramp64u, RampTime::Ramp80u,
+                RampTime::Ramp96u, RampTime::Ramp112u, RampTime::Ramp128u, RampTime::Ramp144u,
+                RampTime::Ramp160u, RampTime::Ramp176u, RampTime::Ramp192u, RampTime::Ramp208u,
+            )?),
+        };
+        let p = &data[CONFIG_COMMON_LEN..];
+        let protocol = match data[1] {
+            0 => {
+                if p.len() < 10 {
+                    return Err(Lr2021Error::InvalidSize);
+                }
+                let modulation = LoraModulationParams {
+                    sf: decode!(p[0], Sf::Sf5, Sf::Sf6, Sf::Sf7, Sf::Sf8, Sf::Sf9, Sf::Sf10, Sf::Sf11, Sf::Sf12)?,
+                    bw: decode!(p[1],
+                        LoraBw::Bw7, LoraBw::Bw15, LoraBw::Bw31, LoraBw::Bw62, LoraBw::Bw125, LoraBw::Bw250,
+                        LoraBw::Bw500, LoraBw::Bw1000, LoraBw::Bw10, LoraBw::Bw20, LoraBw::Bw41, LoraBw::Bw83,
+                        LoraBw::Bw101, LoraBw::Bw203, LoraBw::Bw406, LoraBw::Bw812,
+                    )?,
+                    cr: decode!(p[2],
+                        LoraCr::NoCoding, LoraCr::Cr1Ham45Si, LoraCr::Cr2Ham23Si, LoraCr::Cr3Ham47Si, LoraCr::Cr4Ham12Si,
+                        LoraCr::Cr5Ham45Li, LoraCr::Cr6Ham23Li, LoraCr::Cr7Ham12Li, LoraCr::Cr8Cc23, LoraCr::Cr9Cc12,
+                    )?,
+                    ldro: decode!(p[3], Ldro::Off, Ldro::On)?,
+                };
+                let packet = LoraPacketParams {
+                    pbl_len: u16::from_le_bytes([p[4], p[5]]),
+                    payload_len: p[6],
+                    header_type: decode!(p[7], HeaderType::Explicit, HeaderType::Implicit)?,
+                    crc_en: p[8] & 1 != 0,
+                    invert_iq: p[8] & 2 != 0,
+                };
+                ProtocolConfig::Lora(modulation, packet, p[9])
+            }
+            1 => {
+                if p.len() < 16 {
+                    return Err(Lr2021Error::InvalidSize);
+                }
+                let bitrate = decode!(p[0],
+                    FlrcBitrate::Br2600, FlrcBitrate::Br2080, FlrcBitrate::Br1300, FlrcBitrate::Br1040,
+                    FlrcBitrate::Br0650, FlrcBitrate::Br0520, FlrcBitrate::Br0325, FlrcBitrate::Br0260,
+                )?;
+                let cr = decode!(p[1], FlrcCr::Cr12, FlrcCr::Cr34, FlrcCr::None, FlrcCr::Cr23)?;
+                let pulse_shape = decode!(p[2],
+                    PulseShape::None, PulseShape::Custom, PulseShape::Bt0p3, PulseShape::Bt0p5, PulseShape::Bt0p7,
+                    PulseShape::Bt1p0, PulseShape::Bt2p0, PulseShape::Rc0p3, PulseShape::Rc0p5, PulseShape::Rc0p7,
+                    PulseShape::Rc1p0, PulseShape::Rrc0p3, PulseShape::Rrc0p4, PulseShape::Rrc0p5, PulseShape::Rrc0p7,
+                    PulseShape::Rrc1p0,
+                )?;
+                let packet = FlrcPacketParams {
+                    agc_pbl_len: decode!(p[3],
+                        AgcPblLen::Len4Bits, AgcPblLen::Len8Bits, AgcPblLen::Len12Bits, AgcPblLen::Len16Bits,
+                        AgcPblLen::Len20Bits, AgcPblLen::Len24Bits, AgcPblLen::Len28Bits, AgcPblLen::Len32Bits,
+                    )?,
+                    sw_len: decode!(p[4], SwLen::SwNone, SwLen::Sw16b, SwLen::Sw32b)?,
+                    sw_tx: decode!(p[5], SwTx::SwNone, SwTx::Sw1, SwTx::Sw2, SwTx::Sw3)?,
+                    sw_match: decode!(p[6],
+                        SwMatch::MatchNone, SwMatch::Match1, SwMatch::Match2, SwMatch::Match12,
+                        SwMatch::Match3, SwMatch::Match13, SwMatch::Match23, SwMatch::Match123,
+                    )?,
+                    hdr_format: decode!(p[7], PktFormat::Dynamic, PktFormat::Fixed)?,
+                    crc: decode!(p[8], Crc::CrcOff, Crc::Crc16, Crc::Crc24, Crc::Crc32)?,
+                    pld_len: u16::from_le_bytes([p[9], p[10]]),
+                };
+                let nb_bits = p[11];
+                if nb_bits != 16 && nb_bits != 32 {
+                    return Err(Lr2021Error::InvalidParam);
+                }
+                let sw = u32::from_le_bytes([p[12], p[13], p[14], p[15]]);
+                ProtocolConfig::Flrc(bitrate, cr, pulse_shape, packet, Syncword::from_msb_bits(sw as u64, nb_bits))
+            }
+            _ => return Err(Lr2021Error::InvalidParam),
+        };
+        Ok(Self { rf, rx_path, rx_boost, pa, tx_power, ramp_time, protocol })
+    }
+}
+
+/// Configuration change applied between packets: settings left to None are kept
+#[derive(Debug, Default, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ConfigDelta {
+    /// RF channel (in Hz)
+    pub rf: Option<u32>,
+    /// Protocol configuration (packet type, modulation, packet parameters and syncword)
+    pub protocol: Option<ProtocolConfig>,
+    /// TX power (in half-dB) and PA ramp time selection
+    pub tx_params: Option<(i8, RampSel)>,
+}
+
+impl ConfigDelta {
+    /// Change of the RF channel, protocol and TX power of a full configuration (RX path and PA are kept)
+    pub fn from_config(cfg: &RadioConfig) -> Self {
+        Self { rf: Some(cfg.rf), protocol: Some(cfg.protocol), tx_params: Some((cfg.tx_power, cfg.ramp_time)) }
+    }
+
+    /// Change the RF channel
+    pub fn with_rf(self, rf: u32) -> Self {
+        Self { rf: Some(rf), ..self }
+    }
+
+    /// Change the protocol configuration
+    pub fn with_protocol(self, protocol: ProtocolConfig) -> Self {
+        Self { protocol: Some(protocol), ..self }
+    }
+
+    /// Change the TX power and ramp time selection
+    pub fn with_tx_params(self, tx_power: i8, ramp_time: RampSel) -> Self {
+        Self { tx_params: Some((tx_power, ramp_time)), ..self }
+    }
+}
+
+/// Report of a configuration change
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ReconfigReport {
+    /// Number of commands sent, including the ones triggered internally (e.g. LoRa detection frequency range)
+    pub nb_cmds: u8,
+    /// Time taken by the reconfiguration
+    pub latency: Duration,
+}
+
+impl<O,SPI, M> Lr2021<O,SPI, M> where
+    O: OutputPin, SPI: SpiBus<u8>, M: BusyPin
+{
+    /// Rebuild the full radio configuration from the settings cached by the driver
+    /// Return InvalidParam if the packet type is not supported or if a setting was never configured
+    /// (including a LoRa syncword set with the 2B notation)
+    pub fn current_config(&self) -> Result<RadioConfig, Lr2021Error> {
+        let c = &self.cache;
+        let protocol = match c.packet_type {
+            Some(PacketType::Lora) => ProtocolConfig::Lora(
+                c.lora_modulation.ok_or(Lr2021Error::InvalidParam)?,
+                c.lora_packet.ok_or(Lr2021Error::InvalidParam)?,
+                c.lora_syncword.ok_or(Lr2021Error::InvalidParam)?,
+            ),
+            Some(PacketType::Flrc) => {
+                let (bitrate, cr, pulse_shape) = c.flrc_modulation.ok_or(Lr2021Error::InvalidParam)?;
+                ProtocolConfig::Flrc(bitrate, cr, pulse_shape,
+                    c.flrc_packet.ok_or(Lr2021Error::InvalidParam)?,
+                    c.flrc_syncword.ok_or(Lr2021Error::InvalidParam)?,
+                )
+            }
+            _ => return Err(Lr2021Error::InvalidParam),
+        };
+        let pa = match c.pa {
+            Some(PaSel::LfPa) => {
+                let (mode, duty_cycle, slices) = c.pa_lf.ok_or(Lr2021Error::InvalidParam)?;
+                LinkPa::Lf(mode, duty_cycle, slices)
+            }
+            Some(PaSel::HfPa) => LinkPa::Hf,
+            None => return Err(Lr2021Error::InvalidParam),
+        };
+        let (tx_power, ramp_time) = c.tx_params.ok_or(Lr2021Error::InvalidParam)?;
+        Ok(RadioConfig {
+            rf: c.rf.ok_or(Lr2021Error::InvalidParam)?,
+            rx_path: c.rx_path.ok_or(Lr2021Error::InvalidParam)?,
+            rx_boost: c.rx_boost.unwrap_or(RxBoost::Off),
+            pa,
+            tx_power,
+            ramp_time,
+            protocol,
+        })
+    }
+
+    /// Apply a full radio configuration: packet type, RF channel, RX path, PA, protocol parameters and TX power
+    /// The chip must be in standby
+    pub async fn apply_config(&mut self, cfg: &RadioConfig) -> Result<(), Lr2021Error> {
+        self.set_packet_type(cfg.packet_type()).await?;
+        self.set_rf(cfg.rf).await?;
+        self.set_rx_path(cfg.rx_path, cfg.rx_boost).await?;
+        match cfg.pa {
+            LinkPa::Lf(mode, duty_cycle, slices) => self.set_pa_lf(mode, duty_cycle, slices).await?,
+            LinkPa::Hf => self.set_pa_hf().await?,
+        }
+        match &cfg.protocol {
+            ProtocolConfig::Lora(modulation, packet, syncword) => {
+                self.set_lora_modulation(modulation).await?;
+                self.set_lora_packet(packet).await?;
+                self.set_lora_syncword(*syncword).await?;
+            }
+            ProtocolConfig::Flrc(bitrate, cr, pulse_shape, packet, syncword) => {
+                self.set_flrc_modulation(*bitrate, *cr, *pulse_shape).await?;
+                self.set_flrc_packet(packet).await?;
+                self.set_flrc_syncword(1, *syncword).await?;
+            }
+        }
+        // After the modulation for RampSel::Auto
+        self.set_tx_params_sel(cfg.tx_power, cfg.ramp_time).await
+    }
+
+    /// Settings currently cached as a configuration change (used to restore them)
+    fn cached_delta(&self) -> ConfigDelta {
+        let c = &self.cache;
+        let protocol = match c.packet_type {
+            Some(PacketType::Lora) => match (c.lora_modulation, c.lora_packet, c.lora_syncword) {
+                (Some(modulation), Some(packet), Some(syncword)) => Some(ProtocolConfig::Lora(modulation, packet, syncword)),
+                _ => None,
+            },
+            Some(PacketType::Flrc) => match (c.flrc_modulation, c.flrc_packet, c.flrc_syncword) {
+                (Some((bitrate, cr, pulse_shape)), Some(packet), Some(syncword)) => Some(ProtocolConfig::Flrc(bitrate, cr, pulse_shape, packet, syncword)),
+                _ => None,
+            },
+            _ => None,
+        };
+        ConfigDelta { rf: c.rf, protocol, tx_params: c.tx_params }
+    }
+
+    /// Check a configuration change against the rules enforced by the setters, so that it is rejected before any command
+    fn validate_delta(&self, delta: &ConfigDelta) -> Result<(), Lr2021Error> {
+        match delta.protocol {
+            Some(ProtocolConfig::Lora(modulation, packet, _)) => {
+                modulation.validate()?;
+                modulation.validate_packet(&packet)?;
+            }
+            Some(ProtocolConfig::Flrc(.., packet, syncword)) if packet.pld_len > 511 || !matches!(syncword.nb_bits(), 16 | 32) => {
+                return Err(Lr2021Error::InvalidParam);
+            }
+            _ => {}
+        }
+        if let Some((tx_power, _)) = delta.tx_params
+            && let (Some(board), Some(pa)) = (self.cache.board, self.cache.pa)
+            && tx_power > board.pa_max(pa) {
+            return Err(Lr2021Error::InvalidParam);
+        }
+        Ok(())
+    }
+
+    /// Send the settings of a configuration change differing from the cached ones
+    async fn apply_delta_cmds(&mut self, delta: &ConfigDelta) -> Result<(), Lr2021Error> {
+        let mut modulation_changed = false;
+        let new_type = delta.protocol.map(|p| match p {
+            ProtocolConfig::Lora(..) => PacketType::Lora,
+            ProtocolConfig::Flrc(..) => PacketType::Flrc,
+        });
+        let type_changed = new_type.is_some() && new_type != self.cache.packet_type;
+        if let Some(packet_type) = new_type && type_changed {
+            self.set_packet_type(packet_type).await?;
+        }
+        if let Some(rf) = delta.rf && self.cache.rf != Some(rf) {
+            self.set_rf(rf).await?;
+        }
+        match delta.protocol {
+            Some(ProtocolConfig::Lora(modulation, packet, syncword)) => {
+                if type_changed || self.cache.lora_modulation != Some(modulation) {
+                    self.set_lora_modulation(&modulation).await?;
+                    modulation_changed = true;
+                }
+                if type_changed || self.cache.lora_packet != Some(packet) {
+                    self.set_lora_packet(&packet).await?;
+                }
+                if type_changed || self.cache.lora_syncword != Some(syncword) {
+                    self.set_lora_syncword(syncword).await?;
+                }
+            }
+            Some(ProtocolConfig::Flrc(bitrate, cr, pulse_shape, packet, syncword)) => {
+                if type_changed || self.cache.flrc_modulation != Some((bitrate, cr, pulse_shape)) {
+                    self.set_flrc_modulation(bitrate, cr, pulse_shape).await?;
+                    modulation_changed = true;
+                }
+                if type_changed || self.cache.flrc_packet != Some(packet) {
+                    self.set_flrc_packet(&packet).await?;
+                }
+                if type_changed || self.cache.flrc_syncword != Some(syncword) {
+                    self.set_flrc_syncword(1, syncword).await?;
+                }
+            }
+            None => {}
+        }
+        let tx_params = delta.tx_params.or(self.cache.tx_params);
+        if let Some((tx_power, ramp)) = tx_params {
+            let auto_ramp = modulation_changed && ramp == RampSel::Auto;
+            if auto_ramp || self.cache.tx_params != Some((tx_power, ramp)) {
+                self.set_tx_params_sel(tx_power, ramp).await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Apply a configuration change between packets, sending only the settings differing from the cached ones,
+    /// in the order required by the chip: packet type, RF channel, modulation, packet parameters, syncword, TX power.
+    /// A packet type change resends all the protocol parameters, and a modulation change with an automatic
+    /// ramp time ([`RampSel::Auto`]) resends the TX parameters.
+    ///
+    /// The commands are sent one after the other: the change is validated first (InvalidParam before any command),
+    /// and if a command fails the settings cached before the change are sent again (best effort) before returning the error.
+    /// The chip must be in standby. Return the number of commands sent and the reconfiguration latency
+    pub async fn apply_delta(&mut self, delta: &ConfigDelta) -> Result<ReconfigReport, Lr2021Error> {
+        self.validate_delta(delta)?;
+        let prev = self.cached_delta();
+        let start = Instant::now();
+        let cmd_start = self.cmd_count;
+        if let Err(err) = self.apply_delta_cmds(delta).await {
+            let _ = self.apply_delta_cmds(&prev).await;
+            return Err(err);
+        }
+        let nb_cmds = u8::try_from(self.cmd_count.wrapping_sub(cmd_start)).unwrap_or(u8::MAX);
+        Ok(ReconfigReport { nb_cmds, latency: start.elapsed() })
+    }
+}
@@ -0,0 +1,222 @@
+//! # CRC bytes in the RX FIFO
+//!
+//! When [`force_crc_out`](crate::Lr2021::force_crc_out) is enabled, the CRC received over the air is appended
+//! to the payload in the RX FIFO. Its size, byte order and coverage depend on the protocol:
+//!  - LoRa: 2 bytes, LSB first, computed on the payload only
+//!  - FSK/OOK: 1 to 4 bytes, MSB first, optionally inverted, computed on the length header (not in the FIFO) and the payload
+//!  - BLE: 3 bytes, LSB first, computed on the PDU
+//!  - Z-Wave: 1 byte checksum (R1/R2) or 2 bytes CRC-16 MSB first (R3/LR), computed on the whole frame
+//!
+//! [`CrcFormat`] describes these layouts so that diagnostic tools can split a raw frame into payload and CRC
+//! and verify the CRC in software, without misinterpreting the bytes.
+//! All functions are pure computations: no command is sent to the chip.
+//!
+//! ## Quick Start
+//!
+//! ```rust,no_run
+//! use lr2021::crc_out::CrcFormat;
+//! use lr2021::fsk::{Crc, FskPktFormat};
+//!
+//! lr2021.force_crc_out().await.expect("ForceCrcOut");
+//! // ... receive a packet in buf (payload followed by the CRC)
+//! let fmt = CrcFormat::fsk(Crc::Crc2ByteInv, FskPktFormat::Variable8bit, 0x1021, 0x1D0F).expect("Format");
+//! if let Some(check) = fmt.verify(&buf[..len]) {
+//!     info!("CRC {:06x} (expected {:06x}): {}", check.received, check.computed, check.is_valid());
+//! }
+//! ```
+//!
+//! ## Available Methods
+//! - [`CrcFormat::lora`], [`CrcFormat::fsk`], [`CrcFormat::ook`], [`CrcFormat::ble`], [`CrcFormat::zwave`], [`CrcFormat::msb`] - Layout per protocol
+//! - [`CrcFormat::split`] - Split a frame into payload and CRC value
+//! - [`CrcFormat::compute`] - Compute the CRC expected for a payload
+//! - [`CrcFormat::verify`] - Split a frame and compare its CRC with the one computed
+
+use super::ble::ble_crc24;
+use super::fsk::{Crc, FskPktFormat};
+use super::ook::{Crc as OokCrc, PktFormat as OokPktFormat};
+use super::testvec::{crc_msb, lora_payload_crc};
+use super::zwave::ZwaveMode;
+use super::zwave::frame::{zwave_checksum, zwave_crc16};
+
+/// Order of the CRC bytes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum CrcOrder {
+    /// Most significant byte first
+    MsbFirst,
+    /// Least significant byte first
+    LsbFirst,
+}
+
+/// Length header covered by the CRC but not present in the FIFO
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum CrcHeader {
+    /// No header (fixed length)
+    None,
+    /// 1 byte length
+    Len8,
+    /// 2 bytes length, MSB first
+    Len16,
+}
+
+/// CRC algorithm
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum CrcAlgo {
+    /// Generic MSB-first CRC on the header and payload, optionally inverted (FSK, OOK, ...)
+    Msb { poly: u32, init: u32, inv: bool, header: CrcHeader },
+    /// LoRa payload CRC
+    Lora,
+    /// BLE CRC-24 with its init value
+    Ble { init: u32 },
+    /// Z-Wave R1/R2 8 bits checksum
+    ZwaveChecksum,
+    /// Z-Wave R3/LR CRC-16
+    ZwaveCrc16,
+}
+
+/// Layout of the CRC at the end of a frame
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct CrcFormat {
+    /// Number of CRC bytes (0 to 4)
+    pub len: u8,
+    /// Order of the CRC bytes
+    pub order: CrcOrder,
+    /// Algorithm used for the verification
+    pub algo: CrcAlgo,
+}
+
+/// Result of a CRC verification
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct CrcCheck {
+    /// Length of the payload (frame without CRC)
+    pub payload_len: usize,
+    /// CRC read from the frame
+    pub received: u32,
+    /// CRC computed on the payload
+    pub computed: u32,
+}
+
+impl CrcCheck {
+    /// Flag if the CRC received matches the CRC computed
+    pub fn is_valid(&self) -> bool {
+        self.received == self.computed
+    }
+}
+
+/// Number of bytes and inversion of a FSK/OOK CRC configuration
+fn crc_len_inv(val: u8) -> (u8, bool) {
+    (val & 0x7, val & 0x8 != 0)
+}
+
+impl CrcFormat {
+    /// LoRa payload CRC
+    pub fn lora() -> Self {
+        Self { len: 2, order: CrcOrder::LsbFirst, algo: CrcAlgo::Lora }
+    }
+
+    /// FSK CRC with its polynomial and init value
+    /// Return None for the 9 bits length header (not supported)
+    pub fn fsk(crc: Crc, pkt_format: FskPktFormat, poly: u32, init: u32) -> Option<Self> {
+        let header = match pkt_format {
+            FskPktFormat::FixedLength => CrcHeader::None,
+            FskPktFormat::Variable8bit => CrcHeader::Len8,
+            FskPktFormat::Variable16bit => CrcHeader::Len16,
+            FskPktFormat::Variable9bit => return None,
+        };
+        let (len, inv) = crc_len_inv(crc as u8);
+        Some(Self::msb(len, poly, init, inv).with_header(header))
+    }
+
+    /// OOK CRC with its polynomial and init value (see [`set_ook_crc`](crate::Lr2021::set_ook_crc))
+    pub fn ook(crc: OokCrc, pkt_format: OokPktFormat, poly: u32, init: u32) -> Self {
+        let header = match pkt_format {
+            OokPktFormat::FixedLength => CrcHeader::None,
+            OokPktFormat::Variable8bit => CrcHeader::Len8,
+        };
+        let (len, inv) = crc_len_inv(crc as u8);
+        Self::msb(len, poly, init, inv).with_header(header)
+    }
+
+    /// BLE CRC-24 with its init value (0x555555 for advertising channels)
+    pub fn ble(init: u32) -> Self {
+        Self { len: 3, order: CrcOrder::LsbFirst, algo: CrcAlgo::Ble { init } }
+    }
+
+    /// Z-Wave checksum (R1/R2) or CRC-16 (R3/LR)
+    pub fn zwave(mode: ZwaveMode) -> Self {
+        match mode {
+            ZwaveMode::R1 | ZwaveMode::R2 => Self { len: 1, order: CrcOrder::MsbFirst, algo: CrcAlgo::ZwaveChecksum },
+            _ => Self { len: 2, order: CrcOrder::MsbFirst, algo: CrcAlgo::ZwaveCrc16 },
+        }
+    }
+
+    /// Generic MSB-first CRC of len bytes (max 4) on the payload, without length header
+    pub fn msb(len: u8, poly: u32, init: u32, inv: bool) -> Self {
+        Self { len: len.min(4), order: CrcOrder::MsbFirst, algo: CrcAlgo::Msb { poly, init, inv, header: CrcHeader::None } }
+    }
+
+    /// Set the length header covered by a generic CRC
+    pub fn with_header(self, header: CrcHeader) -> Self {
+        let algo = match self.algo {
+            CrcAlgo::Msb { poly, init, inv, .. } => CrcAlgo::Msb { poly, init, inv, header },
+            algo => algo,
+        };
+        Self { algo, ..self }
+    }
+
+    /// Split a frame into payload and CRC value. Return None if the frame is shorter than the CRC
+    pub fn split<'a>(&self, frame: &'a [u8]) -> Option<(&'a [u8], u32)> {
+        let idx = frame.len().checked_sub(self.len as usize)?;
+        let (payload, crc) = frame.split_at(idx);
+        let value = match self.order {
+            CrcOrder::MsbFirst => crc.iter().fold(0u32, |acc, &b| (acc << 8) | b as u32),
+            CrcOrder::LsbFirst => crc.iter().rev().fold(0u32, |acc, &b| (acc << 8) | b as u32),
+        };
+        Some((payload, value))
+    }
+
+    /// Compute the CRC expected for a payload. Return None if the payload is too long for the length header
+    pub fn compute(&self, payload: &[u8]) -> Option<u32> {
+        let crc = match self.algo {
+            CrcAlgo::Msb { poly, init, inv, header } => {
+                if self.len == 0 {
+                    return Some(0);
+                }
+                let width = self.len * 8;
+                let mut hdr = [0u8; 2];
+                let hdr = match header {
+                    CrcHeader::None => &hdr[..0],
+                    CrcHeader::Len8 => {
+                        hdr[0] = u8::try_from(payload.len()).ok()?;
+                        &hdr[..1]
+                    }
+                    CrcHeader::Len16 => {
+                        hdr = u16::try_from(payload.len()).ok()?.to_be_bytes();
+                        &hdr[..]
+                    }
+                };
+                // Continue the CRC of the header on the payload
+                let crc = crc_msb(poly, init, width, hdr);
+                let crc = crc_msb(poly, crc, width, payload);
+                if inv {crc ^ ((1u64 << width) - 1) as u32} else {crc}
+            }
+            CrcAlgo::Lora => lora_payload_crc(payload) as u32,
+            CrcAlgo::Ble { init } => ble_crc24(init, payload),
+            CrcAlgo::ZwaveChecksum => zwave_checksum(payload) as u32,
+            CrcAlgo::ZwaveCrc16 => zwave_crc16(payload) as u32,
+        };
+        Some(crc)
+    }
+
+    /// Split a frame and compare its CRC with the one computed on the payload
+    /// Return None if the frame is shorter than the CRC or too long for the length header
+    pub fn verify(&self, frame: &[u8]) -> Option<CrcCheck> {
+        let (payload, received) = self.split(frame)?;
+        let computed = self.compute(payload)?;
+        Some(CrcCheck { payload_len: payload.len(), received, computed })
+    }
+}
@@ -0,0 +1,112 @@
+//! # Diagnostic snapshot
+//!
+//! This module gathers a defined set of chip information into a [`DebugSnapshot`]:
+//! status, errors, firmware version, packet type, RF frequency, gain setting, instantaneous RSSI,
+//! FIFO levels, last packet status and the raw value of the frontend and demodulator registers
+//! used by the driver (ADC, AAF, detectors, LoRa RX configuration, ...).
+//!
+//! The snapshot can be printed with `defmt` (or `Debug`) and attached to bug reports:
+//! it gives actionable data for "no RX" issues without ad-hoc register reads.
+//! Taking a snapshot does not change the chip state (interrupts are not cleared).
+//!
+//! ## Quick Start
+//!
+//! ```rust,no_run
+//! // While waiting for a packet which never comes
+//! let snapshot = lr2021.debug_snapshot().await.expect("Snapshot");
+//! info!("{}", snapshot);
+//! ```
+//!
+//! ## Available Methods
+//! - [`debug_snapshot`](Lr2021::debug_snapshot) - Read the diagnostic information from the chip
+
+use embedded_hal::digital::OutputPin;
+use embedded_hal_async::spi::SpiBus;
+
+use crate::constants::*;
+
+use super::radio::{PacketType, PktStatus};
+use super::status::ChipModeStatus;
+use super::{BusyPin, Lr2021, Lr2021Error};
+
+/// Registers included in the snapshot
+pub const DEBUG_REGS: [u32; 10] = [
+    ADDR_FREQ_RF,
+    ADDR_ADC_CTRL,
+    ADDR_AAF_CFG,
+    ADDR_PA_CTRL,
+    ADDR_CPFSK_DETECT,
+    ADDR_CPFSK_DEMOD,
+    ADDR_OOK_DETECT,
+    ADDR_LORA_PARAM,
+    ADDR_LORA_RX_CFG,
+    ADDR_LORA_TIMING_SYNC,
+];
+
+/// Diagnostic information read from the chip
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct DebugSnapshot {
+    /// Firmware version (major, minor)
+    pub version: (u8, u8),
+    /// Chip mode
+    pub chip_mode: ChipModeStatus,
+    /// Pending interrupts
+    pub intr: u32,
+    /// Error flags (see `get_errors`)
+    pub errors: u16,
+    /// Packet type configured through the driver
+    pub packet_type: Option<PacketType>,
+    /// RF frequency (in Hz) read from the chip
+    pub rf: u32,
+    /// Manual RX gain (0 for automatic gain control)
+    pub rx_gain: u8,
+    /// Instantaneous RSSI (in -0.5dBm)
+    pub rssi_inst: u16,
+    /// RX FIFO level (in bytes)
+    pub rx_fifo_lvl: u16,
+    /// TX FIFO level (in bytes)
+    pub tx_fifo_lvl: u16,
+    /// Status of the last packet received (None when not available for the packet type)
+    pub pkt_status: Option<PktStatus>,
+    /// Raw value of the registers listed in [`DEBUG_REGS`]
+    pub regs: [u32; DEBUG_REGS.len()],
+}
+
+impl<O,SPI, M> Lr2021<O,SPI, M> where
+    O: OutputPin, SPI: SpiBus<u8>, M: BusyPin
+{
+    /// Read the diagnostic information from the chip
+    /// The chip must be awake (not in sleep)
+    pub async fn debug_snapshot(&mut self) -> Result<DebugSnapshot, Lr2021Error> {
+        let version = self.get_version().await?;
+        let (status, intr) = self.get_status().await?;
+        let mut errors = self.get_errors().await?;
+        let errors = {
+            let b = errors.as_mut();
+            u16::from_be_bytes([b[2], b[3]])
+        };
+        let pkt_status = match self.cache.packet_type {
+            Some(_) => self.get_packet_status().await.ok(),
+            None => None,
+        };
+        let mut regs = [0; DEBUG_REGS.len()];
+        for (v, &addr) in regs.iter_mut().zip(DEBUG_REGS.iter()) {
+            *v = self.rd_reg(addr).await?;
+        }
+        Ok(DebugSnapshot {
+            version: (version.major(), version.minor()),
+            chip_mode: status.chip_mode(),
+            intr: intr.value(),
+            errors,
+            packet_type: self.cache.packet_type,
+            rf: self.get_rf().await?,
+            rx_gain: self.cache.rx_gain,
+            rssi_inst: self.get_rssi_inst().await?,
+            rx_fifo_lvl: self.get_rx_fifo_lvl().await?,
+            tx_fifo_lvl: self.get_tx_fifo_lvl().await?,
+            pkt_status,
+            regs,
+        })
+    }
+}
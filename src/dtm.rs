@@ -0,0 +1,189 @@
+//! # BLE Direct Test Mode
+//!
+//! This module provides helpers to run sequences similar to the Bluetooth Direct Test Mode (DTM)
+//! used for RF certification: the transmitter sends reference packets (PRBS9, 0x0F or 0x55 payloads)
+//! at the DTM packet interval on an RF channel, and the receiver counts the packets received with a valid CRC.
+//!
+//! DTM packets use the test access address 0x71764129, the CRC init 0x555555 and no whitening.
+//! The RF channel index (0-39) maps to 2402 + 2*k MHz.
+//! The result of a reception is reported in the LE_Packet_Report event format with [`DtmReport::event`].
+//!
+//! RX path, PA and TX power must be configured before running a test.
+//!
+//! ## Quick Start
+//!
+//! ```rust,no_run
+//! use lr2021::ble::BleMode;
+//! use lr2021::dtm::DtmPayload;
+//!
+//! // Transmitter: 1500 packets of 37 bytes of PRBS9 on channel 19 (2440MHz)
+//! let nb = lr2021.dtm_tx(19, BleMode::Le1mb, DtmPayload::Prbs9, 37, 1500).await.expect("DtmTx");
+//! // Receiver: count packets during 2s
+//! let report = lr2021.dtm_rx(19, BleMode::Le1mb, Duration::from_secs(2)).await.expect("DtmRx");
+//! info!("Received {} packets (event 0x{:04x})", report.nb_packets, report.event());
+//! ```
+//!
+//! ## Available Methods
+//! - [`dtm_setup`](Lr2021::dtm_setup) - Configure the radio for DTM packets on an RF channel
+//! - [`dtm_tx`](Lr2021::dtm_tx) - Send test packets at the DTM packet interval
+//! - [`dtm_rx`](Lr2021::dtm_rx) - Receive test packets during a given time and report the packet count
+//! - [`dtm_channel_freq`] - Frequency of an RF channel
+//! - [`dtm_packet_interval_us`] - DTM packet interval for a PHY and a payload length
+
+use embassy_time::{Duration, Instant, Timer};
+use embedded_hal::digital::OutputPin;
+use embedded_hal_async::spi::SpiBus;
+
+use super::ble::{BleMode, ChannelType};
+use super::radio::PacketType;
+use super::system::ChipMode;
+use super::{BusyPin, Lr2021, Lr2021Error};
+
+/// Access address of DTM test packets
+pub const DTM_ACCESS_ADDRESS: u32 = 0x71764129;
+/// CRC init of DTM test packets
+pub const DTM_CRC_INIT: u32 = 0x555555;
+/// Maximum payload length: the 2 bytes PDU header and the payload must fit in a BLE packet of 255 bytes
+pub const DTM_PAYLOAD_MAX: u8 = 253;
+
+/// Payload of DTM test packets
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum DtmPayload {
+    /// Pseudo-random sequence PRBS9
+    Prbs9,
+    /// Repeated '11110000' (0x0F in transmission order)
+    Pattern0F,
+    /// Repeated '10101010' (0x55 in transmission order)
+    Pattern55,
+}
+
+impl DtmPayload {
+    /// Payload type in the header of the test packet
+    pub fn code(&self) -> u8 {
+        match self {
+            DtmPayload::Prbs9 => 0,
+            DtmPayload::Pattern0F => 1,
+            DtmPayload::Pattern55 => 2,
+        }
+    }
+
+    /// Fill a buffer with the payload
+    pub fn fill(&self, buf: &mut [u8]) {
+        match self {
+            DtmPayload::Prbs9 => {
+                // x^9 + x^5 + 1, all ones seed, LSB first
+                let mut lfsr: u16 = 0x1FF;
+                for byte in buf.iter_mut() {
+                    *byte = 0;
+                    for bit in 0..8 {
+                        *byte |= ((lfsr & 1) as u8) << bit;
+                        let fb = (lfsr ^ (lfsr >> 4)) & 1;
+                        lfsr = (lfsr >> 1) | (fb << 8);
+                    }
+                }
+            }
+            DtmPayload::Pattern0F => buf.fill(0x0F),
+            DtmPayload::Pattern55 => buf.fill(0x55),
+        }
+    }
+}
+
+/// Frequency (in Hz) of an RF channel (0-39)
+pub fn dtm_channel_freq(channel: u8) -> u32 {
+    2_402_000_000 + 2_000_000 * channel as u32
+}
+
+/// Duration (in us) of a test packet with a payload length
+fn dtm_packet_duration_us(mode: BleMode, len: u8) -> u32 {
+    // Header, payload and CRC
+    let nb_bytes = 5 + len as u32;
+    match mode {
+        // Preamble 1B + Access Address 4B
+        BleMode::Le1mb => 8 * (5 + nb_bytes),
+        // Preamble 2B + Access Address 4B
+        BleMode::Le2mb => 4 * (6 + nb_bytes),
+        // Preamble, Access Address, CI and TERM1 (376us) + PDU/CRC and TERM2 with S=2 or S=8 coding
+        BleMode::LeCoded500k => 376 + 16 * nb_bytes + 6,
+        BleMode::LeCoded125k => 376 + 64 * nb_bytes + 24,
+    }
+}
+
+/// DTM packet interval (in us): duration of the packet plus 249us, rounded up to a multiple of 625us
+pub fn dtm_packet_interval_us(mode: BleMode, len: u8) -> u32 {
+    (dtm_packet_duration_us(mode, len) + 249).div_ceil(625) * 625
+}
+
+/// Result of a DTM reception
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct DtmReport {
+    /// Number of packets received with a valid CRC
+    pub nb_packets: u16,
+    /// Number of packets received with a CRC error
+    pub nb_crc_error: u16,
+    /// Number of packets received with a length error
+    pub nb_len_error: u16,
+}
+
+impl DtmReport {
+    /// LE_Packet_Report event: bit 15 set and packet count on 15 bits
+    pub fn event(&self) -> u16 {
+        0x8000 | (self.nb_packets & 0x7FFF)
+    }
+}
+
+impl<O,SPI, M> Lr2021<O,SPI, M> where
+    O: OutputPin, SPI: SpiBus<u8>, M: BusyPin
+{
+    /// Configure the radio for DTM packets on an RF channel (0-39)
+    /// Return InvalidParam if the channel is out of range
+    pub async fn dtm_setup(&mut self, channel: u8, mode: BleMode) -> Result<(), Lr2021Error> {
+        if channel > 39 {
+            return Err(Lr2021Error::InvalidParam);
+        }
+        self.set_packet_type(PacketType::Ble).await?;
+        // Whitening initial value of 0 disables the whitening
+        self.set_ble_params(false, ChannelType::Advertiser, 0, DTM_CRC_INIT, DTM_ACCESS_ADDRESS).await?;
+        self.set_ble_modulation(mode).await?;
+        self.set_rf(dtm_channel_freq(channel)).await
+    }
+
+    /// Send nb_packets test packets with a payload of len bytes at the DTM packet interval.
+    /// Each packet waits for its own TxDone before the next one is sent.
+    /// Return the number of packets sent, or InvalidSize if len is above [`DTM_PAYLOAD_MAX`] (radio left unchanged)
+    pub async fn dtm_tx(&mut self, channel: u8, mode: BleMode, payload: DtmPayload, len: u8, nb_packets: u16) -> Result<u16, Lr2021Error> {
+        if len > DTM_PAYLOAD_MAX {
+            return Err(Lr2021Error::InvalidSize);
+        }
+        self.dtm_setup(channel, mode).await?;
+        let mut pdu = [0u8; 2 + DTM_PAYLOAD_MAX as usize];
+        pdu[0] = payload.code();
+        pdu[1] = len;
+        payload.fill(&mut pdu[2..2 + len as usize]);
+        let pdu = &pdu[..2 + len as usize];
+        let interval = Duration::from_micros(dtm_packet_interval_us(mode, len) as u64);
+        let mut next = Instant::now();
+        for _ in 0..nb_packets {
+            Timer::at(next).await;
+            next += interval;
+            self.tx_wait_done(pdu, interval + Duration::from_millis(10)).await?;
+        }
+        Ok(nb_packets)
+    }
+
+    /// Receive test packets during a given time and report the number of packets received
+    pub async fn dtm_rx(&mut self, channel: u8, mode: BleMode, duration: Duration) -> Result<DtmReport, Lr2021Error> {
+        self.dtm_setup(channel, mode).await?;
+        self.clear_rx_stats().await?;
+        self.set_rx_continous().await?;
+        Timer::after(duration).await;
+        self.set_chip_mode(ChipMode::StandbyRc).await?;
+        let stats = self.get_ble_rx_stats().await?;
+        Ok(DtmReport {
+            nb_packets: stats.pkt_rx().saturating_sub(stats.crc_error()),
+            nb_crc_error: stats.crc_error(),
+            nb_len_error: stats.len_error(),
+        })
+    }
+}
@@ -0,0 +1,171 @@
+//! # Radio event notification
+//!
+//! This module provides an integration with `embassy-sync` to publish the interrupts of the radio
+//! to multiple tasks (UI, logger, MAC, ...) without sharing the driver.
+//!
+//! The task owning the driver calls [`publish_irq`](Lr2021::publish_irq) when the IRQ line is asserted:
+//! interrupts are read, cleared and forwarded as a [`RadioEvent`] to an [`EventSink`]:
+//!  - [`WatchSink`]: publish in a `Watch`, where receivers only see the latest event (state observation)
+//!  - [`PubSubSink`]: publish in a `PubSubChannel`, where each subscriber sees every event,
+//!    with a configurable policy when the channel is full ([`OverflowPolicy`])
+//!
+//! This module is only available with the `embassy-sync` feature.
+//!
+//! ## Quick Start
+//!
+//! ```rust,no_run
+//! use embassy_sync::{blocking_mutex::raw::CriticalSectionRawMutex, pubsub::PubSubChannel};
+//! use lr2021::events::{RadioEvent, PubSubSink, OverflowPolicy};
+//!
+//! static RADIO_EVENTS: PubSubChannel<CriticalSectionRawMutex, RadioEvent, 8, 2, 1> = PubSubChannel::new();
+//!
+//! // Radio task
+//! let mut sink = PubSubSink::new(&RADIO_EVENTS, OverflowPolicy::DropOldest).expect("Publisher");
+//! loop {
+//!     irq.wait_for_high().await;
+//!     lr2021.publish_irq(&mut sink).await.expect("PublishIrq");
+//! }
+//!
+//! // Logger task
+//! let mut sub = RADIO_EVENTS.subscriber().expect("Subscriber");
+//! loop {
+//!     let evt = sub.next_message_pure().await;
+//!     info!("{} : {}", evt.timestamp, evt.intr);
+//! }
+//! ```
+//!
+//! ## Available Methods
+//! - [`publish_irq`](Lr2021::publish_irq) - Read and clear the interrupts, then publish them to an event sink
+
+use embassy_sync::blocking_mutex::raw::RawMutex;
+use embassy_sync::pubsub::{self, PubSubChannel, Publisher};
+use embassy_sync::watch::{Sender, Watch};
+use embassy_time::Instant;
+use embedded_hal::digital::OutputPin;
+use embedded_hal_async::spi::SpiBus;
+
+use super::status::Intr;
+use super::{BusyPin, Lr2021, Lr2021Error};
+
+/// Radio event: interrupts raised by the chip
+#[derive(Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct RadioEvent {
+    /// Interrupt flags
+    pub intr: Intr,
+    /// Time at which the interrupts were read
+    pub timestamp: Instant,
+}
+
+/// Behavior when publishing in a full channel
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum OverflowPolicy {
+    /// Drop the new event
+    DropNewest,
+    /// Drop the oldest event not yet read by all subscribers
+    DropOldest,
+    /// Wait for all subscribers to read the oldest event
+    Wait,
+}
+
+/// Statistics of an event sink
+#[derive(Debug, Default, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct EventStats {
+    /// Number of events published
+    pub published: u32,
+    /// Number of events dropped (new or oldest depending on the policy)
+    pub dropped: u32,
+}
+
+/// Destination of the radio events
+pub trait EventSink {
+    /// Publish an event
+    #[allow(async_fn_in_trait)]
+    async fn publish(&mut self, event: RadioEvent);
+}
+
+/// Event sink keeping only the latest event
+pub struct WatchSink<'a, M: RawMutex, const N: usize> {
+    sender: Sender<'a, M, RadioEvent, N>,
+}
+
+impl<'a, M: RawMutex, const N: usize> WatchSink<'a, M, N> {
+    /// Create a sink publishing in a watch
+    pub fn new(watch: &'a Watch<M, RadioEvent, N>) -> Self {
+        Self { sender: watch.sender() }
+    }
+}
+
+impl<M: RawMutex, const N: usize> EventSink for WatchSink<'_, M, N> {
+    async fn publish(&mut self, event: RadioEvent) {
+        self.sender.send(event);
+    }
+}
+
+/// Event sink delivering every event to each subscriber
+pub struct PubSubSink<'a, M: RawMutex, const CAP: usize, const SUBS: usize, const PUBS: usize> {
+    publisher: Publisher<'a, M, RadioEvent, CAP, SUBS, PUBS>,
+    policy: OverflowPolicy,
+    stats: EventStats,
+}
+
+impl<'a, M: RawMutex, const CAP: usize, const SUBS: usize, const PUBS: usize> PubSubSink<'a, M, CAP, SUBS, PUBS> {
+    /// Create a sink publishing in a channel with a given overflow policy
+    /// Fails if the maximum number of publishers of the channel is reached
+    pub fn new(channel: &'a PubSubChannel<M, RadioEvent, CAP, SUBS, PUBS>, policy: OverflowPolicy) -> Result<Self, pubsub::Error> {
+        let publisher = channel.publisher()?;
+        Ok(Self { publisher, policy, stats: EventStats::default() })
+    }
+
+    /// Sink statistics
+    pub fn stats(&self) -> EventStats {
+        self.stats
+    }
+
+    /// Reset sink statistics
+    pub fn clear_stats(&mut self) {
+        self.stats = EventStats::default();
+    }
+}
+
+impl<M: RawMutex, const CAP: usize, const SUBS: usize, const PUBS: usize> EventSink for PubSubSink<'_, M, CAP, SUBS, PUBS> {
+    async fn publish(&mut self, event: RadioEvent) {
+        let published = match self.policy {
+            OverflowPolicy::DropNewest => self.publisher.try_publish(event).is_ok(),
+            OverflowPolicy::DropOldest => {
+                if self.publisher.is_full() {
+                    self.stats.dropped = self.stats.dropped.wrapping_add(1);
+                }
+                self.publisher.publish_immediate(event);
+                true
+            }
+            OverflowPolicy::Wait => {
+                self.publisher.publish(event).await;
+                true
+            }
+        };
+        if published {
+            self.stats.published = self.stats.published.wrapping_add(1);
+        } else {
+            self.stats.dropped = self.stats.dropped.wrapping_add(1);
+        }
+    }
+}
+
+impl<O,SPI, M> Lr2021<O,SPI, M> where
+    O: OutputPin, SPI: SpiBus<u8>, M: BusyPin
+{
+    /// Read and clear the interrupts, then publish them to an event sink
+    /// LoRa packets with error are dropped first when the auto-drop is enabled (see [`lora_rx_service_irq`](Lr2021::lora_rx_service_irq)).
+    /// Nothing is published if no interrupt was pending
+    pub async fn publish_irq<S: EventSink>(&mut self, sink: &mut S) -> Result<Intr, Lr2021Error> {
+        let intr = self.get_and_clear_irq().await?;
+        let intr = self.lora_rx_service_irq(intr).await?;
+        if !intr.none() {
+            sink.publish(RadioEvent { intr, timestamp: Instant::now() }).await;
+        }
+        Ok(intr)
+    }
+}
@@ -0,0 +1,256 @@
+//! # Dual-band link failover
+//!
+//! This module provides a [`FailoverLink`] managing two link configurations, typically a sub-GHz primary link
+//! (e.g. LoRa) and a 2.4GHz secondary link (e.g. FLRC), using the dual-band capability of the chip.
+//!
+//! The application reports the outcome of each transmission (e.g. acknowledged or not) and reception
+//! (with its RSSI): the health of the active link is estimated from the packet error rate (PER)
+//! over a window of exchanges and from the average RSSI.
+//! When the active link is unhealthy, [`failover_update`](Lr2021::failover_update) switches to the other link,
+//! re-applying the protocol configuration, the RX path and the PA / TX power.
+//! After some time on the secondary link, the primary link is tried again.
+//!
+//! Each link configuration is a [`ListenSlot`] (see [`multilisten`](crate::multilisten)) for the protocol part
+//! and a [`LinkRf`] for the radio front-end part.
+//!
+//! ## Quick Start
+//!
+//! ```rust,no_run
+//! use lr2021::failover::{FailoverCfg, FailoverLink, LinkPa, LinkRf};
+//! use lr2021::multilisten::{FlrcSlot, LoraSlot};
+//! use lr2021::radio::{RxBoost, RxPath};
+//!
+//! let lora = LoraSlot::new(868_100_000, sf9, LoraPacketParams::basic(32, &sf9));
+//! let flrc = FlrcSlot::new(2_440_000_000, FlrcBitrate::Br650, FlrcCr::Cr1p2, flrc_params, Syncword::from_msb_bits(0xCD05CAFE, 32));
+//! let mut link = FailoverLink::new(
+//!     lora, LinkRf::new(RxPath::LfPath, LinkPa::Lf(PaLfMode::LfPaFsm, 6, 7), 28),
+//!     flrc, LinkRf::new(RxPath::HfPath, LinkPa::Hf, 24),
+//!     FailoverCfg::default(),
+//! );
+//! lr2021.failover_start(&mut link).await.expect("FailoverStart");
+//! loop {
+//!     // Send a packet and wait for the acknowledge ...
+//!     link.report_tx(acked);
+//!     if let Some(active) = lr2021.failover_update(&mut link).await.expect("FailoverUpdate") {
+//!         info!("Switched to {}", active);
+//!     }
+//! }
+//! ```
+//!
+//! ## Available Methods
+//! - [`failover_start`](Lr2021::failover_start) - Configure the radio for the active link
+//! - [`failover_update`](Lr2021::failover_update) - Switch link when the active one is unhealthy (or to retry the primary)
+
+use embassy_time::{Duration, Instant};
+use embedded_hal::digital::OutputPin;
+use embedded_hal_async::spi::SpiBus;
+
+use super::multilisten::ListenSlot;
+use super::radio::{PaLfMode, RampSel, RxBoost, RxPath};
+use super::rssi::{RssiEwma, RssiFilter};
+use super::{BusyPin, Lr2021, Lr2021Error};
+
+/// PA configuration of a link
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum LinkPa {
+    /// Low-frequency PA: mode, duty-cycle and number of slices
+    Lf(PaLfMode, u8, u8),
+    /// High-frequency PA
+    Hf,
+}
+
+/// Radio front-end configuration of a link
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct LinkRf {
+    /// RX path
+    pub rx_path: RxPath,
+    /// RX boost
+    pub rx_boost: RxBoost,
+    /// PA configuration
+    pub pa: LinkPa,
+    /// TX power (in half-dB)
+    pub tx_power: i8,
+    /// PA ramp time
+    pub ramp_time: RampSel,
+}
+
+impl LinkRf {
+    /// Front-end configuration without RX boost and with automatic ramp time
+    pub fn new(rx_path: RxPath, pa: LinkPa, tx_power: i8) -> Self {
+        Self { rx_path, rx_boost: RxBoost::Off, pa, tx_power, ramp_time: RampSel::Auto }
+    }
+}
+
+/// Link selection
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum LinkSel {
+    /// Primary link
+    Primary,
+    /// Secondary link
+    Secondary,
+}
+
+/// Failover decision parameters
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct FailoverCfg {
+    /// Number of exchanges (TX and RX reports) before evaluating the PER
+    pub window: u16,
+    /// Maximum packet error rate (in percent)
+    pub per_max: u8,
+    /// Weakest average RSSI accepted (in -0.5dBm, 0 to disable)
+    pub rssi_min: u16,
+    /// Time spent on the secondary link before trying the primary link again
+    pub retry_primary: Duration,
+}
+
+impl Default for FailoverCfg {
+    /// PER above 30% over 20 exchanges, RSSI below -115dBm, primary link tried again after 10 minutes
+    fn default() -> Self {
+        Self { window: 20, per_max: 30, rssi_min: 230, retry_primary: Duration::from_secs(600) }
+    }
+}
+
+/// Health of the active link
+#[derive(Debug, Default, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct LinkHealth {
+    /// Number of exchanges reported
+    pub nb_total: u16,
+    /// Number of failed exchanges
+    pub nb_fail: u16,
+    /// Average RSSI of the packets received
+    rssi: RssiEwma,
+}
+
+impl LinkHealth {
+    fn new() -> Self {
+        Self { nb_total: 0, nb_fail: 0, rssi: RssiEwma::new(3) }
+    }
+
+    /// Packet error rate (in percent)
+    pub fn per(&self) -> u8 {
+        (self.nb_fail as u32 * 100 / (self.nb_total as u32).max(1)) as u8
+    }
+
+    /// Average RSSI (in -0.5dBm) of the packets received
+    pub fn rssi(&self) -> Option<u16> {
+        self.rssi.value()
+    }
+}
+
+/// Link with a primary and a secondary configuration
+pub struct FailoverLink<P: ListenSlot, S: ListenSlot> {
+    primary: (P, LinkRf),
+    secondary: (S, LinkRf),
+    cfg: FailoverCfg,
+    active: LinkSel,
+    health: LinkHealth,
+    /// Time of the last switch
+    since: Instant,
+}
+
+impl<P: ListenSlot, S: ListenSlot> FailoverLink<P, S> {
+    /// Create a link starting on the primary configuration
+    pub fn new(primary: P, primary_rf: LinkRf, secondary: S, secondary_rf: LinkRf, cfg: FailoverCfg) -> Self {
+        Self { primary: (primary, primary_rf), secondary: (secondary, secondary_rf), cfg, active: LinkSel::Primary, health: LinkHealth::new(), since: Instant::now() }
+    }
+
+    /// Active link
+    pub fn active(&self) -> LinkSel {
+        self.active
+    }
+
+    /// Health of the active link
+    pub fn health(&self) -> &LinkHealth {
+        &self.health
+    }
+
+    /// Report the outcome of a transmission (e.g. acknowledged or not)
+    pub fn report_tx(&mut self, ok: bool) {
+        self.report(ok);
+    }
+
+    /// Report the outcome of a reception with the RSSI of the packet (in -0.5dBm) when received
+    pub fn report_rx(&mut self, ok: bool, rssi: Option<u16>) {
+        self.report(ok);
+        if let Some(rssi) = rssi {
+            self.health.rssi.update(rssi);
+        }
+    }
+
+    fn report(&mut self, ok: bool) {
+        // Restart the window once full, keeping the RSSI average
+        if self.health.nb_total >= self.cfg.window {
+            self.health.nb_total = 0;
+            self.health.nb_fail = 0;
+        }
+        self.health.nb_total += 1;
+        if !ok {
+            self.health.nb_fail += 1;
+        }
+    }
+
+    /// Flag if the active link is unhealthy: PER too high over a full window or RSSI too weak
+    pub fn is_unhealthy(&self) -> bool {
+        let per_bad = self.health.nb_total >= self.cfg.window && self.health.per() > self.cfg.per_max;
+        let rssi_bad = self.cfg.rssi_min != 0 && self.health.rssi().is_some_and(|r| r > self.cfg.rssi_min);
+        per_bad || rssi_bad
+    }
+
+    /// Link to use: the other one when unhealthy, or the primary once the retry delay elapsed on the secondary
+    fn next(&self) -> LinkSel {
+        match self.active {
+            _ if self.is_unhealthy() => match self.active {
+                LinkSel::Primary => LinkSel::Secondary,
+                LinkSel::Secondary => LinkSel::Primary,
+            },
+            LinkSel::Secondary if self.since.elapsed() >= self.cfg.retry_primary => LinkSel::Primary,
+            active => active,
+        }
+    }
+}
+
+impl<O,SPI, M> Lr2021<O,SPI, M> where
+    O: OutputPin, SPI: SpiBus<u8>, M: BusyPin
+{
+    /// Configure the radio for the active link: protocol, RX path, PA and TX power
+    /// The chip must be in standby
+    pub async fn failover_start<P: ListenSlot, S: ListenSlot>(&mut self, link: &mut FailoverLink<P, S>) -> Result<(), Lr2021Error> {
+        let rf = match link.active {
+            LinkSel::Primary => {
+                link.primary.0.configure(self).await?;
+                link.primary.1
+            }
+            LinkSel::Secondary => {
+                link.secondary.0.configure(self).await?;
+                link.secondary.1
+            }
+        };
+        self.set_rx_path(rf.rx_path, rf.rx_boost).await?;
+        match rf.pa {
+            LinkPa::Lf(mode, duty_cycle, slices) => self.set_pa_lf(mode, duty_cycle, slices).await?,
+            LinkPa::Hf => self.set_pa_hf().await?,
+        }
+        self.set_tx_params_sel(rf.tx_power, rf.ramp_time).await?;
+        link.health = LinkHealth::new();
+        link.since = Instant::now();
+        Ok(())
+    }
+
+    /// Switch to the other link when the active one is unhealthy, or back to the primary after the retry delay.
+    /// Return the new active link when a switch occurred. The chip must be in standby
+    pub async fn failover_update<P: ListenSlot, S: ListenSlot>(&mut self, link: &mut FailoverLink<P, S>) -> Result<Option<LinkSel>, Lr2021Error> {
+        let next = link.next();
+        if next == link.active {
+            return Ok(None);
+        }
+        #[cfg(feature = "defmt")]{defmt::warn!("Failover: {} -> {} (PER {}%)", link.active, next, link.health.per());}
+        link.active = next;
+        self.failover_start(link).await?;
+        Ok(Some(next))
+    }
+}
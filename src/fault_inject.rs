@@ -0,0 +1,122 @@
+//! # Fault injection
+//!
+//! With the `fault-inject` feature, synthetic failures can be injected in the transport layer of the driver
+//! so that the application state machines and recovery paths (watchdog, reset, resynchronisation)
+//! can be exercised deterministically, e.g. in CI with a mocked SPI bus and busy pin.
+//!
+//! A fault is armed on the Nth command sent (counting from the call), once or periodically:
+//!  - [`Fault::BusyTimeout`]: the command is not sent and fails with `BusyTimeout`, as if the chip never released busy
+//!  - [`Fault::CmdFail`] / [`Fault::CmdErr`]: the command is sent but the status reports a failure / a parameter error
+//!  - [`Fault::CorruptStatus`]: the command is sent and the status bytes received are XORed with a mask
+//!
+//! This feature is intended for testing only and must not be enabled in production.
+//!
+//! ## Quick Start
+//!
+//! ```rust,no_run
+//! use lr2021::fault_inject::Fault;
+//!
+//! // Third command from now fails
+//! lr2021.inject_fault(Fault::CmdFail, 3);
+//! // Every 100 commands, the busy pin is stuck
+//! lr2021.inject_fault_every(Fault::BusyTimeout, 100);
+//! // ... run the application and check its recovery
+//! info!("{} faults injected", lr2021.faults_injected());
+//! lr2021.clear_faults();
+//! ```
+//!
+//! ## Available Methods
+//! - [`inject_fault`](Lr2021::inject_fault) - Inject a fault on the Nth command from now
+//! - [`inject_fault_every`](Lr2021::inject_fault_every) - Inject a fault every N commands
+//! - [`clear_faults`](Lr2021::clear_faults) - Disarm the fault injection
+//! - [`faults_injected`](Lr2021::faults_injected) - Number of faults injected since the last clear
+
+use embedded_hal::digital::OutputPin;
+use embedded_hal_async::spi::SpiBus;
+
+use super::status::CmdStatus;
+use super::{BusyPin, Lr2021};
+
+/// Synthetic failure
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Fault {
+    /// Command not sent, failing with a busy timeout
+    BusyTimeout,
+    /// Command status reporting a failure
+    CmdFail,
+    /// Command status reporting a parameter error
+    CmdErr,
+    /// Status bytes XORed with a mask
+    CorruptStatus([u8; 2]),
+}
+
+/// State of the fault injection
+#[derive(Debug, Default, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub(crate) struct FaultInjector {
+    /// Fault armed
+    fault: Option<Fault>,
+    /// Commands left before the fault
+    countdown: u32,
+    /// Period of the fault (0 for a single fault)
+    period: u32,
+    /// Number of faults injected
+    injected: u32,
+}
+
+impl FaultInjector {
+    /// Count a command and return the fault to inject on it, if any
+    pub(crate) fn next_cmd(&mut self) -> Option<Fault> {
+        let fault = self.fault?;
+        self.countdown = self.countdown.saturating_sub(1);
+        if self.countdown > 0 {
+            return None;
+        }
+        if self.period == 0 {
+            self.fault = None;
+        } else {
+            self.countdown = self.period;
+        }
+        self.injected = self.injected.saturating_add(1);
+        Some(fault)
+    }
+
+    /// Apply a fault on the status bytes received with the command
+    pub(crate) fn corrupt(fault: Fault, status: &mut [u8]) {
+        match fault {
+            Fault::BusyTimeout => {}
+            Fault::CmdFail => status[0] = (status[0] & !0x0E) | ((CmdStatus::Fail as u8) << 1),
+            Fault::CmdErr => status[0] = (status[0] & !0x0E) | ((CmdStatus::PErr as u8) << 1),
+            Fault::CorruptStatus(mask) => {
+                status[0] ^= mask[0];
+                status[1] ^= mask[1];
+            }
+        }
+    }
+}
+
+impl<O,SPI, M> Lr2021<O,SPI, M> where
+    O: OutputPin, SPI: SpiBus<u8>, M: BusyPin
+{
+    /// Inject a fault on the Nth command sent from now (1 for the next command), replacing any fault armed
+    pub fn inject_fault(&mut self, fault: Fault, nth: u32) {
+        self.fault_inject = FaultInjector { fault: Some(fault), countdown: nth.max(1), period: 0, injected: self.fault_inject.injected };
+    }
+
+    /// Inject a fault every N commands, replacing any fault armed
+    pub fn inject_fault_every(&mut self, fault: Fault, period: u32) {
+        let period = period.max(1);
+        self.fault_inject = FaultInjector { fault: Some(fault), countdown: period, period, injected: self.fault_inject.injected };
+    }
+
+    /// Disarm the fault injection and reset the counter of faults injected
+    pub fn clear_faults(&mut self) {
+        self.fault_inject = FaultInjector::default();
+    }
+
+    /// Number of faults injected since the last clear
+    pub fn faults_injected(&self) -> u32 {
+        self.fault_inject.injected
+    }
+}
@@ -20,15 +20,46 @@
 //! - [`rd_rx_fifo`](Lr2021::rd_rx_fifo) - Read RX FIFO data to internal buffer
 //! - [`get_rx_fifo_lvl`](Lr2021::get_rx_fifo_lvl) - Get number of bytes in RX FIFO
 //! - [`clear_rx_fifo`](Lr2021::clear_rx_fifo) - Clear all data from RX FIFO
+//!
+//! ### Payload not multiple of a byte
+//! - [`wr_tx_fifo_bits`](Lr2021::wr_tx_fifo_bits) - Write a payload given in bits to the TX FIFO, clearing the padding bits
+//! - [`rd_rx_fifo_bits`](Lr2021::rd_rx_fifo_bits) - Read a payload given in bits from the RX FIFO, clearing the padding bits
+//!
+//! ### Multi-packet RX FIFO
+//! - [`rx_fifo_push_pkt`](Lr2021::rx_fifo_push_pkt) - Record the length of the last packet received (to call on each RxDone)
+//! - [`read_next_packet`](Lr2021::read_next_packet) - Read the oldest packet from the RX FIFO
+//! - [`rx_fifo_nb_pkt`](Lr2021::rx_fifo_nb_pkt) - Number of packets available in the RX FIFO
+//!
+//! ### Aborted reception (length/address error)
+//! - [`set_rx_err_policy`](Lr2021::set_rx_err_policy) - Select how partial data left by a LenError/AddrError is handled
+//! - [`rx_fifo_push_error`](Lr2021::rx_fifo_push_error) - Record the partial data of an aborted reception (to call on each LenError/AddrError)
+//! - [`read_next_packet_info`](Lr2021::read_next_packet_info) - Read the oldest packet from the RX FIFO with its partial flag
+//!
+//! ### Packet filter
+//! - [`set_rx_filter`](Lr2021::set_rx_filter) - Install a filter deciding from the packet status if a received packet is kept
+//! - [`rx_filter_apply`](Lr2021::rx_filter_apply) - Run the filter on RxDone and discard rejected packets without reading them
+//! - [`rx_filtered_count`](Lr2021::rx_filtered_count) - Number of packets discarded by the filter
+//!
+//! ### Streaming presets
+//! - [`set_fifo_preset`](Lr2021::set_fifo_preset) - Configure FIFO thresholds, IRQ and chunk size for a latency/throughput trade-off
+//! - [`tx_fifo_refill`](Lr2021::tx_fifo_refill) - Write the next chunk of a long packet to the TX FIFO
+//! - [`rx_fifo_drain`](Lr2021::rx_fifo_drain) - Read the next chunk of a long packet from the RX FIFO
+//!
+//! ### Debug
+//! - [`set_hexdump_max`](Lr2021::set_hexdump_max) - Set the maximum number of bytes logged when reading the RX FIFO (defmt only)
+//! - [`log_rx_packet`](Lr2021::log_rx_packet) - Log a received packet with its length, RSSI and hexdump (defmt only)
 
 use embedded_hal::digital::OutputPin;
 use embedded_hal_async::spi::SpiBus;
 
 use super::cmd::cmd_system::*;
 
-use super::{BusyPin, Lr2021, Lr2021Error};
+use super::radio::PktStatus;
+use super::status::Intr;
+use super::{BusyPin, Lr2021, Lr2021Error, BUFFER_SIZE};
 
-#[derive(Default, Clone, Copy)]
+#[derive(Debug, Default, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 /// FIFO IRQ enable flags
 pub struct FifoIrqEn(u8);
 
@@ -116,7 +147,8 @@ impl FifoIrqEn {
 
 }
 
-#[derive(Default, Clone, Copy)]
+#[derive(Debug, Default, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 /// FIFO IRQ enable flags
 pub struct FifoIrqCfg{
     pub en: FifoIrqEn,
@@ -131,6 +163,218 @@ impl FifoIrqCfg {
     }
 }
 
+/// FIFO streaming presets, trading IRQ latency for SPI efficiency
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum FifoPreset {
+    /// Small chunks (about 1ms of data, at least 16 bytes): data is available quickly but with many SPI transactions
+    LowLatency,
+    /// Chunks of a quarter of the FIFO
+    #[default]
+    Balanced,
+    /// Largest chunks (limited by the driver buffer): fewest SPI transactions, but the host must react quickly
+    HighThroughput,
+}
+
+/// SPI overhead of a chunk transfer in bytes: FIFO level request/response and FIFO access opcode
+const FIFO_CHUNK_OVERHEAD: u32 = 2 + 4 + 2;
+
+/// FIFO configuration derived from a preset and a bitrate
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct FifoTuning {
+    /// TX FIFO IRQ: low threshold set when a full chunk can be written
+    pub tx: FifoIrqCfg,
+    /// RX FIFO IRQ: high threshold set when a full chunk can be read
+    pub rx: FifoIrqCfg,
+    /// Chunk size in bytes
+    pub chunk: u16,
+    /// Maximum time (in us) the host can take to service a FIFO IRQ before the RX FIFO overflows or the TX FIFO underflows
+    pub max_latency_us: u32,
+    /// Minimum SPI clock (in Hz) required to sustain the bitrate, including the command overhead of each chunk.
+    /// This does not include the host processing time: an SPI clock a few times higher is recommended.
+    pub spi_min_hz: u32,
+}
+
+impl FifoPreset {
+    /// Compute the FIFO thresholds and chunk size for a bitrate (in bit/s) and a FIFO size (in bytes)
+    pub fn tuning(&self, bitrate: u32, fifo_size: u16) -> FifoTuning {
+        let bitrate = bitrate.max(1);
+        let fifo = fifo_size as u32;
+        let chunk = match self {
+            FifoPreset::LowLatency => (bitrate / 8000).max(16),
+            FifoPreset::Balanced => fifo / 4,
+            FifoPreset::HighThroughput => fifo / 2,
+        };
+        let chunk = chunk.min(fifo / 2).min(BUFFER_SIZE as u32).max(1);
+        let margin = fifo - chunk;
+        let tx = FifoIrqCfg::new(FifoIrqEn::none().with_low().with_underflow(), margin as u16, fifo as u16);
+        let rx = FifoIrqCfg::new(FifoIrqEn::none().with_high().with_overflow(), 0, chunk as u16);
+        let max_latency_us = (margin as u64 * 8_000_000 / bitrate as u64) as u32;
+        let spi_min_hz = (bitrate as u64 * (chunk + FIFO_CHUNK_OVERHEAD) as u64 / chunk as u64) as u32;
+        FifoTuning { tx, rx, chunk: chunk as u16, max_latency_us, spi_min_hz }
+    }
+}
+
+/// Length of a payload in bits, for protocols with payloads not multiple of a byte.
+/// Bits are stored MSB first: the valid bits of the last byte are its most significant ones
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct BitLen(pub u16);
+
+impl BitLen {
+    /// Number of bytes holding the payload
+    pub fn nb_bytes(&self) -> usize {
+        (self.0 as usize).div_ceil(8)
+    }
+
+    /// Number of valid bits in the last byte (1 to 8, 0 for an empty payload)
+    pub fn last_byte_bits(&self) -> u8 {
+        match self.0 % 8 {
+            0 if self.0 == 0 => 0,
+            0 => 8,
+            n => n as u8,
+        }
+    }
+
+    /// Mask of the valid bits in the last byte
+    pub fn last_byte_mask(&self) -> u8 {
+        !(0xFFu16 >> self.last_byte_bits()) as u8
+    }
+
+    /// Clear the padding bits of the last byte of the payload
+    pub fn pad(&self, payload: &mut [u8]) {
+        let n = self.nb_bytes();
+        if n > 0 && n <= payload.len() {
+            payload[n-1] &= self.last_byte_mask();
+        }
+    }
+}
+
+/// Slice wrapper to log a hexdump of a packet with defmt, truncated to a maximum number of bytes
+#[derive(Clone, Copy)]
+pub struct HexSlice<'a> {
+    data: &'a [u8],
+    max: usize,
+}
+
+impl<'a> HexSlice<'a> {
+    /// Wrap a slice (no truncation)
+    pub fn new(data: &'a [u8]) -> Self {
+        Self { data, max: usize::MAX }
+    }
+
+    /// Limit the number of bytes displayed
+    pub fn with_max(self, max: usize) -> Self {
+        Self { max, ..self }
+    }
+
+    /// Bytes displayed, i.e. truncated to the maximum
+    pub fn shown(&self) -> &'a [u8] {
+        &self.data[..self.data.len().min(self.max)]
+    }
+
+    /// Flag if the slice is truncated
+    pub fn is_truncated(&self) -> bool {
+        self.data.len() > self.max
+    }
+}
+
+#[cfg(feature = "defmt")]
+impl defmt::Format for HexSlice<'_> {
+    fn format(&self, fmt: defmt::Formatter) {
+        let trunc = if self.is_truncated() {".."} else {""};
+        defmt::write!(fmt, "{}B {=[u8]:02x}{=str}", self.data.len(), self.shown(), trunc);
+    }
+}
+
+/// Maximum number of packets tracked in the RX FIFO
+pub const RX_PKT_RING_SIZE: usize = 8;
+
+/// Policy applied to the partial data left in the RX FIFO when a reception is aborted
+/// by a length error (packet too long) or an address error
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum RxErrPolicy {
+    /// Partial data is silently dropped from the FIFO
+    #[default]
+    Discard,
+    /// Partial data is delivered as a normal packet
+    DeliverPartial,
+    /// Partial data is delivered with the partial flag set (see `read_next_packet_info`)
+    DeliverWithFlag,
+}
+
+/// Kind of an entry in the packet ring
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+enum PktKind {
+    #[default]
+    Complete,
+    Partial,
+    Drop,
+}
+
+/// Information on a packet read from the RX FIFO
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct RxPacketInfo {
+    /// Number of bytes read
+    pub len: usize,
+    /// Packet data is incomplete (reception aborted by a length or address error)
+    pub partial: bool,
+}
+
+/// Packet filter called on RxDone with the packet status (length, RSSI, quality) and the interrupts
+/// (e.g. CRC or address error): return true to keep the packet, false to discard it
+pub type RxFilter = fn(&PktStatus, Intr) -> bool;
+
+/// Ring of packet lengths, allowing to keep packet boundaries in the RX FIFO
+#[derive(Debug, Default, Clone, Copy)]
+pub(crate) struct PktLenRing {
+    lens: [(u16, PktKind); RX_PKT_RING_SIZE],
+    rd: usize,
+    count: usize,
+    policy: RxErrPolicy,
+    /// Packet filter
+    filter: Option<RxFilter>,
+    /// Number of packets discarded by the filter
+    nb_filtered: u32,
+}
+
+impl PktLenRing {
+    fn push(&mut self, len: u16) -> bool {
+        self.push_kind(len, PktKind::Complete)
+    }
+
+    fn push_kind(&mut self, len: u16, kind: PktKind) -> bool {
+        if self.count == RX_PKT_RING_SIZE {
+            return false;
+        }
+        self.lens[(self.rd + self.count) % RX_PKT_RING_SIZE] = (len, kind);
+        self.count += 1;
+        true
+    }
+
+    fn peek(&self) -> Option<(u16, PktKind)> {
+        (self.count > 0).then_some(self.lens[self.rd])
+    }
+
+    /// Total number of bytes recorded
+    fn total(&self) -> usize {
+        (0..self.count).map(|i| self.lens[(self.rd + i) % RX_PKT_RING_SIZE].0 as usize).sum()
+    }
+
+    fn pop(&mut self) {
+        if self.count > 0 {
+            self.rd = (self.rd + 1) % RX_PKT_RING_SIZE;
+            self.count -= 1;
+        }
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.count = 0;
+    }
+}
 
 impl<O,SPI, M> Lr2021<O,SPI, M> where
     O: OutputPin, SPI: SpiBus<u8>, M: BusyPin
@@ -169,8 +413,20 @@ impl<O,SPI, M> Lr2021<O,SPI, M> where
         self.cmd_wr_begin(&[0,2]).await?;
         self.spi
             .transfer_in_place(&mut self.buffer.data_mut()[..len]).await
-            .map_err(|_| Lr2021Error::Spi)?;
-        self.nss.set_high().map_err(|_| Lr2021Error::Pin)
+            .map_err(Lr2021Error::spi)?;
+        self.nss.set_high().map_err(Lr2021Error::pin)
+    }
+
+    /// Write a payload of nb_bits bits to the TX FIFO (MSB first), with the padding bits of the last byte cleared
+    /// Return InvalidSize if the payload is too short or longer than the internal buffer
+    pub async fn wr_tx_fifo_bits(&mut self, payload: &[u8], nb_bits: BitLen) -> Result<(), Lr2021Error> {
+        let len = nb_bits.nb_bytes();
+        if len > payload.len() || len > BUFFER_SIZE {
+            return Err(Lr2021Error::InvalidSize);
+        }
+        self.buffer.data_mut()[..len].copy_from_slice(&payload[..len]);
+        nb_bits.pad(self.buffer.data_mut());
+        self.wr_tx_fifo(len).await
     }
 
     /// Clear TX Fifo
@@ -188,7 +444,21 @@ impl<O,SPI, M> Lr2021<O,SPI, M> where
 
     /// Read data from the RX FIFO
     pub async fn rd_rx_fifo_to(&mut self, buffer: &mut[u8]) -> Result<(), Lr2021Error> {
-        self.cmd_data_rw(&[0,1], buffer).await
+        self.cmd_data_rw(&[0,1], buffer).await?;
+        self.log_rx_packet(buffer, None);
+        Ok(())
+    }
+
+    /// Read a payload of nb_bits bits from the RX FIFO (MSB first), with the padding bits of the last byte cleared
+    /// Return the number of bytes read, or InvalidSize if the buffer is too small
+    pub async fn rd_rx_fifo_bits(&mut self, buffer: &mut [u8], nb_bits: BitLen) -> Result<usize, Lr2021Error> {
+        let len = nb_bits.nb_bytes();
+        if len > buffer.len() {
+            return Err(Lr2021Error::InvalidSize);
+        }
+        self.rd_rx_fifo_to(&mut buffer[..len]).await?;
+        nb_bits.pad(buffer);
+        Ok(len)
     }
 
     /// Read data from the RX FIFO to the local buffer
@@ -196,8 +466,72 @@ impl<O,SPI, M> Lr2021<O,SPI, M> where
         self.cmd_wr_begin(&[0,1]).await?;
         self.spi
             .transfer_in_place(&mut self.buffer.data_mut()[..len]).await
-            .map_err(|_| Lr2021Error::Spi)?;
-        self.nss.set_high().map_err(|_| Lr2021Error::Pin)
+            .map_err(Lr2021Error::spi)?;
+        self.nss.set_high().map_err(Lr2021Error::pin)?;
+        self.log_rx_packet(&self.buffer()[..len], None);
+        Ok(())
+    }
+
+    /// Configure the FIFO IRQ thresholds and the chunk size used by `tx_fifo_refill`/`rx_fifo_drain`
+    /// for a streaming preset at a given bitrate (in bit/s). The FIFO size comes from the firmware capabilities.
+    /// Return the configuration applied, including the host latency and SPI clock required
+    pub async fn set_fifo_preset(&mut self, preset: FifoPreset, bitrate: u32) -> Result<FifoTuning, Lr2021Error> {
+        let tuning = preset.tuning(bitrate, self.capabilities().fifo_size);
+        self.set_fifo_irq_cfg(tuning.tx, tuning.rx).await?;
+        self.cache.fifo_chunk = tuning.chunk;
+        Ok(tuning)
+    }
+
+    /// Write the next chunk of data to the TX FIFO, limited to the chunk size (see `set_fifo_preset`)
+    /// and to the free space in the FIFO. Return the number of bytes written
+    pub async fn tx_fifo_refill(&mut self, data: &[u8]) -> Result<usize, Lr2021Error> {
+        let lvl = self.get_tx_fifo_lvl().await? as usize;
+        let free = (self.capabilities().fifo_size as usize).saturating_sub(lvl);
+        let len = data.len().min(free).min(self.fifo_chunk());
+        if len > 0 {
+            self.wr_tx_fifo_from(&data[..len]).await?;
+        }
+        Ok(len)
+    }
+
+    /// Read the next chunk of data from the RX FIFO, limited to the chunk size (see `set_fifo_preset`),
+    /// the FIFO level and the buffer size. Return the number of bytes read
+    pub async fn rx_fifo_drain(&mut self, buffer: &mut [u8]) -> Result<usize, Lr2021Error> {
+        let lvl = self.get_rx_fifo_lvl().await? as usize;
+        let len = buffer.len().min(lvl).min(self.fifo_chunk());
+        if len > 0 {
+            self.rd_rx_fifo_to(&mut buffer[..len]).await?;
+        }
+        Ok(len)
+    }
+
+    /// Chunk size used for streaming (default to the balanced preset)
+    fn fifo_chunk(&self) -> usize {
+        match self.cache.fifo_chunk {
+            0 => FifoPreset::Balanced.tuning(1, self.capabilities().fifo_size).chunk as usize,
+            chunk => chunk as usize,
+        }
+    }
+
+    /// Set the maximum number of bytes logged when reading the RX FIFO (0 to disable, default)
+    /// Only used with the defmt feature
+    pub fn set_hexdump_max(&mut self, max: usize) {
+        self.hexdump_max = max;
+    }
+
+    /// Log a received packet with its length, RSSI (in -0.5dBm) and hexdump truncated to the hexdump max
+    /// Only used with the defmt feature
+    pub fn log_rx_packet(&self, data: &[u8], rssi: Option<u16>) {
+        #[cfg(feature = "defmt")]
+        if self.hexdump_max > 0 {
+            let dump = HexSlice::new(data).with_max(self.hexdump_max);
+            match rssi {
+                Some(rssi) => defmt::info!("[RX] RSSI -{}dBm : {}", rssi >> 1, dump),
+                None => defmt::info!("[RX] {}", dump),
+            }
+        }
+        #[cfg(not(feature = "defmt"))]
+        let _ = (data, rssi);
     }
 
     /// Return number of byte in RX FIFO
@@ -209,8 +543,142 @@ impl<O,SPI, M> Lr2021<O,SPI, M> where
     }
 
     /// Clear RX FIFO
+    /// This also clears the packet boundaries recorded with `rx_fifo_push_pkt`
     pub async fn clear_rx_fifo(&mut self) -> Result<(), Lr2021Error> {
+        self.rx_pkt_lens.clear();
         self.cmd_wr(&clear_rx_fifo_cmd()).await
     }
 
+    /// Record the length of the last packet received in order to keep track of packet boundaries in the RX FIFO.
+    /// Must be called on each RxDone when multiple packets can accumulate in the FIFO (e.g. continuous RX).
+    /// Return false if too many packets are pending: in this case the RX FIFO is cleared to avoid mixing packets.
+    /// The cached packet status and RX statistics are also refreshed (see [`set_auto_stats`](Lr2021::set_auto_stats))
+    pub async fn rx_fifo_push_pkt(&mut self) -> Result<bool, Lr2021Error> {
+        self.auto_stats_update().await?;
+        let len = self.get_rx_pkt_len().await?;
+        if !self.rx_pkt_lens.push(len) {
+            self.clear_rx_fifo().await?;
+            return Ok(false);
+        }
+        Ok(true)
+    }
+
+    /// Set the policy applied to partial data when a reception is aborted by a LenError/AddrError
+    pub fn set_rx_err_policy(&mut self, policy: RxErrPolicy) {
+        self.rx_pkt_lens.policy = policy;
+    }
+
+    /// Return the policy applied to partial data on LenError/AddrError
+    pub fn rx_err_policy(&self) -> RxErrPolicy {
+        self.rx_pkt_lens.policy
+    }
+
+    /// Record the partial data left in the RX FIFO by a reception aborted by a length or address error.
+    /// Must be called on each LenError/AddrError IRQ instead of `rx_fifo_push_pkt`:
+    /// the number of partial bytes is deduced from the FIFO level and the packets already recorded,
+    /// and the entry is handled according to the policy selected with `set_rx_err_policy`.
+    /// Return the number of partial bytes found in the FIFO
+    pub async fn rx_fifo_push_error(&mut self) -> Result<u16, Lr2021Error> {
+        let lvl = self.get_rx_fifo_lvl().await? as usize;
+        let len = lvl.saturating_sub(self.rx_pkt_lens.total()) as u16;
+        if len == 0 {
+            return Ok(0);
+        }
+        let kind = match self.rx_pkt_lens.policy {
+            // Nothing else in the FIFO: simply clear it
+            RxErrPolicy::Discard if self.rx_pkt_lens.count == 0 => {
+                self.clear_rx_fifo().await?;
+                return Ok(len);
+            }
+            RxErrPolicy::Discard => PktKind::Drop,
+            RxErrPolicy::DeliverPartial => PktKind::Complete,
+            RxErrPolicy::DeliverWithFlag => PktKind::Partial,
+        };
+        if !self.rx_pkt_lens.push_kind(len, kind) {
+            self.clear_rx_fifo().await?;
+        }
+        Ok(len)
+    }
+
+    /// Install a packet filter called by [`rx_filter_apply`](Lr2021::rx_filter_apply), None to remove it.
+    /// The filter only sees the packet status, so rejected packets are discarded without reading the payload
+    pub fn set_rx_filter(&mut self, filter: Option<RxFilter>) {
+        self.rx_pkt_lens.filter = filter;
+    }
+
+    /// Run the packet filter on RxDone: a rejected packet is discarded from the RX FIFO
+    /// (FIFO cleared, or packet recorded to be dropped when other packets are pending, see `rx_fifo_push_pkt`).
+    /// Return true if the packet is kept: it is left in the FIFO and must then be read or recorded as usual.
+    /// Always true when no filter is installed (no SPI access)
+    pub async fn rx_filter_apply(&mut self, intr: Intr) -> Result<bool, Lr2021Error> {
+        let Some(filter) = self.rx_pkt_lens.filter else {
+            return Ok(true);
+        };
+        let status = self.get_packet_status().await?;
+        if filter(&status, intr) {
+            return Ok(true);
+        }
+        self.rx_pkt_lens.nb_filtered = self.rx_pkt_lens.nb_filtered.wrapping_add(1);
+        // Nothing else in the FIFO (or ring full): simply clear it
+        if self.rx_pkt_lens.count == 0 || !self.rx_pkt_lens.push_kind(status.len, PktKind::Drop) {
+            self.clear_rx_fifo().await?;
+        }
+        Ok(false)
+    }
+
+    /// Number of packets discarded by the packet filter
+    pub fn rx_filtered_count(&self) -> u32 {
+        self.rx_pkt_lens.nb_filtered
+    }
+
+    /// Number of packets recorded in the RX FIFO
+    pub fn rx_fifo_nb_pkt(&self) -> usize {
+        self.rx_pkt_lens.count
+    }
+
+    /// Read the oldest packet recorded in the RX FIFO (see `rx_fifo_push_pkt`)
+    /// Return the packet length or None if no packet is available.
+    /// An InvalidSize error is returned if the buffer is too small, leaving the packet in the FIFO
+    pub async fn read_next_packet(&mut self, buffer: &mut [u8]) -> Result<Option<usize>, Lr2021Error> {
+        Ok(self.read_next_packet_info(buffer).await?.map(|info| info.len))
+    }
+
+    /// Read the oldest packet recorded in the RX FIFO (see `rx_fifo_push_pkt` and `rx_fifo_push_error`)
+    /// Partial data recorded with the `Discard` policy is dropped from the FIFO before reading.
+    /// Return the packet length and partial flag or None if no packet is available.
+    /// An InvalidSize error is returned if the buffer is too small, leaving the packet in the FIFO
+    pub async fn read_next_packet_info(&mut self, buffer: &mut [u8]) -> Result<Option<RxPacketInfo>, Lr2021Error> {
+        loop {
+            let Some((len, kind)) = self.rx_pkt_lens.peek() else {
+                return Ok(None);
+            };
+            let len = len as usize;
+            if kind == PktKind::Drop {
+                self.drop_rx_fifo(len).await?;
+                self.rx_pkt_lens.pop();
+                continue;
+            }
+            if len > buffer.len() {
+                return Err(Lr2021Error::InvalidSize);
+            }
+            self.rd_rx_fifo_to(&mut buffer[..len]).await?;
+            self.rx_pkt_lens.pop();
+            return Ok(Some(RxPacketInfo{len, partial: kind == PktKind::Partial}));
+        }
+    }
+
+    /// Read and drop bytes from the RX FIFO
+    async fn drop_rx_fifo(&mut self, mut len: usize) -> Result<(), Lr2021Error> {
+        while len > 0 {
+            let n = len.min(self.buffer.data_mut().len());
+            self.cmd_wr_begin(&[0,1]).await?;
+            self.spi
+                .transfer_in_place(&mut self.buffer.data_mut()[..n]).await
+                .map_err(Lr2021Error::spi)?;
+            self.nss.set_high().map_err(Lr2021Error::pin)?;
+            len -= n;
+        }
+        Ok(())
+    }
+
 }
\ No newline at end of file
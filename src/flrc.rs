@@ -12,6 +12,7 @@
 //! use lr2021::radio::PacketType;
 //! use lr2021::flrc::{FlrcBitrate, FlrcCr, FlrcPacketParams, AgcPblLen, SwLen, SwTx, SwMatch, PktFormat, Crc};
 //! use lr2021::PulseShape;
+//! use lr2021::syncword::Syncword;
 //!
 //! // Set packet type to FLRC
 //! lr2021.set_packet_type(PacketType::Flrc).await.expect("Setting packet type");
@@ -21,9 +22,9 @@
 //!     .await.expect("Setting FLRC modulation");
 //!
 //! // Configure syncwords (up to 3 can be configured)
-//! lr2021.set_flrc_syncword(1, 0xCD05CAFE, true).await.expect("Setting syncword 1");
-//! lr2021.set_flrc_syncword(2, 0x12345678, true).await.expect("Setting syncword 2");
-//! lr2021.set_flrc_syncword(3, 0x9ABCDEF0, true).await.expect("Setting syncword 3");
+//! lr2021.set_flrc_syncword(1, Syncword::from_msb_bits(0xCD05CAFE, 32)).await.expect("Setting syncword 1");
+//! lr2021.set_flrc_syncword(2, Syncword::from_msb_bits(0x12345678, 32)).await.expect("Setting syncword 2");
+//! lr2021.set_flrc_syncword(3, Syncword::from_msb_bits(0x9ABCDEF0, 32)).await.expect("Setting syncword 3");
 //!
 //! // Configure packet parameters
 //! let flrc_params = FlrcPacketParams::new(
@@ -42,16 +43,29 @@
 //!
 //! - [`set_flrc_modulation`](Lr2021::set_flrc_modulation) - Configure bitrate, coding rate and pulse shaping
 //! - [`set_flrc_packet`](Lr2021::set_flrc_packet) - Set packet parameters (preamble, syncword, CRC, length)
+//! - [`set_flrc_payload_len`](Lr2021::set_flrc_payload_len) - Change only the payload length of the packet parameters
 //! - [`set_flrc_syncword`](Lr2021::set_flrc_syncword) - Configure one of the three possible syncwords
 //! - [`get_flrc_packet_status`](Lr2021::get_flrc_packet_status) - Get status of last received packet
+//!   (see [`get_packet_status`](Lr2021::get_packet_status) for a format common to all packet types; FLRC reports no SNR/LQI nor frequency error)
 //! - [`get_flrc_rx_stats`](Lr2021::get_flrc_rx_stats) - Get basic reception statistics
+//! - [`flrc_rx_update`](Lr2021::flrc_rx_update) - Count the last packet received in the statistics of its syncword
+//! - [`flrc_sw_stats`](Lr2021::flrc_sw_stats) - Return the statistics of packets received on a syncword
+//! - [`clear_flrc_sw_stats`](Lr2021::clear_flrc_sw_stats) - Reset the statistics per syncword
+//!
+//! With `SwMatch::Match123` a single receiver can serve up to three logical networks, one per syncword:
+//! [`FlrcPacketStatusRsp::matched_syncword`] tells which syncword matched and
+//! [`flrc_rx_update`](Lr2021::flrc_rx_update) measures the traffic of each network separately (packets, CRC errors, RSSI).
 
 use embedded_hal::digital::OutputPin;
 use embedded_hal_async::spi::SpiBus;
 
 pub use super::cmd::cmd_flrc::*;
+use super::status::Intr;
+use super::syncword::Syncword;
 use super::{BusyPin, Lr2021, Lr2021Error, PulseShape};
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct FlrcPacketParams {
     pub agc_pbl_len: AgcPblLen,
     pub sw_len: SwLen,
@@ -68,6 +82,51 @@ impl FlrcPacketParams {
     }
 }
 
+impl FlrcBitrate {
+    /// Bitrate in bit/s
+    pub fn to_bps(&self) -> u32 {
+        match self {
+            FlrcBitrate::Br2600 => 2_600_000,
+            FlrcBitrate::Br2080 => 2_080_000,
+            FlrcBitrate::Br1300 => 1_300_000,
+            FlrcBitrate::Br1040 => 1_040_000,
+            FlrcBitrate::Br0650 => 650_000,
+            FlrcBitrate::Br0520 => 520_000,
+            FlrcBitrate::Br0325 => 325_000,
+            FlrcBitrate::Br0260 => 260_000,
+        }
+    }
+}
+
+impl FlrcPacketStatusRsp {
+    /// Index (1 to 3) of the syncword matched by the last packet received, None if unknown
+    pub fn matched_syncword(&self) -> Option<u8> {
+        match self.sw_num() {
+            n @ 1..=3 => Some(n),
+            _ => None,
+        }
+    }
+}
+
+/// Statistics of the packets received on one syncword
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct FlrcSwStats {
+    /// Number of packets received
+    pub pkt_rx: u32,
+    /// Number of packets received with a CRC error
+    pub crc_error: u32,
+    /// Sum of the average RSSI of each packet (in -0.5dBm)
+    pub rssi_sum: u32,
+}
+
+impl FlrcSwStats {
+    /// Mean RSSI (in -0.5dBm) of the packets received
+    pub fn rssi_mean(&self) -> Option<u16> {
+        (self.pkt_rx > 0).then(|| (self.rssi_sum / self.pkt_rx) as u16)
+    }
+}
+
 impl<O,SPI, M> Lr2021<O,SPI, M> where
     O: OutputPin, SPI: SpiBus<u8>, M: BusyPin
 {
@@ -75,7 +134,9 @@ impl<O,SPI, M> Lr2021<O,SPI, M> where
     /// Set Modulation parameters: raw bitrate, coding rate and pulse shaping
     pub async fn set_flrc_modulation(&mut self, bitrate: FlrcBitrate, cr: FlrcCr, pulse_shape: PulseShape) -> Result<(), Lr2021Error> {
         let req = set_flrc_modulation_params_cmd(bitrate, cr, pulse_shape);
-        self.cmd_wr(&req).await
+        self.cmd_wr(&req).await?;
+        self.cache.flrc_modulation = Some((bitrate, cr, pulse_shape));
+        Ok(())
     }
 
     /// Set FLRC packet parameters: preamble, syncword, header implicit/explicit, CRC and packet length (max 511)
@@ -88,18 +149,46 @@ impl<O,SPI, M> Lr2021<O,SPI, M> where
             params.hdr_format,
             params.crc,
             params.pld_len);
-        self.cmd_wr(&req).await
+        self.cmd_wr(&req).await?;
+        self.cache.flrc_packet = Some(*params);
+        Ok(())
+    }
+
+    /// Change only the payload length of the packet parameters set with `set_flrc_packet` (max 511)
+    /// The chip has no dedicated length command: the last packet parameters are sent again with the new length,
+    /// and nothing is sent if the length is unchanged. Return InvalidParam if `set_flrc_packet` was never called
+    pub async fn set_flrc_payload_len(&mut self, pld_len: u16) -> Result<(), Lr2021Error> {
+        let Some(params) = self.cache.flrc_packet else {
+            return Err(Lr2021Error::InvalidParam);
+        };
+        if pld_len > 511 {
+            return Err(Lr2021Error::InvalidParam);
+        }
+        if params.pld_len == pld_len {
+            return Ok(());
+        }
+        self.set_flrc_packet(&FlrcPacketParams {pld_len, ..params}).await
     }
 
-    /// Configure one of the three possible syncword
-    pub async fn set_flrc_syncword(&mut self, sw_num: u8, syncword: u32, is_16b: bool) -> Result<(), Lr2021Error> {
-        let sw = if is_16b {syncword << 16} else {syncword};
+    /// Configure one of the three possible syncword (16 or 32 bits)
+    pub async fn set_flrc_syncword(&mut self, sw_num: u8, syncword: Syncword) -> Result<(), Lr2021Error> {
+        let is_16b = match syncword.nb_bits() {
+            16 => true,
+            32 => false,
+            _ => return Err(Lr2021Error::InvalidParam),
+        };
+        let sw = (syncword.msb_bits() as u32) << if is_16b {16} else {0};
         let req = set_flrc_syncword_cmd(sw_num, sw);
         let req_s = if is_16b {&req[..5]} else {&req};
-        self.cmd_wr(req_s).await
+        self.cmd_wr(req_s).await?;
+        if sw_num == 1 {
+            self.cache.flrc_syncword = Some(syncword);
+        }
+        Ok(())
     }
 
     /// Return length of last packet received
+    /// RSSI values are raw: use [`get_packet_status`](Lr2021::get_packet_status) or [`rssi_calibrate`](Lr2021::rssi_calibrate) to apply the RSSI calibration
     pub async fn get_flrc_packet_status(&mut self) -> Result<FlrcPacketStatusRsp, Lr2021Error> {
         let req = get_flrc_packet_status_req();
         let mut rsp = FlrcPacketStatusRsp::new();
@@ -115,4 +204,31 @@ impl<O,SPI, M> Lr2021<O,SPI, M> where
         Ok(rsp)
     }
 
-}
\ No newline at end of file
+    /// Read the status of the last packet received and count it in the statistics of its syncword
+    /// The CRC status is taken from the interrupts read on RxDone.
+    /// Return the packet status (the syncword matched is given by [`FlrcPacketStatusRsp::matched_syncword`])
+    pub async fn flrc_rx_update(&mut self, intr: Intr) -> Result<FlrcPacketStatusRsp, Lr2021Error> {
+        let status = self.get_flrc_packet_status().await?;
+        if let Some(n) = status.matched_syncword() {
+            let stats = &mut self.flrc_sw_stats[n as usize - 1];
+            stats.pkt_rx = stats.pkt_rx.wrapping_add(1);
+            stats.rssi_sum = stats.rssi_sum.wrapping_add(status.rssi_avg() as u32);
+            if intr.crc_error() {
+                stats.crc_error = stats.crc_error.wrapping_add(1);
+            }
+        }
+        Ok(status)
+    }
+
+    /// Return the statistics of the packets received on a syncword (1 to 3)
+    pub fn flrc_sw_stats(&self, sw_num: u8) -> Option<FlrcSwStats> {
+        let idx = (sw_num as usize).checked_sub(1)?;
+        self.flrc_sw_stats.get(idx).copied()
+    }
+
+    /// Reset the statistics per syncword
+    pub fn clear_flrc_sw_stats(&mut self) {
+        self.flrc_sw_stats = Default::default();
+    }
+
+}
@@ -11,7 +11,8 @@
 //!
 //! ```rust,no_run
 //! use lr2021::radio::PacketType;
-//! use lr2021::fsk::{PblLenDetect, PldLenUnit, AddrComp, FskPktFormat, Crc, BitOrder};
+//! use lr2021::fsk::{PblLenDetect, PldLenUnit, AddrComp, FskPktFormat, Crc};
+//! use lr2021::syncword::Syncword;
 //! use lr2021::{PulseShape, RxBw};
 //!
 //! // Set packet type to FSK Legacy (compatible with SX126x/SX127x/LR11xx)
@@ -26,11 +27,7 @@
 //! ).await.expect("Setting FSK modulation");
 //!
 //! // Configure syncword (32-bit, LSB first)
-//! lr2021.set_fsk_syncword(
-//!     0xCD05DEAD,            // Syncword value
-//!     BitOrder::LsbFirst,    // Bit order: LSB first
-//!     32                     // Syncword length: 32 bits
-//! ).await.expect("Setting syncword");
+//! lr2021.set_fsk_syncword(Syncword::from_lsb_bits(0xCD05DEAD, 32)).await.expect("Setting syncword");
 //!
 //! // Configure packet parameters
 //! lr2021.set_fsk_packet(
@@ -51,9 +48,14 @@
 //! ### Core Configuration
 //! - [`set_fsk_modulation`](Lr2021::set_fsk_modulation) - Configure bitrate, pulse shaping, bandwidth, and frequency deviation
 //! - [`set_fsk_packet`](Lr2021::set_fsk_packet) - Set packet parameters (preamble, length format, CRC, addressing, whitening)
-//! - [`set_fsk_syncword`](Lr2021::set_fsk_syncword) - Configure synchronization word (value, bit order, length)
+//! - [`set_fsk_payload_len`](Lr2021::set_fsk_payload_len) - Change only the payload length of the packet parameters
+//! - [`set_fsk_syncword`](Lr2021::set_fsk_syncword) - Configure synchronization word (up to 64 bits)
 //! - [`set_fsk_long_prmb_support`](Lr2021::set_fsk_long_prmb_support) - Enable long preamble support in FSK (more than 2048 symbols)
 //!
+//! ### Payload not multiple of a byte
+//! - [`fsk_tx_bits`](Lr2021::fsk_tx_bits) - Send a payload whose length is given in bits (payload length unit in bits)
+//! - [`fsk_rx_bits`](Lr2021::fsk_rx_bits) - Read a received payload whose length is given in bits (payload length unit in bits)
+//!
 //! ### Status and Statistics  
 //! - [`get_fsk_packet_status`](Lr2021::get_fsk_packet_status) - Get packet status information (length, RSSI, LQI)
 //! - [`get_fsk_rx_stats`](Lr2021::get_fsk_rx_stats) - Get reception statistics (packets received, errors, sync failures)
@@ -62,29 +64,90 @@ use embedded_hal::digital::OutputPin;
 use embedded_hal_async::spi::SpiBus;
 
 pub use super::cmd::cmd_fsk::*;
+use super::rxbw::validate_fsk_modulation;
+use super::fifo::BitLen;
+use super::syncword::Syncword;
 use super::{BusyPin, Lr2021, Lr2021Error};
 
+/// FSK packet parameters, as sent by [`set_fsk_packet`](Lr2021::set_fsk_packet)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct FskPacketParams {
+    pub pbl_len_tx: u16,
+    pub pbl_len_detect: PblLenDetect,
+    pub pbl_long: bool,
+    pub pld_len_unit: PldLenUnit,
+    pub addr_comp: AddrComp,
+    pub fsk_pkt_format: FskPktFormat,
+    pub pld_len: u16,
+    pub crc: Crc,
+    pub dc_free: bool,
+}
+
 impl<O,SPI, M> Lr2021<O,SPI, M> where
     O: OutputPin, SPI: SpiBus<u8>, M: BusyPin
 {
 
     /// Set Modulation parameters: raw bitrate, pulse shaping, Bandwidth and fdev
+    /// Return InvalidParam if the combination is invalid (see [`validate_fsk_modulation`])
     pub async fn set_fsk_modulation(&mut self, bitrate: u32, pulse_shape: PulseShape, rx_bw: RxBw, fdev: u32) -> Result<(), Lr2021Error> {
+        validate_fsk_modulation(bitrate, rx_bw, fdev)?;
         let req = set_fsk_modulation_params_cmd(bitrate, pulse_shape, rx_bw, fdev);
-        self.cmd_wr(&req).await
+        self.cmd_wr(&req).await?;
+        self.cache.fsk_bitrate = Some(bitrate);
+        Ok(())
     }
 
     // TODO: add dedicated struct and find a good default set of values
     #[allow(clippy::too_many_arguments)]
     /// Set packet parameters (preamble, length format, CRC, addressing, whitening)
     pub async fn set_fsk_packet(&mut self, pbl_len_tx: u16, pbl_len_detect: PblLenDetect, pbl_long: bool, pld_len_unit: PldLenUnit, addr_comp: AddrComp, fsk_pkt_format: FskPktFormat, pld_len: u16, crc: Crc, dc_free: bool) -> Result<(), Lr2021Error> {
-        let req = set_fsk_packet_params_cmd(pbl_len_tx, pbl_len_detect, pbl_long, pld_len_unit, addr_comp, fsk_pkt_format, pld_len, crc, dc_free);
-        self.cmd_wr(&req).await
+        let params = FskPacketParams {pbl_len_tx, pbl_len_detect, pbl_long, pld_len_unit, addr_comp, fsk_pkt_format, pld_len, crc, dc_free};
+        self.wr_fsk_packet(&params).await
+    }
+
+    /// Send the packet parameters and cache them
+    async fn wr_fsk_packet(&mut self, p: &FskPacketParams) -> Result<(), Lr2021Error> {
+        let req = set_fsk_packet_params_cmd(p.pbl_len_tx, p.pbl_len_detect, p.pbl_long, p.pld_len_unit, p.addr_comp, p.fsk_pkt_format, p.pld_len, p.crc, p.dc_free);
+        self.cmd_wr(&req).await?;
+        self.cache.fsk_packet = Some(*p);
+        Ok(())
+    }
+
+    /// Change only the payload length of the packet parameters set with `set_fsk_packet`
+    /// The chip has no dedicated length command: the last packet parameters are sent again with the new length,
+    /// and nothing is sent if the length is unchanged. Return InvalidParam if `set_fsk_packet` was never called
+    pub async fn set_fsk_payload_len(&mut self, pld_len: u16) -> Result<(), Lr2021Error> {
+        let Some(params) = self.cache.fsk_packet else {
+            return Err(Lr2021Error::InvalidParam);
+        };
+        if params.pld_len == pld_len {
+            return Ok(());
+        }
+        self.wr_fsk_packet(&FskPacketParams {pld_len, ..params}).await
+    }
+
+    /// Send a payload of nb_bits bits: write it in the TX FIFO (padding bits cleared), update the payload length and start TX.
+    /// The packet parameters must have been set with `PldLenUnit::Bits`, so that the payload length is counted in bits
+    /// Timeout is given in LF clock step (1/32.768kHz ~ 30.5us)
+    pub async fn fsk_tx_bits(&mut self, payload: &[u8], nb_bits: BitLen, tx_timeout: u32) -> Result<(), Lr2021Error> {
+        self.clear_tx_fifo().await?;
+        self.wr_tx_fifo_bits(payload, nb_bits).await?;
+        self.set_fsk_payload_len(nb_bits.0).await?;
+        self.set_tx(tx_timeout).await
+    }
+
+    /// Read the last packet received with a payload length in bits (packet parameters set with `PldLenUnit::Bits`)
+    /// Return the payload length in bits: the last byte holds `last_byte_bits()` valid bits, the padding bits are cleared
+    pub async fn fsk_rx_bits(&mut self, buffer: &mut [u8]) -> Result<BitLen, Lr2021Error> {
+        let nb_bits = BitLen(self.get_rx_pkt_len().await?);
+        self.rd_rx_fifo_bits(buffer, nb_bits).await?;
+        Ok(nb_bits)
     }
 
-    /// Configure syncword
-    pub async fn set_fsk_syncword(&mut self, syncword: u64, bit_order: BitOrder, nb_bits: u8) -> Result<(), Lr2021Error> {
-        let req = set_fsk_sync_word_cmd(syncword, bit_order, nb_bits);
+    /// Configure syncword (up to 64 bits)
+    pub async fn set_fsk_syncword(&mut self, syncword: Syncword) -> Result<(), Lr2021Error> {
+        let req = set_fsk_sync_word_cmd(syncword.msb_bits(), BitOrder::MsbFirst, syncword.nb_bits());
         self.cmd_wr(&req).await
     }
 
@@ -94,6 +157,7 @@ impl<O,SPI, M> Lr2021<O,SPI, M> where
     }
 
     /// Return length of last packet received
+    /// RSSI values are raw: use [`get_packet_status`](Lr2021::get_packet_status) or [`rssi_calibrate`](Lr2021::rssi_calibrate) to apply the RSSI calibration
     pub async fn get_fsk_packet_status(&mut self) -> Result<FskPacketStatusRsp, Lr2021Error> {
         let req = get_fsk_packet_status_req();
         let mut rsp = FskPacketStatusRsp::new();
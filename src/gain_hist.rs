@@ -0,0 +1,106 @@
+//! # AGC gain step histogram
+//!
+//! This module accumulates the AGC gain steps used by the receiver into a [`GainHistogram`],
+//! giving an insight into the dynamic range actually used and into the level of nearby interferers:
+//! a receiver mostly detecting at low gain steps sees strong signals (or a strong interferer raising the AGC).
+//!
+//! The chip reports the gain step selected by the AGC only for ranging exchanges
+//! (see [`get_ranging_gain`](Lr2021::get_ranging_gain)): the histogram is updated after each exchange
+//! with [`gain_hist_update_ranging`](Lr2021::gain_hist_update_ranging).
+//! Gain steps obtained by other means (e.g. a manual gain sweep) can be added with [`GainHistogram::record`].
+//!
+//! ## Quick Start
+//!
+//! ```rust,no_run
+//! // After each ranging exchange
+//! lr2021.gain_hist_update_ranging(false).await.expect("GainHist");
+//! // Periodically
+//! let hist = lr2021.gain_histogram();
+//! info!("Gain steps: {} (mean {})", hist.counts(), hist.mean_x10());
+//! lr2021.clear_gain_histogram();
+//! ```
+//!
+//! ## Available Methods
+//! - [`gain_hist_update_ranging`](Lr2021::gain_hist_update_ranging) - Add the gain steps of the last ranging exchange
+//! - [`gain_histogram`](Lr2021::gain_histogram) - Return the gain step histogram
+//! - [`clear_gain_histogram`](Lr2021::clear_gain_histogram) - Reset the gain step histogram
+
+use embedded_hal::digital::OutputPin;
+use embedded_hal_async::spi::SpiBus;
+
+use super::{BusyPin, Lr2021, Lr2021Error};
+
+/// Number of gain steps tracked (0 to 15)
+pub const NB_GAIN_STEPS: usize = 16;
+
+/// Histogram of the AGC gain steps
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct GainHistogram {
+    counts: [u32; NB_GAIN_STEPS],
+}
+
+impl GainHistogram {
+    /// Count a gain step (values above the last step are counted in the last bin)
+    pub fn record(&mut self, step: u8) {
+        let idx = (step as usize).min(NB_GAIN_STEPS - 1);
+        self.counts[idx] = self.counts[idx].saturating_add(1);
+    }
+
+    /// Number of occurrences of each gain step
+    pub fn counts(&self) -> &[u32; NB_GAIN_STEPS] {
+        &self.counts
+    }
+
+    /// Total number of gain steps counted
+    pub fn total(&self) -> u32 {
+        self.counts.iter().fold(0u32, |acc, &c| acc.saturating_add(c))
+    }
+
+    /// Gain steps range used (min, max), None when empty
+    pub fn range(&self) -> Option<(u8, u8)> {
+        let min = self.counts.iter().position(|&c| c > 0)?;
+        let max = self.counts.iter().rposition(|&c| c > 0)?;
+        Some((min as u8, max as u8))
+    }
+
+    /// Mean gain step with one decimal (x10), 0 when empty
+    pub fn mean_x10(&self) -> u16 {
+        let total: u64 = self.counts.iter().map(|&c| c as u64).sum();
+        if total == 0 {
+            return 0;
+        }
+        let sum: u64 = self.counts.iter().enumerate().map(|(i, &c)| i as u64 * c as u64).sum();
+        (sum * 10 / total) as u16
+    }
+
+    /// Reset all counts
+    pub fn clear(&mut self) {
+        self.counts = [0; NB_GAIN_STEPS];
+    }
+}
+
+impl<O,SPI, M> Lr2021<O,SPI, M> where
+    O: OutputPin, SPI: SpiBus<u8>, M: BusyPin
+{
+    /// Add the gain steps used by the last ranging exchange to the histogram
+    /// (the second gain step is only added for extended ranging)
+    pub async fn gain_hist_update_ranging(&mut self, extended: bool) -> Result<(), Lr2021Error> {
+        let rsp = self.get_ranging_gain().await?;
+        self.gain_hist.record(rsp.gain1());
+        if extended {
+            self.gain_hist.record(rsp.gain2());
+        }
+        Ok(())
+    }
+
+    /// Return the gain step histogram
+    pub fn gain_histogram(&self) -> GainHistogram {
+        self.gain_hist
+    }
+
+    /// Reset the gain step histogram
+    pub fn clear_gain_histogram(&mut self) {
+        self.gain_hist.clear();
+    }
+}
@@ -0,0 +1,239 @@
+//! # Hardware-in-the-loop test harness
+//!
+//! This module defines the [`RadioTestHarness`] trait used to run scripted two-node tests
+//! (example applications, CI rigs with real hardware): configure the link, send packets,
+//! check the packets received and the resulting statistics.
+//!
+//! [`DualHarness`] is the reference implementation using two drivers on the same MCU (each on its own SPI bus):
+//! one node transmits while the other receives, and the roles can be swapped.
+//! Any configuration supported by [`RadioConfig`] can be used (LoRa, FLRC), so the same script
+//! covers several protocols.
+//!
+//! ## Quick Start
+//!
+//! ```rust,no_run
+//! use lr2021::harness::{DualHarness, RadioTestHarness};
+//!
+//! let mut harness = DualHarness::new(&mut lr2021_a, &mut lr2021_b);
+//! for cfg in [&cfg_lora, &cfg_flrc] {
+//!     harness.init(cfg).await.expect("Init");
+//!     for i in 0..100u8 {
+//!         let payload = [i; 16];
+//!         harness.tx(&payload).await.expect("Tx");
+//!         harness.rx_expect(&payload, Duration::from_millis(500)).await.expect("Rx");
+//!     }
+//!     harness.assert_stats(99, 1).expect("Packet loss");
+//! }
+//! ```
+//!
+//! ## Available Methods
+//! - [`RadioTestHarness::init`] - Configure both nodes and reset the statistics
+//! - [`RadioTestHarness::tx`] - Send a packet (the receiving node is started before)
+//! - [`RadioTestHarness::rx_expect`] - Wait for a packet and compare it with the expected payload
+//! - [`RadioTestHarness::assert_stats`] - Check the statistics against a minimum of good packets and a maximum of errors
+//! - [`DualHarness::swap_roles`] - Exchange the transmitting and receiving nodes
+
+use embassy_time::{Duration, Instant, Timer};
+use embedded_hal::digital::OutputPin;
+use embedded_hal_async::spi::SpiBus;
+
+use super::config::RadioConfig;
+use super::status::{Intr, IRQ_MASK_CRC_ERROR, IRQ_MASK_HEADER_ERR, IRQ_MASK_LEN_ERROR, IRQ_MASK_RX_DONE, IRQ_MASK_TIMEOUT};
+use super::system::ChipMode;
+use super::{BusyPin, Lr2021, Lr2021Error};
+
+/// Statistics of a test run
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct HarnessStats {
+    /// Packets sent
+    pub tx: u32,
+    /// Packets received matching the expected payload
+    pub rx_ok: u32,
+    /// Packets received with a different payload
+    pub rx_mismatch: u32,
+    /// Packets received with an error (CRC, header, length)
+    pub rx_error: u32,
+    /// Packets not received before the timeout
+    pub rx_timeout: u32,
+}
+
+impl HarnessStats {
+    /// Number of failed receptions (mismatch, error or timeout)
+    pub fn failures(&self) -> u32 {
+        self.rx_mismatch + self.rx_error + self.rx_timeout
+    }
+}
+
+/// Scripted two-node radio test
+pub trait RadioTestHarness {
+    /// Configure the nodes and reset the statistics
+    #[allow(async_fn_in_trait)]
+    async fn init(&mut self, cfg: &RadioConfig) -> Result<(), Lr2021Error>;
+
+    /// Send a packet from the transmitting node
+    #[allow(async_fn_in_trait)]
+    async fn tx(&mut self, payload: &[u8]) -> Result<(), Lr2021Error>;
+
+    /// Wait for a packet on the receiving node and compare it with the expected payload
+    /// Return true if the packet was received and matches
+    #[allow(async_fn_in_trait)]
+    async fn rx_expect(&mut self, expected: &[u8], timeout: Duration) -> Result<bool, Lr2021Error>;
+
+    /// Statistics since the last init
+    fn stats(&self) -> HarnessStats;
+
+    /// Check the statistics: at least min_ok good packets and at most max_failures failed receptions
+    /// Return the statistics on failure
+    fn assert_stats(&self, min_ok: u32, max_failures: u32) -> Result<(), HarnessStats> {
+        let stats = self.stats();
+        if stats.rx_ok >= min_ok && stats.failures() <= max_failures {
+            Ok(())
+        } else {
+            Err(stats)
+        }
+    }
+}
+
+/// Interrupts ending a reception
+const HARNESS_RX_MASK: u32 = IRQ_MASK_RX_DONE | IRQ_MASK_CRC_ERROR | IRQ_MASK_HEADER_ERR | IRQ_MASK_LEN_ERROR | IRQ_MASK_TIMEOUT;
+
+/// Reference harness with two drivers on the same MCU: node A transmits and node B receives (see [`swap_roles`](DualHarness::swap_roles))
+pub struct DualHarness<'a, O1, SPI1, M1: BusyPin, O2, SPI2, M2: BusyPin> {
+    a: &'a mut Lr2021<O1,SPI1, M1>,
+    b: &'a mut Lr2021<O2,SPI2, M2>,
+    /// Node B is transmitting
+    swapped: bool,
+    /// Maximum duration of a transmission
+    tx_timeout: Duration,
+    stats: HarnessStats,
+    buf: [u8; 255],
+}
+
+impl<'a, O1, SPI1, M1, O2, SPI2, M2> DualHarness<'a, O1, SPI1, M1, O2, SPI2, M2> where
+    O1: OutputPin, SPI1: SpiBus<u8>, M1: BusyPin,
+    O2: OutputPin, SPI2: SpiBus<u8>, M2: BusyPin,
+{
+    /// Create a harness with node A transmitting and node B receiving (TX timeout of 2s)
+    pub fn new(a: &'a mut Lr2021<O1,SPI1, M1>, b: &'a mut Lr2021<O2,SPI2, M2>) -> Self {
+        Self { a, b, swapped: false, tx_timeout: Duration::from_secs(2), stats: HarnessStats::default(), buf: [0; 255] }
+    }
+
+    /// Set the maximum duration of a transmission
+    pub fn with_tx_timeout(self, tx_timeout: Duration) -> Self {
+        Self { tx_timeout, ..self }
+    }
+
+    /// Exchange the transmitting and receiving nodes
+    pub fn swap_roles(&mut self) {
+        self.swapped = !self.swapped;
+    }
+
+    /// Node A is the transmitting node
+    pub fn a_is_tx(&self) -> bool {
+        !self.swapped
+    }
+}
+
+/// Outcome of a reception
+enum HarnessRx {
+    /// Packet received (length)
+    Packet(usize),
+    /// Packet received with an error
+    Error,
+    /// No packet before the timeout
+    Timeout,
+}
+
+/// Start a reception on a node, discarding any previous packet
+async fn harness_rx_start<O: OutputPin, SPI: SpiBus<u8>, M: BusyPin>(lr: &mut Lr2021<O,SPI, M>) -> Result<(), Lr2021Error> {
+    lr.set_chip_mode(ChipMode::StandbyRc).await?;
+    lr.clear_rx_fifo().await?;
+    lr.clear_irqs(Intr::new(HARNESS_RX_MASK)).await?;
+    lr.set_rx(0, true).await
+}
+
+/// Wait for the end of a reception and read the packet
+async fn harness_rx_wait<O: OutputPin, SPI: SpiBus<u8>, M: BusyPin>(lr: &mut Lr2021<O,SPI, M>, buf: &mut [u8], timeout: Duration) -> Result<HarnessRx, Lr2021Error> {
+    let start = Instant::now();
+    let intr = loop {
+        let (_, intr) = lr.get_status().await?;
+        if intr.intr_match(HARNESS_RX_MASK) {
+            break intr;
+        }
+        if start.elapsed() >= timeout {
+            lr.set_chip_mode(ChipMode::StandbyRc).await?;
+            return Ok(HarnessRx::Timeout);
+        }
+        Timer::after_micros(500).await;
+    };
+    lr.clear_irqs(Intr::new(HARNESS_RX_MASK)).await?;
+    if intr.intr_match(IRQ_MASK_CRC_ERROR | IRQ_MASK_HEADER_ERR | IRQ_MASK_LEN_ERROR) {
+        lr.clear_rx_fifo().await?;
+        return Ok(HarnessRx::Error);
+    }
+    if !intr.rx_done() {
+        return Ok(HarnessRx::Timeout);
+    }
+    let len = (lr.get_rx_pkt_len().await? as usize).min(buf.len());
+    lr.rd_rx_fifo_to(&mut buf[..len]).await?;
+    lr.clear_rx_fifo().await?;
+    Ok(HarnessRx::Packet(len))
+}
+
+impl<O1, SPI1, M1, O2, SPI2, M2> RadioTestHarness for DualHarness<'_, O1, SPI1, M1, O2, SPI2, M2> where
+    O1: OutputPin, SPI1: SpiBus<u8>, M1: BusyPin,
+    O2: OutputPin, SPI2: SpiBus<u8>, M2: BusyPin,
+{
+    async fn init(&mut self, cfg: &RadioConfig) -> Result<(), Lr2021Error> {
+        self.a.set_chip_mode(ChipMode::StandbyRc).await?;
+        self.b.set_chip_mode(ChipMode::StandbyRc).await?;
+        self.a.apply_config(cfg).await?;
+        self.b.apply_config(cfg).await?;
+        self.stats = HarnessStats::default();
+        Ok(())
+    }
+
+    async fn tx(&mut self, payload: &[u8]) -> Result<(), Lr2021Error> {
+        if self.swapped {
+            harness_rx_start(self.a).await?;
+            self.b.tx_wait_done(payload, self.tx_timeout).await?;
+        } else {
+            harness_rx_start(self.b).await?;
+            self.a.tx_wait_done(payload, self.tx_timeout).await?;
+        }
+        self.stats.tx = self.stats.tx.wrapping_add(1);
+        Ok(())
+    }
+
+    async fn rx_expect(&mut self, expected: &[u8], timeout: Duration) -> Result<bool, Lr2021Error> {
+        let rx = if self.swapped {
+            harness_rx_wait(self.a, &mut self.buf, timeout).await?
+        } else {
+            harness_rx_wait(self.b, &mut self.buf, timeout).await?
+        };
+        let ok = match rx {
+            HarnessRx::Packet(len) if &self.buf[..len] == expected => {
+                self.stats.rx_ok = self.stats.rx_ok.wrapping_add(1);
+                true
+            }
+            HarnessRx::Packet(_) => {
+                self.stats.rx_mismatch = self.stats.rx_mismatch.wrapping_add(1);
+                false
+            }
+            HarnessRx::Error => {
+                self.stats.rx_error = self.stats.rx_error.wrapping_add(1);
+                false
+            }
+            HarnessRx::Timeout => {
+                self.stats.rx_timeout = self.stats.rx_timeout.wrapping_add(1);
+                false
+            }
+        };
+        Ok(ok)
+    }
+
+    fn stats(&self) -> HarnessStats {
+        self.stats
+    }
+}
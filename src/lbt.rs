@@ -0,0 +1,162 @@
+//! # Listen-Before-Talk with audit log
+//!
+//! This module provides Listen-Before-Talk checks based on a LoRa CAD ([`lbt_cad`](Lr2021::lbt_cad))
+//! or on a Clear Channel Assessment with an RSSI threshold ([`lbt_cca`](Lr2021::lbt_cca)).
+//!
+//! Each decision can be recorded in an audit log kept by the driver (timestamp, channel, measured RSSI, decision):
+//! some certifications and customers require such evidence of polite spectrum access.
+//! The log is a ring of the last [`LBT_LOG_SIZE`] decisions, disabled by default,
+//! and is read back with [`lbt_log`](Lr2021::lbt_log). The LBT of the [`repeater`](crate::repeater) is also recorded.
+//!
+//! ## Quick Start
+//!
+//! ```rust,no_run
+//! lr2021.set_lbt_log(true);
+//! // Channel busy if a signal above -85dBm is measured during 5ms
+//! if lr2021.lbt_cca(160_000, -85).await.expect("LBT") {
+//!     lr2021.tx_payload(b"Hello", 0).await.expect("TX");
+//! }
+//! // Send the audit log to the host
+//! for rec in lr2021.lbt_log().iter() {
+//!     info!("{}: {} {} {}", rec.timestamp.as_millis(), rec.rf, rec.rssi, rec.clear);
+//! }
+//! ```
+//!
+//! ## Available Methods
+//! - [`lbt_cad`](Lr2021::lbt_cad) - Check the channel is free with a LoRa CAD
+//! - [`lbt_cca`](Lr2021::lbt_cca) - Check the channel is free with a CCA and an RSSI threshold
+//! - [`set_lbt_log`](Lr2021::set_lbt_log) - Enable/disable the recording of the LBT decisions
+//! - [`lbt_log`](Lr2021::lbt_log) - Audit log of the last LBT decisions
+//! - [`clear_lbt_log`](Lr2021::clear_lbt_log) - Remove all records from the audit log
+
+use embassy_time::Instant;
+use embedded_hal::digital::OutputPin;
+use embedded_hal_async::spi::SpiBus;
+
+use super::lora::LoraCadParams;
+use super::{BusyPin, Lr2021, Lr2021Error};
+
+/// Number of LBT decisions kept in the audit log
+pub const LBT_LOG_SIZE: usize = 16;
+
+/// Channel assessment method
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum LbtMethod {
+    /// LoRa Channel Activity Detection
+    #[default]
+    Cad,
+    /// Clear Channel Assessment with an RSSI threshold
+    Cca,
+}
+
+/// LBT decision recorded in the audit log
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct LbtRecord {
+    /// Time of the decision
+    pub timestamp: Instant,
+    /// RF channel (in Hz), None if not set through the driver
+    pub rf: Option<u32>,
+    /// Maximum RSSI measured (in -0.5dBm), None for a CAD
+    pub rssi: Option<u16>,
+    /// Assessment method
+    pub method: LbtMethod,
+    /// Channel found free
+    pub clear: bool,
+}
+
+/// Ring of the last LBT decisions
+#[derive(Debug, Default, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct LbtLog {
+    records: [Option<LbtRecord>; LBT_LOG_SIZE],
+    /// Index of the next record
+    wr: usize,
+    /// Recording enabled
+    enabled: bool,
+    /// Total number of decisions recorded (including the ones overwritten)
+    total: u32,
+}
+
+impl LbtLog {
+    /// Add a record, overwriting the oldest one when full
+    fn push(&mut self, rec: LbtRecord) {
+        self.records[self.wr] = Some(rec);
+        self.wr = (self.wr + 1) % LBT_LOG_SIZE;
+        self.total = self.total.wrapping_add(1);
+    }
+
+    /// Records from the oldest to the most recent
+    pub fn iter(&self) -> impl Iterator<Item = &LbtRecord> {
+        self.records[self.wr..].iter()
+            .chain(self.records[..self.wr].iter())
+            .flatten()
+    }
+
+    /// Number of records available
+    pub fn len(&self) -> usize {
+        self.records.iter().flatten().count()
+    }
+
+    /// No record available
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Total number of decisions recorded, including the ones overwritten
+    pub fn total(&self) -> u32 {
+        self.total
+    }
+
+    /// Number of channels found busy among the records available
+    pub fn nb_busy(&self) -> usize {
+        self.iter().filter(|r| !r.clear).count()
+    }
+}
+
+impl<O,SPI, M> Lr2021<O,SPI, M> where
+    O: OutputPin, SPI: SpiBus<u8>, M: BusyPin
+{
+    /// Enable/disable the recording of the LBT decisions (records are kept)
+    pub fn set_lbt_log(&mut self, en: bool) {
+        self.lbt_log.enabled = en;
+    }
+
+    /// Audit log of the last LBT decisions
+    pub fn lbt_log(&self) -> &LbtLog {
+        &self.lbt_log
+    }
+
+    /// Remove all records from the audit log
+    pub fn clear_lbt_log(&mut self) {
+        self.lbt_log = LbtLog { enabled: self.lbt_log.enabled, ..LbtLog::default() };
+    }
+
+    /// Record an LBT decision if the log is enabled
+    fn lbt_record(&mut self, method: LbtMethod, rssi: Option<u16>, clear: bool) {
+        if self.lbt_log.enabled {
+            let rec = LbtRecord { timestamp: Instant::now(), rf: self.cache.rf, rssi, method, clear };
+            self.lbt_log.push(rec);
+        }
+    }
+
+    /// Check the channel is free with a LoRa CAD (see [`lora_cad_run`](Lr2021::lora_cad_run)) and record the decision
+    /// Return true if no LoRa activity was detected
+    pub async fn lbt_cad(&mut self, params: &LoraCadParams) -> Result<bool, Lr2021Error> {
+        let clear = !self.lora_cad_run(params).await?;
+        self.lbt_record(LbtMethod::Cad, None, clear);
+        Ok(clear)
+    }
+
+    /// Check the channel is free with a CCA of duration (31.25ns) and record the decision
+    /// The channel is busy if the maximum RSSI measured (with the RSSI calibration applied) is above thr_dbm.
+    /// Note: Chip must be standby or FS before issuing the command
+    pub async fn lbt_cca(&mut self, duration: u32, thr_dbm: i16) -> Result<bool, Lr2021Error> {
+        let rsp = self.set_and_get_cca(duration, None).await?;
+        let rssi = self.rssi_calibrate(rsp.rssi_max());
+        let clear = -((rssi >> 1) as i16) <= thr_dbm;
+        self.lbt_record(LbtMethod::Cca, Some(rssi), clear);
+        Ok(clear)
+    }
+}
@@ -58,26 +58,78 @@
 //!
 //! The driver is organized into several modules:
 //!
-//! - [`cmd`] - Low-level command interface and protocol-specific commands
+//! - [`cmd`] - Low-level command interface and protocol-specific commands, with the [opcode catalog](cmd::opcode::Opcode) used by [`raw_command`](Lr2021::raw_command)
 //! - [`status`] - Status and interrupt handling
 //! - [`system`] - System-level operations (reset, sleep, etc.)
 //! - [`radio`] - Common radio operations
 //! - Protocol modules: [`lora`], [`ble`], [`flrc`], [`fsk`], [`ook`], [`zigbee`], [`zwave`], etc.
+//! - [`timesync`] - Time distribution between nodes
+//! - [`antenna`] - Antenna tuning feedback loop
+//! - [`syncword`] - Syncword representation independent of bit/byte order conventions
+//! - [`wake`] - Wake-on-preamble flow with duty-cycled RX
+//! - [`battery`] - Battery monitoring with EOL detection
+//! - [`thermal`] - Thermal protection for sustained transmissions
+//! - [`quirks`] - Encoding patches and capabilities depending on the firmware version
+//! - [`bitrate`] - Measurement and compensation of the bitrate error
+//! - [`clock`] - Chip mode management balancing current consumption and latency
+//! - [`multilisten`] - Multi-protocol listening by time-slicing RX windows
+//! - [`profile`] - Declarative configuration profiles and presets
+//! - [`rxbw`] - RX bandwidth and FSK/OOK modulation parameter checks
+//! - [`beacon`] - Periodic beacon transmitter with duty-cycle budget
+//! - [`capture`] - Timing capture of the preamble, sync and end of received packets
+//! - [`rssi`] - RSSI filtering strategies (moving average, median, peak hold)
+//! - [`failover`] - Dual-band link failover based on the link health
+//! - [`boards`] - Board support presets (RF switch, TCXO, crystal trimming, PA limits)
+//! - [`config`] - Full radio configuration export/import in a compact binary format
+//! - [`rxboost`] - RX boost profiles and policy enabling the boost on weak links
+//! - [`ranging`] - Fusion of the ranging distance with an RSSI path-loss estimate and NLOS detection
+//! - [`repeater`] - LoRa store-and-forward repeater with hop count and Listen-Before-Talk
+//! - [`dtm`] - BLE Direct Test Mode sequences for RF certification
+//! - [`replay`] - Capture and replay of sub-GHz OOK remotes
+//! - [`telemetry`] - Periodic temperature and battery voltage sampling while the radio is idle
+//! - [`diag`] - Diagnostic snapshot of the chip state and demodulator registers for bug reports
+//! - [`cad_wake`] - Wake-up sequences (long preamble or packet train) for duty-cycled CAD receivers
+//! - [`rng`] - Pluggable random number sources (chip-seeded PRNG) and CSPRNG seeding helper
+//! - [`testvec`] - Expected on-air FSK bits and LoRa nibbles of a packet for bench instruments
+//! - [`gain_hist`] - Histogram of the AGC gain steps for interference analysis
+//! - [`harness`] - Hardware-in-the-loop test harness trait with a reference two-node implementation
+//! - [`autostats`] - Packet status and RX statistics refreshed automatically on RxDone
+//! - [`link_quality`] - Link quality score and trend combining RSSI, SNR/LQI and packet error rate
+//! - [`lbt`] - Listen-Before-Talk checks (CAD or CCA) with an audit log of the decisions
+//! - [`tof`] - Coarse two-way ranging for FSK/FLRC links from the TX/RX timestamps
+//! - [`blanking`] - Automatic LoRa blanking level based on the packet error rate and SNR margin
+//! - [`crc_out`] - Split and verify the CRC bytes output in the RX FIFO (`force_crc_out`) per protocol
+//! - [`rx_decode`] - Host-side decoding of captured chip responses and FIFO dumps for unit tests
+//! - [`bus_stats`] - SPI bus statistics (feature `bus-stats`)
+//! - [`timing_check`] - Protocol timing conformance checks (feature `timing-check`)
+//! - [`shared`] - Driver shared between tasks with prioritized access (feature `embassy-sync`)
+//! - [`fault_inject`] - Synthetic transport failures for robustness testing (feature `fault-inject`)
+//! - [`mock`] - Mock SPI bus and pins capturing the commands and replaying responses for host tests (feature `mock`)
 //!
 //! ## Error Handling
 //!
 //! The driver uses the [`Lr2021Error`] enum for error reporting:
 //!
-//! - `Pin` - GPIO pin operation failed  
-//! - `Spi` - SPI communication error
+//! - `Pin` - GPIO pin operation failed (with the HAL error kind)
+//! - `Spi` - SPI communication error (with the HAL error kind)
 //! - `CmdFail` - LR2021 command execution failed
 //! - `CmdErr` - Invalid command sent to LR2021  
 //! - `BusyTimeout` - Timeout waiting for busy pin
+//! - `Timeout` - Operation did not complete in its expected duration
 //! - `InvalidSize` - Command size exceeds buffer limits
+//! - `InvalidParam` - Invalid parameter or configuration combination
 //!
 //! ## Cargo Features
 //!
 //! - `defmt` - Enable defmt logging support for debugging
+//! - `heapless` - Enable the `rx_queue` module to queue received packets without allocation, and the W-MBus meter whitelist
+//! - `lorawan-crypto` - Enable the `lorawan` module with software AES-128 and LoRaWAN payload encryption/MIC
+//! - `embassy-sync` - Enable the `events` module to publish radio interrupts to multiple tasks, and the `shared` module to share the driver between tasks with priorities
+//! - `stream` - Enable the `stream` module providing a `futures_core::Stream` of received packets
+//! - `bus-stats` - Enable the `bus_stats` module counting commands, bytes and busy wait time on the SPI bus
+//! - `timing-check` - Enable the `timing_check` module measuring protocol timings (BLE T_IFS, ACK turnaround) with the chip timestamps
+//! - `fault-inject` - Enable the `fault_inject` module injecting busy timeouts, command failures or corrupted status (testing only)
+//! - `mock` - Enable the `mock` module providing a simulated SPI bus and pins to run the driver on a host (testing only)
 //!
 //! ## Examples
 //!
@@ -102,16 +154,86 @@ pub mod lrfhss;
 pub mod wmbus;
 pub mod wisun;
 pub mod bpsk_tx;
+pub mod timesync;
+pub mod antenna;
+pub mod syncword;
+pub mod wake;
+pub mod battery;
+pub mod thermal;
+pub mod quirks;
+pub mod bitrate;
+pub mod clock;
+pub mod multilisten;
+pub mod profile;
+pub mod rxbw;
+pub mod beacon;
+pub mod capture;
+pub mod rssi;
+pub mod failover;
+pub mod boards;
+pub mod config;
+pub mod rxboost;
+pub mod ranging;
+pub mod repeater;
+pub mod dtm;
+pub mod replay;
+pub mod telemetry;
+pub mod diag;
+pub mod cad_wake;
+pub mod rng;
+pub mod testvec;
+pub mod gain_hist;
+pub mod harness;
+pub mod autostats;
+pub mod link_quality;
+pub mod lbt;
+pub mod tof;
+pub mod blanking;
+pub mod crc_out;
+pub mod rx_decode;
+#[cfg(feature = "heapless")]
+pub mod rx_queue;
+#[cfg(feature = "embassy-sync")]
+pub mod events;
+#[cfg(feature = "embassy-sync")]
+pub mod shared;
+#[cfg(feature = "stream")]
+pub mod stream;
+#[cfg(feature = "lorawan-crypto")]
+pub mod lorawan;
+#[cfg(feature = "bus-stats")]
+pub mod bus_stats;
+#[cfg(feature = "timing-check")]
+pub mod timing_check;
+#[cfg(feature = "fault-inject")]
+pub mod fault_inject;
+#[cfg(feature = "mock")]
+pub mod mock;
 mod constants;
 
 use core::marker::PhantomData;
 
 use embassy_time::{with_timeout, Duration, Instant, Timer};
-use embedded_hal::digital::{OutputPin, InputPin};
+use embedded_hal::digital::{self, OutputPin, InputPin};
+use embedded_hal::spi;
 use embedded_hal_async::{digital::Wait, spi::SpiBus};
 
 use status::{CmdStatus, Intr, Status};
+use radio::{PaLfMode, PaSel, PacketType, RampSel, RssiCalibration, RxBoost, RxPath, ScanState};
+use lora::{FreqRange, LoraModulationParams, LoraPacketParams};
+use zwave::ZwaveScanCfg;
+use quirks::Quirks;
+use system::{DioConfiguration, TcxoVoltage};
+use wmbus::WmbusPacketParams;
+use zigbee::ZigbeeFcsStats;
+use boards::BoardPreset;
+use flrc::{FlrcBitrate, FlrcCr, FlrcPacketParams, FlrcSwStats};
+use fsk::FskPacketParams;
+use ook::OokPacketParams;
+use syncword::Syncword;
+use gain_hist::GainHistogram;
 pub use cmd::{RxBw, PulseShape}; // Re-export Bandwidth enum as it is used for all packet types
+use cmd::opcode::Opcode;
 
 trait Sealed{}
 #[allow(private_bounds)]
@@ -142,7 +264,7 @@ impl<I: InputPin> BusyPin for BusyBlocking<I> {
     /// Poll busy pin until it goes low
     async fn wait_ready(pin: &mut I, timeout: Duration) -> Result<(), Lr2021Error> {
         let start = Instant::now();
-        while pin.is_high().map_err(|_| Lr2021Error::Pin)? {
+        while pin.is_high().map_err(Lr2021Error::pin)? {
             if start.elapsed() >= timeout {
                 return Err(Lr2021Error::BusyTimeout);
             }
@@ -158,9 +280,9 @@ impl<I: InputPin + Wait> BusyPin for BusyAsync<I> {
     /// Wait for an interrupt on th busy pin to go low (if not already)
     async fn wait_ready(pin: &mut I, timeout: Duration) -> Result<(), Lr2021Error> {
         // Option 1: Use the Wait trait for more efficient waiting
-        if pin.is_high().map_err(|_| Lr2021Error::Pin)? {
+        if pin.is_high().map_err(Lr2021Error::pin)? {
             match with_timeout(timeout, pin.wait_for_low()).await {
-                Ok(_) => Ok(()),
+                Ok(r) => r.map_err(Lr2021Error::pin),
                 Err(_) => Err(Lr2021Error::BusyTimeout),
             }
         } else {
@@ -171,29 +293,49 @@ impl<I: InputPin + Wait> BusyPin for BusyAsync<I> {
 
 /// Size of an the internal buffer set to the largest command (outside those with variable number of parameters)
 const BUFFER_SIZE: usize = 256;
+/// Alignment of the data region of the command buffer (cache line of Cortex-M7, covers DMA constraints of STM32/nRF)
+pub const BUFFER_ALIGN: usize = 32;
+
 /// Command Buffer:
-pub struct CmdBuffer ([u8;BUFFER_SIZE+2]);
+/// two status bytes followed by a data region of 256 bytes, stored contiguously.
+///
+/// The data region (returned by [`data`](CmdBuffer::data), [`data_mut`](CmdBuffer::data_mut) and used for FIFO transfers)
+/// is aligned on [`BUFFER_ALIGN`] bytes and its size is a multiple of it, so that a DMA-driven SPI can use it directly
+/// (no bounce buffer) and cache maintenance on it does not touch any other data.
+/// All SPI transfers done by the driver use a single contiguous slice of this buffer or of the user buffer.
+#[repr(C, align(32))]
+pub struct CmdBuffer {
+    /// Padding placing the data region on the alignment boundary
+    _pad: [u8; BUFFER_ALIGN-2],
+    /// Status bytes and data region
+    bytes: [u8; BUFFER_SIZE+2],
+}
+
+// Data region aligned and with a size multiple of the alignment
+const _: () = assert!(core::mem::align_of::<CmdBuffer>() == BUFFER_ALIGN);
+const _: () = assert!(core::mem::offset_of!(CmdBuffer, bytes) + 2 == BUFFER_ALIGN);
+const _: () = assert!(BUFFER_SIZE.is_multiple_of(BUFFER_ALIGN));
 
 impl CmdBuffer {
     /// Create a zero initialized buffer
     pub fn new() -> Self {
-        CmdBuffer([0;BUFFER_SIZE+2])
+        CmdBuffer { _pad: [0; BUFFER_ALIGN-2], bytes: [0; BUFFER_SIZE+2] }
     }
 
     /// Set first two byte to 0 corresponding to the NOP command
     pub fn nop(&mut self) {
-        self.0[0] = 0;
-        self.0[1] = 0;
+        self.bytes[0] = 0;
+        self.bytes[1] = 0;
     }
 
     /// Return the first two bytes as a status
     pub fn status(&self) -> Status {
-        Status::from_array([self.0[0],self.0[1]])
+        Status::from_array([self.bytes[0],self.bytes[1]])
     }
 
     /// Update the status from a slice of bytes
     pub fn updt_status(&mut self, bytes: &[u8]) {
-        self.0.iter_mut()
+        self.bytes.iter_mut()
             .zip(bytes)
             .take(2)
             .for_each(|(s,&b)| *s = b);
@@ -201,18 +343,31 @@ impl CmdBuffer {
 
     /// Return the command status (Ok, fail, ...)
     pub fn cmd_status(&self) -> CmdStatus {
-        let bits_cmd = (self.0[0] >> 1) & 7;
+        let bits_cmd = (self.bytes[0] >> 1) & 7;
         bits_cmd.into()
     }
 
     /// Give read access to the the last 256 bytes
     pub fn data(&self) -> &[u8] {
-        &self.0[2..]
+        &self.bytes[2..]
     }
 
     /// Give read/write access to the last 256 bytes
     pub fn data_mut(&mut self) -> &mut [u8] {
-        &mut self.0[2..]
+        &mut self.bytes[2..]
+    }
+
+    /// Give read/write access to the first len bytes of the buffer, status included (len clamped to 258)
+    /// Used to receive a response starting with the status
+    pub fn frame_mut(&mut self, len: usize) -> &mut [u8] {
+        &mut self.bytes[..len.min(BUFFER_SIZE+2)]
+    }
+
+    /// Split the buffer in the status bytes (read-only) and the data region (read/write),
+    /// allowing to access both at the same time
+    pub fn parts_mut(&mut self) -> (&[u8], &mut [u8]) {
+        let (status, data) = self.bytes.split_at_mut(2);
+        (status, data)
     }
 }
 
@@ -224,10 +379,228 @@ impl Default for CmdBuffer {
 
 impl AsMut<[u8]> for CmdBuffer {
     fn as_mut(&mut self) -> &mut [u8] {
-        &mut self.0[2..]
+        &mut self.bytes[2..]
     }
 }
 
+/// Configuration cached by the driver
+/// Allows to update some settings without requiring the user to provide again the full configuration
+#[derive(Debug, Default, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct CfgCache {
+    /// Last packet type set
+    packet_type: Option<PacketType>,
+    /// Last LoRa modulation parameters set
+    lora_modulation: Option<LoraModulationParams>,
+    /// Last LoRa packet parameters set
+    lora_packet: Option<LoraPacketParams>,
+    /// Last RX path set
+    rx_path: Option<RxPath>,
+    /// Last RX boost set with the RX path
+    rx_boost: Option<RxBoost>,
+    /// Manual RX gain (0 for automatic)
+    rx_gain: u8,
+    /// Last Z-Wave scan configuration set
+    zwave_scan: Option<ZwaveScanCfg>,
+    /// State of the scan engine
+    scan_state: ScanState,
+    /// Encoding patches for the firmware version
+    quirks: Quirks,
+    /// DIO assignments
+    dio: DioConfiguration,
+    /// Last W-MBus packet parameters set
+    wmbus_packet: Option<WmbusPacketParams>,
+    /// Last TX power and ramp time selection set
+    tx_params: Option<(i8, RampSel)>,
+    /// Last PA selected
+    pa: Option<PaSel>,
+    /// TCXO voltage and start time when configured
+    tcxo: Option<(TcxoVoltage, u32)>,
+    /// LF clock frequency error (in ppm) compensated on sleep and duty-cycle durations
+    lf_ppm: i32,
+    /// Chunk size for FIFO streaming (0 for default)
+    fifo_chunk: u16,
+    /// Zigbee FCS written in the FIFO and checked by software
+    zigbee_fcs_in_fifo: bool,
+    /// Board preset attached to the driver
+    board: Option<BoardPreset>,
+    /// Last RF channel set (in Hz)
+    rf: Option<u32>,
+    /// Last LF PA configuration set (mode, duty-cycle, slices)
+    pa_lf: Option<(PaLfMode, u8, u8)>,
+    /// Last LoRa syncword set (1B notation)
+    lora_syncword: Option<u8>,
+    /// Last FLRC modulation set (bitrate, coding rate, pulse shape)
+    flrc_modulation: Option<(FlrcBitrate, FlrcCr, PulseShape)>,
+    /// Last FLRC packet parameters set
+    flrc_packet: Option<FlrcPacketParams>,
+    /// Last FLRC syncword 1 set
+    flrc_syncword: Option<Syncword>,
+    /// Crystal tolerance of the link (in ppm) used to select the LoRa frequency range
+    xtal_ppm: Option<u16>,
+    /// Last LoRa detection frequency range set
+    lora_freq_range: Option<FreqRange>,
+    /// Last FSK packet parameters set
+    fsk_packet: Option<FskPacketParams>,
+    /// Last OOK packet parameters set
+    ook_packet: Option<OokPacketParams>,
+    /// Last FSK bitrate set (in bit/s)
+    fsk_bitrate: Option<u32>,
+    /// Last OOK bitrate set (in bit/s)
+    ook_bitrate: Option<u32>,
+}
+
+impl CfgCache {
+    /// Last packet type set
+    pub fn packet_type(&self) -> Option<PacketType> {
+        self.packet_type
+    }
+
+    /// Last LoRa modulation parameters set
+    pub fn lora_modulation(&self) -> Option<LoraModulationParams> {
+        self.lora_modulation
+    }
+
+    /// Last LoRa packet parameters set
+    pub fn lora_packet(&self) -> Option<LoraPacketParams> {
+        self.lora_packet
+    }
+
+    /// Last RX path set
+    pub fn rx_path(&self) -> Option<RxPath> {
+        self.rx_path
+    }
+
+    /// Last RX boost set with the RX path
+    pub fn rx_boost(&self) -> Option<RxBoost> {
+        self.rx_boost
+    }
+
+    /// Manual RX gain (0 for automatic)
+    pub fn rx_gain(&self) -> u8 {
+        self.rx_gain
+    }
+
+    /// Last Z-Wave scan configuration set
+    pub fn zwave_scan(&self) -> Option<ZwaveScanCfg> {
+        self.zwave_scan
+    }
+
+    /// State of the scan engine
+    pub fn scan_state(&self) -> ScanState {
+        self.scan_state
+    }
+
+    /// Encoding patches for the firmware version
+    pub fn quirks(&self) -> Quirks {
+        self.quirks
+    }
+
+    /// DIO assignments
+    pub fn dio(&self) -> DioConfiguration {
+        self.dio
+    }
+
+    /// Last W-MBus packet parameters set
+    pub fn wmbus_packet(&self) -> Option<WmbusPacketParams> {
+        self.wmbus_packet
+    }
+
+    /// Last TX power and ramp time selection set
+    pub fn tx_params(&self) -> Option<(i8, RampSel)> {
+        self.tx_params
+    }
+
+    /// Last PA selected
+    pub fn pa(&self) -> Option<PaSel> {
+        self.pa
+    }
+
+    /// TCXO voltage and start time when configured
+    pub fn tcxo(&self) -> Option<(TcxoVoltage, u32)> {
+        self.tcxo
+    }
+
+    /// LF clock frequency error (in ppm) compensated on sleep and duty-cycle durations
+    pub fn lf_ppm(&self) -> i32 {
+        self.lf_ppm
+    }
+
+    /// Chunk size for FIFO streaming (0 for default)
+    pub fn fifo_chunk(&self) -> u16 {
+        self.fifo_chunk
+    }
+
+    /// Zigbee FCS written in the FIFO and checked by software
+    pub fn zigbee_fcs_in_fifo(&self) -> bool {
+        self.zigbee_fcs_in_fifo
+    }
+
+    /// Board preset attached to the driver
+    pub fn board(&self) -> Option<BoardPreset> {
+        self.board
+    }
+
+    /// Last RF channel set (in Hz)
+    pub fn rf(&self) -> Option<u32> {
+        self.rf
+    }
+
+    /// Last LF PA configuration set (mode, duty-cycle, slices)
+    pub fn pa_lf(&self) -> Option<(PaLfMode, u8, u8)> {
+        self.pa_lf
+    }
+
+    /// Last LoRa syncword set (1B notation)
+    pub fn lora_syncword(&self) -> Option<u8> {
+        self.lora_syncword
+    }
+
+    /// Last FLRC modulation set (bitrate, coding rate, pulse shape)
+    pub fn flrc_modulation(&self) -> Option<(FlrcBitrate, FlrcCr, PulseShape)> {
+        self.flrc_modulation
+    }
+
+    /// Last FLRC packet parameters set
+    pub fn flrc_packet(&self) -> Option<FlrcPacketParams> {
+        self.flrc_packet
+    }
+
+    /// Last FLRC syncword 1 set
+    pub fn flrc_syncword(&self) -> Option<Syncword> {
+        self.flrc_syncword
+    }
+
+    /// Crystal tolerance of the link (in ppm) used to select the LoRa frequency range
+    pub fn xtal_ppm(&self) -> Option<u16> {
+        self.xtal_ppm
+    }
+
+    /// Last LoRa detection frequency range set
+    pub fn lora_freq_range(&self) -> Option<FreqRange> {
+        self.lora_freq_range
+    }
+
+    /// Last FSK packet parameters set
+    pub fn fsk_packet(&self) -> Option<FskPacketParams> {
+        self.fsk_packet
+    }
+
+    /// Last OOK packet parameters set
+    pub fn ook_packet(&self) -> Option<OokPacketParams> {
+        self.ook_packet
+    }
+
+    /// Last FSK bitrate set (in bit/s)
+    pub fn fsk_bitrate(&self) -> Option<u32> {
+        self.fsk_bitrate
+    }
+
+    /// Last OOK bitrate set (in bit/s)
+    pub fn ook_bitrate(&self) -> Option<u32> {
+        self.ook_bitrate
+    }
+}
 
 /// LR2021 Device
 pub struct Lr2021<O,SPI, M: BusyPin> {
@@ -241,35 +614,98 @@ pub struct Lr2021<O,SPI, M: BusyPin> {
     nss: O,
     /// Buffer to store SPI commands/response
     buffer: CmdBuffer,
+    /// Configuration cache
+    cache: CfgCache,
+    /// Length of packets pending in the RX FIFO
+    rx_pkt_lens: fifo::PktLenRing,
+    /// Board RSSI calibration
+    rssi_cal: RssiCalibration,
+    /// Maximum number of bytes logged when reading the RX FIFO
+    hexdump_max: usize,
+    /// Automatic drop of LoRa packets received with error
+    rx_drop: lora::RxAutoDrop,
+    /// Packet status and RX statistics refreshed on RxDone
+    auto_stats: autostats::AutoStats,
+    /// Audit log of the LBT decisions
+    lbt_log: lbt::LbtLog,
+    /// Number of commands sent (wrapping)
+    cmd_count: u32,
+    /// FLRC statistics per syncword matched
+    flrc_sw_stats: [FlrcSwStats; 3],
+    /// Histogram of the AGC gain steps
+    gain_hist: GainHistogram,
+    /// Zigbee FCS checked by software
+    zigbee_fcs_stats: ZigbeeFcsStats,
+    /// SPI bus statistics
+    #[cfg(feature = "bus-stats")]
+    bus_stats: bus_stats::BusStats,
+    /// Faults injected in the transport layer
+    #[cfg(feature = "fault-inject")]
+    fault_inject: fault_inject::FaultInjector,
 }
 
 /// Error using the LR2021
 #[derive(Debug, Clone, Copy)]
-#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum Lr2021Error {
     /// Unable to Set/Get a pin level
-    Pin,
+    Pin(digital::ErrorKind),
     /// Unable to use SPI
-    Spi,
+    Spi(spi::ErrorKind),
     /// Last command failed
     CmdFail,
     /// Last command was invalid
     CmdErr,
     /// Timeout while waiting for busy
     BusyTimeout,
+    /// Operation (or wait for the driver) did not complete in its expected duration
+    Timeout,
     /// Command with invalid size (>18B)
     InvalidSize,
+    /// Invalid parameter or configuration combination
+    InvalidParam,
+    /// Command rejected because the firmware does not support the feature (see [`quirks::Feature`])
+    Unsupported,
     /// Unknown error
     Unknown,
 }
 
+impl Lr2021Error {
+    /// Convert a pin error, keeping its kind
+    pub(crate) fn pin<E: digital::Error>(err: E) -> Self {
+        Lr2021Error::Pin(err.kind())
+    }
+
+    /// Convert a SPI error, keeping its kind
+    pub(crate) fn spi<E: spi::Error>(err: E) -> Self {
+        Lr2021Error::Spi(err.kind())
+    }
+}
+
+#[cfg(feature = "defmt")]
+impl defmt::Format for Lr2021Error {
+    fn format(&self, fmt: defmt::Formatter) {
+        match self {
+            Lr2021Error::Pin(kind) => defmt::write!(fmt, "Pin({})", defmt::Debug2Format(kind)),
+            Lr2021Error::Spi(kind) => defmt::write!(fmt, "Spi({})", defmt::Debug2Format(kind)),
+            Lr2021Error::CmdFail => defmt::write!(fmt, "CmdFail"),
+            Lr2021Error::CmdErr => defmt::write!(fmt, "CmdErr"),
+            Lr2021Error::BusyTimeout => defmt::write!(fmt, "BusyTimeout"),
+            Lr2021Error::Timeout => defmt::write!(fmt, "Timeout"),
+            Lr2021Error::InvalidSize => defmt::write!(fmt, "InvalidSize"),
+            Lr2021Error::InvalidParam => defmt::write!(fmt, "InvalidParam"),
+            Lr2021Error::Unsupported => defmt::write!(fmt, "Unsupported"),
+            Lr2021Error::Unknown => defmt::write!(fmt, "Unknown"),
+        }
+    }
+}
+
 // Create driver with busy pin not implementing wait
 impl<I,O,SPI> Lr2021<O,SPI, BusyBlocking<I>> where
     I: InputPin, O: OutputPin, SPI: SpiBus<u8>
 {
     /// Create a LR2021 Device with blocking access on the busy pin
     pub fn new_blocking(nreset: O, busy: I, spi: SPI, nss: O) -> Self {
-        Self { nreset, busy, spi, nss, buffer: CmdBuffer::new()}
+        Self::with_parts(nreset, busy, spi, nss)
     }
 
 }
@@ -280,7 +716,20 @@ impl<I,O,SPI> Lr2021<O,SPI, BusyAsync<I>> where
 {
     /// Create a LR2021 Device with async busy pin
     pub fn new(nreset: O, busy: I, spi: SPI, nss: O) -> Self {
-        Self { nreset, busy, spi, nss, buffer: CmdBuffer::new()}
+        Self::with_parts(nreset, busy, spi, nss)
+    }
+}
+
+impl<O,SPI, M: BusyPin> Lr2021<O,SPI, M> {
+    /// Create the driver from its pins and SPI bus, with an empty configuration cache
+    fn with_parts(nreset: O, busy: M::Pin, spi: SPI, nss: O) -> Self {
+        Self { nreset, busy, spi, nss, buffer: CmdBuffer::new(), cache: CfgCache::default(), rx_pkt_lens: fifo::PktLenRing::default(), rssi_cal: RssiCalibration::default(), hexdump_max: 0, rx_drop: lora::RxAutoDrop::default(), auto_stats: autostats::AutoStats::default(), lbt_log: lbt::LbtLog::default(), cmd_count: 0,
+            flrc_sw_stats: Default::default(), gain_hist: GainHistogram::default(), zigbee_fcs_stats: ZigbeeFcsStats::default(),
+            #[cfg(feature = "bus-stats")]
+            bus_stats: bus_stats::BusStats::default(),
+            #[cfg(feature = "fault-inject")]
+            fault_inject: fault_inject::FaultInjector::default(),
+        }
     }
 }
 
@@ -290,10 +739,13 @@ impl<O,SPI, M> Lr2021<O,SPI, M> where
 
     /// Reset the chip
     pub async fn reset(&mut self) -> Result<(), Lr2021Error> {
-        self.nreset.set_low().map_err(|_| Lr2021Error::Pin)?;
+        self.nreset.set_low().map_err(Lr2021Error::pin)?;
         Timer::after_millis(10).await;
-        self.nreset.set_high().map_err(|_| Lr2021Error::Pin)?;
+        self.nreset.set_high().map_err(Lr2021Error::pin)?;
         Timer::after_millis(10).await;
+        // All DIO are back to their default function and the TCXO is not configured anymore
+        self.cache.dio = DioConfiguration::default();
+        self.cache.tcxo = None;
         Ok(())
     }
 
@@ -317,6 +769,17 @@ impl<O,SPI, M> Lr2021<O,SPI, M> where
         self.buffer.data_mut()
     }
 
+    /// Split access to the internal buffer: last status (read-only) and data region (read/write, aligned on [`BUFFER_ALIGN`] bytes)
+    pub fn buffer_parts(&mut self) -> (Status, &mut [u8]) {
+        let (status, data) = self.buffer.parts_mut();
+        (Status::from_array([status[0], status[1]]), data)
+    }
+
+    /// Configuration cached by the driver (packet type, packet parameters, ...)
+    pub fn cfg_cache(&self) -> &CfgCache {
+        &self.cache
+    }
+
     /// Last captured interrupt status
     /// Note: might be incomplete if last command was less than 6 bytes
     pub fn last_intr(&self) -> Intr {
@@ -325,7 +788,12 @@ impl<O,SPI, M> Lr2021<O,SPI, M> where
 
     /// Wait for LR2021 to be ready for a command, i.e. busy pin low
     pub async fn wait_ready(&mut self, timeout: Duration) -> Result<(), Lr2021Error> {
-        M::wait_ready(&mut self.busy, timeout).await
+        #[cfg(feature = "bus-stats")]
+        let start = Instant::now();
+        let res = M::wait_ready(&mut self.busy, timeout).await;
+        #[cfg(feature = "bus-stats")]
+        self.bus_stats.add_busy(start, matches!(res, Err(Lr2021Error::BusyTimeout)));
+        res
     }
 
     /// Write the beginning of a command, allowing to fill with variable length fields
@@ -333,12 +801,25 @@ impl<O,SPI, M> Lr2021<O,SPI, M> where
         if req.len() > BUFFER_SIZE {
             return Err(Lr2021Error::InvalidSize);
         }
+        #[cfg(feature = "fault-inject")]
+        let fault = self.fault_inject.next_cmd();
+        #[cfg(feature = "fault-inject")]
+        if fault == Some(fault_inject::Fault::BusyTimeout) {
+            return Err(Lr2021Error::BusyTimeout);
+        }
         self.wait_ready(Duration::from_millis(100)).await?;
-        self.nss.set_low().map_err(|_| Lr2021Error::Pin)?;
-        let rsp_buf = &mut self.buffer.0[..req.len()];
+        #[cfg(feature = "bus-stats")]
+        self.bus_stats.add_cmd(req.len());
+        self.cmd_count = self.cmd_count.wrapping_add(1);
+        self.nss.set_low().map_err(Lr2021Error::pin)?;
+        let rsp_buf = self.buffer.frame_mut(req.len());
         self.spi
             .transfer(rsp_buf, req).await
-            .map_err(|_| Lr2021Error::Spi)?;
+            .map_err(Lr2021Error::spi)?;
+        #[cfg(feature = "fault-inject")]
+        if let Some(fault) = fault {
+            fault_inject::FaultInjector::corrupt(fault, self.buffer.frame_mut(2));
+        }
         self.buffer.cmd_status().check()
     }
 
@@ -346,7 +827,7 @@ impl<O,SPI, M> Lr2021<O,SPI, M> where
     pub async fn cmd_wr(&mut self, req: &[u8]) -> Result<(), Lr2021Error> {
         // #[cfg(feature = "defmt")]{defmt::info!("[CMD WR] {:02x}", req);}
         self.cmd_wr_begin(req).await?;
-        self.nss.set_high().map_err(|_| Lr2021Error::Pin)
+        self.nss.set_high().map_err(Lr2021Error::pin)
     }
 
     /// Write a command and read response
@@ -357,11 +838,13 @@ impl<O,SPI, M> Lr2021<O,SPI, M> where
         // Some command can have large delay: temperature measurement with highest resolution (13b) takes more than 270us
         self.wait_ready(Duration::from_millis(1)).await?;
         // Read response by transfering a buffer starting with two 0 and replacing it by the read bytes
-        self.nss.set_low().map_err(|_| Lr2021Error::Pin)?;
+        #[cfg(feature = "bus-stats")]
+        self.bus_stats.add_bytes(rsp.len());
+        self.nss.set_low().map_err(Lr2021Error::pin)?;
         self.spi
             .transfer_in_place(rsp).await
-            .map_err(|_| Lr2021Error::Spi)?;
-        self.nss.set_high().map_err(|_| Lr2021Error::Pin)?;
+            .map_err(Lr2021Error::spi)?;
+        self.nss.set_high().map_err(Lr2021Error::pin)?;
         // #[cfg(feature = "defmt")]{defmt::info!("[CMD RD] {:02x} => {:02x}", req, rsp);}
         // Save the first two bytes from the response to keep the command status
         self.buffer.updt_status(rsp);
@@ -372,31 +855,52 @@ impl<O,SPI, M> Lr2021<O,SPI, M> where
     /// Any feedback data will be available in side the local buffer
     pub async fn cmd_data_wr(&mut self, opcode: &[u8], data: &[u8]) -> Result<(), Lr2021Error> {
         self.cmd_wr_begin(opcode).await?;
+        #[cfg(feature = "bus-stats")]
+        self.bus_stats.add_bytes(data.len());
         let rsp = &mut self.buffer.data_mut()[..data.len()];
         self.spi
             .transfer(rsp, data).await
-            .map_err(|_| Lr2021Error::Spi)?;
-        self.nss.set_high().map_err(|_| Lr2021Error::Pin)
+            .map_err(Lr2021Error::spi)?;
+        self.nss.set_high().map_err(Lr2021Error::pin)
     }
 
     /// Write a command with variable length payload, and save result provided buffer
     pub async fn cmd_data_rw(&mut self, opcode: &[u8], data: &mut [u8]) -> Result<(), Lr2021Error> {
         self.cmd_wr_begin(opcode).await?;
+        #[cfg(feature = "bus-stats")]
+        self.bus_stats.add_bytes(data.len());
         self.spi
             .transfer_in_place(data).await
-            .map_err(|_| Lr2021Error::Spi)?;
-        self.nss.set_high().map_err(|_| Lr2021Error::Pin)
+            .map_err(Lr2021Error::spi)?;
+        self.nss.set_high().map_err(Lr2021Error::pin)
     }
 
-    /// Send content of the local buffer as a command
+    /// Send content of the local buffer as a command and check the status received
     pub async fn cmd_buf_wr(&mut self, len: usize) -> Result<(), Lr2021Error> {
         // #[cfg(feature = "defmt")]{defmt::info!("[CMD BUF WR] {:02x}", self.buffer.data_mut()[..len]);}
+        #[cfg(feature = "fault-inject")]
+        let fault = self.fault_inject.next_cmd();
+        #[cfg(feature = "fault-inject")]
+        if fault == Some(fault_inject::Fault::BusyTimeout) {
+            return Err(Lr2021Error::BusyTimeout);
+        }
         self.wait_ready(Duration::from_millis(100)).await?;
-        self.nss.set_low().map_err(|_| Lr2021Error::Pin)?;
+        #[cfg(feature = "bus-stats")]
+        self.bus_stats.add_cmd(len);
+        self.cmd_count = self.cmd_count.wrapping_add(1);
+        self.nss.set_low().map_err(Lr2021Error::pin)?;
         self.spi
             .transfer_in_place(&mut self.buffer.as_mut()[..len]).await
-            .map_err(|_| Lr2021Error::Spi)?;
-        self.nss.set_high().map_err(|_| Lr2021Error::Pin)
+            .map_err(Lr2021Error::spi)?;
+        self.nss.set_high().map_err(Lr2021Error::pin)?;
+        // Status was received in place of the first two bytes of the command: save it
+        #[cfg(feature = "fault-inject")]
+        if let Some(fault) = fault {
+            fault_inject::FaultInjector::corrupt(fault, self.buffer.data_mut());
+        }
+        let status = [self.buffer.data()[0], self.buffer.data()[1]];
+        self.buffer.updt_status(&status);
+        self.buffer.cmd_status().check()
     }
 
     /// Send content of the local buffer as a command and read a response in the provided buffer
@@ -406,21 +910,40 @@ impl<O,SPI, M> Lr2021<O,SPI, M> where
         // Some command can have large delay: temperature measurement with highest resolution (13b) takes more than 270us
         self.wait_ready(Duration::from_millis(1)).await?;
         // Read response by transfering a buffer full of 0 and replacing it by the read bytes
-        self.nss.set_low().map_err(|_| Lr2021Error::Pin)?;
+        #[cfg(feature = "bus-stats")]
+        self.bus_stats.add_bytes(rsp.len());
+        self.nss.set_low().map_err(Lr2021Error::pin)?;
         self.spi
             .transfer_in_place(rsp).await
-            .map_err(|_| Lr2021Error::Spi)?;
-        self.nss.set_high().map_err(|_| Lr2021Error::Pin)?;
+            .map_err(Lr2021Error::spi)?;
+        self.nss.set_high().map_err(Lr2021Error::pin)?;
         // Save the first two bytes from the response to keep the command status
         self.buffer.updt_status(rsp);
         self.buffer.cmd_status().check()
     }
 
+    /// Send a command not wrapped by the driver, identified by its opcode, and read the response if any
+    /// The response buffer must have the size of the expected response (including the two status bytes) or be empty
+    pub async fn raw_command(&mut self, op: Opcode, params: &[u8], rsp: &mut [u8]) -> Result<(), Lr2021Error> {
+        let len = params.len() + 2;
+        if len > BUFFER_SIZE {
+            return Err(Lr2021Error::InvalidSize);
+        }
+        let buffer = self.buffer.as_mut();
+        buffer[..2].copy_from_slice(&op.to_bytes());
+        buffer[2..len].copy_from_slice(params);
+        if rsp.is_empty() {
+            self.cmd_buf_wr(len).await
+        } else {
+            self.cmd_buf_rd(len, rsp).await
+        }
+    }
+
     /// Wake-up the chip from a sleep mode (Set NSS low until busy goes low)
     pub async fn wake_up(&mut self) -> Result<(), Lr2021Error> {
-        self.nss.set_low().map_err(|_| Lr2021Error::Pin)?;
+        self.nss.set_low().map_err(Lr2021Error::pin)?;
         self.wait_ready(Duration::from_millis(100)).await?;
-        self.nss.set_high().map_err(|_| Lr2021Error::Pin)
+        self.nss.set_high().map_err(Lr2021Error::pin)
     }
 
 }
@@ -0,0 +1,241 @@
+//! # Link quality estimator
+//!
+//! This module provides [`LinkQuality`], an aggregator turning the packet statuses (RSSI, SNR/LQI)
+//! and the RX statistics deltas (packet error rate) into a single smoothed score from 0 (no link) to 100 (perfect link)
+//! with a [`Trend`], usable uniformly across protocols to drive ADR, link failover or UI indicators.
+//!
+//! Each metric is mapped linearly to 0-100 between a floor and a ceiling (see [`LinkQualityCfg`]),
+//! and the available metrics are combined with their weights: a metric not reported yet
+//! (e.g. no SNR/LQI for FLRC, no statistics read) is simply ignored.
+//! The score is smoothed with an exponential moving average and compared with a slower average to give the trend.
+//!
+//! ## Quick Start
+//!
+//! ```rust,no_run
+//! use lr2021::link_quality::{LinkQuality, LinkQualityCfg};
+//!
+//! let mut lq = LinkQuality::new(LinkQualityCfg::default());
+//! // On each RxDone
+//! lr2021.link_quality_update(&mut lq).await.expect("LinkQuality");
+//! if let Some(score) = lq.score() {
+//!     info!("Link quality {}% ({})", score, lq.trend());
+//! }
+//! ```
+//!
+//! ## Available Methods
+//! - [`LinkQuality::update_packet`] - Add the status of a received packet (RSSI, SNR/LQI)
+//! - [`LinkQuality::update_stats`] - Add RX statistics: the packet error rate is computed from the previous statistics
+//! - [`LinkQuality::score`] - Smoothed score (0-100)
+//! - [`LinkQuality::trend`] - Score trend (improving, stable, degrading)
+//! - [`link_quality_update`](Lr2021::link_quality_update) - Read the packet status and RX statistics and update an estimator
+
+use embedded_hal::digital::OutputPin;
+use embedded_hal_async::spi::SpiBus;
+
+use super::autostats::RxStats;
+use super::radio::PktStatus;
+use super::{BusyPin, Lr2021, Lr2021Error};
+
+/// Mapping and weights of the metrics
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct LinkQualityCfg {
+    /// RSSI giving a score of 0 (in -0.5dBm)
+    pub rssi_floor: u16,
+    /// RSSI giving a score of 100 (in -0.5dBm)
+    pub rssi_ceil: u16,
+    /// SNR/LQI giving a score of 0 (in 0.25dB)
+    pub quality_floor: i16,
+    /// SNR/LQI giving a score of 100 (in 0.25dB)
+    pub quality_ceil: i16,
+    /// Packet error rate giving a score of 0 (in percent)
+    pub per_max: u8,
+    /// Weight of the RSSI
+    pub w_rssi: u8,
+    /// Weight of the SNR/LQI
+    pub w_quality: u8,
+    /// Weight of the packet error rate
+    pub w_per: u8,
+    /// Smoothing factor of the score: each new value has a weight of 1/2^shift (max 6)
+    pub shift: u8,
+    /// Difference between the score and its slow average needed to report a trend
+    pub trend_hyst: u8,
+}
+
+impl Default for LinkQualityCfg {
+    /// RSSI from -120dBm to -80dBm, SNR from -20dB to +10dB, PER up to 50%, weights 40/30/30
+    fn default() -> Self {
+        Self {
+            rssi_floor: 240, rssi_ceil: 160,
+            quality_floor: -80, quality_ceil: 40,
+            per_max: 50,
+            w_rssi: 40, w_quality: 30, w_per: 30,
+            shift: 2, trend_hyst: 5,
+        }
+    }
+}
+
+impl LinkQualityCfg {
+    /// Set the RSSI range (in -0.5dBm) mapped to 0-100
+    pub fn with_rssi(self, floor: u16, ceil: u16) -> Self {
+        Self { rssi_floor: floor, rssi_ceil: ceil, ..self }
+    }
+
+    /// Set the SNR/LQI range (in 0.25dB) mapped to 0-100
+    pub fn with_quality(self, floor: i16, ceil: i16) -> Self {
+        Self { quality_floor: floor, quality_ceil: ceil, ..self }
+    }
+
+    /// Set the weights of the RSSI, SNR/LQI and packet error rate
+    pub fn with_weights(self, w_rssi: u8, w_quality: u8, w_per: u8) -> Self {
+        Self { w_rssi, w_quality, w_per, ..self }
+    }
+}
+
+/// Evolution of the link quality
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Trend {
+    /// Score increasing
+    Improving,
+    /// Score stable (or not enough samples)
+    #[default]
+    Stable,
+    /// Score decreasing
+    Degrading,
+}
+
+/// Link quality estimator combining RSSI, SNR/LQI and packet error rate
+#[derive(Debug, Default, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct LinkQuality {
+    cfg: LinkQualityCfg,
+    /// Last RSSI score
+    rssi: Option<u8>,
+    /// Last SNR/LQI score
+    quality: Option<u8>,
+    /// Last packet error rate score
+    per: Option<u8>,
+    /// Previous statistics, to compute the deltas
+    prev_stats: Option<RxStats>,
+    /// Smoothed score with 8 fractional bits
+    fast: Option<u32>,
+    /// Slow average of the score with 8 fractional bits
+    slow: Option<u32>,
+}
+
+/// Map a value linearly to 0-100 between a floor and a ceiling
+fn map_score(value: i32, floor: i32, ceil: i32) -> u8 {
+    if floor == ceil {
+        return if value >= ceil {100} else {0};
+    }
+    ((value - floor) * 100 / (ceil - floor)).clamp(0, 100) as u8
+}
+
+/// Update an exponential moving average with 8 fractional bits
+fn ewma(avg: Option<u32>, value: u8, shift: u8) -> u32 {
+    let sample = (value as u32) << 8;
+    match avg {
+        Some(avg) => avg - (avg >> shift) + (sample >> shift),
+        None => sample,
+    }
+}
+
+impl LinkQuality {
+    /// Create an estimator without any sample
+    pub fn new(cfg: LinkQualityCfg) -> Self {
+        Self { cfg: LinkQualityCfg { shift: cfg.shift.min(6), ..cfg }, rssi: None, quality: None, per: None, prev_stats: None, fast: None, slow: None }
+    }
+
+    /// Add the status of a received packet
+    pub fn update_packet(&mut self, status: &PktStatus) {
+        let cfg = &self.cfg;
+        // RSSI is a positive attenuation: floor and ceiling are reversed
+        self.rssi = Some(map_score(-(status.rssi_avg as i32), -(cfg.rssi_floor as i32), -(cfg.rssi_ceil as i32)));
+        if let Some(q) = status.quality {
+            self.quality = Some(map_score(q as i32, cfg.quality_floor as i32, cfg.quality_ceil as i32));
+        }
+        self.update_score();
+    }
+
+    /// Add RX statistics: the packet error rate is computed on the packets received since the previous statistics.
+    /// The first statistics (or statistics cleared in between) are only used as reference
+    pub fn update_stats(&mut self, stats: &RxStats) {
+        let prev = self.prev_stats.replace(*stats);
+        let Some(prev) = prev else {
+            return;
+        };
+        if stats.pkt_rx < prev.pkt_rx {
+            return;
+        }
+        let total = (stats.pkt_rx - prev.pkt_rx) as u32;
+        if total == 0 {
+            return;
+        }
+        let errors = (stats.crc_error.wrapping_sub(prev.crc_error) as u32 + stats.len_error.wrapping_sub(prev.len_error) as u32).min(total);
+        let per = (errors * 100 / total) as i32;
+        self.per = Some(map_score(-per, -(self.cfg.per_max as i32), 0));
+        self.update_score();
+    }
+
+    /// Combine the available metrics and update the averages
+    fn update_score(&mut self) {
+        let cfg = &self.cfg;
+        let metrics = [(self.rssi, cfg.w_rssi), (self.quality, cfg.w_quality), (self.per, cfg.w_per)];
+        let (sum, weights) = metrics.iter()
+            .filter_map(|&(score, w)| score.map(|s| (s as u32 * w as u32, w as u32)))
+            .fold((0, 0), |(sum, weights), (s, w)| (sum + s, weights + w));
+        if weights == 0 {
+            return;
+        }
+        let score = (sum / weights) as u8;
+        self.fast = Some(ewma(self.fast, score, cfg.shift));
+        self.slow = Some(ewma(self.slow, score, cfg.shift + 2));
+    }
+
+    /// Smoothed score from 0 (no link) to 100 (perfect link), None until a sample was added
+    pub fn score(&self) -> Option<u8> {
+        self.fast.map(|avg| ((avg + 0x80) >> 8) as u8)
+    }
+
+    /// Trend of the score: comparison of the smoothed score with its slow average
+    pub fn trend(&self) -> Trend {
+        let (Some(fast), Some(slow)) = (self.fast, self.slow) else {
+            return Trend::Stable;
+        };
+        let hyst = (self.cfg.trend_hyst as u32) << 8;
+        if fast > slow + hyst {
+            Trend::Improving
+        } else if fast + hyst < slow {
+            Trend::Degrading
+        } else {
+            Trend::Stable
+        }
+    }
+
+    /// Clear all samples, keeping the configuration
+    pub fn reset(&mut self) {
+        *self = Self::new(self.cfg);
+    }
+}
+
+impl<O,SPI, M> Lr2021<O,SPI, M> where
+    O: OutputPin, SPI: SpiBus<u8>, M: BusyPin
+{
+    /// Update a link quality estimator with the status of the last packet received and the RX statistics
+    /// The values cached by the automatic refresh are used when available (see [`set_auto_stats`](Lr2021::set_auto_stats)),
+    /// avoiding any SPI access
+    pub async fn link_quality_update(&mut self, lq: &mut LinkQuality) -> Result<(), Lr2021Error> {
+        let status = match self.last_packet_status() {
+            Some(status) => status,
+            None => self.get_packet_status().await?,
+        };
+        lq.update_packet(&status);
+        let stats = match self.stats() {
+            Some(stats) => stats,
+            None => self.get_rx_stats().await?,
+        };
+        lq.update_stats(&stats);
+        Ok(())
+    }
+}
@@ -32,22 +32,37 @@
 //! - [`set_lora_syncword_ext`](Lr2021::set_lora_syncword_ext) - Set syncword using extended 2-byte format
 //! - [`set_lora_synch_timeout`](Lr2021::set_lora_synch_timeout) - Configure synchronization timeout
 //! - [`set_lora_address`](Lr2021::set_lora_address) - Set address filtering parameters
+//! - [`set_lora_preamble_duration`](Lr2021::set_lora_preamble_duration) - Set the preamble length from a duration, checking the longest preamble achievable
+//! - [`set_lora_implicit_rx`](Lr2021::set_lora_implicit_rx) - Configure reception of implicit header packets (length, coding rate, CRC)
 //!
 //! ### Status and Statistics
+//! - [`get_lora_modulation`](Lr2021::get_lora_modulation) - Return the last modulation parameters set
+//! - [`get_lora_packet`](Lr2021::get_lora_packet) - Return the last packet parameters set
 //! - [`get_lora_packet_status`](Lr2021::get_lora_packet_status) - Get basic packet status information
 //! - [`get_lora_rx_stats`](Lr2021::get_lora_rx_stats) - Get reception statistics
+//! - [`set_lora_rx_auto_drop`](Lr2021::set_lora_rx_auto_drop) - Automatically drop packets with CRC/header error and re-arm RX
+//! - [`lora_rx_service_irq`](Lr2021::lora_rx_service_irq) - Apply the auto-drop on the interrupts read
+//! - [`lora_rx_drop_stats`](Lr2021::lora_rx_drop_stats) - Get the number of packets dropped automatically
+//! - [`clear_lora_rx_drop_stats`](Lr2021::clear_lora_rx_drop_stats) - Reset the number of packets dropped automatically
 //!
 //! ### Channel Activity Detection (CAD)
 //! - [`set_lora_cad_params`](Lr2021::set_lora_cad_params) - Configure CAD parameters
 //! - [`set_lora_cad`](Lr2021::set_lora_cad) - Start channel activity detection
+//! - [`lora_cad_run`](Lr2021::lora_cad_run) - Run a CAD and wait for its completion
+//! - [`lora_cad_rx`](Lr2021::lora_cad_rx) - Run a CAD followed by a reception on detection, and report the outcome
+//! - [`lora_cad_tune_noise`](Lr2021::lora_cad_tune_noise) - Find the lowest detection threshold with acceptable false alarms on a quiet channel
+//! - [`lora_cad_tune_signal`](Lr2021::lora_cad_tune_signal) - Find the highest detection threshold reaching a target detection rate
 //!
 //! ### Misc Features
 //! - [`comp_sx127x_sf6_sw`](Lr2021::comp_sx127x_sf6_sw) - Enable SX127x compatibility for SF6 and syncword format
 //! - [`comp_sx127x_hopping`](Lr2021::comp_sx127x_hopping) - Enable compatibility with SX127x for frequency hopping communication
 //! - [`set_lora_preamble_modulation`](Lr2021::set_lora_preamble_modulation) - Enable preamble phase modulation
+//! - [`set_lora_preamble_modulation_rx`](Lr2021::set_lora_preamble_modulation_rx) - Configure reception of a modulated preamble, sleeping until the end of the preamble
 //! - [`set_lora_blanking`](Lr2021::set_lora_blanking) - Configure blanking (algorithm to reduce impact of interferers)
+//! - [`lora_blanking_tune`](Lr2021::lora_blanking_tune) - Step the blanking level automatically (see [`blanking`](crate::blanking))
 //! - [`set_lora_hopping`](Lr2021::set_lora_hopping) - Configure intra-packet frequency hopping
 //! - [`set_lora_freq_range`](Lr2021::set_lora_freq_range) - Configure the frequency error range supported by detection
+//! - [`set_lora_freq_range_auto`](Lr2021::set_lora_freq_range_auto) - Select the narrowest frequency range tolerating the crystal error
 //!
 //! ### Side-Detection (Multi-SF receiver)
 //! - [`set_lora_sidedet_cfg`](Lr2021::set_lora_sidedet_cfg) - Configure side-detector for multiple SF detection
@@ -70,17 +85,21 @@
 //! - [`set_lora_timing_sync`](Lr2021::set_lora_timing_sync) - Configure timing synchronization mode
 //! - [`set_lora_timing_sync_pulse`](Lr2021::set_lora_timing_sync_pulse) - Configure timing sync pulse parameters
 
+use embassy_time::{Duration, Timer};
 use embedded_hal::digital::OutputPin;
 use embedded_hal_async::spi::SpiBus;
 
 use crate::constants::*;
-use crate::system::DioNum;
+use crate::quirks::Feature;
+use crate::radio::PacketType;
+use crate::status::{Intr, IRQ_MASK_ADDR_ERROR, IRQ_MASK_CAD_DETECTED, IRQ_MASK_CAD_DONE, IRQ_MASK_CRC_ERROR, IRQ_MASK_HEADER_ERR, IRQ_MASK_LEN_ERROR, IRQ_MASK_RX_DONE, IRQ_MASK_TIMEOUT};
+use crate::system::{ChipMode, DioNum};
 
 pub use super::cmd::cmd_lora::*;
 pub use super::cmd::cmd_ranging::*;
 use super::{BusyPin, Lr2021, Lr2021Error};
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 /// LoRa Modulation parameters: SF, Bandwidth, Code-rate, LDRO
 pub struct LoraModulationParams {
@@ -94,11 +113,84 @@ pub struct LoraModulationParams {
     pub ldro: Ldro,
 }
 
+/// Symbol duration (in us) above which the Low Data-Rate Optimisation is required
+pub const LDRO_SYMB_TIME_US: u32 = 16_384;
+
+/// Minimum bandwidth (in Hz) with a calibrated ranging delay
+pub const RANGING_MIN_BW_HZ: u32 = 125_000;
+
+/// Illegal combination of LoRa modulation parameters
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum LoraParamError {
+    /// Symbol duration requires the Low Data-Rate Optimisation
+    LdroRequired,
+    /// Bandwidth larger than the channel bandwidth allowed
+    BwTooLarge,
+    /// Bandwidth too small for ranging
+    BwTooSmallForRanging,
+    /// Implicit header requires a non-null payload length
+    ImplicitNoLength,
+    /// Long interleaving: payload and CRC must fit in 255 bytes
+    LiPayloadTooLong,
+    /// Preamble duration longer than 65535 symbols
+    PreambleTooLong,
+    /// More than 3 side-detectors
+    SidedetTooMany,
+    /// Side-detector SF identical to the main SF
+    SidedetSameSf,
+    /// Two side-detectors with the same SF
+    SidedetDuplicateSf,
+    /// Side-detector symbol duration (with the main bandwidth) requires the Low Data-Rate Optimisation
+    SidedetLdroRequired,
+}
+
+/// Interleaving of a LoRa coding rate
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Interleaving {
+    /// No coding
+    None,
+    /// Short interleaving: codewords are interleaved on blocks of 4+CR symbols (SX127x compatible)
+    Short,
+    /// Long interleaving: codewords are interleaved over the whole payload, improving robustness to interferers and
+    /// benefitting the most from blanking (see [`set_lora_blanking`](Lr2021::set_lora_blanking))
+    Long,
+}
+
+impl LoraCr {
+    /// Interleaving used by the coding rate
+    pub fn interleaving(&self) -> Interleaving {
+        match self {
+            LoraCr::NoCoding => Interleaving::None,
+            _ if self.is_li() => Interleaving::Long,
+            _ => Interleaving::Short,
+        }
+    }
+
+    /// Return the short interleaving coding rate with the same redundancy (None for the convolutional codes)
+    pub fn to_short(&self) -> Option<LoraCr> {
+        match self {
+            LoraCr::Cr5Ham45Li => Some(LoraCr::Cr1Ham45Si),
+            LoraCr::Cr6Ham23Li => Some(LoraCr::Cr2Ham23Si),
+            LoraCr::Cr7Ham12Li => Some(LoraCr::Cr4Ham12Si),
+            LoraCr::Cr8Cc23 | LoraCr::Cr9Cc12 => None,
+            _ => Some(*self),
+        }
+    }
+}
+
+/// LoRa symbol duration in us
+pub fn lora_symb_time_us(sf: Sf, bw: LoraBw) -> u32 {
+    (((1u64 << sf as u8) * 1_000_000) / bw.to_hz() as u64) as u32
+}
+
 impl LoraModulationParams {
     /// Modulation with default coderate (4/5) and LDRO based on SF/BW
     pub fn basic(sf: Sf, bw: LoraBw) -> Self {
         let ldro_en = (sf==Sf::Sf12 && !matches!(bw,LoraBw::Bw1000|LoraBw::Bw500))
-                    || (sf==Sf::Sf11 && !matches!(bw,LoraBw::Bw1000|LoraBw::Bw500|LoraBw::Bw250) );
+                    || (sf==Sf::Sf11 && !matches!(bw,LoraBw::Bw1000|LoraBw::Bw500|LoraBw::Bw250) )
+                    || lora_symb_time_us(sf, bw) >= LDRO_SYMB_TIME_US;
         Self {
             sf, bw,
             cr: LoraCr::Cr1Ham45Si,
@@ -110,9 +202,93 @@ impl LoraModulationParams {
     pub fn new(sf: Sf, bw: LoraBw, cr: LoraCr, ldro: Ldro) -> Self {
         Self {sf, bw, cr, ldro}
     }
+
+    /// Symbol duration in us
+    pub fn symb_time_us(&self) -> u32 {
+        lora_symb_time_us(self.sf, self.bw)
+    }
+
+    /// Check the combination of parameters is legal: LDRO must be enabled for long symbols
+    pub fn validate(&self) -> Result<(), LoraParamError> {
+        if self.ldro == Ldro::Off && self.symb_time_us() >= LDRO_SYMB_TIME_US {
+            return Err(LoraParamError::LdroRequired);
+        }
+        Ok(())
+    }
+
+    /// Longest preamble (65535 symbols) with this modulation
+    pub fn preamble_max(&self) -> Duration {
+        Duration::from_micros(u16::MAX as u64 * self.symb_time_us() as u64)
+    }
+
+    /// Number of preamble symbols lasting at least the duration
+    /// Return PreambleTooLong if more than 65535 symbols are needed (see [`preamble_max`](LoraModulationParams::preamble_max))
+    pub fn preamble_len_for(&self, duration: Duration) -> Result<u16, LoraParamError> {
+        let symb = self.symb_time_us().max(1) as u64;
+        let nb = duration.as_micros().div_ceil(symb);
+        u16::try_from(nb).map_err(|_| LoraParamError::PreambleTooLong)
+    }
+
+    /// Check the parameters against a regional restriction on the channel bandwidth (in Hz)
+    pub fn validate_channel_bw(&self, max_bw_hz: u32) -> Result<(), LoraParamError> {
+        self.validate()?;
+        if self.bw.to_hz() > max_bw_hz {
+            return Err(LoraParamError::BwTooLarge);
+        }
+        Ok(())
+    }
+
+    /// Check packet parameters are compatible with the modulation:
+    ///  - implicit header requires the payload length known a priori
+    ///  - long interleaving spans the whole payload: payload and CRC must fit in 255 bytes
+    pub fn validate_packet(&self, packet: &LoraPacketParams) -> Result<(), LoraParamError> {
+        if packet.header_type == HeaderType::Implicit && packet.payload_len == 0 {
+            return Err(LoraParamError::ImplicitNoLength);
+        }
+        if self.cr.interleaving() == Interleaving::Long && packet.payload_len as u16 + packet.crc_bytes() as u16 > 255 {
+            return Err(LoraParamError::LiPayloadTooLong);
+        }
+        Ok(())
+    }
+
+    /// Check side-detectors are compatible with the modulation: at most 3 side-detectors,
+    /// each on a SF different from the main SF and the other side-detectors, with LDRO enabled when required
+    /// (side-detectors use the main bandwidth)
+    pub fn validate_sidedet(&self, cfg: &[SidedetCfg]) -> Result<(), LoraParamError> {
+        if cfg.len() > SIDEDET_MAX {
+            return Err(LoraParamError::SidedetTooMany);
+        }
+        for (i, c) in cfg.iter().enumerate() {
+            if c.sf() == self.sf {
+                return Err(LoraParamError::SidedetSameSf);
+            }
+            if cfg[..i].iter().any(|p| p.sf() == c.sf()) {
+                return Err(LoraParamError::SidedetDuplicateSf);
+            }
+            if c.ldro() == Ldro::Off && lora_symb_time_us(c.sf(), self.bw) >= LDRO_SYMB_TIME_US {
+                return Err(LoraParamError::SidedetLdroRequired);
+            }
+        }
+        Ok(())
+    }
+
+    /// Check the parameters are usable for ranging: the ranging delay is only calibrated for bandwidth of 125kHz and higher
+    pub fn validate_ranging(&self) -> Result<(), LoraParamError> {
+        self.validate()?;
+        if self.bw.to_hz() < RANGING_MIN_BW_HZ {
+            return Err(LoraParamError::BwTooSmallForRanging);
+        }
+        Ok(())
+    }
 }
 
-#[derive(Debug, Clone, Copy)]
+impl From<LoraParamError> for Lr2021Error {
+    fn from(_: LoraParamError) -> Self {
+        Lr2021Error::InvalidParam
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 /// LoRa Modulation parameters: SF, Bandwidth, Code-rate, LDRO
 pub struct LoraPacketParams {
@@ -144,6 +320,43 @@ impl LoraPacketParams {
     pub fn new(pbl_len: u16, payload_len: u8, header_type: HeaderType, crc_en: bool, invert_iq: bool) -> Self {
         Self {pbl_len, payload_len, header_type, crc_en, invert_iq}
     }
+
+    /// Number of CRC bytes
+    fn crc_bytes(&self) -> u8 {
+        if self.crc_en {2} else {0}
+    }
+
+    /// Number of payload symbols (including CRC, excluding the first 8 symbols holding the header)
+    ///  - short interleaving: payload is coded in blocks of 4+CR symbols
+    ///  - long interleaving: codewords span the whole payload and only the total number of coded bits matters
+    pub fn payload_symbols(&self, modulation: &LoraModulationParams) -> u32 {
+        let sf = modulation.sf as i32;
+        let explicit = self.header_type == HeaderType::Explicit;
+        // Bits remaining after the first 8 symbols
+        let mut nb_bits = 8 * self.payload_len as i32 + 8 * self.crc_bytes() as i32 - 4 * sf + if explicit {20} else {0};
+        let bits_per_symb = if sf <= 6 {
+            sf
+        } else {
+            nb_bits += 8;
+            if modulation.ldro == Ldro::On {sf - 2} else {sf}
+        };
+        let nb_bits = nb_bits.max(0) as u32;
+        let den = modulation.cr.denominator() as u32;
+        let div = 4 * bits_per_symb as u32;
+        match modulation.cr.interleaving() {
+            Interleaving::Long => (nb_bits * den).div_ceil(div),
+            _ => nb_bits.div_ceil(div) * den,
+        }
+    }
+
+    /// Time on air in us
+    /// For long interleaving the duration is estimated without the block granularity of short interleaving
+    pub fn time_on_air_us(&self, modulation: &LoraModulationParams) -> u32 {
+        // Number of symbols in quarter of symbol: preamble, 4.25 symbols of syncword/SFD (6.25 for SF5/6), header block and payload
+        let sfd_q4 = if modulation.sf <= Sf::Sf6 {25} else {17};
+        let nb_q4 = 4 * (self.pbl_len as u64 + 8 + self.payload_symbols(modulation) as u64) + sfd_q4;
+        ((nb_q4 << modulation.sf as u8) * 1_000_000 / (4 * modulation.bw.to_hz() as u64)) as u32
+    }
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -164,6 +377,16 @@ pub struct LoraCadParams {
     pub delta: u8,
 }
 
+/// Outcome of a CAD followed by a reception (see [`lora_cad_rx`](Lr2021::lora_cad_rx))
+pub enum CadRxOutcome {
+    /// No LoRa activity detected: no reception was started
+    NoActivity,
+    /// Activity detected but no valid packet received: RX timeout or reception error (interrupts flagged)
+    DetectedButNoPacket(Intr),
+    /// Packet received in the buffer (length in bytes) with its status
+    Packet(usize, LoraPacketStatusRsp),
+}
+
 /// Recommended CAD threshold for a given SF and number of symbols
 pub fn lora_cad_thr(sf: Sf, nb_symbols: u8) -> u8 {
     let base_symb = match nb_symbols {
@@ -210,6 +433,11 @@ impl LoraCadParams {
         LoraCadParams {nb_symbols, preamble_only, exit_mode, timeout, thr, delta}
     }
 
+    /// Number of symbols used for the detection
+    pub fn nb_symbols(&self) -> u8 {
+        self.nb_symbols
+    }
+
     /// Create CAD parameter with manual detection threshold
     pub fn new(nb_symbols: u8, thr: u8, exit_mode: ExitMode, timeout: u32, delta: u8) -> Self {
         let nb_symbols = nb_symbols.clamp(1,15);
@@ -231,9 +459,14 @@ const RANGING_DELAY : [u32; 56] = [
     19688, 19649, 19560, 19387, 19043, 18350, 16967, 14191,
 ];
 
-#[derive(Debug, Clone, Copy)]
+/// Maximum number of side-detectors
+pub const SIDEDET_MAX: usize = 3;
+
+/// Side-detector configuration: additional SF detected with the main bandwidth
+#[derive(Clone, Copy, PartialEq, Eq)]
 pub struct SidedetCfg(u8);
 impl SidedetCfg {
+    /// Side-detector on a SF, with LDRO and inverted IQ
     pub fn new(sf: Sf, ldro: Ldro, inv: bool) -> Self{
         let b = ((sf as u8) << 4) |
             (ldro as u8) << 2 |
@@ -241,11 +474,59 @@ impl SidedetCfg {
         Self(b)
     }
 
+    /// Side-detector on a SF using the bandwidth and IQ of the main modulation, with LDRO enabled when required
+    pub fn basic(sf: Sf, modulation: &LoraModulationParams, inv: bool) -> Self {
+        let ldro = LoraModulationParams::basic(sf, modulation.bw).ldro;
+        Self::new(sf, ldro, inv)
+    }
+
+    /// Spreading factor
+    pub fn sf(&self) -> Sf {
+        match self.0 >> 4 {
+            5 => Sf::Sf5,
+            6 => Sf::Sf6,
+            7 => Sf::Sf7,
+            8 => Sf::Sf8,
+            9 => Sf::Sf9,
+            10 => Sf::Sf10,
+            11 => Sf::Sf11,
+            _ => Sf::Sf12,
+        }
+    }
+
+    /// Low Data-Rate Optimisation
+    pub fn ldro(&self) -> Ldro {
+        if (self.0 >> 2) & 1 == 1 {Ldro::On} else {Ldro::Off}
+    }
+
+    /// Inverted IQ
+    pub fn inverted(&self) -> bool {
+        self.0 & 1 == 1
+    }
+
+    /// Byte sent in the command
     pub fn to_byte(&self) -> u8 {
         self.0
     }
 }
 
+impl core::fmt::Debug for SidedetCfg {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("SidedetCfg")
+            .field("sf", &self.sf())
+            .field("ldro", &self.ldro())
+            .field("inv", &self.inverted())
+            .finish()
+    }
+}
+
+#[cfg(feature = "defmt")]
+impl defmt::Format for SidedetCfg {
+    fn format(&self, fmt: defmt::Formatter) {
+        defmt::write!(fmt, "SidedetCfg {{ sf: {}, ldro: {}, inv: {} }}", self.sf(), self.ldro(), self.inverted());
+    }
+}
+
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 /// LoRa Blanking configuration
@@ -309,6 +590,49 @@ impl BlankingCfg {
     }
 }
 
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+/// Detection of the preamble phase modulation (decoding of the number of symbols left)
+pub struct PreambleModDetect {
+    /// Error threshold (0-127)
+    pub err_thr: u8,
+    /// Minimum number of symbols (0-15)
+    pub min_sym: u8,
+    /// Detection time in symbols (0-15)
+    pub detect_time_sym: u8,
+    /// Start offset
+    pub start_offset: u8,
+    /// End offset
+    pub end_offset: u8,
+}
+
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+/// Reception of a modulated preamble: once the number of symbols left is decoded,
+/// the chip sleeps and wakes up just before the end of the preamble instead of listening through it
+pub struct PreambleModRxCfg {
+    /// DRAM retention configuration during the sleep (0-7)
+    pub dram_ret: u8,
+    /// Wakeup time, anticipating the end of the preamble
+    pub wakeup_time: u16,
+    /// Minimum sleep time: the chip keeps listening if the preamble ends earlier
+    pub min_sleep_time: u32,
+    /// Detection parameters (None to keep the chip defaults)
+    pub detect: Option<PreambleModDetect>,
+}
+
+impl PreambleModRxCfg {
+    /// Reception with default detection parameters
+    pub fn new(dram_ret: u8, wakeup_time: u16, min_sleep_time: u32) -> Self {
+        Self { dram_ret, wakeup_time, min_sleep_time, detect: None }
+    }
+
+    /// Set the detection parameters
+    pub fn with_detect(self, detect: PreambleModDetect) -> Self {
+        Self { detect: Some(detect), ..self }
+    }
+}
+
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 /// Frequency estimation during ranging exchange (valid only on responder side)
@@ -327,7 +651,8 @@ pub enum TimingSyncPulseWidth {
     W1 = 0, W5 = 1, W52 = 2, W520 = 3, W5200 = 4, W52k = 5, W260k = 6, W1024k = 7
 }
 
-#[derive(Debug, Clone, Copy, Default)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 /// Define Frequency range toelrated by detector
 pub enum FreqRange {#[default]
     /// +/- Bandwidth/4
@@ -338,18 +663,94 @@ pub enum FreqRange {#[default]
     Wide = 2,
 }
 
+impl FreqRange {
+    /// Maximum frequency error (in Hz) tolerated by the detector for a bandwidth
+    pub fn max_error_hz(&self, bw: LoraBw) -> u32 {
+        let div = match self {
+            FreqRange::Narrow => 4,
+            FreqRange::Medium => 3,
+            FreqRange::Wide => 2,
+        };
+        bw.to_hz() / div
+    }
+
+    /// Narrowest range tolerating a frequency error (in Hz), preserving sensitivity.
+    /// Return None if the error exceeds even the wide range
+    pub fn for_error(err_hz: u32, bw: LoraBw) -> Option<FreqRange> {
+        [FreqRange::Narrow, FreqRange::Medium, FreqRange::Wide]
+            .into_iter()
+            .find(|r| err_hz <= r.max_error_hz(bw))
+    }
+}
+
+/// Worst case frequency error (in Hz) between two devices on a RF channel,
+/// given the crystal tolerance of the link in ppm (sum of the tolerance of both ends)
+pub fn lora_freq_error_hz(rf: u32, xtal_ppm: u16) -> u32 {
+    (rf as u64 * xtal_ppm as u64 / 1_000_000) as u32
+}
+
+/// Action performed after a packet dropped automatically on CRC or header error
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum RxRearm {
+    /// Continuous RX: the chip restarts the reception by itself, only the FIFO is cleared
+    Continuous,
+    /// Restart a single RX with a timeout (in LF clock step)
+    Rx(u32),
+    /// Restart the duty-cycled RX (see [`set_rx_duty_cycle`](Lr2021::set_rx_duty_cycle))
+    DutyCycle {
+        /// Listen time of each RX window (in HF tick)
+        listen_time: u32,
+        /// Period of the RX windows (in HF tick)
+        cycle_time: u32,
+        /// Perform a CAD instead of a RX
+        use_lora_cad: bool,
+        /// Bit mask of DRAM to keep in retention during sleep
+        dram_ret: u8,
+    },
+}
+
+/// Number of LoRa packets dropped automatically
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct RxDropStats {
+    /// Packets dropped on CRC error
+    pub crc: u32,
+    /// Packets dropped on header error
+    pub header: u32,
+}
+
+/// Automatic drop of LoRa packets received with error
+#[derive(Debug, Default, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub(crate) struct RxAutoDrop {
+    /// Re-arm action (None when disabled)
+    rearm: Option<RxRearm>,
+    /// Drop counters
+    stats: RxDropStats,
+}
+
 impl<O,SPI, M> Lr2021<O,SPI, M> where
     O: OutputPin, SPI: SpiBus<u8>, M: BusyPin
 {
 
     /// Set LoRa Modulation parameters
+    /// Return InvalidParam if the parameters are illegal (see [`LoraModulationParams::validate`])
     pub async fn set_lora_modulation(&mut self, params: &LoraModulationParams) -> Result<(), Lr2021Error> {
+        params.validate()?;
         let req = set_lora_modulation_params_cmd(params.sf, params.bw, params.cr, params.ldro, LoraFilter::Auto);
-        self.cmd_wr(&req).await
+        self.cmd_wr(&req).await?;
+        self.cache.lora_modulation = Some(*params);
+        if self.cache.xtal_ppm.is_some() && self.cache.rf.is_some() {
+            self.set_lora_freq_range_auto().await?;
+        }
+        Ok(())
     }
 
     /// Set LoRa Modulation parameters for ranging operation
+    /// Return InvalidParam if the parameters are illegal (see [`LoraModulationParams::validate`])
     pub async fn set_ranging_modulation(&mut self, params: &LoraModulationParams, is_initiator: bool) -> Result<(), Lr2021Error> {
+        params.validate()?;
         let filter = match (params.bw.is_fractional(),is_initiator) {
             (true, true) => LoraFilter::Dcc,
             (true, false) => LoraFilter::DccF,
@@ -360,22 +761,79 @@ impl<O,SPI, M> Lr2021<O,SPI, M> where
     }
 
     /// Set LoRa Packet parameters
+    /// Return InvalidParam if the parameters are incompatible with the modulation set (see [`LoraModulationParams::validate_packet`])
     pub async fn set_lora_packet(&mut self, params: &LoraPacketParams) -> Result<(), Lr2021Error> {
+        if let Some(modulation) = self.cache.lora_modulation {
+            modulation.validate_packet(params)?;
+        }
         let req = set_lora_packet_params_cmd(params.pbl_len, params.payload_len, params.header_type, params.crc_en, params.invert_iq);
-        self.cmd_wr(&req).await
+        self.cmd_wr(&req).await?;
+        self.cache.lora_packet = Some(*params);
+        Ok(())
+    }
+
+    /// Set the preamble length from a duration (e.g. wake-on-radio transmitter), keeping the other packet parameters.
+    /// Modulation and packet parameters must have been set with `set_lora_modulation` and `set_lora_packet`.
+    /// Return the preamble length in symbols, or InvalidParam if the duration exceeds the longest preamble (65535 symbols)
+    /// achievable with the modulation (see [`LoraModulationParams::preamble_max`])
+    pub async fn set_lora_preamble_duration(&mut self, duration: Duration) -> Result<u16, Lr2021Error> {
+        let (Some(modulation), Some(packet)) = (self.cache.lora_modulation, self.cache.lora_packet) else {
+            return Err(Lr2021Error::InvalidParam);
+        };
+        let pbl_len = match modulation.preamble_len_for(duration) {
+            Ok(pbl_len) => pbl_len,
+            Err(err) => {
+                #[cfg(feature = "defmt")]{defmt::warn!("Preamble of {}ms too long: max {}ms", duration.as_millis(), modulation.preamble_max().as_millis());}
+                return Err(err.into());
+            }
+        };
+        self.set_lora_packet(&LoraPacketParams {pbl_len, ..packet}).await?;
+        Ok(pbl_len)
+    }
+
+    /// Configure reception of packets with implicit header: payload length, coding rate and CRC must be known a priori
+    /// Modulation must have been configured with `set_lora_modulation` and is only updated if the coding rate changes.
+    /// Other packet parameters (preamble, IQ inversion) are kept from the last `set_lora_packet`.
+    /// Return InvalidParam if the length is null or if the SX127x compatibility mode is enabled with a coding rate not supported by SX127x
+    pub async fn set_lora_implicit_rx(&mut self, len: u8, cr: LoraCr, crc_en: bool) -> Result<(), Lr2021Error> {
+        let Some(modulation) = self.cache.lora_modulation else {
+            return Err(Lr2021Error::InvalidParam);
+        };
+        if len == 0 {
+            return Err(Lr2021Error::InvalidParam);
+        }
+        let sx127x_compat = ((self.rd_reg(ADDR_LORA_PARAM).await? >> 18) & 3) == 2;
+        if sx127x_compat && (cr.is_li() || cr == LoraCr::NoCoding) {
+            return Err(Lr2021Error::InvalidParam);
+        }
+        if modulation.cr != cr {
+            self.set_lora_modulation(&LoraModulationParams {cr, ..modulation}).await?;
+            // Compatibility mode is reset by the modulation command
+            if sx127x_compat {
+                self.comp_sx127x_sf6_sw(true, None).await?;
+            }
+        }
+        let params = self.cache.lora_packet.unwrap_or(LoraPacketParams::basic(len, &modulation));
+        let params = LoraPacketParams {payload_len: len, header_type: HeaderType::Implicit, crc_en, ..params};
+        self.set_lora_packet(&params).await
     }
 
     /// Set LoRa Syncword using legacy (SX127x) 1B notation: 0x34 for public network, 0x12 for private
     pub async fn set_lora_syncword(&mut self, syncword: u8) -> Result<(), Lr2021Error> {
         let req = set_lora_syncword_cmd(syncword);
-        self.cmd_wr(&req).await
+        self.cmd_wr(&req).await?;
+        self.cache.lora_syncword = Some(syncword);
+        Ok(())
     }
 
     /// Set LoRa Syncword, using 2B notation (2 values on 5b signed each)
     /// Public network is (6,8) and private network is (2,4)
     pub async fn set_lora_syncword_ext(&mut self, s1: i8, s2: i8) -> Result<(), Lr2021Error> {
         let req = set_lora_syncword_extended_cmd((s1&0x1F) as u8, (s2&0x1F) as u8);
-        self.cmd_wr(&req).await
+        self.cmd_wr(&req).await?;
+        // Not representable in the 1B notation
+        self.cache.lora_syncword = None;
+        Ok(())
     }
 
     /// Set synchronisation timeout
@@ -394,7 +852,20 @@ impl<O,SPI, M> Lr2021<O,SPI, M> where
         self.cmd_wr(&req[..len]).await
     }
 
+    /// Return the last modulation parameters set with `set_lora_modulation`
+    /// The chip does not provide a way to read back the modulation: None is returned if it was not configured through this driver
+    pub fn get_lora_modulation(&self) -> Option<LoraModulationParams> {
+        self.cache.lora_modulation
+    }
+
+    /// Return the last packet parameters set with `set_lora_packet`
+    /// The chip does not provide a way to read back the packet parameters: None is returned if they were not configured through this driver
+    pub fn get_lora_packet(&self) -> Option<LoraPacketParams> {
+        self.cache.lora_packet
+    }
+
     /// Return Information about last packet received
+    /// RSSI values are raw: use [`get_packet_status`](Lr2021::get_packet_status) or [`rssi_calibrate`](Lr2021::rssi_calibrate) to apply the RSSI calibration
     pub async fn get_lora_packet_status(&mut self) -> Result<LoraPacketStatusRsp, Lr2021Error> {
         let req = get_lora_packet_status_req();
         let mut rsp = LoraPacketStatusRsp::new();
@@ -410,6 +881,50 @@ impl<O,SPI, M> Lr2021<O,SPI, M> where
         Ok(rsp)
     }
 
+    /// Enable automatic drop of LoRa packets received with a CRC or header error:
+    /// when the interrupts read are passed to [`lora_rx_service_irq`](Lr2021::lora_rx_service_irq)
+    /// (done by `publish_irq`), the RX FIFO is cleared,
+    /// the reception re-armed and the error interrupts (with the associated RxDone) are removed.
+    /// Use None to disable
+    pub fn set_lora_rx_auto_drop(&mut self, rearm: Option<RxRearm>) {
+        self.rx_drop.rearm = rearm;
+    }
+
+    /// Number of LoRa packets dropped automatically
+    pub fn lora_rx_drop_stats(&self) -> RxDropStats {
+        self.rx_drop.stats
+    }
+
+    /// Reset the number of LoRa packets dropped automatically
+    pub fn clear_lora_rx_drop_stats(&mut self) {
+        self.rx_drop.stats = RxDropStats::default();
+    }
+
+    /// Service the interrupts read with [`get_and_clear_irq`](Lr2021::get_and_clear_irq) or [`get_status`](Lr2021::get_status):
+    /// drop a LoRa packet received with error and re-arm the reception if the auto-drop is enabled.
+    /// Return the interrupts without the ones related to the dropped packet (unchanged when the auto-drop is disabled)
+    pub async fn lora_rx_service_irq(&mut self, intr: Intr) -> Result<Intr, Lr2021Error> {
+        let Some(rearm) = self.rx_drop.rearm else {
+            return Ok(intr);
+        };
+        if !intr.intr_match(IRQ_MASK_CRC_ERROR | IRQ_MASK_HEADER_ERR) || self.cache.packet_type != Some(PacketType::Lora) {
+            return Ok(intr);
+        }
+        self.clear_rx_fifo().await?;
+        match rearm {
+            RxRearm::Continuous => {}
+            RxRearm::Rx(timeout) => self.set_rx(timeout, false).await?,
+            RxRearm::DutyCycle { listen_time, cycle_time, use_lora_cad, dram_ret } =>
+                self.set_rx_duty_cycle(listen_time, cycle_time, use_lora_cad, dram_ret).await?,
+        }
+        if intr.header_err() {
+            self.rx_drop.stats.header = self.rx_drop.stats.header.wrapping_add(1);
+        } else {
+            self.rx_drop.stats.crc = self.rx_drop.stats.crc.wrapping_add(1);
+        }
+        Ok(Intr::new(intr.value() & !(IRQ_MASK_CRC_ERROR | IRQ_MASK_HEADER_ERR | IRQ_MASK_RX_DONE)))
+    }
+
     /// Set LoRa Channel Activity Detection parameters
     pub async fn set_lora_cad_params(&mut self, params: &LoraCadParams) -> Result<(), Lr2021Error> {
         let req = set_lora_cad_params_cmd(params.nb_symbols, params.preamble_only, params.delta, params.exit_mode, params.timeout, params.thr);
@@ -422,6 +937,160 @@ impl<O,SPI, M> Lr2021<O,SPI, M> where
         self.cmd_wr(&req).await
     }
 
+    /// Run a CAD only operation (exit mode of the parameters is ignored) and wait for its completion
+    /// Return true if LoRa activity was detected
+    /// The modulation must have been configured with `set_lora_modulation` to estimate the CAD duration
+    /// Return Timeout if the CAD does not complete (the chip is then set in Standby)
+    pub async fn lora_cad_run(&mut self, params: &LoraCadParams) -> Result<bool, Lr2021Error> {
+        let Some(modulation) = self.cache.lora_modulation else {
+            return Err(Lr2021Error::InvalidParam);
+        };
+        let symb_us = (modulation.symb_time_us() as u64).max(1);
+        let params = LoraCadParams {exit_mode: ExitMode::CadOnly, ..*params};
+        let mask = Intr::new(IRQ_MASK_CAD_DONE | IRQ_MASK_CAD_DETECTED);
+        self.clear_irqs(mask).await?;
+        self.set_lora_cad_params(&params).await?;
+        self.set_lora_cad().await?;
+        Timer::after_micros(symb_us * params.nb_symbols as u64).await;
+        // Poll for CAD done, with a timeout corresponding to twice the expected duration
+        for _ in 0..=params.nb_symbols {
+            let (_, intr) = self.get_status().await?;
+            if intr.cad_done() {
+                self.clear_irqs(mask).await?;
+                return Ok(intr.cad_detected());
+            }
+            Timer::after_micros(symb_us).await;
+        }
+        self.set_chip_mode(ChipMode::StandbyRc).await?;
+        self.clear_irqs(mask).await?;
+        Err(Lr2021Error::Timeout)
+    }
+
+    /// Run a CAD and start a reception with timeout `rx_timeout` (in LF clock step, must not be 0) if LoRa activity is detected.
+    /// The exit mode of the CAD parameters is forced to CadRx. On success the packet is read into `buf`.
+    /// Interrupts are mapped to the outcome: CadDone without CadDetected is `NoActivity`,
+    /// CadDetected followed by a timeout or a RxDone with an error (CRC, header, length, address) is `DetectedButNoPacket`.
+    /// The modulation must have been configured with `set_lora_modulation` to estimate the CAD duration.
+    /// Return InvalidSize if the packet does not fit in the buffer (the RX FIFO is then cleared)
+    /// and Timeout if the CAD or the reception does not complete (the chip is then set in Standby)
+    pub async fn lora_cad_rx(&mut self, buf: &mut [u8], params: &LoraCadParams, rx_timeout: u32) -> Result<CadRxOutcome, Lr2021Error> {
+        let Some(modulation) = self.cache.lora_modulation else {
+            return Err(Lr2021Error::InvalidParam);
+        };
+        if rx_timeout == 0 {
+            return Err(Lr2021Error::InvalidParam);
+        }
+        let symb_us = (modulation.symb_time_us() as u64).max(1);
+        let params = LoraCadParams {exit_mode: ExitMode::CadRx, timeout: rx_timeout, ..*params};
+        let err_mask = IRQ_MASK_CRC_ERROR | IRQ_MASK_HEADER_ERR | IRQ_MASK_LEN_ERROR | IRQ_MASK_ADDR_ERROR;
+        let mask = Intr::new(IRQ_MASK_CAD_DONE | IRQ_MASK_CAD_DETECTED | IRQ_MASK_RX_DONE | IRQ_MASK_TIMEOUT | err_mask);
+        self.clear_irqs(mask).await?;
+        self.set_lora_cad_params(&params).await?;
+        self.set_lora_cad().await?;
+        Timer::after_micros(symb_us * params.nb_symbols as u64).await;
+        // Poll for CAD done, with a timeout corresponding to twice the expected duration
+        let mut detected = None;
+        for _ in 0..=params.nb_symbols {
+            let (_, intr) = self.get_status().await?;
+            if intr.cad_done() {
+                detected = Some(intr.cad_detected());
+                break;
+            }
+            Timer::after_micros(symb_us).await;
+        }
+        match detected {
+            None => {
+                self.set_chip_mode(ChipMode::StandbyRc).await?;
+                self.clear_irqs(mask).await?;
+                return Err(Lr2021Error::Timeout);
+            }
+            Some(false) => {
+                self.clear_irqs(mask).await?;
+                return Ok(CadRxOutcome::NoActivity);
+            }
+            Some(true) => {}
+        }
+        // Wait for the end of the reception: RX timeout plus the packet duration when known
+        let toa_us = self.cache.lora_packet.map(|p| p.time_on_air_us(&modulation) as u64).unwrap_or(0);
+        let max_us = ((rx_timeout as u64 * 1_000_000) >> 15) + toa_us + 2 * symb_us;
+        let mut elapsed_us = 0;
+        let intr = loop {
+            let (_, intr) = self.get_status().await?;
+            if intr.rx_done() || intr.timeout() {
+                break intr;
+            }
+            if elapsed_us > max_us {
+                self.set_chip_mode(ChipMode::StandbyRc).await?;
+                self.clear_irqs(mask).await?;
+                return Err(Lr2021Error::Timeout);
+            }
+            Timer::after_micros(symb_us).await;
+            elapsed_us += symb_us;
+        };
+        self.clear_irqs(mask).await?;
+        if intr.timeout() || intr.intr_match(err_mask) {
+            if intr.rx_done() {
+                self.clear_rx_fifo().await?;
+            }
+            return Ok(CadRxOutcome::DetectedButNoPacket(intr));
+        }
+        let len = self.get_rx_pkt_len().await? as usize;
+        if len > buf.len() {
+            self.clear_rx_fifo().await?;
+            return Err(Lr2021Error::InvalidSize);
+        }
+        self.rd_rx_fifo_to(&mut buf[..len]).await?;
+        let status = self.get_lora_packet_status().await?;
+        Ok(CadRxOutcome::Packet(len, status))
+    }
+
+    /// Run a CAD multiple times and return the number of detections
+    async fn lora_cad_count(&mut self, params: &LoraCadParams, nb_trials: u16) -> Result<u16, Lr2021Error> {
+        let mut nb_det = 0;
+        for _ in 0..nb_trials {
+            if self.lora_cad_run(params).await? {
+                nb_det += 1;
+            }
+        }
+        Ok(nb_det)
+    }
+
+    /// First step of the CAD threshold tuning, to run on a quiet channel (no LoRa transmitter):
+    /// starting from 10 below the threshold of the parameters, increase the detection threshold
+    /// until the number of false alarms over nb_trials CAD is at most max_false_alarm.
+    /// Return the lowest threshold meeting the false alarm target.
+    pub async fn lora_cad_tune_noise(&mut self, params: &LoraCadParams, nb_trials: u16, max_false_alarm: u16) -> Result<u8, Lr2021Error> {
+        let mut thr = params.thr.saturating_sub(10);
+        loop {
+            let nb_det = self.lora_cad_count(&LoraCadParams {thr, ..*params}, nb_trials).await?;
+            if nb_det <= max_false_alarm || thr == u8::MAX {
+                return Ok(thr);
+            }
+            thr += 1;
+        }
+    }
+
+    /// Second step of the CAD threshold tuning, to run with a known transmitter sending LoRa packets continuously:
+    /// starting from the threshold found on the quiet channel, increase the detection threshold
+    /// as long as the number of detections over nb_trials CAD is at least min_detect.
+    /// Return the highest threshold meeting the detection target (i.e. the one minimizing false alarms)
+    /// or None if the target is not reached even at the lowest threshold.
+    pub async fn lora_cad_tune_signal(&mut self, params: &LoraCadParams, thr_noise: u8, nb_trials: u16, min_detect: u16) -> Result<Option<u8>, Lr2021Error> {
+        let mut best = None;
+        let mut thr = thr_noise;
+        loop {
+            let nb_det = self.lora_cad_count(&LoraCadParams {thr, ..*params}, nb_trials).await?;
+            if nb_det < min_detect {
+                return Ok(best);
+            }
+            best = Some(thr);
+            if thr == u8::MAX {
+                return Ok(best);
+            }
+            thr += 1;
+        }
+    }
+
     /// Enable compatibility with SX127x for SF6 communication and syncword format
     /// When enabled, use `set_lora_syncword_ext` to configure syncword with only even value in the range 0..30
     /// Must be called after each SetLoraModulation
@@ -450,7 +1119,14 @@ impl<O,SPI, M> Lr2021<O,SPI, M> where
     /// Configure Side-Detector allowing multiple SF to be detected
     /// Must be called after set_lora_modulation
     /// If cfg is an empty slice, this disabled all side-detector
+    /// Return InvalidParam if more than 3 side-detectors are given or if the configuration
+    /// is not compatible with the last modulation set (see [`validate_sidedet`](LoraModulationParams::validate_sidedet))
     pub async fn set_lora_sidedet_cfg(&mut self, cfg: &[SidedetCfg]) -> Result<(), Lr2021Error> {
+        match self.cache.lora_modulation {
+            Some(modulation) => modulation.validate_sidedet(cfg)?,
+            None if cfg.len() > SIDEDET_MAX => return Err(Lr2021Error::InvalidParam),
+            None => {}
+        }
         let req = [
             0x02, 0x24,
             cfg.get(0).map(|c| c.to_byte()).unwrap_or(0),
@@ -458,7 +1134,8 @@ impl<O,SPI, M> Lr2021<O,SPI, M> where
             cfg.get(2).map(|c| c.to_byte()).unwrap_or(0),
         ];
         let len = cfg.len() + 2;
-        self.cmd_wr(&req[..len]).await
+        let res = self.cmd_wr(&req[..len]).await;
+        self.feature_check(Feature::LoraSidedet, res)
     }
 
     #[allow(clippy::get_first)]
@@ -471,27 +1148,72 @@ impl<O,SPI, M> Lr2021<O,SPI, M> where
             sw.get(2).copied().unwrap_or(0x24),
         ];
         let len = sw.len() + 2;
-        self.cmd_wr(&req[..len]).await
+        let res = self.cmd_wr(&req[..len]).await;
+        self.feature_check(Feature::LoraSidedet, res)
     }
 
     /// Configure the frequency error range supported by detection
     /// Medium range (+/-BW/3) has only a very minor sensitivity impact while the max range can degrade sensitivity by 2dB
     pub async fn set_lora_freq_range(&mut self, range: FreqRange) -> Result<(), Lr2021Error> {
-        self.wr_field(ADDR_LORA_RX_CFG, range as u32, 16, 2).await
+        self.wr_field(ADDR_LORA_RX_CFG, range as u32, 16, 2).await?;
+        self.cache.lora_freq_range = Some(range);
+        Ok(())
+    }
+
+    /// Select the narrowest frequency range tolerating the crystal error (see [`set_xtal_ppm`](Lr2021::set_xtal_ppm))
+    /// on the current RF channel and LoRa bandwidth, and configure it if different from the current one.
+    /// Called automatically when setting the LoRa modulation once the crystal tolerance is set,
+    /// but must be called explicitly after a large RF change (e.g. from sub-GHz to 2.4GHz).
+    /// When the error exceeds even the wide range (+/-BW/2) the wide range is used: a larger bandwidth
+    /// or a better crystal is required for reliable reception.
+    /// Return InvalidParam if the crystal tolerance, RF channel or LoRa modulation is not set
+    pub async fn set_lora_freq_range_auto(&mut self) -> Result<FreqRange, Lr2021Error> {
+        let ppm = self.cache.xtal_ppm.ok_or(Lr2021Error::InvalidParam)?;
+        let rf = self.cache.rf.ok_or(Lr2021Error::InvalidParam)?;
+        let bw = self.cache.lora_modulation.ok_or(Lr2021Error::InvalidParam)?.bw;
+        let err_hz = lora_freq_error_hz(rf, ppm);
+        let range = match FreqRange::for_error(err_hz, bw) {
+            Some(range) => range,
+            None => {
+                #[cfg(feature = "defmt")]{defmt::warn!("Frequency error {}Hz exceeds LoRa detection range (BW={}Hz)", err_hz, bw.to_hz());}
+                FreqRange::Wide
+            }
+        };
+        if self.cache.lora_freq_range != Some(range) {
+            self.set_lora_freq_range(range).await?;
+        }
+        Ok(range)
     }
 
     /// Long preamble can be modulated in phase in order to provide information about how many symbols are left
     /// This allows a receiver to go back to sleep if beginning of the frame starts in a long time
     pub async fn set_lora_preamble_modulation(&mut self, en: bool, dram_ret: u8, wakeup_time: u16, min_sleep_time: u32) -> Result<(), Lr2021Error> {
         let req = config_lora_preamble_modulation_cmd(en, dram_ret, wakeup_time, min_sleep_time);
-        self.cmd_wr(&req).await
+        let res = self.cmd_wr(&req).await;
+        self.feature_check(Feature::LoraPreambleModulation, res)
+    }
+
+    /// Configure reception of a long preamble with phase modulation (see [`set_lora_preamble_modulation`](Lr2021::set_lora_preamble_modulation) on the transmitter)
+    /// The receiver decodes the number of symbols left, sleeps and wakes up before the end of the preamble.
+    /// The synchronisation timeout is disabled so that the reception is not aborted while tracking a multi-second preamble
+    pub async fn set_lora_preamble_modulation_rx(&mut self, cfg: &PreambleModRxCfg) -> Result<(), Lr2021Error> {
+        match cfg.detect {
+            Some(d) => {
+                let req = config_lora_preamble_modulation_adv_cmd(true, cfg.dram_ret, cfg.wakeup_time, cfg.min_sleep_time, d.err_thr, d.min_sym, d.detect_time_sym, d.start_offset, d.end_offset);
+                let res = self.cmd_wr(&req).await;
+                self.feature_check(Feature::LoraPreambleModulation, res)?;
+            }
+            None => self.set_lora_preamble_modulation(true, cfg.dram_ret, cfg.wakeup_time, cfg.min_sleep_time).await?,
+        }
+        self.set_lora_synch_timeout(0, TimeoutFormat::Integer).await
     }
 
     /// Configure blanking (algorithm to reduce impact of interferers)
     /// Works best when long interleaving is enabled (i.e. any CR > 4)
     pub async fn set_lora_blanking(&mut self, cfg: BlankingCfg) -> Result<(), Lr2021Error> {
         let req = set_lora_blanking_cmd(cfg.thr_gain, cfg.snr_thr, cfg.symb_gain, cfg.detect, cfg.rssi_thr);
-        self.cmd_wr(&req).await
+        let res = self.cmd_wr(&req).await;
+        self.feature_check(Feature::LoraBlanking, res)
     }
 
     /// Configure intra-packet frequency hopping
@@ -510,7 +1232,8 @@ impl<O,SPI, M> Lr2021<O,SPI, M> where
             buffer[7+4*i] = ( f        & 0xFF) as u8;
         }
         let len = 3 + 4*freq_hops.len();
-        self.cmd_buf_wr(len).await
+        let res = self.cmd_buf_wr(len).await;
+        self.feature_check(Feature::LoraHopping, res)
     }
 
     /// Patch the RF setting for ranging operation
@@ -0,0 +1,274 @@
+//! # LoRaWAN payload cryptography
+//!
+//! This module provides a software AES-128 implementation with the CTR/CMAC constructions
+//! used by LoRaWAN 1.0.x, allowing a minimal MAC layer to encrypt payloads and compute MICs
+//! without pulling a separate crypto crate.
+//!
+//! The implementation is a straightforward table-based AES and is not hardened against side-channel attacks.
+//!
+//! This module is only available with the `lorawan-crypto` feature.
+//!
+//! ## Quick Start
+//!
+//! ```rust,no_run
+//! use lr2021::lorawan::*;
+//!
+//! // Build an uplink: encrypt FRMPayload with AppSKey and append MIC computed with NwkSKey
+//! let mut frm_payload = [0x01, 0x02, 0x03];
+//! lorawan_payload_crypt(&app_skey, dev_addr, fcnt, Dir::Uplink, &mut frm_payload);
+//! // ... build the PHYPayload (MHDR | FHDR | FPort | FRMPayload) in msg
+//! let mic = lorawan_mic(&nwk_skey, dev_addr, fcnt, Dir::Uplink, &msg);
+//! ```
+//!
+//! ## Available Functions
+//! - [`lorawan_payload_crypt`] - Encrypt/Decrypt a FRMPayload (AES-128 CTR)
+//! - [`lorawan_mic`] - Compute the MIC of a data frame
+//! - [`lorawan_join_mic`] - Compute the MIC of a join request/accept
+//! - [`lorawan_join_accept_decrypt`] - Decrypt a join accept
+//! - [`lorawan_session_keys`] - Derive the session keys after a join accept
+//! - [`aes128_cmac`] - AES-128 CMAC (RFC 4493)
+
+/// AES-128 block size
+pub const AES_BLOCK_SIZE: usize = 16;
+
+/// AES key (or block)
+pub type AesKey = [u8; AES_BLOCK_SIZE];
+
+const SBOX: [u8; 256] = [
+    0x63, 0x7c, 0x77, 0x7b, 0xf2, 0x6b, 0x6f, 0xc5, 0x30, 0x01, 0x67, 0x2b, 0xfe, 0xd7, 0xab, 0x76,
+    0xca, 0x82, 0xc9, 0x7d, 0xfa, 0x59, 0x47, 0xf0, 0xad, 0xd4, 0xa2, 0xaf, 0x9c, 0xa4, 0x72, 0xc0,
+    0xb7, 0xfd, 0x93, 0x26, 0x36, 0x3f, 0xf7, 0xcc, 0x34, 0xa5, 0xe5, 0xf1, 0x71, 0xd8, 0x31, 0x15,
+    0x04, 0xc7, 0x23, 0xc3, 0x18, 0x96, 0x05, 0x9a, 0x07, 0x12, 0x80, 0xe2, 0xeb, 0x27, 0xb2, 0x75,
+    0x09, 0x83, 0x2c, 0x1a, 0x1b, 0x6e, 0x5a, 0xa0, 0x52, 0x3b, 0xd6, 0xb3, 0x29, 0xe3, 0x2f, 0x84,
+    0x53, 0xd1, 0x00, 0xed, 0x20, 0xfc, 0xb1, 0x5b, 0x6a, 0xcb, 0xbe, 0x39, 0x4a, 0x4c, 0x58, 0xcf,
+    0xd0, 0xef, 0xaa, 0xfb, 0x43, 0x4d, 0x33, 0x85, 0x45, 0xf9, 0x02, 0x7f, 0x50, 0x3c, 0x9f, 0xa8,
+    0x51, 0xa3, 0x40, 0x8f, 0x92, 0x9d, 0x38, 0xf5, 0xbc, 0xb6, 0xda, 0x21, 0x10, 0xff, 0xf3, 0xd2,
+    0xcd, 0x0c, 0x13, 0xec, 0x5f, 0x97, 0x44, 0x17, 0xc4, 0xa7, 0x7e, 0x3d, 0x64, 0x5d, 0x19, 0x73,
+    0x60, 0x81, 0x4f, 0xdc, 0x22, 0x2a, 0x90, 0x88, 0x46, 0xee, 0xb8, 0x14, 0xde, 0x5e, 0x0b, 0xdb,
+    0xe0, 0x32, 0x3a, 0x0a, 0x49, 0x06, 0x24, 0x5c, 0xc2, 0xd3, 0xac, 0x62, 0x91, 0x95, 0xe4, 0x79,
+    0xe7, 0xc8, 0x37, 0x6d, 0x8d, 0xd5, 0x4e, 0xa9, 0x6c, 0x56, 0xf4, 0xea, 0x65, 0x7a, 0xae, 0x08,
+    0xba, 0x78, 0x25, 0x2e, 0x1c, 0xa6, 0xb4, 0xc6, 0xe8, 0xdd, 0x74, 0x1f, 0x4b, 0xbd, 0x8b, 0x8a,
+    0x70, 0x3e, 0xb5, 0x66, 0x48, 0x03, 0xf6, 0x0e, 0x61, 0x35, 0x57, 0xb9, 0x86, 0xc1, 0x1d, 0x9e,
+    0xe1, 0xf8, 0x98, 0x11, 0x69, 0xd9, 0x8e, 0x94, 0x9b, 0x1e, 0x87, 0xe9, 0xce, 0x55, 0x28, 0xdf,
+    0x8c, 0xa1, 0x89, 0x0d, 0xbf, 0xe6, 0x42, 0x68, 0x41, 0x99, 0x2d, 0x0f, 0xb0, 0x54, 0xbb, 0x16,
+];
+
+const RCON: [u8; 10] = [0x01, 0x02, 0x04, 0x08, 0x10, 0x20, 0x40, 0x80, 0x1b, 0x36];
+
+/// Multiplication by 2 in GF(2^8)
+fn xtime(x: u8) -> u8 {
+    (x << 1) ^ if (x & 0x80) != 0 {0x1b} else {0}
+}
+
+/// AES-128 cipher (encryption only, which is all LoRaWAN needs)
+#[derive(Clone)]
+pub struct Aes128 {
+    round_keys: [[u8; AES_BLOCK_SIZE]; 11],
+}
+
+impl Aes128 {
+    /// Expand the key
+    pub fn new(key: &AesKey) -> Self {
+        let mut round_keys = [[0u8; AES_BLOCK_SIZE]; 11];
+        round_keys[0] = *key;
+        for r in 1..11 {
+            let prev = round_keys[r-1];
+            let mut t = [prev[13], prev[14], prev[15], prev[12]];
+            for b in t.iter_mut() {
+                *b = SBOX[*b as usize];
+            }
+            t[0] ^= RCON[r-1];
+            let rk = &mut round_keys[r];
+            for i in 0..AES_BLOCK_SIZE {
+                let w = if i < 4 {t[i]} else {rk[i-4]};
+                rk[i] = prev[i] ^ w;
+            }
+        }
+        Self { round_keys }
+    }
+
+    /// Encrypt a block in place
+    pub fn encrypt_block(&self, block: &mut AesKey) {
+        add_round_key(block, &self.round_keys[0]);
+        for r in 1..11 {
+            sub_shift(block);
+            if r != 10 {
+                mix_columns(block);
+            }
+            add_round_key(block, &self.round_keys[r]);
+        }
+    }
+}
+
+fn add_round_key(block: &mut AesKey, rk: &AesKey) {
+    for (b, k) in block.iter_mut().zip(rk) {
+        *b ^= k;
+    }
+}
+
+/// SubBytes and ShiftRows (state is column-major)
+fn sub_shift(block: &mut AesKey) {
+    let s = *block;
+    for c in 0..4 {
+        for r in 0..4 {
+            block[4*c + r] = SBOX[s[4*((c + r) % 4) + r] as usize];
+        }
+    }
+}
+
+fn mix_columns(block: &mut AesKey) {
+    for col in block.chunks_exact_mut(4) {
+        let a = [col[0], col[1], col[2], col[3]];
+        let all = a[0] ^ a[1] ^ a[2] ^ a[3];
+        for i in 0..4 {
+            col[i] = a[i] ^ all ^ xtime(a[i] ^ a[(i + 1) % 4]);
+        }
+    }
+}
+
+/// Incremental AES-128 CMAC (RFC 4493)
+pub struct Cmac {
+    aes: Aes128,
+    state: AesKey,
+    buf: AesKey,
+    len: usize,
+}
+
+impl Cmac {
+    /// Create a CMAC context
+    pub fn new(key: &AesKey) -> Self {
+        Self { aes: Aes128::new(key), state: [0; AES_BLOCK_SIZE], buf: [0; AES_BLOCK_SIZE], len: 0 }
+    }
+
+    /// Add data to the message
+    pub fn update(&mut self, data: &[u8]) {
+        for &b in data {
+            // Last block must be kept for finalize
+            if self.len == AES_BLOCK_SIZE {
+                add_round_key(&mut self.state, &self.buf);
+                self.aes.encrypt_block(&mut self.state);
+                self.len = 0;
+            }
+            self.buf[self.len] = b;
+            self.len += 1;
+        }
+    }
+
+    /// Return the CMAC of the message
+    pub fn finalize(mut self) -> AesKey {
+        let mut k1 = [0; AES_BLOCK_SIZE];
+        self.aes.encrypt_block(&mut k1);
+        let k1 = dbl(&k1);
+        if self.len == AES_BLOCK_SIZE {
+            add_round_key(&mut self.buf, &k1);
+        } else {
+            let k2 = dbl(&k1);
+            self.buf[self.len] = 0x80;
+            self.buf[self.len+1..].fill(0);
+            add_round_key(&mut self.buf, &k2);
+        }
+        add_round_key(&mut self.state, &self.buf);
+        self.aes.encrypt_block(&mut self.state);
+        self.state
+    }
+}
+
+/// Subkey generation: multiplication by x in GF(2^128)
+fn dbl(v: &AesKey) -> AesKey {
+    let mut out = [0; AES_BLOCK_SIZE];
+    for i in 0..AES_BLOCK_SIZE {
+        out[i] = (v[i] << 1) | if i < AES_BLOCK_SIZE - 1 {v[i+1] >> 7} else {0};
+    }
+    if (v[0] & 0x80) != 0 {
+        out[AES_BLOCK_SIZE-1] ^= 0x87;
+    }
+    out
+}
+
+/// Compute the AES-128 CMAC of a message
+pub fn aes128_cmac(key: &AesKey, data: &[u8]) -> AesKey {
+    let mut cmac = Cmac::new(key);
+    cmac.update(data);
+    cmac.finalize()
+}
+
+/// Frame direction
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Dir {
+    Uplink = 0,
+    Downlink = 1,
+}
+
+/// Build the A_i/B_0 block used for encryption and MIC: first byte, direction, device address, frame counter and last byte
+fn frame_block(first: u8, dev_addr: u32, fcnt: u32, dir: Dir, last: u8) -> AesKey {
+    let mut block = [0; AES_BLOCK_SIZE];
+    block[0] = first;
+    block[5] = dir as u8;
+    block[6..10].copy_from_slice(&dev_addr.to_le_bytes());
+    block[10..14].copy_from_slice(&fcnt.to_le_bytes());
+    block[15] = last;
+    block
+}
+
+/// Encrypt or decrypt (same operation) a FRMPayload in place
+/// The key is the AppSKey (or NwkSKey when FPort is 0)
+pub fn lorawan_payload_crypt(key: &AesKey, dev_addr: u32, fcnt: u32, dir: Dir, payload: &mut [u8]) {
+    let aes = Aes128::new(key);
+    for (i, chunk) in payload.chunks_mut(AES_BLOCK_SIZE).enumerate() {
+        let mut s = frame_block(0x01, dev_addr, fcnt, dir, (i + 1) as u8);
+        aes.encrypt_block(&mut s);
+        for (b, k) in chunk.iter_mut().zip(s) {
+            *b ^= k;
+        }
+    }
+}
+
+/// Compute the MIC of a data frame: msg is MHDR | FHDR | FPort | FRMPayload (encrypted)
+pub fn lorawan_mic(key: &AesKey, dev_addr: u32, fcnt: u32, dir: Dir, msg: &[u8]) -> [u8; 4] {
+    let b0 = frame_block(0x49, dev_addr, fcnt, dir, msg.len() as u8);
+    let mut cmac = Cmac::new(key);
+    cmac.update(&b0);
+    cmac.update(msg);
+    let mac = cmac.finalize();
+    [mac[0], mac[1], mac[2], mac[3]]
+}
+
+/// Compute the MIC of a join request or a decrypted join accept (msg excluding the MIC)
+pub fn lorawan_join_mic(key: &AesKey, msg: &[u8]) -> [u8; 4] {
+    let mac = aes128_cmac(key, msg);
+    [mac[0], mac[1], mac[2], mac[3]]
+}
+
+/// Decrypt a join accept in place (payload after MHDR, including MIC: 16 or 32 bytes)
+/// Return false if the length is not a multiple of the block size
+pub fn lorawan_join_accept_decrypt(key: &AesKey, payload: &mut [u8]) -> bool {
+    if !payload.len().is_multiple_of(AES_BLOCK_SIZE) {
+        return false;
+    }
+    let aes = Aes128::new(key);
+    for chunk in payload.chunks_exact_mut(AES_BLOCK_SIZE) {
+        let mut block = [0; AES_BLOCK_SIZE];
+        block.copy_from_slice(chunk);
+        // The network server uses AES decrypt so that the device only needs encryption
+        aes.encrypt_block(&mut block);
+        chunk.copy_from_slice(&block);
+    }
+    true
+}
+
+/// Derive the session keys (NwkSKey, AppSKey) from the AppKey and the join accept/request fields
+pub fn lorawan_session_keys(app_key: &AesKey, app_nonce: [u8; 3], net_id: [u8; 3], dev_nonce: u16) -> (AesKey, AesKey) {
+    let aes = Aes128::new(app_key);
+    let mut block = [0; AES_BLOCK_SIZE];
+    block[1..4].copy_from_slice(&app_nonce);
+    block[4..7].copy_from_slice(&net_id);
+    block[7..9].copy_from_slice(&dev_nonce.to_le_bytes());
+    let mut nwk_skey = block;
+    nwk_skey[0] = 0x01;
+    aes.encrypt_block(&mut nwk_skey);
+    let mut app_skey = block;
+    app_skey[0] = 0x02;
+    aes.encrypt_block(&mut app_skey);
+    (nwk_skey, app_skey)
+}
@@ -11,12 +11,13 @@
 //! ```rust,no_run
 //! use lr2021::radio::PacketType;
 //! use lr2021::lrfhss::{LrfhssCr, Grid, Hopping, LrfhssBw};
+//! use lr2021::syncword::Syncword;
 //!
 //! // Set packet type to LR-FHSS
 //! lr2021.set_packet_type(PacketType::LrFhss).await.expect("Setting packet type");
 //!
 //! // Configure syncword (default is 0x2C0F7995)
-//! lr2021.set_lrfhss_syncword(0x2C0F7995).await.expect("Setting syncword");
+//! lr2021.set_lrfhss_syncword(Syncword::from_msb_bits(0x2C0F7995, 32)).await.expect("Setting syncword");
 //!
 //! // Build LR-FHSS packet with payload
 //! let payload = b"Hello, LR-FHSS!";
@@ -45,6 +46,7 @@ use embedded_hal::digital::OutputPin;
 use embedded_hal_async::spi::SpiBus;
 
 pub use super::cmd::cmd_lrfhss::*;
+use super::syncword::Syncword;
 use super::{BusyPin, Lr2021, Lr2021Error};
 
 #[derive(Clone)]
@@ -69,8 +71,12 @@ impl<O,SPI, M> Lr2021<O,SPI, M> where
 
     /// Configure Syncword of LRFHSS packet
     /// Default value is 0x2C0F7995
-    pub async fn set_lrfhss_syncword(&mut self, syncword: u32) -> Result<(), Lr2021Error> {
-        let req = set_lr_fhss_sync_word_cmd(syncword);
+    /// Syncword must be 32 bits long
+    pub async fn set_lrfhss_syncword(&mut self, syncword: Syncword) -> Result<(), Lr2021Error> {
+        if syncword.nb_bits() != 32 {
+            return Err(Lr2021Error::InvalidParam);
+        }
+        let req = set_lr_fhss_sync_word_cmd(syncword.msb_bits() as u32);
         self.cmd_wr(&req).await
     }
 
@@ -87,9 +93,9 @@ impl<O,SPI, M> Lr2021<O,SPI, M> where
             self.buffer_mut()[4] = ((hop.len >> 8) & 0xFF) as u8;
             self.buffer_mut()[5] = ((hop.len     ) & 0xFF) as u8;
             self.spi.transfer_in_place(&mut self.buffer.data_mut()[..6]).await
-                .map_err(|_| Lr2021Error::Spi)?;
+                .map_err(Lr2021Error::spi)?;
         }
-        self.nss.set_high().map_err(|_| Lr2021Error::Pin)
+        self.nss.set_high().map_err(Lr2021Error::pin)
     }
 
 
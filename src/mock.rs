@@ -0,0 +1,409 @@
+//! # Mock transport
+//!
+//! With the `mock` feature, the driver can run on a host without any hardware: [`MockBus`] provides
+//! the SPI bus, the NSS/reset output pins and the busy input pin of a simulated chip.
+//!
+//! Every SPI transaction (NSS low to NSS high) is captured in a buffer provided by the application,
+//! so that the exact byte stream sent by the driver can be checked against a reference capture.
+//! The chip answers every transaction with an OK status, except for the read phase following
+//! a command listed in the replies: the responses programmed with [`MockReply`] are then replayed.
+//!
+//! The busy pin is always low and the driver does not wait on it: the timers of the driver still
+//! require an `embassy-time` driver (e.g. a simulated time driver in the test crate).
+//!
+//! This feature is intended for testing only and must not be enabled in production.
+//!
+//! ## Quick Start
+//!
+//! ```rust,no_run
+//! use lr2021::Lr2021;
+//! use lr2021::mock::{MockBus, MockReply};
+//!
+//! // GetStatus (0x0100) reports no interrupt on the first call, then TxDone
+//! const STATUS: [&[u8]; 2] = [&[0x04, 0x00, 0x00, 0x00, 0x00, 0x00], &[0x04, 0x00, 0x00, 0x08, 0x00, 0x00]];
+//! let replies = [MockReply::new(0x0100, &STATUS)];
+//! let mut capture = [0u8; 1024];
+//! let bus = MockBus::new(&mut capture, &replies);
+//! let mut lr2021 = Lr2021::new(bus.pin(), bus.pin(), bus.spi(), bus.nss());
+//! let (_, intr) = lr2021.get_status().await.expect("GetStatus");
+//! assert!(!intr.tx_done());
+//! // The read phase answered by the reply is not part of the commands
+//! assert_eq!(bus.capture().compare(&[&[0x01, 0x00]]), Ok(()));
+//! ```
+//!
+//! ## Available Methods
+//! - [`MockBus::new`] - Create a mock bus capturing into a buffer and replaying a list of replies
+//! - [`MockBus::spi`] / [`MockBus::nss`] / [`MockBus::pin`] - Handles given to the driver
+//! - [`MockBus::capture`] - Transactions captured since the creation or the last clear
+//! - [`MockBus::clear`] - Remove all transactions captured
+//! - [`MockCapture::frames`] / [`MockCapture::commands`] - Iterate over the captured transactions
+//! - [`MockCapture::compare`] - Compare the commands captured with a reference capture
+
+use core::cell::{Cell, Ref, RefCell};
+use core::convert::Infallible;
+
+use embedded_hal::digital::{self, InputPin, OutputPin};
+use embedded_hal::spi;
+use embedded_hal_async::digital::Wait;
+use embedded_hal_async::spi::SpiBus;
+
+/// Status returned by the mock chip when no reply is programmed (command OK)
+pub const MOCK_STATUS_OK: [u8; 2] = [0x04, 0x00];
+
+/// Size of the header of a frame in the capture buffer (length and flags)
+const FRAME_HDR: usize = 3;
+
+/// Reply of the mock chip to a command, returned on the read phase following it
+#[derive(Debug)]
+pub struct MockReply<'a> {
+    /// Opcode of the command
+    opcode: u16,
+    /// Successive responses (including the two status bytes)
+    rsps: &'a [&'a [u8]],
+    /// Number of responses already returned
+    nb_used: Cell<usize>,
+}
+
+impl<'a> MockReply<'a> {
+    /// Reply to a command: successive calls get the successive responses, the last one being repeated
+    pub const fn new(opcode: u16, rsps: &'a [&'a [u8]]) -> Self {
+        Self { opcode, rsps, nb_used: Cell::new(0) }
+    }
+
+    /// Number of times the reply was returned
+    pub fn nb_used(&self) -> usize {
+        self.nb_used.get()
+    }
+
+    fn next(&self) -> &'a [u8] {
+        let idx = self.nb_used.get().min(self.rsps.len().saturating_sub(1));
+        self.nb_used.set(self.nb_used.get() + 1);
+        self.rsps.get(idx).copied().unwrap_or(&[])
+    }
+}
+
+/// Transaction captured on the mock bus
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MockFrame<'c> {
+    /// Bytes sent by the driver (MOSI)
+    pub mosi: &'c [u8],
+    /// Read phase answered by a programmed reply
+    pub reply: bool,
+}
+
+/// Difference between a capture and its reference
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CaptureMismatch {
+    /// Command at this index differs from the reference
+    Frame(usize),
+    /// Number of commands captured differs from the reference (captured, expected)
+    Count(usize, usize),
+    /// Capture buffer was too small to record all transactions
+    Overflow,
+}
+
+/// Transactions captured on the mock bus
+pub struct MockCapture<'c> {
+    bytes: Ref<'c, [u8]>,
+    overflow: bool,
+}
+
+impl MockCapture<'_> {
+    /// All transactions captured, in order
+    pub fn frames(&self) -> impl Iterator<Item = MockFrame<'_>> {
+        let mut rem: &[u8] = &self.bytes;
+        core::iter::from_fn(move || {
+            if rem.len() < FRAME_HDR {
+                return None;
+            }
+            let len = u16::from_le_bytes([rem[0], rem[1]]) as usize;
+            let frame = MockFrame { mosi: &rem[FRAME_HDR..FRAME_HDR + len], reply: rem[2] != 0 };
+            rem = &rem[FRAME_HDR + len..];
+            Some(frame)
+        })
+    }
+
+    /// Transactions captured excluding the read phases answered by a reply
+    pub fn commands(&self) -> impl Iterator<Item = &[u8]> {
+        self.frames().filter(|f| !f.reply).map(|f| f.mosi)
+    }
+
+    /// Flag when some transactions were not recorded because the capture buffer was full
+    pub fn overflow(&self) -> bool {
+        self.overflow
+    }
+
+    /// Compare the commands captured with a reference capture
+    pub fn compare(&self, expected: &[&[u8]]) -> Result<(), CaptureMismatch> {
+        if self.overflow {
+            return Err(CaptureMismatch::Overflow);
+        }
+        let mut nb = 0;
+        for (i, cmd) in self.commands().enumerate() {
+            match expected.get(i) {
+                Some(exp) if *exp == cmd => {}
+                Some(_) => return Err(CaptureMismatch::Frame(i)),
+                None => {}
+            }
+            nb += 1;
+        }
+        if nb != expected.len() {
+            return Err(CaptureMismatch::Count(nb, expected.len()));
+        }
+        Ok(())
+    }
+}
+
+/// State of the mock bus
+struct MockState<'a> {
+    /// Capture buffer: frames stored as length (LE), flags and MOSI bytes
+    capture: &'a mut [u8],
+    /// Number of bytes used in the capture buffer
+    len: usize,
+    /// Capture buffer full
+    overflow: bool,
+    /// Transaction in progress (NSS low)
+    active: bool,
+    /// Offset of the header of the frame in progress (None when not recorded)
+    frame: Option<usize>,
+    /// Number of bytes exchanged in the frame in progress
+    pos: usize,
+    /// First two bytes of the frame in progress
+    opcode: [u8; 2],
+    /// Reply to the last command, if programmed
+    reply: Option<&'a MockReply<'a>>,
+    /// Response replayed in the frame in progress
+    rsp: Option<&'a [u8]>,
+    /// Opcode of the last command frame
+    last_opcode: Option<u16>,
+}
+
+impl<'a> MockState<'a> {
+    fn open(&mut self, replies: &'a [MockReply<'a>]) {
+        // The response is only consumed when the transaction starts
+        self.reply = self.last_opcode.and_then(|op| replies.iter().find(|r| r.opcode == op));
+        self.rsp = None;
+        self.pos = 0;
+        self.active = true;
+        if !self.overflow && self.len + FRAME_HDR <= self.capture.len() {
+            self.frame = Some(self.len);
+            self.len += FRAME_HDR;
+        } else {
+            self.overflow = true;
+            self.frame = None;
+        }
+    }
+
+    fn close(&mut self) {
+        if !self.active {
+            return;
+        }
+        self.active = false;
+        match self.frame.take() {
+            // NSS toggled without any transfer (e.g. wake-up): nothing to record
+            Some(start) if self.pos == 0 => self.len = start,
+            Some(start) if self.overflow => self.len = start,
+            Some(start) => {
+                let len = (self.len - start - FRAME_HDR) as u16;
+                self.capture[start..start + 2].copy_from_slice(&len.to_le_bytes());
+                self.capture[start + 2] = self.rsp.is_some() as u8;
+            }
+            None => {}
+        }
+        if self.pos > 0 {
+            self.last_opcode = if self.rsp.is_some() {None} else {Some(u16::from_be_bytes(self.opcode))};
+        }
+        self.rsp = None;
+    }
+
+    fn xfer(&mut self, mosi: u8) -> u8 {
+        if self.pos == 0 {
+            self.rsp = self.reply.map(|r| r.next());
+        }
+        let miso = match self.rsp {
+            Some(rsp) => rsp.get(self.pos).copied().unwrap_or(0),
+            None => MOCK_STATUS_OK.get(self.pos).copied().unwrap_or(0),
+        };
+        if self.pos < 2 {
+            self.opcode[self.pos] = mosi;
+        }
+        self.pos += 1;
+        if self.frame.is_some() && !self.overflow {
+            match self.capture.get_mut(self.len) {
+                Some(b) => {
+                    *b = mosi;
+                    self.len += 1;
+                }
+                None => self.overflow = true,
+            }
+        }
+        miso
+    }
+}
+
+/// Simulated chip shared by the mock SPI bus and pins
+pub struct MockBus<'a> {
+    state: RefCell<MockState<'a>>,
+    replies: &'a [MockReply<'a>],
+}
+
+impl<'a> MockBus<'a> {
+    /// Create a mock bus capturing the transactions into `capture` and replaying `replies`
+    pub fn new(capture: &'a mut [u8], replies: &'a [MockReply<'a>]) -> Self {
+        let state = MockState { capture, len: 0, overflow: false, active: false, frame: None, pos: 0, opcode: [0; 2], reply: None, rsp: None, last_opcode: None };
+        Self { state: RefCell::new(state), replies }
+    }
+
+    /// SPI bus of the chip
+    pub fn spi(&self) -> MockSpi<'_, 'a> {
+        MockSpi { bus: self }
+    }
+
+    /// NSS pin of the chip: delimits the captured transactions
+    pub fn nss(&self) -> MockPin<'_, 'a> {
+        MockPin { bus: self, nss: true }
+    }
+
+    /// Any other pin (reset output, busy input always low)
+    pub fn pin(&self) -> MockPin<'_, 'a> {
+        MockPin { bus: self, nss: false }
+    }
+
+    /// Transactions captured since the creation or the last clear
+    pub fn capture(&self) -> MockCapture<'_> {
+        let state = self.state.borrow();
+        let overflow = state.overflow;
+        let bytes = Ref::map(state, |s| {
+            // Exclude the frame in progress
+            let end = s.frame.unwrap_or(s.len);
+            &s.capture[..end]
+        });
+        MockCapture { bytes, overflow }
+    }
+
+    /// Remove all transactions captured
+    pub fn clear(&self) {
+        let mut state = self.state.borrow_mut();
+        state.len = 0;
+        state.overflow = false;
+        // A transaction in progress is not recorded anymore
+        state.frame = None;
+    }
+}
+
+/// SPI bus of the mock chip
+pub struct MockSpi<'b, 'a> {
+    bus: &'b MockBus<'a>,
+}
+
+impl MockSpi<'_, '_> {
+    fn exchange(&mut self, read: &mut [u8], write: &[u8]) {
+        let mut state = self.bus.state.borrow_mut();
+        if !state.active {
+            state.open(self.bus.replies);
+        }
+        for i in 0..read.len().max(write.len()) {
+            let miso = state.xfer(write.get(i).copied().unwrap_or(0));
+            if let Some(b) = read.get_mut(i) {
+                *b = miso;
+            }
+        }
+    }
+}
+
+impl spi::ErrorType for MockSpi<'_, '_> {
+    type Error = Infallible;
+}
+
+impl SpiBus<u8> for MockSpi<'_, '_> {
+    async fn read(&mut self, words: &mut [u8]) -> Result<(), Infallible> {
+        self.exchange(words, &[]);
+        Ok(())
+    }
+
+    async fn write(&mut self, words: &[u8]) -> Result<(), Infallible> {
+        self.exchange(&mut [], words);
+        Ok(())
+    }
+
+    async fn transfer(&mut self, read: &mut [u8], write: &[u8]) -> Result<(), Infallible> {
+        self.exchange(read, write);
+        Ok(())
+    }
+
+    async fn transfer_in_place(&mut self, words: &mut [u8]) -> Result<(), Infallible> {
+        let mut state = self.bus.state.borrow_mut();
+        if !state.active {
+            state.open(self.bus.replies);
+        }
+        for w in words.iter_mut() {
+            *w = state.xfer(*w);
+        }
+        Ok(())
+    }
+
+    async fn flush(&mut self) -> Result<(), Infallible> {
+        Ok(())
+    }
+}
+
+/// Pin of the mock chip
+pub struct MockPin<'b, 'a> {
+    bus: &'b MockBus<'a>,
+    /// NSS pin delimiting the transactions
+    nss: bool,
+}
+
+impl digital::ErrorType for MockPin<'_, '_> {
+    type Error = Infallible;
+}
+
+impl OutputPin for MockPin<'_, '_> {
+    fn set_low(&mut self) -> Result<(), Infallible> {
+        if self.nss {
+            let mut state = self.bus.state.borrow_mut();
+            state.close();
+            state.open(self.bus.replies);
+        }
+        Ok(())
+    }
+
+    fn set_high(&mut self) -> Result<(), Infallible> {
+        if self.nss {
+            self.bus.state.borrow_mut().close();
+        }
+        Ok(())
+    }
+}
+
+impl InputPin for MockPin<'_, '_> {
+    fn is_high(&mut self) -> Result<bool, Infallible> {
+        Ok(false)
+    }
+
+    fn is_low(&mut self) -> Result<bool, Infallible> {
+        Ok(true)
+    }
+}
+
+impl Wait for MockPin<'_, '_> {
+    async fn wait_for_high(&mut self) -> Result<(), Infallible> {
+        Ok(())
+    }
+
+    async fn wait_for_low(&mut self) -> Result<(), Infallible> {
+        Ok(())
+    }
+
+    async fn wait_for_rising_edge(&mut self) -> Result<(), Infallible> {
+        Ok(())
+    }
+
+    async fn wait_for_falling_edge(&mut self) -> Result<(), Infallible> {
+        Ok(())
+    }
+
+    async fn wait_for_any_edge(&mut self) -> Result<(), Infallible> {
+        Ok(())
+    }
+}
@@ -0,0 +1,336 @@
+//! # Multi-protocol listener
+//!
+//! This module provides a [`MultiProtocolListener`] interleaving short RX windows across several protocols
+//! (e.g. LoRa and FSK wake-up frames) with a single radio, for bridges or gateways.
+//!
+//! Each protocol is described by a slot implementing [`ListenSlot`], which re-applies its configuration
+//! (packet type, RF, modulation, packet parameters, ...) at the start of its window.
+//! Configurations are only re-applied when switching slot, and the RX timeout is stopped on preamble detection
+//! so that a packet starting at the end of a window is not lost.
+//! Each packet received is reported with the index of the slot ([`TaggedPacket`]).
+//!
+//! Built-in slots are provided for LoRa ([`LoraSlot`]), FSK ([`FskSlot`]) and FLRC ([`FlrcSlot`]); any other protocol can be
+//! supported by implementing [`ListenSlot`] (directly or on an enum grouping several configurations).
+//!
+//! ## Quick Start
+//!
+//! ```rust,no_run
+//! use lr2021::multilisten::{MultiProtocolListener, LoraSlot};
+//! use lr2021::lora::{LoraModulationParams, LoraPacketParams, Sf, LoraBw};
+//!
+//! let sf7 = LoraModulationParams::basic(Sf::Sf7, LoraBw::Bw125);
+//! let sf9 = LoraModulationParams::basic(Sf::Sf9, LoraBw::Bw125);
+//! let slots = [
+//!     LoraSlot::new(868_100_000, sf7, LoraPacketParams::basic(32, &sf7)),
+//!     LoraSlot::new(869_525_000, sf9, LoraPacketParams::basic(32, &sf9)),
+//! ];
+//! // Listen 20ms on each slot
+//! let mut listener = MultiProtocolListener::new(slots, Duration::from_millis(20));
+//! lr2021.multi_listen_start(&mut listener).await.expect("ListenStart");
+//! loop {
+//!     let pkt = lr2021.multi_listen(&mut listener).await.expect("Listen");
+//!     info!("Slot {} : {:02x}", pkt.slot, &lr2021.buffer()[..pkt.len]);
+//! }
+//! ```
+//!
+//! ## Available Methods
+//! - [`multi_listen_start`](Lr2021::multi_listen_start) - Prepare the chip for the multi-protocol listening
+//! - [`multi_listen`](Lr2021::multi_listen) - Rotate over the slots until a packet is received
+
+use embassy_time::{Duration, Instant, Timer};
+use embedded_hal::digital::OutputPin;
+use embedded_hal_async::spi::SpiBus;
+
+use super::flrc::{FlrcBitrate, FlrcCr, FlrcPacketParams};
+use super::fsk::{AddrComp, Crc, FskPktFormat, PblLenDetect, PldLenUnit};
+use super::lora::{LoraModulationParams, LoraPacketParams};
+use super::radio::PacketType;
+use super::status::{Intr, IRQ_MASK_ADDR_ERROR, IRQ_MASK_CRC_ERROR, IRQ_MASK_HEADER_ERR, IRQ_MASK_LEN_ERROR, IRQ_MASK_PREAMBLE_DETECTED, IRQ_MASK_RX_DONE, IRQ_MASK_TIMEOUT};
+use super::syncword::Syncword;
+use super::system::ChipMode;
+use super::{BusyPin, Lr2021, Lr2021Error, PulseShape, RxBw, BUFFER_SIZE};
+
+/// Interrupts monitored during a RX window
+const IRQ_MASK_LISTEN : u32 = IRQ_MASK_PREAMBLE_DETECTED | IRQ_MASK_RX_DONE | IRQ_MASK_TIMEOUT | IRQ_MASK_HEADER_ERR | IRQ_MASK_CRC_ERROR | IRQ_MASK_LEN_ERROR | IRQ_MASK_ADDR_ERROR;
+
+/// Interrupts flagging a failed reception
+const IRQ_MASK_RX_ERROR : u32 = IRQ_MASK_HEADER_ERR | IRQ_MASK_CRC_ERROR | IRQ_MASK_LEN_ERROR | IRQ_MASK_ADDR_ERROR;
+
+/// Period between two status checks during a RX window
+const POLL_PERIOD: Duration = Duration::from_micros(500);
+
+/// End of a RX window
+enum WindowEnd {
+    /// No packet received
+    Timeout,
+    /// Reception failed
+    Error,
+    /// Packet received (length in bytes)
+    Packet(usize),
+}
+
+/// Protocol configuration of a listening slot
+pub trait ListenSlot {
+    /// Apply the configuration (packet type, RF, modulation, packet parameters, ...)
+    #[allow(async_fn_in_trait)]
+    async fn configure<O: OutputPin, SPI: SpiBus<u8>, M: BusyPin>(&self, lr: &mut Lr2021<O,SPI,M>) -> Result<(), Lr2021Error>;
+}
+
+/// LoRa listening slot
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct LoraSlot {
+    /// RF frequency (in Hz)
+    pub rf: u32,
+    /// Modulation parameters
+    pub modulation: LoraModulationParams,
+    /// Packet parameters
+    pub packet: LoraPacketParams,
+    /// Syncword (SX127x 1B notation)
+    pub syncword: u8,
+}
+
+impl LoraSlot {
+    /// LoRa slot with the public network syncword
+    pub fn new(rf: u32, modulation: LoraModulationParams, packet: LoraPacketParams) -> Self {
+        Self { rf, modulation, packet, syncword: 0x34 }
+    }
+
+    /// Set the syncword (SX127x 1B notation: 0x34 for public network, 0x12 for private)
+    pub fn with_syncword(self, syncword: u8) -> Self {
+        Self { syncword, ..self }
+    }
+}
+
+impl ListenSlot for LoraSlot {
+    async fn configure<O: OutputPin, SPI: SpiBus<u8>, M: BusyPin>(&self, lr: &mut Lr2021<O,SPI,M>) -> Result<(), Lr2021Error> {
+        lr.set_packet_type(PacketType::Lora).await?;
+        lr.set_rf(self.rf).await?;
+        lr.set_lora_modulation(&self.modulation).await?;
+        lr.set_lora_packet(&self.packet).await?;
+        lr.set_lora_syncword(self.syncword).await
+    }
+}
+
+/// FSK listening slot (SX126x/SX127x compatible packet)
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct FskSlot {
+    /// RF frequency (in Hz)
+    pub rf: u32,
+    /// Bitrate (in bit/s)
+    pub bitrate: u32,
+    /// Pulse shaping
+    pub pulse_shape: PulseShape,
+    /// RX bandwidth
+    pub rx_bw: RxBw,
+    /// Frequency deviation (in Hz)
+    pub fdev: u32,
+    /// Preamble length detected
+    pub pbl_len_detect: PblLenDetect,
+    /// Payload length unit
+    pub pld_len_unit: PldLenUnit,
+    /// Address filtering
+    pub addr_comp: AddrComp,
+    /// Packet format
+    pub pkt_format: FskPktFormat,
+    /// Maximum payload length
+    pub pld_len: u16,
+    /// CRC
+    pub crc: Crc,
+    /// DC-free encoding (whitening)
+    pub dc_free: bool,
+    /// Syncword
+    pub syncword: Syncword,
+}
+
+impl FskSlot {
+    /// FSK slot with variable length packets (8 bits length field), 2B CRC and whitening
+    pub fn new(rf: u32, bitrate: u32, rx_bw: RxBw, fdev: u32, syncword: Syncword, max_len: u16) -> Self {
+        Self {
+            rf, bitrate, pulse_shape: PulseShape::Bt0p5, rx_bw, fdev,
+            pbl_len_detect: PblLenDetect::None, pld_len_unit: PldLenUnit::Bytes, addr_comp: AddrComp::Off,
+            pkt_format: FskPktFormat::Variable8bit, pld_len: max_len, crc: Crc::Crc2Byte, dc_free: true,
+            syncword,
+        }
+    }
+}
+
+impl ListenSlot for FskSlot {
+    async fn configure<O: OutputPin, SPI: SpiBus<u8>, M: BusyPin>(&self, lr: &mut Lr2021<O,SPI,M>) -> Result<(), Lr2021Error> {
+        lr.set_packet_type(PacketType::FskLegacy).await?;
+        lr.set_rf(self.rf).await?;
+        lr.set_fsk_modulation(self.bitrate, self.pulse_shape, self.rx_bw, self.fdev).await?;
+        lr.set_fsk_packet(16, self.pbl_len_detect, false, self.pld_len_unit, self.addr_comp, self.pkt_format, self.pld_len, self.crc, self.dc_free).await?;
+        lr.set_fsk_syncword(self.syncword).await
+    }
+}
+
+/// FLRC listening slot
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct FlrcSlot {
+    /// RF frequency (in Hz)
+    pub rf: u32,
+    /// Bitrate
+    pub bitrate: FlrcBitrate,
+    /// Coding rate
+    pub cr: FlrcCr,
+    /// Pulse shaping
+    pub pulse_shape: PulseShape,
+    /// Packet parameters
+    pub packet: FlrcPacketParams,
+    /// Syncword 1
+    pub syncword: Syncword,
+}
+
+impl FlrcSlot {
+    /// FLRC slot with BT=1.0 pulse shaping
+    pub fn new(rf: u32, bitrate: FlrcBitrate, cr: FlrcCr, packet: FlrcPacketParams, syncword: Syncword) -> Self {
+        Self { rf, bitrate, cr, pulse_shape: PulseShape::Bt1p0, packet, syncword }
+    }
+}
+
+impl ListenSlot for FlrcSlot {
+    async fn configure<O: OutputPin, SPI: SpiBus<u8>, M: BusyPin>(&self, lr: &mut Lr2021<O,SPI,M>) -> Result<(), Lr2021Error> {
+        lr.set_packet_type(PacketType::Flrc).await?;
+        lr.set_rf(self.rf).await?;
+        lr.set_flrc_modulation(self.bitrate, self.cr, self.pulse_shape).await?;
+        lr.set_flrc_packet(&self.packet).await?;
+        lr.set_flrc_syncword(1, self.syncword).await
+    }
+}
+
+/// Packet received by the listener, available in the local buffer
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct TaggedPacket {
+    /// Index of the slot which received the packet
+    pub slot: usize,
+    /// Packet length in bytes
+    pub len: usize,
+}
+
+/// Listener interleaving RX windows across several protocol slots
+pub struct MultiProtocolListener<S: ListenSlot, const N: usize> {
+    /// Protocol slots
+    slots: [S; N],
+    /// RX window duration (in LF clock step)
+    window: u32,
+    /// Maximum duration of a packet after the preamble detection
+    max_pkt_time: Duration,
+    /// Index of the next slot to listen on
+    next: usize,
+    /// Index of the slot currently configured
+    current: Option<usize>,
+    /// Number of packets received per slot
+    nb_pkt: [u32; N],
+    /// Number of failed receptions per slot
+    nb_err: [u32; N],
+}
+
+impl<S: ListenSlot, const N: usize> MultiProtocolListener<S, N> {
+    /// Create a listener with a RX window duration per slot
+    /// By default a packet can last up to 1s after the preamble detection
+    pub fn new(slots: [S; N], window: Duration) -> Self {
+        let window = ((window.as_micros() * 32_768) / 1_000_000).clamp(1, 0xFFFFFE) as u32;
+        Self {
+            slots, window, max_pkt_time: Duration::from_secs(1),
+            next: 0, current: None, nb_pkt: [0; N], nb_err: [0; N],
+        }
+    }
+
+    /// Set the maximum duration of a packet after the preamble detection
+    pub fn with_max_packet_time(self, max_pkt_time: Duration) -> Self {
+        Self { max_pkt_time, ..self }
+    }
+
+    /// Access a slot configuration
+    pub fn slot(&self, idx: usize) -> Option<&S> {
+        self.slots.get(idx)
+    }
+
+    /// Number of packets received on a slot
+    pub fn nb_pkt(&self, idx: usize) -> u32 {
+        self.nb_pkt.get(idx).copied().unwrap_or(0)
+    }
+
+    /// Number of failed receptions (CRC, header, length or address error) on a slot
+    pub fn nb_err(&self, idx: usize) -> u32 {
+        self.nb_err.get(idx).copied().unwrap_or(0)
+    }
+
+    /// Force the configuration of all slots to be applied again (e.g. after a sleep without retention)
+    pub fn invalidate(&mut self) {
+        self.current = None;
+    }
+}
+
+impl<O,SPI, M> Lr2021<O,SPI, M> where
+    O: OutputPin, SPI: SpiBus<u8>, M: BusyPin
+{
+    /// Prepare the chip for the multi-protocol listening: RX timeout stopped on preamble detection
+    pub async fn multi_listen_start<S: ListenSlot, const N: usize>(&mut self, listener: &mut MultiProtocolListener<S, N>) -> Result<(), Lr2021Error> {
+        self.set_stop_timeout(true).await?;
+        listener.invalidate();
+        Ok(())
+    }
+
+    /// Rotate over the slots until a packet is received
+    /// The packet is available in the local buffer
+    pub async fn multi_listen<S: ListenSlot, const N: usize>(&mut self, listener: &mut MultiProtocolListener<S, N>) -> Result<TaggedPacket, Lr2021Error> {
+        if N == 0 {
+            return Err(Lr2021Error::InvalidParam);
+        }
+        loop {
+            let idx = listener.next;
+            listener.next = (idx + 1) % N;
+            if listener.current != Some(idx) {
+                self.set_chip_mode(ChipMode::StandbyXosc).await?;
+                listener.slots[idx].configure(self).await?;
+                listener.current = Some(idx);
+            }
+            self.clear_irqs(Intr::new(IRQ_MASK_LISTEN)).await?;
+            self.set_rx(listener.window, false).await?;
+            match self.multi_listen_window(listener.max_pkt_time).await? {
+                WindowEnd::Packet(len) if len <= BUFFER_SIZE => {
+                    self.rd_rx_fifo(len).await?;
+                    listener.nb_pkt[idx] = listener.nb_pkt[idx].wrapping_add(1);
+                    return Ok(TaggedPacket { slot: idx, len });
+                }
+                WindowEnd::Packet(_) | WindowEnd::Error => {
+                    self.clear_rx_fifo().await?;
+                    listener.nb_err[idx] = listener.nb_err[idx].wrapping_add(1);
+                }
+                WindowEnd::Timeout => {}
+            }
+        }
+    }
+
+    /// Wait for the end of a RX window
+    async fn multi_listen_window(&mut self, max_pkt_time: Duration) -> Result<WindowEnd, Lr2021Error> {
+        let mut preamble_time: Option<Instant> = None;
+        loop {
+            Timer::after(POLL_PERIOD).await;
+            let (_, intr) = self.get_status().await?;
+            if intr.intr_match(IRQ_MASK_RX_ERROR) {
+                return Ok(WindowEnd::Error);
+            }
+            if intr.rx_done() {
+                let len = self.get_rx_fifo_lvl().await? as usize;
+                return Ok(WindowEnd::Packet(len));
+            }
+            if intr.timeout() {
+                return Ok(WindowEnd::Timeout);
+            }
+            if intr.preamble_detected() {
+                let start = *preamble_time.get_or_insert_with(Instant::now);
+                // False detection without syncword: abort the reception
+                if start.elapsed() > max_pkt_time {
+                    self.set_chip_mode(ChipMode::StandbyXosc).await?;
+                    return Ok(WindowEnd::Timeout);
+                }
+            }
+        }
+    }
+}
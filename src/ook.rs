@@ -52,10 +52,16 @@
 //! ### Core Configuration
 //! - [`set_ook_modulation`](Lr2021::set_ook_modulation) - Configure bitrate, bandwidth, and pulse shaping
 //! - [`set_ook_packet`](Lr2021::set_ook_packet) - Set packet parameters (length, CRC, encoding, addressing)
+//! - [`set_ook_payload_len`](Lr2021::set_ook_payload_len) - Change only the payload length of the packet parameters
 //! - [`set_ook_detector`](Lr2021::set_ook_detector) - Configure preamble detection and start frame delimiter
-//! - [`set_ook_syncword`](Lr2021::set_ook_syncword) - Configure synchronization word (value, length, bit order)
+//! - [`set_ook_syncword`](Lr2021::set_ook_syncword) - Configure synchronization word (up to 32 bits)
 //! - [`set_ook_crc`](Lr2021::set_ook_crc) - Configure CRC polynomial and initialization value
 //! - [`set_ook_thr`](Lr2021::set_ook_thr) - Set detection threshold above noise level
+//! - [`set_ook_whitening`](Lr2021::set_ook_whitening) - Configure whitening polynomial and initialization value
+//!
+//! ### TX Frame Settings
+//! - [`set_ook_tx_sfd`](Lr2021::set_ook_tx_sfd) - Configure the Start of Frame Delimiter sent in TX
+//! - [`set_ook_tx_postamble`](Lr2021::set_ook_tx_postamble) - Configure the postamble sent after the packet
 //!
 //! ### Pre-configured Protocols
 //! - [`set_ook_adsb`](Lr2021::set_ook_adsb) - Configure modem for ADS-B protocol (2Mbps, Manchester encoding, 11B + 3B CRC)
@@ -71,26 +77,66 @@ use embedded_hal_async::spi::SpiBus;
 use crate::{
     cmd::cmd_regmem::write_reg_mem_mask32_cmd,
     constants::ADDR_OOK_DETECT,
-    radio::PacketType, RxBw
+    radio::PacketType, syncword::Syncword, RxBw
 };
 
 pub use super::cmd::cmd_ook::*;
+use super::rxbw::validate_ook_modulation;
 use super::{BusyPin, Lr2021, Lr2021Error, PulseShape};
 
+/// OOK packet parameters, as sent by [`set_ook_packet`](Lr2021::set_ook_packet)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct OokPacketParams {
+    pub pre_len_tx: u16,
+    pub addr_comp: AddrComp,
+    pub pkt_format: PktFormat,
+    pub pld_len: u16,
+    pub crc: Crc,
+    pub encoding: Encoding,
+}
+
 impl<O,SPI, M> Lr2021<O,SPI, M> where
     O: OutputPin, SPI: SpiBus<u8>, M: BusyPin
 {
 
     /// Set Modulation parameters: raw bitrate, bandwidth and pulse shaping
+    /// Return InvalidParam if the bitrate does not fit in the bandwidth (see [`validate_ook_modulation`])
     pub async fn set_ook_modulation(&mut self, bitrate: u32, rx_bw: RxBw, pulse_shape: PulseShape) -> Result<(), Lr2021Error> {
+        validate_ook_modulation(bitrate, rx_bw)?;
         let req = set_ook_modulation_params_cmd(bitrate, pulse_shape, rx_bw);
-        self.cmd_wr(&req).await
+        self.cmd_wr(&req).await?;
+        self.cache.ook_bitrate = Some(bitrate);
+        Ok(())
     }
 
     /// Set OOK packet parameter: preamble length (TX), Address filtering, header implicit/explicit, payload length, CRC and encoding
+    /// The encoding register value is adapted to the firmware version (see [`quirks`](crate::quirks))
     pub async fn set_ook_packet(&mut self, pre_len_tx: u16, addr_comp: AddrComp, pkt_format: PktFormat, pld_len: u16, crc: Crc, encoding: Encoding) -> Result<(), Lr2021Error> {
-        let req = set_ook_packet_params_cmd(pre_len_tx, addr_comp, pkt_format, pld_len, crc, encoding);
-        self.cmd_wr(&req).await
+        self.wr_ook_packet(&OokPacketParams {pre_len_tx, addr_comp, pkt_format, pld_len, crc, encoding}).await
+    }
+
+    /// Send the packet parameters and cache them
+    async fn wr_ook_packet(&mut self, p: &OokPacketParams) -> Result<(), Lr2021Error> {
+        let mut req = set_ook_packet_params_cmd(p.pre_len_tx, p.addr_comp, p.pkt_format, p.pld_len, p.crc, p.encoding);
+        // Encoding value depends on the firmware version
+        req[7] = (req[7] & 0xF0) | (self.cache.quirks.ook_encoding(p.encoding) & 0xF);
+        self.cmd_wr(&req).await?;
+        self.cache.ook_packet = Some(*p);
+        Ok(())
+    }
+
+    /// Change only the payload length of the packet parameters set with `set_ook_packet`
+    /// The chip has no dedicated length command: the last packet parameters are sent again with the new length,
+    /// and nothing is sent if the length is unchanged. Return InvalidParam if `set_ook_packet` was never called
+    pub async fn set_ook_payload_len(&mut self, pld_len: u16) -> Result<(), Lr2021Error> {
+        let Some(params) = self.cache.ook_packet else {
+            return Err(Lr2021Error::InvalidParam);
+        };
+        if params.pld_len == pld_len {
+            return Ok(());
+        }
+        self.wr_ook_packet(&OokPacketParams {pld_len, ..params}).await
     }
 
     /// Set OOK detector: Preamble (pattern/length/repetition), Sync encoding, Start of Frame delimiter
@@ -99,9 +145,12 @@ impl<O,SPI, M> Lr2021<O,SPI, M> where
         self.cmd_wr(&req).await
     }
 
-    /// Configure syncword (value, length and bit order)
-    pub async fn set_ook_syncword(&mut self, syncword: u32, bit_order: BitOrder, nb_bits: u8) -> Result<(), Lr2021Error> {
-        let req = set_ook_sync_word_cmd(syncword, bit_order, nb_bits);
+    /// Configure syncword (up to 32 bits)
+    pub async fn set_ook_syncword(&mut self, syncword: Syncword) -> Result<(), Lr2021Error> {
+        if syncword.nb_bits() > 32 {
+            return Err(Lr2021Error::InvalidParam);
+        }
+        let req = set_ook_sync_word_cmd(syncword.msb_bits() as u32, BitOrder::MsbFirst, syncword.nb_bits());
         self.cmd_wr(&req).await
     }
 
@@ -111,6 +160,33 @@ impl<O,SPI, M> Lr2021<O,SPI, M> where
         self.cmd_wr(&req).await
     }
 
+    /// Configure OOK whitening: polynomial, initial value and index of the bit used as whitening output
+    /// A polynomial of 0 disables the whitening
+    pub async fn set_ook_whitening(&mut self, bit_idx: u8, polynom: u16, init: u16) -> Result<(), Lr2021Error> {
+        let req = set_ook_whitening_params_cmd(bit_idx, polynom, init);
+        self.cmd_wr(&req).await
+    }
+
+    /// Configure the Start of Frame Delimiter sent in TX (up to 32 bits) between the preamble and the syncword
+    /// Use Syncword::NONE to disable
+    pub async fn set_ook_tx_sfd(&mut self, sfd: Syncword) -> Result<(), Lr2021Error> {
+        if sfd.nb_bits() > 32 {
+            return Err(Lr2021Error::InvalidParam);
+        }
+        let pattern = (sfd.msb_bits() as u32).to_be_bytes();
+        let req = [0x02, 0x31, pattern[0], pattern[1], pattern[2], pattern[3], sfd.nb_bits()];
+        self.cmd_wr(&req).await
+    }
+
+    /// Configure the postamble sent in TX after the packet: pattern sent LSB first and its length in bits (up to 8)
+    /// Note: layout derived from the Somfy RTS preset (other fields of the command are kept to 0)
+    pub async fn set_ook_tx_postamble(&mut self, pattern: u8, nb_bits: u8) -> Result<(), Lr2021Error> {
+        if nb_bits > 8 {
+            return Err(Lr2021Error::InvalidParam);
+        }
+        self.cmd_wr(&[0x02, 0x38, pattern, nb_bits, 0x00, 0x00]).await
+    }
+
     /// Configure OOK Detection absolute threshold
     /// Typically add a few dB above the ambiant noise level
     pub async fn set_ook_thr(&mut self, threshold: i8) -> Result<(), Lr2021Error> {
@@ -126,7 +202,7 @@ impl<O,SPI, M> Lr2021<O,SPI, M> where
         self.set_packet_type(PacketType::Ook).await?;
         self.set_ook_modulation(2_000_000, RxBw::Bw3076, PulseShape::None).await?;
         self.set_ook_packet(8, AddrComp::Off, PktFormat::FixedLength, 11, Crc::Crc3Byte, Encoding::ManchesterInv).await?;
-        self.set_ook_syncword(0, BitOrder::LsbFirst, 0).await?;
+        self.set_ook_syncword(Syncword::NONE).await?;
         self.set_ook_detector(0x285, 15, 0, false, SfdKind::FallingEdge, 0).await?;
         self.set_ook_crc(0x1FFF409, 0).await?;
         Ok(())
@@ -143,16 +219,17 @@ impl<O,SPI, M> Lr2021<O,SPI, M> where
         self.set_packet_type(PacketType::Ook).await?;
         self.set_ook_packet(8, AddrComp::Off, PktFormat::FixedLength, 7, Crc::CrcOff, Encoding::Manchester).await?;
         self.set_ook_modulation(1562, RxBw::Bw7p4, PulseShape::None).await?;
-        self.set_ook_syncword(0xA, BitOrder::MsbFirst, 4).await?;
+        self.set_ook_syncword(Syncword::from_msb_bits(0xA, 4)).await?;
         self.set_ook_detector(0x1E, 7, 1, false, SfdKind::FallingEdge, 9).await?;
         // Additional TX packet settings
-        self.cmd_wr(&[0x02,0x38, 0x0E, 0x02, 0x00, 0x00]).await?;
-        self.cmd_wr(&[0x02,0x31, 0x00, 0x00, 0x01, 0xFE, 0x0A]).await?;
+        self.set_ook_tx_postamble(0x0E, 2).await?;
+        self.set_ook_tx_sfd(Syncword::from_msb_bits(0x1FE, 10)).await?;
         self.wr_reg(0xF30814, 0x0009480F).await?;
         Ok(())
     }
 
     /// Return stats about last packet received: length, RSSI, LQI
+    /// RSSI values are raw: use [`get_packet_status`](Lr2021::get_packet_status) or [`rssi_calibrate`](Lr2021::rssi_calibrate) to apply the RSSI calibration
     pub async fn get_ook_packet_status(&mut self) -> Result<OokPacketStatusRsp, Lr2021Error> {
         let req = get_ook_packet_status_req();
         let mut rsp = OokPacketStatusRsp::new();
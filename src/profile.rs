@@ -0,0 +1,237 @@
+//! # Declarative configuration profiles
+//!
+//! A [`Profile`] is a list of typed configuration steps (packet type, RF, modulation, packet parameters,
+//! syncword, TX parameters, DIO interrupts) that replaces the sequence of configuration calls
+//! at the start of an application by a single data literal.
+//!
+//! [`apply_profile`](Lr2021::apply_profile) first validates the profile (single packet type, protocol steps
+//! matching the packet type, legal LoRa modulation) and then executes the steps in the order expected by the chip,
+//! whatever the order in the list: packet type first, BLE channel parameters before the BLE modulation,
+//! TX parameters after the modulation (for [`RampSel::Auto`]), DIO interrupts last.
+//!
+//! Presets are available for common configurations: [`PROFILE_LORA_EU868_SF7`], [`PROFILE_BLE_1M_ADV`] and [`PROFILE_ZIGBEE_CH15`].
+//! They do not include TX power and DIO, which depend on the board.
+//!
+//! ## Quick Start
+//!
+//! ```rust,no_run
+//! use lr2021::profile::{Profile, ProfileStep, PROFILE_BLE_1M_ADV};
+//! use lr2021::status::{Intr, IRQ_MASK_RX_DONE, IRQ_MASK_TX_DONE};
+//!
+//! // Preset
+//! lr2021.apply_profile(&PROFILE_BLE_1M_ADV).await.expect("Profile");
+//!
+//! // Custom profile
+//! const MY_LORA: Profile = Profile::new(&[
+//!     ProfileStep::PacketType(PacketType::Lora),
+//!     ProfileStep::Rf(915_000_000),
+//!     ProfileStep::RxPath(RxPath::LfPath, RxBoost::Off),
+//!     ProfileStep::LoraModulation(LoraModulationParams { sf: Sf::Sf9, bw: LoraBw::Bw125, cr: LoraCr::Cr1Ham45Si, ldro: Ldro::Off }),
+//!     ProfileStep::LoraPacket(LoraPacketParams { pbl_len: 8, payload_len: 32, header_type: HeaderType::Explicit, crc_en: true, invert_iq: false }),
+//!     ProfileStep::TxParams(22, RampSel::Auto),
+//!     ProfileStep::DioIrq(DioNum::Dio7, Intr::new(IRQ_MASK_RX_DONE|IRQ_MASK_TX_DONE)),
+//! ]);
+//! lr2021.apply_profile(&MY_LORA).await.expect("Profile");
+//! ```
+//!
+//! ## Available Methods
+//! - [`apply_profile`](Lr2021::apply_profile) - Validate and apply a profile
+//! - [`Profile::validate`] - Check a profile without applying it
+
+use embedded_hal::digital::OutputPin;
+use embedded_hal_async::spi::SpiBus;
+
+use super::ble::{BleMode, ChannelType};
+use super::lora::{HeaderType, Ldro, LoraBw, LoraCr, LoraModulationParams, LoraPacketParams, LoraParamError, Sf};
+use super::radio::{PacketType, RampSel, RxBoost, RxPath};
+use super::status::Intr;
+use super::system::DioNum;
+use super::zigbee::{FcsMode, ZigbeeMode, ZigbeePacketParams};
+use super::{BusyPin, Lr2021, Lr2021Error, RxBw};
+
+/// Configuration step of a profile
+#[derive(Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ProfileStep {
+    /// Packet type (exactly one per profile)
+    PacketType(PacketType),
+    /// RF channel in Hz
+    Rf(u32),
+    /// RX path and boost
+    RxPath(RxPath, RxBoost),
+    /// TX power (half-dB) and ramp time selection
+    TxParams(i8, RampSel),
+    /// LoRa modulation
+    LoraModulation(LoraModulationParams),
+    /// LoRa packet parameters
+    LoraPacket(LoraPacketParams),
+    /// LoRa syncword
+    LoraSyncword(u8),
+    /// BLE mode
+    BleModulation(BleMode),
+    /// BLE channel parameters: CRC in FIFO, channel type, whitening init, CRC init, access code
+    BleParams(bool, ChannelType, u8, u32, u32),
+    /// Zigbee packet parameters
+    ZigbeePacket(ZigbeePacketParams),
+    /// Interrupts routed to a DIO
+    DioIrq(DioNum, Intr),
+}
+
+impl ProfileStep {
+    /// Execution phase: steps are applied by increasing phase
+    fn phase(&self) -> u8 {
+        match self {
+            ProfileStep::PacketType(_) => 0,
+            ProfileStep::Rf(_) |
+            ProfileStep::RxPath(_, _) => 1,
+            // BLE parameters must be set before the modulation to keep the BLE coded settings
+            ProfileStep::BleParams(..) => 2,
+            ProfileStep::LoraModulation(_) |
+            ProfileStep::BleModulation(_) => 3,
+            ProfileStep::LoraPacket(_) |
+            ProfileStep::ZigbeePacket(_) => 4,
+            ProfileStep::LoraSyncword(_) => 5,
+            // Automatic ramp time depends on the modulation
+            ProfileStep::TxParams(_, _) => 6,
+            ProfileStep::DioIrq(_, _) => 7,
+        }
+    }
+
+    /// Packet type required by the step (None if generic)
+    fn packet_type(&self) -> Option<PacketType> {
+        match self {
+            ProfileStep::LoraModulation(_) |
+            ProfileStep::LoraPacket(_) |
+            ProfileStep::LoraSyncword(_) => Some(PacketType::Lora),
+            ProfileStep::BleModulation(_) |
+            ProfileStep::BleParams(..) => Some(PacketType::Ble),
+            ProfileStep::ZigbeePacket(_) => Some(PacketType::Zigbee),
+            _ => None,
+        }
+    }
+}
+
+/// Invalid profile
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ProfileError {
+    /// No packet type step
+    MissingPacketType,
+    /// More than one packet type step
+    MultiplePacketType,
+    /// Step (index) not matching the packet type
+    PacketTypeMismatch(usize),
+    /// Illegal LoRa modulation
+    Lora(LoraParamError),
+}
+
+impl From<ProfileError> for Lr2021Error {
+    fn from(_: ProfileError) -> Self {
+        Lr2021Error::InvalidParam
+    }
+}
+
+/// Last execution phase
+const PHASE_MAX: u8 = 7;
+
+/// List of configuration steps
+#[derive(Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Profile<'a> {
+    steps: &'a [ProfileStep],
+}
+
+impl<'a> Profile<'a> {
+    /// Create a profile from a list of steps (in any order)
+    pub const fn new(steps: &'a [ProfileStep]) -> Self {
+        Self { steps }
+    }
+
+    /// List of steps
+    pub fn steps(&self) -> &[ProfileStep] {
+        self.steps
+    }
+
+    /// Check the profile: single packet type, steps matching the packet type and legal LoRa modulation
+    /// Return the packet type
+    pub fn validate(&self) -> Result<PacketType, ProfileError> {
+        let mut types = self.steps.iter().filter_map(|s| match s {
+            ProfileStep::PacketType(t) => Some(*t),
+            _ => None,
+        });
+        let packet_type = types.next().ok_or(ProfileError::MissingPacketType)?;
+        if types.next().is_some() {
+            return Err(ProfileError::MultiplePacketType);
+        }
+        for (idx, step) in self.steps.iter().enumerate() {
+            if step.packet_type().is_some_and(|t| t != packet_type) {
+                return Err(ProfileError::PacketTypeMismatch(idx));
+            }
+            if let ProfileStep::LoraModulation(params) = step {
+                params.validate().map_err(ProfileError::Lora)?;
+            }
+        }
+        Ok(packet_type)
+    }
+}
+
+/// LoRa EU868 channel 868.1MHz, SF7/125kHz, CR 4/5 with LoRaWAN public syncword
+pub const PROFILE_LORA_EU868_SF7: Profile = Profile::new(&[
+    ProfileStep::PacketType(PacketType::Lora),
+    ProfileStep::Rf(868_100_000),
+    ProfileStep::RxPath(RxPath::LfPath, RxBoost::Off),
+    ProfileStep::LoraModulation(LoraModulationParams { sf: Sf::Sf7, bw: LoraBw::Bw125, cr: LoraCr::Cr1Ham45Si, ldro: Ldro::Off }),
+    ProfileStep::LoraPacket(LoraPacketParams { pbl_len: 8, payload_len: 255, header_type: HeaderType::Explicit, crc_en: true, invert_iq: false }),
+    ProfileStep::LoraSyncword(0x34),
+]);
+
+/// BLE 1Mb/s on advertising channel 37 (2402MHz)
+pub const PROFILE_BLE_1M_ADV: Profile = Profile::new(&[
+    ProfileStep::PacketType(PacketType::Ble),
+    ProfileStep::Rf(2_402_000_000),
+    ProfileStep::RxPath(RxPath::HfPath, RxBoost::Off),
+    ProfileStep::BleParams(false, ChannelType::Advertiser, 0x53, 0x555555, 0x8e89bed6),
+    ProfileStep::BleModulation(BleMode::Le1mb),
+]);
+
+/// Zigbee 2.4GHz O-QPSK 250kb/s on channel 15 (2425MHz)
+pub const PROFILE_ZIGBEE_CH15: Profile = Profile::new(&[
+    ProfileStep::PacketType(PacketType::Zigbee),
+    ProfileStep::Rf(2_425_000_000),
+    ProfileStep::RxPath(RxPath::HfPath, RxBoost::Off),
+    ProfileStep::ZigbeePacket(ZigbeePacketParams { mode: ZigbeeMode::Oqpsk250, rx_bw: RxBw::BwAuto, pld_len: 127, pbl_len_tx: 32, addr_filt_en: false, fcs_mode: FcsMode::FcsOn }),
+]);
+
+impl<O,SPI, M> Lr2021<O,SPI, M> where
+    O: OutputPin, SPI: SpiBus<u8>, M: BusyPin
+{
+    /// Apply a step of a profile
+    async fn apply_profile_step(&mut self, step: &ProfileStep) -> Result<(), Lr2021Error> {
+        match step {
+            ProfileStep::PacketType(t) => self.set_packet_type(*t).await,
+            ProfileStep::Rf(freq) => self.set_rf(*freq).await,
+            ProfileStep::RxPath(path, boost) => self.set_rx_path(*path, *boost).await,
+            ProfileStep::TxParams(power, ramp) => self.set_tx_params_sel(*power, *ramp).await,
+            ProfileStep::LoraModulation(params) => self.set_lora_modulation(params).await,
+            ProfileStep::LoraPacket(params) => self.set_lora_packet(params).await,
+            ProfileStep::LoraSyncword(sw) => self.set_lora_syncword(*sw).await,
+            ProfileStep::BleModulation(mode) => self.set_ble_modulation(*mode).await,
+            ProfileStep::BleParams(crc_in_fifo, channel_type, whit_init, crc_init, access_code) =>
+                self.set_ble_params(*crc_in_fifo, *channel_type, *whit_init, *crc_init, *access_code).await,
+            ProfileStep::ZigbeePacket(params) => self.set_zigbee_packet(params).await,
+            ProfileStep::DioIrq(dio, intr) => self.set_dio_irq(*dio, *intr).await,
+        }
+    }
+
+    /// Validate a profile and apply all its steps in the order expected by the chip
+    /// Return InvalidParam without sending any command if the profile is invalid (see [`Profile::validate`])
+    pub async fn apply_profile(&mut self, profile: &Profile<'_>) -> Result<(), Lr2021Error> {
+        profile.validate()?;
+        for phase in 0..=PHASE_MAX {
+            for step in profile.steps.iter().filter(|s| s.phase() == phase) {
+                self.apply_profile_step(step).await?;
+            }
+        }
+        Ok(())
+    }
+}
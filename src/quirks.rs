@@ -0,0 +1,297 @@
+//! # Firmware quirks
+//!
+//! Some enum values used in commands are encoded differently depending on the chip firmware
+//! (e.g. a datasheet value not matching the silicon behavior).
+//! This module provides a quirks table keyed by firmware version: once loaded with
+//! [`load_quirks`](Lr2021::load_quirks), the driver translates the affected enum values
+//! to the encoding expected by the chip, so the same application code works across firmware variants.
+//!
+//! The built-in table [`KNOWN_QUIRKS`] only contains patches confirmed on silicon.
+//! Additional patches can be applied manually with [`Quirks::with_patch`] and [`set_quirks`](Lr2021::set_quirks).
+//!
+//! The [`Capabilities`] of the firmware (supported protocols, FIFO size, active patches, ...) are derived
+//! from the same firmware version: the driver rejects packet types not supported by the firmware
+//! and applications can use them to adapt at runtime.
+//!
+//! Optional [`Feature`]s can be queried with [`is_supported`](Lr2021::is_supported): when a command of a feature
+//! is rejected by a firmware predating it, the driver reports [`Lr2021Error::Unsupported`] instead of the generic `CmdErr`.
+//!
+//! Note: no limitation has been confirmed on silicon yet, so the table [`KNOWN_LIMITS`] is empty.
+//! Until an entry is added, every firmware reports all protocols and features as supported:
+//! the detection is inert and [`Lr2021Error::Unsupported`] is never returned.
+//!
+//! ## Quick Start
+//!
+//! ```rust,no_run
+//! use lr2021::quirks::{Quirks, QuirkPatch};
+//! use lr2021::ook::Encoding;
+//!
+//! // Select the patches matching the firmware version
+//! let quirks = lr2021.load_quirks().await.expect("LoadQuirks");
+//! info!("Firmware {} : {}", quirks.version(), quirks);
+//!
+//! // Manual patch: use encoding value 0xD for inverted Manchester
+//! lr2021.set_quirks(quirks.with_patch(QuirkPatch::OokEncoding(Encoding::ManchesterInv, 0xD)));
+//! ```
+//!
+//! ## Available Methods
+//! - [`load_quirks`](Lr2021::load_quirks) - Read the firmware version and select the matching patches
+//! - [`set_quirks`](Lr2021::set_quirks) - Set the patches used by the driver
+//! - [`quirks`](Lr2021::quirks) - Return the patches used by the driver
+//! - [`capabilities`](Lr2021::capabilities) - Return the capabilities of the firmware
+//! - [`is_supported`](Lr2021::is_supported) - Check if an optional feature is supported by the firmware
+
+use embedded_hal::digital::OutputPin;
+use embedded_hal_async::spi::SpiBus;
+
+use super::ook::Encoding;
+use super::radio::PacketType;
+use super::{BusyPin, Lr2021, Lr2021Error};
+
+/// Maximum number of patches active at the same time
+pub const QUIRKS_MAX_PATCH: usize = 4;
+
+/// Firmware version
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct FwVersion {
+    /// Major version
+    pub major: u8,
+    /// Minor version
+    pub minor: u8,
+}
+
+impl FwVersion {
+    /// Create a firmware version
+    pub const fn new(major: u8, minor: u8) -> Self {
+        Self { major, minor }
+    }
+}
+
+/// Encoding patch
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum QuirkPatch {
+    /// Register value used for an OOK encoding
+    OokEncoding(Encoding, u8),
+}
+
+/// Patch applicable to a range of firmware versions
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Quirk {
+    /// First firmware version affected
+    pub from: FwVersion,
+    /// Last firmware version affected
+    pub to: FwVersion,
+    /// Patch to apply
+    pub patch: QuirkPatch,
+}
+
+/// Known quirks confirmed on silicon
+pub const KNOWN_QUIRKS: &[Quirk] = &[];
+
+/// Patches active for a firmware version
+#[derive(Debug, Default, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Quirks {
+    /// Firmware version
+    version: FwVersion,
+    /// Active patches
+    patches: [Option<QuirkPatch>; QUIRKS_MAX_PATCH],
+}
+
+impl Quirks {
+    /// Select the patches of a table matching a firmware version
+    pub fn from_table(version: FwVersion, table: &[Quirk]) -> Self {
+        table.iter()
+            .filter(|q| q.from <= version && version <= q.to)
+            .fold(Self { version, ..Self::default() }, |quirks, q| quirks.with_patch(q.patch))
+    }
+
+    /// Select the known patches matching a firmware version
+    pub fn for_version(version: FwVersion) -> Self {
+        Self::from_table(version, KNOWN_QUIRKS)
+    }
+
+    /// Add a patch, replacing any patch on the same enum value
+    /// The patch is ignored if the maximum number of patches is reached
+    pub fn with_patch(self, patch: QuirkPatch) -> Self {
+        let mut patches = self.patches;
+        let QuirkPatch::OokEncoding(encoding, _) = patch;
+        let slot = patches.iter().position(|p| matches!(p, Some(QuirkPatch::OokEncoding(e, _)) if *e == encoding))
+            .or_else(|| patches.iter().position(|p| p.is_none()));
+        if let Some(idx) = slot {
+            patches[idx] = Some(patch);
+        }
+        Self { patches, ..self }
+    }
+
+    /// Firmware version
+    pub fn version(&self) -> FwVersion {
+        self.version
+    }
+
+    /// Flag if no patch is active
+    pub fn is_empty(&self) -> bool {
+        self.patches.iter().all(|p| p.is_none())
+    }
+
+    /// Register value of an OOK encoding
+    pub fn ook_encoding(&self, encoding: Encoding) -> u8 {
+        self.patches.iter()
+            .find_map(|p| match p {
+                Some(QuirkPatch::OokEncoding(e, value)) if *e == encoding => Some(*value),
+                _ => None,
+            })
+            .unwrap_or(encoding as u8)
+    }
+}
+
+/// Default size of the RX and TX FIFO (in bytes)
+pub const DEFAULT_FIFO_SIZE: u16 = 1024;
+
+/// Mask of all packet types
+const ALL_PROTOCOLS: u16 = (1 << (PacketType::Zigbee as u16 + 1)) - 1;
+
+/// Optional feature which may be missing on some firmware versions
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Feature {
+    /// LoRa side-detectors (multi-SF reception)
+    LoraSidedet = 0,
+    /// LoRa blanking
+    LoraBlanking = 1,
+    /// LoRa intra-packet frequency hopping
+    LoraHopping = 2,
+    /// LoRa preamble phase modulation
+    LoraPreambleModulation = 3,
+    /// Z-Wave multi-channel scan
+    ZwaveScan = 4,
+}
+
+/// Mask of all features
+const ALL_FEATURES: u32 = (1 << (Feature::ZwaveScan as u32 + 1)) - 1;
+
+/// Limitation of a range of firmware versions
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct CapabilityLimit {
+    /// First firmware version affected
+    pub from: FwVersion,
+    /// Last firmware version affected
+    pub to: FwVersion,
+    /// Mask of packet types not supported (bit index is the PacketType value)
+    pub no_protocols: u16,
+    /// Mask of features not supported (bit index is the Feature value)
+    pub no_features: u32,
+    /// Size of the FIFO if different from the default
+    pub fifo_size: Option<u16>,
+}
+
+/// Known limitations confirmed on silicon (currently none: all firmware report full capabilities)
+pub const KNOWN_LIMITS: &[CapabilityLimit] = &[];
+
+/// Capabilities of a firmware version
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Capabilities {
+    /// Mask of supported packet types (bit index is the PacketType value)
+    protocols: u16,
+    /// Mask of supported features (bit index is the Feature value)
+    features: u32,
+    /// Size of the RX and TX FIFO (in bytes)
+    pub fifo_size: u16,
+    /// Encoding patches required by the firmware (known errata)
+    pub quirks: Quirks,
+    /// SIMO configuration must be patched after modulation changes when SIMO is enabled (see `patch_simo`)
+    pub simo_patch: bool,
+}
+
+impl Capabilities {
+    /// Capabilities of a firmware version from a table of limitations
+    /// An unknown version (0.0, i.e. not read from the chip) has no limitation
+    pub fn from_table(quirks: Quirks, table: &[CapabilityLimit]) -> Self {
+        let version = quirks.version();
+        table.iter()
+            .filter(|l| l.from <= version && version <= l.to)
+            .fold(Self { protocols: ALL_PROTOCOLS, features: ALL_FEATURES, fifo_size: DEFAULT_FIFO_SIZE, quirks, simo_patch: true }, |caps, l| Self {
+                protocols: caps.protocols & !l.no_protocols,
+                features: caps.features & !l.no_features,
+                fifo_size: l.fifo_size.unwrap_or(caps.fifo_size),
+                ..caps
+            })
+    }
+
+    /// Capabilities from the known limitations for the version of the quirks
+    pub fn for_quirks(quirks: Quirks) -> Self {
+        Self::from_table(quirks, KNOWN_LIMITS)
+    }
+
+    /// Firmware version
+    pub fn version(&self) -> FwVersion {
+        self.quirks.version()
+    }
+
+    /// Flag if a packet type is supported
+    pub fn supports(&self, packet_type: PacketType) -> bool {
+        self.protocols & (1 << packet_type as u16) != 0
+    }
+
+    /// Iterate over supported packet types
+    pub fn protocols(&self) -> impl Iterator<Item = PacketType> + '_ {
+        [
+            PacketType::Lora, PacketType::FskGeneric, PacketType::FskLegacy, PacketType::Ble,
+            PacketType::Ranging, PacketType::Flrc, PacketType::Bpsk, PacketType::LrFhss,
+            PacketType::Wmbus, PacketType::Wisun, PacketType::Ook, PacketType::Raw,
+            PacketType::Zwave, PacketType::Zigbee,
+        ].into_iter().filter(|p| self.supports(*p))
+    }
+
+    /// Flag if an optional feature is supported
+    pub fn is_supported(&self, feature: Feature) -> bool {
+        self.features & (1 << feature as u32) != 0
+    }
+}
+
+impl<O,SPI, M> Lr2021<O,SPI, M> where
+    O: OutputPin, SPI: SpiBus<u8>, M: BusyPin
+{
+    /// Read the firmware version and select the matching patches from the known quirks
+    /// Must be called after a reset
+    pub async fn load_quirks(&mut self) -> Result<Quirks, Lr2021Error> {
+        let rsp = self.get_version().await?;
+        let quirks = Quirks::for_version(FwVersion::new(rsp.major(), rsp.minor()));
+        self.cache.quirks = quirks;
+        Ok(quirks)
+    }
+
+    /// Set the patches used by the driver
+    pub fn set_quirks(&mut self, quirks: Quirks) {
+        self.cache.quirks = quirks;
+    }
+
+    /// Return the patches used by the driver
+    pub fn quirks(&self) -> &Quirks {
+        &self.cache.quirks
+    }
+
+    /// Return the capabilities of the firmware, derived from the version read with `load_quirks`
+    pub fn capabilities(&self) -> Capabilities {
+        Capabilities::for_quirks(self.cache.quirks)
+    }
+
+    /// Check if an optional feature is supported by the firmware (see [`capabilities`](Lr2021::capabilities))
+    /// Features are reported as supported until the firmware version is read with `load_quirks`
+    pub fn is_supported(&self, feature: Feature) -> bool {
+        self.capabilities().is_supported(feature)
+    }
+
+    /// Report a command rejected because of a missing feature as Unsupported
+    pub(crate) fn feature_check<T>(&self, feature: Feature, res: Result<T, Lr2021Error>) -> Result<T, Lr2021Error> {
+        match res {
+            Err(Lr2021Error::CmdErr) if !self.is_supported(feature) => Err(Lr2021Error::Unsupported),
+            res => res,
+        }
+    }
+}
@@ -12,9 +12,12 @@
 //! - [`set_rf_ranging`](Lr2021::set_rf_ranging) - Set the RF channel (in Hz) for ranging operation
 //! - [`set_rx_path`](Lr2021::set_rx_path) - Configure RX path (LF/HF) with boost settings
 //! - [`set_packet_type`](Lr2021::set_packet_type) - Set packet type (LoRa, FSK, BLE, Z-Wave, etc.)
+//! - [`get_rf`](Lr2021::get_rf) - Read back the RF frequency channel in Hz
+//! - [`get_packet_type`](Lr2021::get_packet_type) - Read back the current packet type
 //!
 //! ### Power Amplifier Configuration
 //! - [`set_tx_params`](Lr2021::set_tx_params) - Set TX power level and ramp time
+//! - [`set_tx_params_sel`](Lr2021::set_tx_params_sel) - Set TX power level with a fixed or automatic ramp time
 //! - [`set_pa_lf`](Lr2021::set_pa_lf) - Configure Low Frequency Power Amplifier (sub-GHz)
 //! - [`set_pa_hf`](Lr2021::set_pa_hf) - Configure High Frequency Power Amplifier (2.4GHz)
 //! - [`set_pa_lf_ocp_threshold`](Lr2021::set_pa_lf_ocp_threshold) - Change PA LF Over-Current Protection Threshold
@@ -22,11 +25,20 @@
 //! ### Operation Mode Control
 //! - [`set_fallback`](Lr2021::set_fallback) - Set fallback mode after TX/RX completion
 //! - [`set_tx`](Lr2021::set_tx) - Enter transmission mode with timeout
+//! - [`tx_payload`](Lr2021::tx_payload) - Write a payload in the FIFO, update the packet length if needed and start transmission
+//! - [`tx_with_power`](Lr2021::tx_with_power) - Send a packet with a different TX power, restoring the previous one once sent
 //! - [`set_tx_test`](Lr2021::set_tx_test) - Start TX in test mode (infinite preamble, continuous wave or PRBS9)
 //! - [`set_rx`](Lr2021::set_rx) - Enter reception mode with timeout and ready wait option
 //! - [`set_rx_continous`](Lr2021::set_rx_continous) - Start RX in continuous mode
 //! - [`set_rx_duty_cycle`](Lr2021::set_rx_duty_cycle) - Start periodic RX
 //! - [`set_auto_rxtx`](Lr2021::set_auto_rxtx) - Configure automatic Transmission/reception after RxDone/TxDone
+//! - [`abort_tx`](Lr2021::abort_tx) - Abort an on-going transmission, clearing the TX FIFO and interrupts
+//! - [`abort_rx`](Lr2021::abort_rx) - Abort an on-going reception, clearing the RX FIFO and interrupts
+//!
+//! ### Scan Engine
+//! - [`pause_scan`](Lr2021::pause_scan) - Pause the scan engine, keeping its configuration
+//! - [`resume_scan`](Lr2021::resume_scan) - Resume a paused scan without reconfiguring it
+//! - [`scan_state`](Lr2021::scan_state) - Current state of the scan engine
 //!
 //! ### Channel Activity Detection (CAD)
 //! - [`set_cad_params`](Lr2021::set_cad_params) - Configure CAD parameters (timeout, threshold, exit mode)
@@ -41,10 +53,14 @@
 //! - [`set_rx_gain`](Lr2021::set_rx_gain) - Set manual RX gain (0=auto, max=13)
 //! - [`get_rssi_inst`](Lr2021::get_rssi_inst) - Get instantaneous RSSI measurement
 //! - [`get_rssi_avg`](Lr2021::get_rssi_avg) - Get average RSSI measurement over specified duration
+//! - [`set_rssi_calibration`](Lr2021::set_rssi_calibration) - Set the board RSSI calibration applied on RSSI measurements (protocol specific packet status stay raw)
+//! - [`rssi_calibrate`](Lr2021::rssi_calibrate) - Apply the RSSI calibration on a raw RSSI value
+//! - [`calibrate_rssi`](Lr2021::calibrate_rssi) - Measure the RSSI offset with a known input level
 //!
 //! ### Reception Management
 //! - [`clear_rx_stats`](Lr2021::clear_rx_stats) - Clear reception statistics
 //! - [`get_rx_pkt_len`](Lr2021::get_rx_pkt_len) - Get length of last received packet
+//! - [`get_packet_status`](Lr2021::get_packet_status) - Get status of last received packet (length, RSSI, SNR/LQI) for any packet type
 //! - [`force_crc_out`](Lr2021::force_crc_out) - Force CRC output to FIFO even when hardware-checked
 //!
 //! ### Timing
@@ -55,20 +71,252 @@
 //!
 
 
-use embassy_time::{Duration, Timer};
+use embassy_time::{Duration, Instant, Timer};
 use embedded_hal::digital::OutputPin;
 use embedded_hal_async::spi::SpiBus;
 
-use crate::{cmd::cmd_regmem::write_reg_mem_mask32_cmd, constants::*};
+use crate::{cmd::cmd_regmem::write_reg_mem_mask32_cmd, constants::*, system::{pllstep_to_hz, ChipMode}};
 
 pub use super::cmd::cmd_common::*;
 use super::{BusyPin, Lr2021, Lr2021Error};
+use super::lora::LoraPacketParams;
+use super::status::{
+    ChipModeStatus, Intr, IRQ_MASK_ADDR_ERROR, IRQ_MASK_CRC_ERROR, IRQ_MASK_HEADER_ERR, IRQ_MASK_HEADER_VALID, IRQ_MASK_LEN_ERROR,
+    IRQ_MASK_PREAMBLE_DETECTED, IRQ_MASK_RX_DONE, IRQ_MASK_RX_FIFO, IRQ_MASK_SYNC_FAIL, IRQ_MASK_TIMEOUT, IRQ_MASK_TX_DONE, IRQ_MASK_TX_FIFO,
+};
+use super::cmd::{
+    cmd_ble::BlePacketStatusRsp, cmd_flrc::FlrcPacketStatusRsp, cmd_fsk::FskPacketStatusRsp, cmd_lora::LoraPacketStatusRsp,
+    cmd_ook::OokPacketStatusRsp, cmd_wisun::WisunPacketStatusRsp, cmd_wmbus::WmbusPacketStatusRsp,
+    cmd_zigbee::ZigbeePacketStatusRsp, cmd_zwave::ZwavePacketStatusRsp,
+};
 
 #[derive(Clone, Copy)]
 pub enum PaLfOcpThr {
     Default = 55, Low900Mhz = 41,
 }
 
+/// Ramp time values in us
+const RAMP_TIMES: [(u32, RampTime); 16] = [
+    (  2, RampTime::Ramp2u),   (  4, RampTime::Ramp4u),   (  8, RampTime::Ramp8u),   ( 16, RampTime::Ramp16u),
+    ( 32, RampTime::Ramp32u),  ( 48, RampTime::Ramp48u),  ( 64, RampTime::Ramp64u),  ( 80, RampTime::Ramp80u),
+    ( 96, RampTime::Ramp96u),  (112, RampTime::Ramp112u), (128, RampTime::Ramp128u), (144, RampTime::Ramp144u),
+    (160, RampTime::Ramp160u), (176, RampTime::Ramp176u), (192, RampTime::Ramp192u), (208, RampTime::Ramp208u),
+];
+
+impl RampTime {
+    /// Recommended ramp time for a given signal bandwidth (in Hz), i.e. the shortest ramp time above 4/Bandwidth
+    pub fn recommended_for(bw_hz: u32) -> Self {
+        let target = 4_000_000u32.div_ceil(bw_hz.max(1));
+        RAMP_TIMES.iter()
+            .find(|(t,_)| *t >= target)
+            .map(|(_,r)| *r)
+            .unwrap_or(RampTime::Ramp208u)
+    }
+
+    /// Ramp time in us
+    pub fn to_us(&self) -> u32 {
+        RAMP_TIMES.iter()
+            .find(|(_,r)| r == self)
+            .map(|(t,_)| *t)
+            .unwrap_or(0)
+    }
+}
+
+/// PA ramp time selection
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum RampSel {
+    /// Ramp time selected by the driver from the modulation configured (see [`auto_ramp_time`](Lr2021::auto_ramp_time))
+    Auto,
+    /// Fixed ramp time
+    Fixed(RampTime),
+}
+
+impl From<RampTime> for RampSel {
+    fn from(ramp_time: RampTime) -> Self {
+        RampSel::Fixed(ramp_time)
+    }
+}
+
+impl PacketType {
+    /// Maximum payload length (in bytes) of a packet sent with `tx_payload`, None when limited only by the FIFO size
+    ///  - LoRa, W-MBus, BLE (PDU): 255
+    ///  - Zigbee (PSDU): 127
+    ///  - FLRC: 511
+    ///  - Wi-SUN (PSDU): 2047
+    pub fn max_payload_len(&self) -> Option<usize> {
+        match self {
+            PacketType::Lora | PacketType::Wmbus | PacketType::Ble => Some(255),
+            PacketType::Zigbee => Some(127),
+            PacketType::Flrc => Some(511),
+            PacketType::Wisun => Some(2047),
+            _ => None,
+        }
+    }
+}
+
+/// State of the scan engine (Z-Wave multi-channel scan)
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ScanState {
+    /// No scan started
+    #[default]
+    Idle,
+    /// Scan running
+    Running,
+    /// Scan paused, configuration saved
+    Paused,
+}
+
+/// Host-side RSSI calibration, compensating board losses (matching, LNA, ...)
+/// All offsets are in 0.5dB unit and are added to the measured power
+#[derive(Debug, Default, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct RssiCalibration {
+    /// Offset for the LF path
+    pub lf_offset: i16,
+    /// Offset for the HF path
+    pub hf_offset: i16,
+    /// Additional offset for each manual gain step (1 to 13), not applied when the gain is automatic
+    pub per_gain_step: [i8; 13],
+}
+
+impl RssiCalibration {
+    /// Calibration with offsets for LF and HF path
+    pub fn new(lf_offset: i16, hf_offset: i16) -> Self {
+        Self { lf_offset, hf_offset, per_gain_step: [0; 13] }
+    }
+
+    /// Set the additional offset for each manual gain step
+    pub fn with_gain_steps(self, per_gain_step: [i8; 13]) -> Self {
+        Self { per_gain_step, ..self }
+    }
+
+    /// Offset (in 0.5dB) for a given path and manual gain (0 for automatic gain)
+    pub fn offset(&self, rx_path: Option<RxPath>, gain: u8) -> i16 {
+        let path_offset = match rx_path {
+            Some(RxPath::LfPath) => self.lf_offset,
+            Some(RxPath::HfPath) => self.hf_offset,
+            None => 0,
+        };
+        let gain_offset = match gain {
+            1..=13 => self.per_gain_step[gain as usize - 1] as i16,
+            _ => 0,
+        };
+        path_offset + gain_offset
+    }
+}
+
+/// Packet status common to all packet types, built from the protocol specific packet status
+/// Frequency error is not part of the packet status reported by the chip
+#[derive(Debug, Default, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct PktStatus {
+    /// Length of the last packet received
+    pub len: u16,
+    /// Average RSSI over the packet (-0.5dBm)
+    pub rssi_avg: u16,
+    /// RSSI latched on syncword detection, or signal RSSI after despreading for LoRa (-0.5dBm)
+    pub rssi_sync: Option<u16>,
+    /// Signal quality in 0.25dB: SNR for LoRa, LQI for the other packet types (None for FLRC)
+    pub quality: Option<i16>,
+}
+
+impl PktStatus {
+    /// Average RSSI in dBm
+    pub fn rssi_avg_dbm(&self) -> i16 {
+        -((self.rssi_avg >> 1) as i16)
+    }
+
+    /// RSSI on syncword in dBm
+    pub fn rssi_sync_dbm(&self) -> Option<i16> {
+        self.rssi_sync.map(|r| -((r >> 1) as i16))
+    }
+
+    /// Signal quality in dB
+    pub fn quality_db(&self) -> Option<i16> {
+        self.quality.map(|q| q / 4)
+    }
+}
+
+impl From<&LoraPacketStatusRsp> for PktStatus {
+    fn from(rsp: &LoraPacketStatusRsp) -> Self {
+        Self { len: rsp.pkt_length() as u16, rssi_avg: rsp.rssi_pkt(), rssi_sync: Some(rsp.rssi_signal_pkt()), quality: Some(rsp.snr_pkt() as i16) }
+    }
+}
+
+impl From<&FskPacketStatusRsp> for PktStatus {
+    fn from(rsp: &FskPacketStatusRsp) -> Self {
+        Self { len: rsp.pkt_len(), rssi_avg: rsp.rssi_avg(), rssi_sync: Some(rsp.rssi_sync()), quality: Some(rsp.lqi() as i16) }
+    }
+}
+
+impl From<&FlrcPacketStatusRsp> for PktStatus {
+    fn from(rsp: &FlrcPacketStatusRsp) -> Self {
+        Self { len: rsp.pkt_len(), rssi_avg: rsp.rssi_avg(), rssi_sync: Some(rsp.rssi_sync()), quality: None }
+    }
+}
+
+impl From<&BlePacketStatusRsp> for PktStatus {
+    fn from(rsp: &BlePacketStatusRsp) -> Self {
+        Self { len: rsp.pkt_len(), rssi_avg: rsp.rssi_avg(), rssi_sync: Some(rsp.rssi_sync()), quality: Some(rsp.lqi() as i16) }
+    }
+}
+
+impl From<&OokPacketStatusRsp> for PktStatus {
+    fn from(rsp: &OokPacketStatusRsp) -> Self {
+        Self { len: rsp.pkt_len(), rssi_avg: rsp.rssi_avg(), rssi_sync: None, quality: Some(rsp.lqi() as i16) }
+    }
+}
+
+impl From<&ZigbeePacketStatusRsp> for PktStatus {
+    fn from(rsp: &ZigbeePacketStatusRsp) -> Self {
+        Self { len: rsp.pkt_len(), rssi_avg: rsp.rssi_avg(), rssi_sync: Some(rsp.rssi_sync()), quality: Some(rsp.lqi() as i16) }
+    }
+}
+
+impl From<&ZwavePacketStatusRsp> for PktStatus {
+    fn from(rsp: &ZwavePacketStatusRsp) -> Self {
+        Self { len: rsp.pkt_len(), rssi_avg: rsp.rssi_avg(), rssi_sync: Some(rsp.rssi_sync()), quality: Some(rsp.lqi() as i16) }
+    }
+}
+
+impl From<&WmbusPacketStatusRsp> for PktStatus {
+    fn from(rsp: &WmbusPacketStatusRsp) -> Self {
+        Self { len: rsp.pkt_len(), rssi_avg: rsp.rssi_avg(), rssi_sync: Some(rsp.rssi_sync()), quality: Some(rsp.lqi() as i16) }
+    }
+}
+
+impl From<&WisunPacketStatusRsp> for PktStatus {
+    fn from(rsp: &WisunPacketStatusRsp) -> Self {
+        Self { len: rsp.pkt_len(), rssi_avg: rsp.rssi_avg(), rssi_sync: Some(rsp.rssi_sync()), quality: Some(rsp.lqi() as i16) }
+    }
+}
+
+impl TryFrom<u8> for PacketType {
+    type Error = Lr2021Error;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0  => Ok(PacketType::Lora),
+            1  => Ok(PacketType::FskGeneric),
+            2  => Ok(PacketType::FskLegacy),
+            3  => Ok(PacketType::Ble),
+            4  => Ok(PacketType::Ranging),
+            5  => Ok(PacketType::Flrc),
+            6  => Ok(PacketType::Bpsk),
+            7  => Ok(PacketType::LrFhss),
+            8  => Ok(PacketType::Wmbus),
+            9  => Ok(PacketType::Wisun),
+            10 => Ok(PacketType::Ook),
+            11 => Ok(PacketType::Raw),
+            12 => Ok(PacketType::Zwave),
+            13 => Ok(PacketType::Zigbee),
+            _  => Err(Lr2021Error::Unknown),
+        }
+    }
+}
+
 impl<O,SPI, M> Lr2021<O,SPI, M> where
     O: OutputPin, SPI: SpiBus<u8>, M: BusyPin
 {
@@ -76,7 +324,15 @@ impl<O,SPI, M> Lr2021<O,SPI, M> where
     /// Set the RF channel (in Hz)
     pub async fn set_rf(&mut self, freq: u32) -> Result<(), Lr2021Error> {
         let req = set_rf_frequency_cmd(freq);
-        self.cmd_wr(&req).await
+        self.cmd_wr(&req).await?;
+        self.cache.rf = Some(freq);
+        Ok(())
+    }
+
+    /// Read back the RF channel (in Hz) from the chip
+    pub async fn get_rf(&mut self) -> Result<u32, Lr2021Error> {
+        let rf_step = self.rd_reg(ADDR_FREQ_RF).await?;
+        Ok(pllstep_to_hz(rf_step))
     }
 
     /// Set the RF channel (in Hz) for ranging operation
@@ -89,27 +345,86 @@ impl<O,SPI, M> Lr2021<O,SPI, M> where
     /// Set the RX Path (LF/HF)
     pub async fn set_rx_path(&mut self, rx_path: RxPath, rx_boost: RxBoost) -> Result<(), Lr2021Error> {
         let req = set_rx_path_adv_cmd(rx_path, rx_boost);
-        self.cmd_wr(&req).await
+        self.cmd_wr(&req).await?;
+        self.cache.rx_path = Some(rx_path);
+        self.cache.rx_boost = Some(rx_boost);
+        Ok(())
     }
 
     /// Set the packet type
+    /// Return InvalidParam if the packet type is not supported by the firmware (see [`capabilities`](Lr2021::capabilities))
     pub async fn set_packet_type(&mut self, packet_type: PacketType) -> Result<(), Lr2021Error> {
+        if !self.capabilities().supports(packet_type) {
+            return Err(Lr2021Error::InvalidParam);
+        }
         let req = set_packet_type_cmd(packet_type);
-        self.cmd_wr(&req).await
+        self.cmd_wr(&req).await?;
+        self.cache.packet_type = Some(packet_type);
+        Ok(())
+    }
+
+    /// Read back the packet type from the chip
+    /// The packet type cached by the driver is updated, allowing to resync after an MCU reboot
+    pub async fn get_packet_type(&mut self) -> Result<PacketType, Lr2021Error> {
+        let req = get_packet_type_req();
+        let mut rsp = PacketTypeRsp::new();
+        self.cmd_rd(&req, rsp.as_mut()).await?;
+        let packet_type = PacketType::try_from(rsp.packet_type())?;
+        self.cache.packet_type = Some(packet_type);
+        Ok(packet_type)
     }
 
     /// Set Tx power and ramp time
     /// TX Power in given in half-dB unit. Range is -19..44 for LF Path and -39..24 for HF path
     /// Ramp-time is important to reduce Out-of-band emission. A safe rule of thumb is to set it to around 4/Bandwidth.
+    /// Return InvalidParam if the power exceeds the limit of the board (see [`with_board`](Lr2021::with_board)) for the PA selected
     pub async fn set_tx_params(&mut self, tx_power: i8, ramp_time: RampTime) -> Result<(), Lr2021Error> {
+        self.set_tx_params_sel(tx_power, RampSel::Fixed(ramp_time)).await
+    }
+
+    /// Set Tx power and ramp time selection (see [`set_tx_params`](Lr2021::set_tx_params))
+    /// RampSel::Auto selects the ramp time from the modulation configured (see [`auto_ramp_time`](Lr2021::auto_ramp_time)):
+    /// the selection is cached so that the ramp time can be updated when the modulation changes
+    pub async fn set_tx_params_sel(&mut self, tx_power: i8, ramp: RampSel) -> Result<(), Lr2021Error> {
+        if let (Some(board), Some(pa)) = (self.cache.board, self.cache.pa) && tx_power > board.pa_max(pa) {
+            return Err(Lr2021Error::InvalidParam);
+        }
+        let ramp_time = match ramp {
+            RampSel::Auto => self.auto_ramp_time(),
+            RampSel::Fixed(ramp_time) => ramp_time,
+        };
         let req = set_tx_params_cmd(tx_power, ramp_time);
-        self.cmd_wr(&req).await
+        self.cmd_wr(&req).await?;
+        self.cache.tx_params = Some((tx_power, ramp));
+        Ok(())
+    }
+
+    /// Ramp time recommended for the current configuration, from the bandwidth of the modulation:
+    ///  - LoRa: bandwidth of the modulation
+    ///  - FLRC, FSK, OOK: bitrate of the modulation, a lower bound of the occupied bandwidth giving a conservative ramp time
+    ///  - BLE, Zigbee: fixed rate of the PHY
+    ///
+    /// Fallback to the longest ramp time when the modulation is not known by the driver.
+    pub fn auto_ramp_time(&self) -> RampTime {
+        let bw_hz = match self.cache.packet_type {
+            Some(PacketType::Lora) => self.cache.lora_modulation.map(|m| m.bw.to_hz()),
+            Some(PacketType::Flrc) => self.cache.flrc_modulation.map(|(bitrate, _, _)| bitrate.to_bps()),
+            Some(PacketType::FskGeneric | PacketType::FskLegacy) => self.cache.fsk_bitrate,
+            Some(PacketType::Ook) => self.cache.ook_bitrate,
+            Some(PacketType::Ble) => Some(1_000_000),
+            Some(PacketType::Zigbee) => Some(2_000_000),
+            _ => None,
+        };
+        bw_hz.map_or(RampTime::Ramp208u, RampTime::recommended_for)
     }
 
     /// Configure LF Power Amplifier
     pub async fn set_pa_lf(&mut self, pa_lf_mode: PaLfMode, pa_lf_duty_cycle: u8, pa_lf_slices: u8) -> Result<(), Lr2021Error> {
         let req = set_pa_config_cmd(PaSel::LfPa, pa_lf_mode, pa_lf_duty_cycle, pa_lf_slices);
-        self.cmd_wr(&req).await
+        self.cmd_wr(&req).await?;
+        self.cache.pa = Some(PaSel::LfPa);
+        self.cache.pa_lf = Some((pa_lf_mode, pa_lf_duty_cycle, pa_lf_slices));
+        Ok(())
     }
 
     /// Change PA LF Over-Current Protection Threshold
@@ -128,7 +443,9 @@ impl<O,SPI, M> Lr2021<O,SPI, M> where
     /// Configure HF Power Amplifier
     pub async fn set_pa_hf(&mut self) -> Result<(), Lr2021Error> {
         let req = set_pa_config_cmd(PaSel::HfPa, PaLfMode::LfPaFsm, 6, 7);
-        self.cmd_wr(&req).await
+        self.cmd_wr(&req).await?;
+        self.cache.pa = Some(PaSel::HfPa);
+        Ok(())
     }
 
     /// Set the Fallback mode after TX/RX
@@ -144,6 +461,126 @@ impl<O,SPI, M> Lr2021<O,SPI, M> where
         self.cmd_wr(&req).await
     }
 
+    /// Send a packet: clear the TX FIFO, write the payload, update the packet length when the protocol requires it and start TX.
+    /// The length update depends on the packet type set with `set_packet_type`:
+    ///  - LoRa: packet parameters are sent again with the new payload length (requires a previous call to `set_lora_packet`)
+    ///  - BLE: PDU length is updated
+    ///  - Zigbee/WiSUN: TX packet length is updated
+    ///  - WMBus: packet parameters are sent again with the new payload length (requires a previous call to `set_wmbus_packet`)
+    ///  - FSK Legacy/FLRC/OOK: payload length is updated when it changed (requires a previous call to `set_fsk_packet`, `set_flrc_packet` or `set_ook_packet`)
+    ///  - Other protocols: no update, the packet length must match the one provided in the packet parameters
+    ///
+    /// Return InvalidSize, before writing the FIFO, if the payload exceeds the maximum length of the packet type
+    /// (see [`PacketType::max_payload_len`]) or the FIFO size.
+    /// Timeout is given in LF clock step (1/32.768kHz ~ 30.5us)
+    pub async fn tx_payload(&mut self, payload: &[u8], tx_timeout: u32) -> Result<(), Lr2021Error> {
+        let len = payload.len();
+        let fifo_size = self.capabilities().fifo_size as usize;
+        let max_len = self.cache.packet_type
+            .and_then(|t| t.max_payload_len())
+            .map_or(fifo_size, |max| max.min(fifo_size));
+        if len > max_len {
+            return Err(Lr2021Error::InvalidSize);
+        }
+        self.clear_tx_fifo().await?;
+        self.wr_tx_fifo_from(payload).await?;
+        match self.cache.packet_type {
+            Some(PacketType::Lora) => {
+                if let Some(params) = self.cache.lora_packet && params.payload_len as usize != len {
+                    let params = LoraPacketParams {payload_len: len as u8, ..params};
+                    self.set_lora_packet(&params).await?;
+                }
+            }
+            Some(PacketType::Ble) => self.set_ble_tx_pdu_len(len as u8).await?,
+            Some(PacketType::Zigbee) => self.set_zigbee_packet_len(len as u8).await?,
+            Some(PacketType::Wisun) => self.set_wisun_packet_len(len as u16).await?,
+            Some(PacketType::Wmbus) => {
+                if let Some(params) = self.cache.wmbus_packet && params.pld_len() as usize != len {
+                    self.set_wmbus_packet(params.with_pld_len(len as u8)).await?;
+                }
+            }
+            Some(PacketType::FskLegacy) if self.cache.fsk_packet.is_some() => self.set_fsk_payload_len(len as u16).await?,
+            Some(PacketType::Flrc) if self.cache.flrc_packet.is_some() => self.set_flrc_payload_len(len as u16).await?,
+            Some(PacketType::Ook) if self.cache.ook_packet.is_some() => self.set_ook_payload_len(len as u16).await?,
+            _ => {}
+        }
+        self.set_tx(tx_timeout).await
+    }
+
+    /// Send a packet with a TX power (in half-dB) different from the one configured with `set_tx_params`,
+    /// wait for the end of the transmission and restore the previous TX power.
+    /// The power must be in the range of the PA selected (-19..44 for LF, -39..24 for HF) when known by the driver.
+    /// Return InvalidParam if no TX parameters were set (nothing to restore) or if the power is out of range,
+    /// and Timeout if the transmission does not end before the timeout (the chip is then set in Standby)
+    pub async fn tx_with_power(&mut self, payload: &[u8], power_half_db: i8, timeout: Duration) -> Result<(), Lr2021Error> {
+        let Some((prev_power, ramp)) = self.cache.tx_params else {
+            return Err(Lr2021Error::InvalidParam);
+        };
+        let range = match self.cache.pa {
+            Some(PaSel::LfPa) => -19..=44,
+            Some(PaSel::HfPa) => -39..=24,
+            None => i8::MIN..=i8::MAX,
+        };
+        if !range.contains(&power_half_db) {
+            return Err(Lr2021Error::InvalidParam);
+        }
+        self.set_tx_params_sel(power_half_db, ramp).await?;
+        let res = self.tx_wait_done(payload, timeout).await;
+        // Always restore the TX power, even when the transmission failed
+        self.set_tx_params_sel(prev_power, ramp).await?;
+        res
+    }
+
+    /// Abort an on-going transmission: set the chip in standby, clear the TX FIFO and the TX interrupts (TxDone, Timeout, TX FIFO).
+    /// Return true if a packet was partially transmitted (chip still in TX without TxDone)
+    pub async fn abort_tx(&mut self) -> Result<bool, Lr2021Error> {
+        let (status, intr) = self.get_status().await?;
+        let partial = status.chip_mode() == ChipModeStatus::Tx && !intr.tx_done();
+        self.set_chip_mode(ChipMode::StandbyRc).await?;
+        self.clear_tx_fifo().await?;
+        self.clear_irqs(Intr::new(IRQ_MASK_TX_DONE | IRQ_MASK_TIMEOUT | IRQ_MASK_TX_FIFO)).await?;
+        Ok(partial)
+    }
+
+    /// Abort an on-going reception: set the chip in standby, clear the RX FIFO (including the packets recorded
+    /// with `rx_fifo_push_pkt`) and the RX interrupts (preamble/header detection, RxDone, errors, Timeout, RX FIFO).
+    /// Return true if a packet was partially received (preamble or header detected without RxDone): its data is discarded
+    pub async fn abort_rx(&mut self) -> Result<bool, Lr2021Error> {
+        let (status, intr) = self.get_status().await?;
+        let partial = status.chip_mode() == ChipModeStatus::Rx && !intr.rx_done()
+            && intr.intr_match(IRQ_MASK_PREAMBLE_DETECTED | IRQ_MASK_HEADER_VALID);
+        self.set_chip_mode(ChipMode::StandbyRc).await?;
+        self.clear_rx_fifo().await?;
+        self.clear_irqs(Intr::new(
+            IRQ_MASK_PREAMBLE_DETECTED | IRQ_MASK_HEADER_VALID | IRQ_MASK_HEADER_ERR | IRQ_MASK_SYNC_FAIL |
+            IRQ_MASK_RX_DONE | IRQ_MASK_TIMEOUT | IRQ_MASK_CRC_ERROR | IRQ_MASK_LEN_ERROR | IRQ_MASK_ADDR_ERROR | IRQ_MASK_RX_FIFO
+        )).await?;
+        Ok(partial)
+    }
+
+    /// Send a packet and poll the status until TX done
+    /// TxDone is cleared before the transmission starts and once seen, so that each call waits for its own packet.
+    /// Return Timeout if the transmission does not end before the timeout (the chip is then set in Standby)
+    pub(crate) async fn tx_wait_done(&mut self, payload: &[u8], timeout: Duration) -> Result<(), Lr2021Error> {
+        let mask = Intr::new(IRQ_MASK_TX_DONE | IRQ_MASK_TIMEOUT);
+        self.clear_irqs(mask).await?;
+        self.tx_payload(payload, 0).await?;
+        let start = Instant::now();
+        loop {
+            let (_, intr) = self.get_status().await?;
+            if intr.tx_done() {
+                self.clear_irqs(mask).await?;
+                return Ok(());
+            }
+            if start.elapsed() >= timeout {
+                self.set_chip_mode(ChipMode::StandbyRc).await?;
+                self.clear_irqs(mask).await?;
+                return Err(Lr2021Error::Timeout);
+            }
+            Timer::after_micros(500).await;
+        }
+    }
+
     /// Start TX in test mode (infinite preamble, continuous wave or PRBS9)
     pub async fn set_tx_test(&mut self, mode: TestMode) -> Result<(), Lr2021Error> {
         let req = set_tx_test_mode_cmd(mode);
@@ -172,11 +609,42 @@ impl<O,SPI, M> Lr2021<O,SPI, M> where
     /// Repeat operation every `cycle_time` (which must be bigger than rx_max_time)
     /// The `use_lora_cad` is only valid if packet type was set to LoRa and performs a CAD instead of a standard reception.
     /// In this case the exit mode of the CAD is performed, i.e. it can start a TX if configured as Listen-Before-Talk
+    /// The sleep part of the cycle (timed by the LF clock) is compensated by the LF clock error (see `set_lf_clock_ppm`)
     pub async fn set_rx_duty_cycle(&mut self, listen_time: u32, cycle_time: u32, use_lora_cad: bool, dram_ret: u8) -> Result<(), Lr2021Error> {
+        let sleep_time = cycle_time.saturating_sub(listen_time);
+        let cycle_time = listen_time.saturating_add(self.lf_ticks_compensated(sleep_time));
         let req = set_rx_duty_cycle_cmd(listen_time, cycle_time, use_lora_cad, dram_ret);
         self.cmd_wr(&req).await
     }
 
+    /// Pause the scan engine: the chip is set in standby (XOSC) and the scan configuration is kept
+    /// Nothing is done if no scan is running
+    pub async fn pause_scan(&mut self) -> Result<(), Lr2021Error> {
+        if self.cache.scan_state == ScanState::Running {
+            self.set_chip_mode(ChipMode::StandbyXosc).await?;
+            self.cache.scan_state = ScanState::Paused;
+        }
+        Ok(())
+    }
+
+    /// Resume a paused scan, restoring the packet type and the scan configuration
+    /// Return InvalidParam if no scan was configured
+    pub async fn resume_scan(&mut self) -> Result<(), Lr2021Error> {
+        let Some(cfg) = self.cache.zwave_scan else {
+            return Err(Lr2021Error::InvalidParam);
+        };
+        if self.cache.packet_type != Some(PacketType::Zwave) {
+            self.set_packet_type(PacketType::Zwave).await?;
+        }
+        self.set_zwave_scan_config(&cfg).await?;
+        self.start_zwave_scan().await
+    }
+
+    /// Current state of the scan engine
+    pub fn scan_state(&self) -> ScanState {
+        self.cache.scan_state
+    }
+
     /// Configure automatic Transmission/reception after RxDone/TxDone
     /// This mode triggers only once and must re-enabled.
     /// When clear is set, the auto_txrx is cleared even on RX timeout.
@@ -234,7 +702,9 @@ impl<O,SPI, M> Lr2021<O,SPI, M> where
     ///   - Max gain is 13
     pub async fn set_rx_gain(&mut self, gain: u8) -> Result<(), Lr2021Error> {
         let req = set_agc_gain_manual_cmd(gain.min(13));
-        self.cmd_wr(&req).await
+        self.cmd_wr(&req).await?;
+        self.cache.rx_gain = gain.min(13);
+        Ok(())
     }
 
     /// Clear RX stats
@@ -250,14 +720,46 @@ impl<O,SPI, M> Lr2021<O,SPI, M> where
         Ok(rsp.pkt_length())
     }
 
+    /// Return the status of the last packet received in a format common to all packet types
+    /// RSSI values are corrected by the RSSI calibration
+    pub async fn get_packet_status(&mut self) -> Result<PktStatus, Lr2021Error> {
+        let packet_type = match self.cache.packet_type {
+            Some(t) => t,
+            None => self.get_packet_type().await?,
+        };
+        let mut status = match packet_type {
+            PacketType::Lora => PktStatus::from(&self.get_lora_packet_status().await?),
+            PacketType::FskGeneric | PacketType::FskLegacy => PktStatus::from(&self.get_fsk_packet_status().await?),
+            PacketType::Flrc => PktStatus::from(&self.get_flrc_packet_status().await?),
+            PacketType::Ble => PktStatus::from(&self.get_ble_packet_status().await?),
+            PacketType::Ook => PktStatus::from(&self.get_ook_packet_status().await?),
+            PacketType::Zigbee => PktStatus::from(&self.get_zigbee_packet_status().await?),
+            PacketType::Zwave => PktStatus::from(&self.get_zwave_packet_status().await?),
+            PacketType::Wmbus => PktStatus::from(&self.get_wmbus_packet_status().await?),
+            PacketType::Wisun => PktStatus::from(&self.get_wisun_packet_status().await?),
+            _ => return Err(Lr2021Error::InvalidParam),
+        };
+        status.rssi_avg = self.rssi_calibrate(status.rssi_avg);
+        status.rssi_sync = status.rssi_sync.map(|r| self.rssi_calibrate(r));
+        Ok(status)
+    }
+
     /// Output CRC to the FIFO even when already checked by hardware
+    /// The CRC layout depends on the protocol: see [`CrcFormat`](crate::crc_out::CrcFormat) to split and verify it
     pub async fn force_crc_out(&mut self) -> Result<(), Lr2021Error> {
         let req = write_reg_mem_mask32_cmd(0xF30844, 0x01000000, 0);
         self.cmd_wr(&req).await
     }
 
-    /// Measure RSSI instantaneous
+    /// Measure RSSI instantaneous (in -0.5dBm)
+    /// The RSSI calibration is applied (see `set_rssi_calibration`)
     pub async fn get_rssi_inst(&mut self) -> Result<u16, Lr2021Error> {
+        let rssi = self.get_rssi_inst_raw().await?;
+        Ok(self.rssi_calibrate(rssi))
+    }
+
+    /// Measure RSSI instantaneous (in -0.5dBm) without calibration
+    async fn get_rssi_inst_raw(&mut self) -> Result<u16, Lr2021Error> {
         let req = get_rssi_inst_req();
         let mut rsp = RssiInstRsp::new();
         self.cmd_rd(&req, rsp.as_mut()).await?;
@@ -274,6 +776,53 @@ impl<O,SPI, M> Lr2021<O,SPI, M> where
         Ok((rssi + (nb_meas>>1)) / nb_meas)
     }
 
+    /// Set the RSSI calibration applied on RSSI measurements
+    /// The calibration is applied by `get_rssi_inst`, `get_rssi_avg` and `get_packet_status`:
+    /// the protocol specific packet status (e.g. `get_lora_packet_status`) are left raw
+    pub fn set_rssi_calibration(&mut self, cal: RssiCalibration) {
+        self.rssi_cal = cal;
+    }
+
+    /// Current RSSI calibration
+    pub fn rssi_calibration(&self) -> &RssiCalibration {
+        &self.rssi_cal
+    }
+
+    /// Apply the RSSI calibration on a raw RSSI value (in -0.5dBm), e.g. from a packet status
+    /// Uses the RX path and manual gain last configured
+    pub fn rssi_calibrate(&self, rssi: u16) -> u16 {
+        let offset = self.rssi_cal.offset(self.cache.rx_path, self.cache.rx_gain);
+        (rssi as i32 - offset as i32).clamp(0, u16::MAX as i32) as u16
+    }
+
+    /// Calibrate the RSSI with a known input level (in dBm) on the current RX path.
+    /// The chip must be in RX with a CW signal at the input. The measurement is averaged over nb_meas.
+    /// With automatic gain, the offset of the RX path is updated, otherwise the offset of the current manual gain step.
+    /// Return the offset (in 0.5dB) measured
+    pub async fn calibrate_rssi(&mut self, input_dbm: i16, nb_meas: u16) -> Result<i16, Lr2021Error> {
+        let Some(rx_path) = self.cache.rx_path else {
+            return Err(Lr2021Error::InvalidParam);
+        };
+        let nb_meas = nb_meas.max(1);
+        let mut rssi = 0u32;
+        for _ in 0..nb_meas {
+            rssi += self.get_rssi_inst_raw().await? as u32;
+        }
+        let rssi = ((rssi + (nb_meas as u32 >> 1)) / nb_meas as u32) as i16;
+        // Measured power is -rssi/2 dBm: offset in 0.5dB is 2*input - (-rssi)
+        let offset = 2 * input_dbm + rssi;
+        match (self.cache.rx_gain, rx_path) {
+            (1..=13, _) => {
+                let path_offset = self.rssi_cal.offset(Some(rx_path), 0);
+                let gain_offset = (offset - path_offset).clamp(i8::MIN as i16, i8::MAX as i16);
+                self.rssi_cal.per_gain_step[self.cache.rx_gain as usize - 1] = gain_offset as i8;
+            }
+            (_, RxPath::LfPath) => self.rssi_cal.lf_offset = offset,
+            (_, RxPath::HfPath) => self.rssi_cal.hf_offset = offset,
+        }
+        Ok(offset)
+    }
+
     /// Set default timeout for TX/RX operation
     /// Used when started on DIO trigger
     pub async fn set_default_timeout(&mut self, tx: u32, rx: u32) -> Result<(), Lr2021Error> {
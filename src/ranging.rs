@@ -0,0 +1,184 @@
+//! # Ranging distance fusion
+//!
+//! This module combines the time of flight (ToF) distance measured by a LoRa ranging exchange with
+//! the distance estimated from the RSSI through a log-distance path-loss model,
+//! giving a fused distance with a confidence interval.
+//!
+//! Both estimates are weighted by their uncertainty: a constant standard deviation for the ToF,
+//! and for the RSSI a shadowing standard deviation in dB, which gives an uncertainty proportional to the distance.
+//! The RSSI therefore mainly helps at short range, where the ToF resolution is poor.
+//!
+//! A large discrepancy between the path loss measured and the one expected at the ToF distance
+//! flags a non-line-of-sight (NLOS) condition: an obstacle attenuating the signal, or a reflected path
+//! lengthening the time of flight. In that case the RSSI is not trusted and the ToF distance is returned alone
+//! with an uncertainty covering both estimates.
+//!
+//! All computations use fixed-point arithmetic (no floating point math library required).
+//!
+//! ## Quick Start
+//!
+//! ```rust,no_run
+//! use lr2021::ranging::{PathLossModel, RangingFusion};
+//!
+//! // 14dBm, 31dB at 1m (868MHz), exponent 2.7, shadowing 6dB, ToF standard deviation 1.5m, NLOS above 12dB
+//! let fusion = RangingFusion::new(PathLossModel::new(14, 31, 27), 6, 150, 12);
+//! // After a ranging exchange (initiator side)
+//! let est = lr2021.get_ranging_fused(&fusion).await.expect("Ranging");
+//! info!("Distance {}m +/- {}m (NLOS={})", est.distance_cm / 100, est.sigma_cm / 50, est.nlos);
+//! ```
+//!
+//! ## Available Methods
+//! - [`get_ranging_fused`](Lr2021::get_ranging_fused) - Read the last ranging result and fuse it with the RSSI estimate
+//! - [`RangingFusion::fuse`] - Fuse a ToF distance and an RSSI
+
+use embedded_hal::digital::OutputPin;
+use embedded_hal_async::spi::SpiBus;
+
+use super::{BusyPin, Lr2021, Lr2021Error};
+
+/// log2(100) with 8 fractional bits
+const LOG2_100_Q8: i32 = 1701;
+
+/// Base 2 logarithm with 8 fractional bits (linear interpolation of the mantissa), x must be non-null
+fn log2_q8(x: u32) -> i32 {
+    let ip = 31 - x.leading_zeros() as i32;
+    let frac = ((x as u64) << (32 - ip) >> 24) as i32 & 0xFF;
+    (ip << 8) | frac
+}
+
+/// Power of 2 with 8 fractional bits on the exponent (linear interpolation), scaled by a factor
+fn exp2_q8(y: i32, scale: u32) -> u32 {
+    let ip = y >> 8;
+    let mant = scale as u64 * (256 + (y & 0xFF) as u64);
+    let val = if ip >= 8 {mant << (ip - 8)} else {mant >> (8 - ip)};
+    val.min(u32::MAX as u64) as u32
+}
+
+/// Log-distance path-loss model: PL(d) = PL(1m) + 10*n*log10(d)
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct PathLossModel {
+    /// TX power of the remote device (in dBm), including antenna gains
+    pub tx_power: i8,
+    /// Path loss at 1m (in dB): about 31dB at 868MHz, 40dB at 2.4GHz
+    pub pl_1m: u8,
+    /// Path-loss exponent multiplied by 10: 20 in free space, 27 to 35 in urban or indoor environments
+    pub exponent_x10: u8,
+}
+
+impl PathLossModel {
+    /// Create a path-loss model
+    pub fn new(tx_power: i8, pl_1m: u8, exponent_x10: u8) -> Self {
+        Self { tx_power, pl_1m, exponent_x10: exponent_x10.max(1) }
+    }
+
+    /// Path loss (in dB) measured for an RSSI (in dBm)
+    pub fn path_loss(&self, rssi_dbm: i16) -> i16 {
+        self.tx_power as i16 - rssi_dbm
+    }
+
+    /// Distance (in cm) corresponding to an RSSI (in dBm)
+    pub fn distance_cm(&self, rssi_dbm: i16) -> u32 {
+        let excess = (self.path_loss(rssi_dbm) - self.pl_1m as i16) as i32;
+        // log2(d) = excess * log2(10) / (10*n)
+        let l = (excess * 850 / self.exponent_x10 as i32).clamp(-8 << 8, 24 << 8);
+        exp2_q8(l, 100)
+    }
+
+    /// Path loss (in dB) expected at a distance (in cm)
+    pub fn expected_path_loss(&self, distance_cm: u32) -> i16 {
+        let l = log2_q8(distance_cm.max(1)) - LOG2_100_Q8;
+        // 10*n*log10(d) = 10*n*log2(d)*log10(2)
+        let pl = self.exponent_x10 as i32 * l * 77 / 65536;
+        (self.pl_1m as i32 + pl) as i16
+    }
+}
+
+/// Fusion parameters
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct RangingFusion {
+    /// Path-loss model
+    pub model: PathLossModel,
+    /// Standard deviation of the shadowing (in dB)
+    pub rssi_sigma_db: u8,
+    /// Standard deviation of the ToF distance (in cm)
+    pub tof_sigma_cm: u32,
+    /// Difference (in dB) between the path loss measured and the one expected at the ToF distance above which NLOS is flagged
+    pub nlos_thr_db: u8,
+}
+
+/// Fused distance estimation
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct RangingEstimate {
+    /// Fused distance (in cm)
+    pub distance_cm: u32,
+    /// Standard deviation of the fused distance (in cm)
+    pub sigma_cm: u32,
+    /// ToF distance (in cm)
+    pub tof_cm: u32,
+    /// RSSI distance (in cm)
+    pub rssi_cm: u32,
+    /// Path loss measured minus path loss expected at the ToF distance (in dB):
+    /// positive when the signal is weaker than expected
+    pub excess_loss_db: i16,
+    /// Non-line-of-sight condition detected
+    pub nlos: bool,
+}
+
+impl RangingEstimate {
+    /// Lower bound of the 95% confidence interval (in cm)
+    pub fn low_cm(&self) -> u32 {
+        self.distance_cm.saturating_sub(2 * self.sigma_cm)
+    }
+
+    /// Upper bound of the 95% confidence interval (in cm)
+    pub fn high_cm(&self) -> u32 {
+        self.distance_cm.saturating_add(2 * self.sigma_cm)
+    }
+}
+
+impl RangingFusion {
+    /// Create fusion parameters
+    pub fn new(model: PathLossModel, rssi_sigma_db: u8, tof_sigma_cm: u32, nlos_thr_db: u8) -> Self {
+        Self { model, rssi_sigma_db, tof_sigma_cm: tof_sigma_cm.max(1), nlos_thr_db }
+    }
+
+    /// Fuse a ToF distance (in cm, negative values are clamped to 0) with an RSSI (in dBm)
+    pub fn fuse(&self, tof_cm: i32, rssi_dbm: i16) -> RangingEstimate {
+        let tof_cm = tof_cm.max(0) as u32;
+        let rssi_cm = self.model.distance_cm(rssi_dbm);
+        let excess_loss_db = self.model.path_loss(rssi_dbm) - self.model.expected_path_loss(tof_cm);
+        let nlos = excess_loss_db.unsigned_abs() > self.nlos_thr_db as u16;
+        let (distance_cm, sigma_cm) = if nlos {
+            (tof_cm, self.tof_sigma_cm.max(tof_cm.abs_diff(rssi_cm) / 2))
+        } else {
+            // Inverse variance weighting, RSSI standard deviation being d*ln(10)*sigma/(10*n)
+            let rssi_sigma = (rssi_cm as u64 * self.rssi_sigma_db as u64 * 2303 / (self.model.exponent_x10 as u64 * 1000)).max(1);
+            let var_t = self.tof_sigma_cm as u64 * self.tof_sigma_cm as u64;
+            let var_r = rssi_sigma * rssi_sigma;
+            let dist = (tof_cm as u64 * var_r + rssi_cm as u64 * var_t) / (var_t + var_r);
+            let var = var_t * var_r / (var_t + var_r);
+            (dist as u32, var.isqrt() as u32)
+        };
+        RangingEstimate { distance_cm, sigma_cm, tof_cm, rssi_cm, excess_loss_db, nlos }
+    }
+}
+
+impl<O,SPI, M> Lr2021<O,SPI, M> where
+    O: OutputPin, SPI: SpiBus<u8>, M: BusyPin
+{
+    /// Read the result of the last ranging exchange and fuse the ToF distance with the RSSI distance estimate.
+    /// The RSSI is corrected with [`get_ranging_rssi_offset`](Lr2021::get_ranging_rssi_offset).
+    /// Return InvalidParam if the LoRa modulation was not configured (bandwidth needed to convert the ToF)
+    pub async fn get_ranging_fused(&mut self, fusion: &RangingFusion) -> Result<RangingEstimate, Lr2021Error> {
+        let modulation = self.cache.lora_modulation.ok_or(Lr2021Error::InvalidParam)?;
+        let rsp = self.get_ranging_result().await?;
+        let offset = self.get_ranging_rssi_offset().await?;
+        // Distance in meter is rng*150/(2^12*Bandwidth in MHz)
+        let tof_cm = rsp.rng() as i64 * 15_000_000_000 / (4096 * modulation.bw.to_hz() as i64);
+        let rssi_dbm = offset + (rsp.rssi() >> 1) as i16;
+        Ok(fusion.fuse(tof_cm as i32, rssi_dbm))
+    }
+}
@@ -0,0 +1,259 @@
+//! # LoRa store-and-forward repeater
+//!
+//! This module provides a simple repeater: packets received on one LoRa configuration are stored
+//! in a buffer, optionally get their hop count updated, and are retransmitted on another configuration
+//! (channel, SF, ...) after a configurable delay, with an optional Listen-Before-Talk using a LoRa CAD
+//! (with a random backoff between attempts).
+//! This is mainly useful for range extension demos and mesh experiments.
+//!
+//! The hop count is a byte of the payload incremented on each retransmission:
+//! packets which already reached the maximum number of hops are not forwarded.
+//!
+//! ## Quick Start
+//!
+//! ```rust,no_run
+//! use lr2021::multilisten::LoraSlot;
+//! use lr2021::repeater::{HopCount, Repeater, RepeaterCfg};
+//!
+//! let sf7 = LoraModulationParams::basic(Sf::Sf7, LoraBw::Bw125);
+//! let sf9 = LoraModulationParams::basic(Sf::Sf9, LoraBw::Bw125);
+//! let rx = LoraSlot::new(868_100_000, sf7, LoraPacketParams::basic(32, &sf7));
+//! let tx = LoraSlot::new(869_525_000, sf9, LoraPacketParams::basic(32, &sf9));
+//! let cfg = RepeaterCfg::new(rx, tx, Duration::from_millis(50))
+//!     .with_hop_count(HopCount { offset: 0, max: 3 })
+//!     .with_lbt(LoraCadParams::new_cad_only(Sf::Sf9, 4, false));
+//! let rng = lr2021.chip_rng().await.expect("ChipRng");
+//! let mut repeater = Repeater::new(cfg).with_rng(rng);
+//! lr2021.repeater_start(&repeater).await.expect("RepeaterStart");
+//! loop {
+//!     let outcome = lr2021.repeater_service(&mut repeater).await.expect("RepeaterService");
+//!     Timer::after_millis(1).await;
+//! }
+//! ```
+//!
+//! ## Available Methods
+//! - [`repeater_start`](Lr2021::repeater_start) - Configure the RX side and start a continuous reception
+//! - [`repeater_service`](Lr2021::repeater_service) - Check for a received packet and forward it
+
+use embassy_time::{Duration, Timer};
+use embedded_hal::digital::OutputPin;
+use embedded_hal_async::spi::SpiBus;
+
+use super::lora::{LoraCadParams, LoraPacketParams};
+use super::multilisten::{ListenSlot, LoraSlot};
+use super::rng::{ChipRng, RandomSource};
+use super::status::{Intr, IRQ_MASK_CRC_ERROR, IRQ_MASK_HEADER_ERR, IRQ_MASK_LEN_ERROR, IRQ_MASK_RX_DONE};
+use super::system::ChipMode;
+use super::{BusyPin, Lr2021, Lr2021Error};
+
+/// Maximum size of a LoRa packet
+const REPEATER_BUF_SIZE: usize = 255;
+
+/// Hop count byte in the payload
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct HopCount {
+    /// Position of the hop count byte in the payload
+    pub offset: usize,
+    /// Maximum number of hops: packets with a hop count reaching this value are not forwarded
+    pub max: u8,
+}
+
+/// Repeater configuration
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct RepeaterCfg {
+    /// Configuration used to receive packets
+    pub rx: LoraSlot,
+    /// Configuration used to forward packets
+    pub tx: LoraSlot,
+    /// Delay between the end of the reception and the retransmission
+    pub delay: Duration,
+    /// Hop count updated before forwarding (None to forward packets unchanged)
+    pub hop: Option<HopCount>,
+    /// CAD parameters for the Listen-Before-Talk (None to transmit without checking the channel)
+    pub lbt: Option<LoraCadParams>,
+    /// Number of CAD attempts before giving up
+    pub lbt_attempts: u8,
+    /// Maximum random delay between two CAD attempts
+    pub lbt_backoff: Duration,
+}
+
+impl RepeaterCfg {
+    /// Repeater without hop count nor Listen-Before-Talk
+    pub fn new(rx: LoraSlot, tx: LoraSlot, delay: Duration) -> Self {
+        Self { rx, tx, delay, hop: None, lbt: None, lbt_attempts: 3, lbt_backoff: Duration::from_millis(20) }
+    }
+
+    /// Update a hop count byte before forwarding
+    pub fn with_hop_count(self, hop: HopCount) -> Self {
+        Self { hop: Some(hop), ..self }
+    }
+
+    /// Check the channel with a CAD before forwarding
+    pub fn with_lbt(self, lbt: LoraCadParams) -> Self {
+        Self { lbt: Some(lbt), ..self }
+    }
+}
+
+/// Repeater statistics
+#[derive(Debug, Default, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct RepeaterStats {
+    /// Packets received without error
+    pub received: u32,
+    /// Packets forwarded
+    pub forwarded: u32,
+    /// Packets received with an error (CRC, header, length)
+    pub rx_errors: u32,
+    /// Packets not forwarded since the maximum number of hops was reached
+    pub max_hops: u32,
+    /// Packets not forwarded since the channel was busy
+    pub channel_busy: u32,
+}
+
+/// Result of a repeater service call
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum RepeatOutcome {
+    /// No packet received
+    Idle,
+    /// Packet received with an error and dropped
+    RxError,
+    /// Packet forwarded (length in bytes)
+    Forwarded(usize),
+    /// Packet not forwarded: maximum number of hops reached
+    MaxHops,
+    /// Packet not forwarded: channel busy on all LBT attempts
+    ChannelBusy,
+}
+
+/// Store-and-forward repeater state, with the random source used for the LBT backoff
+pub struct Repeater<R: RandomSource = ChipRng> {
+    cfg: RepeaterCfg,
+    buf: [u8; REPEATER_BUF_SIZE],
+    stats: RepeaterStats,
+    rng: R,
+}
+
+impl Repeater {
+    /// Create a repeater using a PRNG with a default seed for the LBT backoff
+    /// (see [`with_rng`](Repeater::with_rng) to use a seeded one)
+    pub fn new(cfg: RepeaterCfg) -> Self {
+        Self { cfg, buf: [0; REPEATER_BUF_SIZE], stats: RepeaterStats::default(), rng: ChipRng::default() }
+    }
+}
+
+impl<R: RandomSource> Repeater<R> {
+    /// Replace the random source used for the LBT backoff
+    pub fn with_rng<R2: RandomSource>(self, rng: R2) -> Repeater<R2> {
+        Repeater { cfg: self.cfg, buf: self.buf, stats: self.stats, rng }
+    }
+
+    /// Repeater configuration
+    pub fn cfg(&self) -> &RepeaterCfg {
+        &self.cfg
+    }
+
+    /// Repeater statistics
+    pub fn stats(&self) -> &RepeaterStats {
+        &self.stats
+    }
+
+    /// Reset the statistics
+    pub fn clear_stats(&mut self) {
+        self.stats = RepeaterStats::default();
+    }
+
+    /// Last packet received
+    pub fn last_packet(&self, len: usize) -> &[u8] {
+        &self.buf[..len.min(REPEATER_BUF_SIZE)]
+    }
+
+    /// Update the hop count of a packet: return false if the maximum number of hops was reached
+    fn update_hop(&mut self, len: usize) -> bool {
+        let Some(hop) = self.cfg.hop else {
+            return true;
+        };
+        match self.buf[..len].get_mut(hop.offset) {
+            Some(count) if *count < hop.max => {
+                *count += 1;
+                true
+            }
+            // Packet too short to contain a hop count: forward unchanged
+            None => true,
+            _ => false,
+        }
+    }
+}
+
+impl<O,SPI, M> Lr2021<O,SPI, M> where
+    O: OutputPin, SPI: SpiBus<u8>, M: BusyPin
+{
+    /// Configure the RX side of the repeater and start a continuous reception
+    /// RX path, PA and TX power must be configured before (both configurations must use the same RF path)
+    pub async fn repeater_start<R: RandomSource>(&mut self, repeater: &Repeater<R>) -> Result<(), Lr2021Error> {
+        repeater.cfg.rx.configure(self).await?;
+        self.clear_irqs(Intr::new(IRQ_MASK_RX_DONE | IRQ_MASK_CRC_ERROR | IRQ_MASK_HEADER_ERR | IRQ_MASK_LEN_ERROR)).await?;
+        self.set_rx_continous().await
+    }
+
+    /// Check for a received packet and forward it: the packet is read in the repeater buffer,
+    /// its hop count updated and it is sent on the TX configuration after the delay (and a successful LBT).
+    /// The reception is then restarted on the RX configuration.
+    /// The delay is awaited inside this call, so no packet is received while forwarding
+    pub async fn repeater_service<R: RandomSource>(&mut self, repeater: &mut Repeater<R>) -> Result<RepeatOutcome, Lr2021Error> {
+        let (_, intr) = self.get_status().await?;
+        if !intr.rx_done() {
+            return Ok(RepeatOutcome::Idle);
+        }
+        let mask = Intr::new(IRQ_MASK_RX_DONE | IRQ_MASK_CRC_ERROR | IRQ_MASK_HEADER_ERR | IRQ_MASK_LEN_ERROR);
+        self.clear_irqs(mask).await?;
+        if intr.intr_match(IRQ_MASK_CRC_ERROR | IRQ_MASK_HEADER_ERR | IRQ_MASK_LEN_ERROR) {
+            self.clear_rx_fifo().await?;
+            repeater.stats.rx_errors = repeater.stats.rx_errors.wrapping_add(1);
+            return Ok(RepeatOutcome::RxError);
+        }
+        let len = (self.get_rx_pkt_len().await? as usize).min(REPEATER_BUF_SIZE);
+        self.rd_rx_fifo_to(&mut repeater.buf[..len]).await?;
+        self.clear_rx_fifo().await?;
+        repeater.stats.received = repeater.stats.received.wrapping_add(1);
+        if !repeater.update_hop(len) {
+            repeater.stats.max_hops = repeater.stats.max_hops.wrapping_add(1);
+            return Ok(RepeatOutcome::MaxHops);
+        }
+        // Stop the continuous reception and forward the packet
+        self.set_chip_mode(ChipMode::StandbyRc).await?;
+        let outcome = self.repeater_forward(repeater, len).await;
+        repeater.cfg.rx.configure(self).await?;
+        self.set_rx_continous().await?;
+        outcome
+    }
+
+    /// Send a packet from the repeater buffer on the TX configuration after the delay and LBT
+    async fn repeater_forward<R: RandomSource>(&mut self, repeater: &mut Repeater<R>, len: usize) -> Result<RepeatOutcome, Lr2021Error> {
+        let cfg = repeater.cfg;
+        Timer::after(cfg.delay).await;
+        cfg.tx.configure(self).await?;
+        if let Some(lbt) = cfg.lbt {
+            let mut free = false;
+            for _ in 0..cfg.lbt_attempts.max(1) {
+                if self.lbt_cad(&lbt).await? {
+                    free = true;
+                    break;
+                }
+                Timer::after(repeater.rng.duration_below(cfg.lbt_backoff)).await;
+            }
+            if !free {
+                repeater.stats.channel_busy = repeater.stats.channel_busy.wrapping_add(1);
+                return Ok(RepeatOutcome::ChannelBusy);
+            }
+        }
+        // Timeout at twice the time on air plus a margin
+        let packet = LoraPacketParams { payload_len: len as u8, ..cfg.tx.packet };
+        let toa_us = packet.time_on_air_us(&cfg.tx.modulation) as u64;
+        self.tx_wait_done(&repeater.buf[..len], Duration::from_micros(2 * toa_us + 10_000)).await?;
+        repeater.stats.forwarded = repeater.stats.forwarded.wrapping_add(1);
+        Ok(RepeatOutcome::Forwarded(len))
+    }
+}
@@ -0,0 +1,264 @@
+//! # Sub-GHz remote capture and replay
+//!
+//! This module records the transmission of a simple OOK remote (garage door, plugs, ...) and replays it.
+//! The OOK modem is used as a sampler: the reception runs at a sampling rate much higher than the remote symbol rate,
+//! without encoding nor CRC, so that each bit of the payload is a sample of the signal level.
+//! The samples are converted to a list of pulses (alternating high/low durations), normalized to the bit period
+//! of the remote (estimated from the shortest pulses) and stored as a number of periods per pulse.
+//! The replay regenerates the bit-stream at the remote bitrate and sends it with the OOK modem.
+//!
+//! The reception starts when the detector pattern (sent LSB first) is found: its samples are added
+//! at the beginning of the recording. A pattern of a few high samples is usually enough.
+//!
+//! ## Quick Start
+//!
+//! ```rust,no_run
+//! use lr2021::replay::CaptureCfg;
+//!
+//! // Sample at 10kHz (100us) during 200ms on 433.92MHz, starting on 4 high samples
+//! let cfg = CaptureCfg::new(433_920_000, 10_000, 250, Duration::from_secs(10));
+//! if let Some(rec) = lr2021.capture_and_replay(&cfg, 5, Duration::from_millis(10)).await.expect("CaptureReplay") {
+//!     info!("Captured {} pulses with a period of {}us", rec.nb_pulses(), rec.period_us());
+//! }
+//! ```
+//!
+//! ## Available Methods
+//! - [`remote_capture`](Lr2021::remote_capture) - Record the transmission of a remote
+//! - [`remote_replay`](Lr2021::remote_replay) - Replay a recording with a repeat count
+//! - [`capture_and_replay`](Lr2021::capture_and_replay) - Record a transmission then replay it
+
+use embassy_time::{Duration, Instant, Timer};
+use embedded_hal::digital::OutputPin;
+use embedded_hal_async::spi::SpiBus;
+
+use super::ook::{AddrComp, Crc, Encoding, PktFormat, SfdKind};
+use super::radio::PacketType;
+use super::status::{Intr, IRQ_MASK_RX_DONE};
+use super::syncword::Syncword;
+use super::system::ChipMode;
+use super::{BusyPin, Lr2021, Lr2021Error, PulseShape, RxBw};
+
+/// Maximum number of pulses in a recording
+pub const REPLAY_MAX_PULSES: usize = 256;
+/// Maximum number of sample bytes in a capture (and of bit-stream bytes in a replay)
+const REPLAY_MAX_BYTES: usize = 255;
+
+/// Capture configuration
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct CaptureCfg {
+    /// RF frequency (in Hz)
+    pub rf: u32,
+    /// Sampling rate (in Hz): should be at least 4 times the remote symbol rate
+    pub sample_rate: u32,
+    /// RX bandwidth
+    pub rx_bw: RxBw,
+    /// Number of sample bytes (8 samples per byte, up to 255)
+    pub nb_bytes: u8,
+    /// Detector pattern starting the capture (LSB first)
+    pub pattern: u16,
+    /// Detector pattern length (in samples)
+    pub pattern_len: u8,
+    /// Maximum time waiting for a transmission
+    pub timeout: Duration,
+}
+
+impl CaptureCfg {
+    /// Capture starting on 4 high samples, with an automatic RX bandwidth
+    pub fn new(rf: u32, sample_rate: u32, nb_bytes: u8, timeout: Duration) -> Self {
+        Self { rf, sample_rate, rx_bw: RxBw::BwAuto, nb_bytes, pattern: 0xF, pattern_len: 4, timeout }
+    }
+}
+
+/// Recording of a remote transmission: pulses alternate between high and low levels, starting high
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct RemoteRecording {
+    /// Bit period (in us)
+    period_us: u32,
+    /// Duration of each pulse in number of bit periods
+    pulses: [u8; REPLAY_MAX_PULSES],
+    /// Number of pulses
+    nb_pulses: usize,
+}
+
+impl RemoteRecording {
+    /// Build a recording from samples (MSB first) taken every sample_us.
+    /// Leading and trailing low samples are ignored, pulses of a single sample are considered as glitches
+    /// and merged with their neighbours.
+    /// Return None if no pulse was found
+    pub fn from_samples(samples: &[u8], sample_us: u32) -> Option<Self> {
+        // Runs of identical samples, starting with a high level
+        let mut runs = [0u16; REPLAY_MAX_PULSES];
+        let mut nb_runs = 0;
+        let mut level = true;
+        let bits = samples.iter().flat_map(|b| (0..8).rev().map(move |i| (b >> i) & 1 != 0));
+        for bit in bits.skip_while(|b| !b) {
+            if bit != level {
+                // Glitch: merge with the previous run
+                if runs[nb_runs] <= 1 && nb_runs > 0 {
+                    nb_runs -= 1;
+                    runs[nb_runs] += runs[nb_runs + 1];
+                    runs[nb_runs + 1] = 0;
+                } else {
+                    nb_runs += 1;
+                    if nb_runs == REPLAY_MAX_PULSES {
+                        break;
+                    }
+                }
+                level = bit;
+            }
+            runs[nb_runs] = runs[nb_runs].saturating_add(1);
+        }
+        // Drop trailing low samples
+        if nb_runs < REPLAY_MAX_PULSES && runs[nb_runs] > 0 {
+            nb_runs += 1;
+        }
+        if nb_runs > 0 && nb_runs % 2 == 0 {
+            nb_runs -= 1;
+        }
+        let runs = &runs[..nb_runs];
+        // Bit period: average of the pulses shorter than 1.5 times the shortest one
+        let min = *runs.iter().min()?;
+        let (sum, cnt) = runs.iter()
+            .filter(|&&r| 2 * r as u32 <= 3 * min as u32)
+            .fold((0u32, 0u32), |(s, c), &r| (s + r as u32, c + 1));
+        let period_us = (sum * sample_us / cnt).max(1);
+        let mut pulses = [0; REPLAY_MAX_PULSES];
+        for (p, &r) in pulses.iter_mut().zip(runs) {
+            let n = (r as u32 * sample_us + period_us / 2) / period_us;
+            *p = n.clamp(1, 255) as u8;
+        }
+        Some(Self { period_us, pulses, nb_pulses: nb_runs })
+    }
+
+    /// Bit period (in us)
+    pub fn period_us(&self) -> u32 {
+        self.period_us
+    }
+
+    /// Number of pulses
+    pub fn nb_pulses(&self) -> usize {
+        self.nb_pulses
+    }
+
+    /// Pulse durations in number of bit periods (alternating high/low, starting high)
+    pub fn pulses(&self) -> &[u8] {
+        &self.pulses[..self.nb_pulses]
+    }
+
+    /// Duration of the transmission (in us)
+    pub fn duration_us(&self) -> u32 {
+        self.pulses().iter().map(|&p| p as u32).sum::<u32>() * self.period_us
+    }
+
+    /// Generate the bit-stream (MSB first) at the bit period in a buffer and return the number of bytes used
+    /// Return None if the buffer is too small
+    pub fn to_bitstream(&self, buf: &mut [u8]) -> Option<usize> {
+        let nb_bits: usize = self.pulses().iter().map(|&p| p as usize).sum();
+        let nb_bytes = nb_bits.div_ceil(8);
+        let buf = buf.get_mut(..nb_bytes)?;
+        buf.fill(0);
+        let mut pos = 0;
+        for (i, &p) in self.pulses().iter().enumerate() {
+            if i % 2 == 0 {
+                for b in pos..pos + p as usize {
+                    buf[b / 8] |= 0x80 >> (b % 8);
+                }
+            }
+            pos += p as usize;
+        }
+        Some(nb_bytes)
+    }
+}
+
+impl<O,SPI, M> Lr2021<O,SPI, M> where
+    O: OutputPin, SPI: SpiBus<u8>, M: BusyPin
+{
+    /// Record the transmission of a remote: the OOK modem samples the signal after the detector pattern.
+    /// Return None if nothing was received before the timeout or if no pulse was found
+    pub async fn remote_capture(&mut self, cfg: &CaptureCfg) -> Result<Option<RemoteRecording>, Lr2021Error> {
+        if cfg.nb_bytes == 0 || cfg.pattern_len == 0 || cfg.pattern_len > 16 {
+            return Err(Lr2021Error::InvalidParam);
+        }
+        self.set_packet_type(PacketType::Ook).await?;
+        self.set_rf(cfg.rf).await?;
+        self.set_ook_modulation(cfg.sample_rate, cfg.rx_bw, PulseShape::None).await?;
+        self.set_ook_packet(0, AddrComp::Off, PktFormat::FixedLength, cfg.nb_bytes as u16, Crc::CrcOff, Encoding::None).await?;
+        self.set_ook_syncword(Syncword::NONE).await?;
+        self.set_ook_detector(cfg.pattern, cfg.pattern_len, 0, false, SfdKind::FallingEdge, 0).await?;
+        self.clear_irqs(Intr::new(IRQ_MASK_RX_DONE)).await?;
+        self.set_rx(0, true).await?;
+        let start = Instant::now();
+        loop {
+            let (_, intr) = self.get_status().await?;
+            if intr.rx_done() {
+                break;
+            }
+            if start.elapsed() >= cfg.timeout {
+                self.set_chip_mode(ChipMode::StandbyRc).await?;
+                return Ok(None);
+            }
+            Timer::after_millis(1).await;
+        }
+        self.clear_irqs(Intr::new(IRQ_MASK_RX_DONE)).await?;
+        // Detector pattern followed by the samples
+        let mut samples = [0u8; REPLAY_MAX_BYTES];
+        let nb_bytes = cfg.nb_bytes as usize;
+        self.rd_rx_fifo_to(&mut samples[..nb_bytes]).await?;
+        // Insert the pattern (received LSB first) before the samples
+        let pattern = cfg.pattern.reverse_bits() >> (16 - cfg.pattern_len);
+        let mut acc = pattern as u32;
+        let mut nb_acc = cfg.pattern_len as u32;
+        let mut out = [0u8; REPLAY_MAX_BYTES + 2];
+        let mut nb_out = 0;
+        for &b in &samples[..nb_bytes] {
+            acc = (acc << 8) | b as u32;
+            nb_acc += 8;
+            while nb_acc >= 8 {
+                out[nb_out] = (acc >> (nb_acc - 8)) as u8;
+                nb_out += 1;
+                nb_acc -= 8;
+            }
+        }
+        if nb_acc > 0 {
+            out[nb_out] = (acc << (8 - nb_acc)) as u8;
+            nb_out += 1;
+        }
+        let sample_us = (1_000_000 / cfg.sample_rate.max(1)).max(1);
+        Ok(RemoteRecording::from_samples(&out[..nb_out], sample_us))
+    }
+
+    /// Replay a recording repeat times with a gap between each transmission
+    /// RF frequency, PA and TX power must be configured before (i.e. as for the capture)
+    /// Return InvalidSize if the recording does not fit in a single packet
+    pub async fn remote_replay(&mut self, rec: &RemoteRecording, repeat: u8, gap: Duration) -> Result<(), Lr2021Error> {
+        let mut stream = [0u8; REPLAY_MAX_BYTES];
+        let nb_bytes = rec.to_bitstream(&mut stream).ok_or(Lr2021Error::InvalidSize)?;
+        let bitrate = 1_000_000 / rec.period_us.max(1);
+        self.set_packet_type(PacketType::Ook).await?;
+        self.set_ook_modulation(bitrate, RxBw::BwAuto, PulseShape::None).await?;
+        self.set_ook_packet(0, AddrComp::Off, PktFormat::FixedLength, nb_bytes as u16, Crc::CrcOff, Encoding::None).await?;
+        self.set_ook_syncword(Syncword::NONE).await?;
+        self.set_ook_tx_sfd(Syncword::NONE).await?;
+        self.set_ook_tx_postamble(0, 0).await?;
+        let timeout = Duration::from_micros(2 * 8 * nb_bytes as u64 * rec.period_us as u64 + 10_000);
+        for i in 0..repeat {
+            if i > 0 {
+                Timer::after(gap).await;
+            }
+            self.tx_wait_done(&stream[..nb_bytes], timeout).await?;
+        }
+        Ok(())
+    }
+
+    /// Record the transmission of a remote then replay it repeat times on the same frequency
+    /// Return the recording, or None if nothing was captured (nothing is sent)
+    pub async fn capture_and_replay(&mut self, cfg: &CaptureCfg, repeat: u8, gap: Duration) -> Result<Option<RemoteRecording>, Lr2021Error> {
+        let Some(rec) = self.remote_capture(cfg).await? else {
+            return Ok(None);
+        };
+        self.remote_replay(&rec, repeat, gap).await?;
+        Ok(Some(rec))
+    }
+}
@@ -0,0 +1,138 @@
+//! # Random number sources
+//!
+//! Helpers needing randomness (beacon jitter, LBT backoff, hop tables, ...) use the [`RandomSource`] trait
+//! instead of calling [`get_random_number`](Lr2021::get_random_number) directly:
+//! fetching entropy from the chip costs an SPI command (and requires the chip to be awake) on each draw.
+//!
+//! The default source is [`ChipRng`], a small PRNG (xoshiro128**) seeded once with random numbers from the chip.
+//! It is fast and has good statistical properties but is not cryptographically secure:
+//! applications with an MCU TRNG (or their own generator) can implement [`RandomSource`] for it
+//! and pass it to the helpers instead.
+//!
+//! To seed a cryptographically secure generator from the chip entropy,
+//! [`get_random_seed`](Lr2021::get_random_seed) fills a seed buffer of any size.
+//!
+//! ## Quick Start
+//!
+//! ```rust,no_run
+//! use lr2021::rng::RandomSource;
+//!
+//! // Seed a PRNG once from the chip
+//! let mut rng = lr2021.chip_rng().await.expect("ChipRng");
+//! let backoff = rng.duration_below(Duration::from_millis(50));
+//!
+//! // Seed a CSPRNG from the chip entropy
+//! let mut seed = [0u8; 32];
+//! lr2021.get_random_seed(&mut seed).await.expect("RandomSeed");
+//! ```
+//!
+//! ## Available Methods
+//! - [`chip_rng`](Lr2021::chip_rng) - Create a PRNG seeded with random numbers from the chip
+//! - [`get_random_seed`](Lr2021::get_random_seed) - Fill a buffer with random bytes from the chip
+
+use embassy_time::Duration;
+use embedded_hal::digital::OutputPin;
+use embedded_hal_async::spi::SpiBus;
+
+use super::{BusyPin, Lr2021, Lr2021Error};
+
+/// Source of random numbers used by the driver helpers
+pub trait RandomSource {
+    /// Return a random 32 bits value
+    fn next_u32(&mut self) -> u32;
+
+    /// Return a random value uniformly distributed in [0, n) (0 when n is 0)
+    fn below(&mut self, n: u32) -> u32 {
+        if n == 0 {
+            return 0;
+        }
+        // Reject the values in the incomplete last interval to avoid any bias
+        let zone = u32::MAX - (u32::MAX - n + 1) % n;
+        loop {
+            let v = self.next_u32();
+            if v <= zone {
+                return v % n;
+            }
+        }
+    }
+
+    /// Return a random duration uniformly distributed in [0, max]
+    fn duration_below(&mut self, max: Duration) -> Duration {
+        let ticks = max.as_ticks();
+        let hi = self.next_u32() as u64;
+        let rnd = ((hi << 32) | self.next_u32() as u64) % ticks.saturating_add(1);
+        Duration::from_ticks(rnd)
+    }
+
+    /// Return the period randomly offset by up to +/- jitter (jitter clamped to the period)
+    fn jitter(&mut self, period: Duration, jitter: Duration) -> Duration {
+        let jitter = jitter.min(period);
+        let rnd = self.duration_below(jitter * 2);
+        period - jitter + rnd
+    }
+}
+
+/// Default seed of a [`ChipRng`] created without the chip
+const CHIP_RNG_DEFAULT_SEED: [u32; 4] = [0x9E3779B9, 0x243F6A88, 0xB7E15162, 0x6A09E667];
+
+/// Pseudo-random generator (xoshiro128**) seeded from the chip entropy
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ChipRng {
+    state: [u32; 4],
+}
+
+impl Default for ChipRng {
+    fn default() -> Self {
+        Self::from_seed(CHIP_RNG_DEFAULT_SEED)
+    }
+}
+
+impl ChipRng {
+    /// Create a generator from a seed (an all-zero seed is replaced by a default one)
+    pub fn from_seed(seed: [u32; 4]) -> Self {
+        if seed == [0; 4] {
+            Self { state: CHIP_RNG_DEFAULT_SEED }
+        } else {
+            Self { state: seed }
+        }
+    }
+}
+
+impl RandomSource for ChipRng {
+    fn next_u32(&mut self) -> u32 {
+        let s = &mut self.state;
+        let result = s[1].wrapping_mul(5).rotate_left(7).wrapping_mul(9);
+        let t = s[1] << 9;
+        s[2] ^= s[0];
+        s[3] ^= s[1];
+        s[1] ^= s[2];
+        s[0] ^= s[3];
+        s[2] ^= t;
+        s[3] = s[3].rotate_left(11);
+        result
+    }
+}
+
+impl<O,SPI, M> Lr2021<O,SPI, M> where
+    O: OutputPin, SPI: SpiBus<u8>, M: BusyPin
+{
+    /// Create a PRNG seeded with random numbers from the chip
+    pub async fn chip_rng(&mut self) -> Result<ChipRng, Lr2021Error> {
+        let mut seed = [0; 4];
+        for s in seed.iter_mut() {
+            *s = self.get_random_number().await?;
+        }
+        Ok(ChipRng::from_seed(seed))
+    }
+
+    /// Fill a buffer with random bytes from the chip (e.g. to seed a cryptographically secure generator)
+    /// Each 4 bytes require one command
+    pub async fn get_random_seed(&mut self, seed: &mut [u8]) -> Result<(), Lr2021Error> {
+        for chunk in seed.chunks_mut(4) {
+            let rnd = self.get_random_number().await?.to_le_bytes();
+            chunk.copy_from_slice(&rnd[..chunk.len()]);
+        }
+        Ok(())
+    }
+}
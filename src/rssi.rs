@@ -0,0 +1,190 @@
+//! # RSSI filtering
+//!
+//! This module provides the [`RssiFilter`] trait to smooth RSSI measurements (in -0.5dBm)
+//! with a few common strategies:
+//!  - [`RssiEwma`]: exponential moving average, for a stable noise floor estimation
+//!  - [`RssiMedian`]: median of the last N samples, robust to short bursts of interference
+//!  - [`RssiPeakHold`]: strongest level seen with a slow decay, to detect intermittent activity
+//!
+//! Filters are fed with instantaneous RSSI ([`get_rssi_filtered`](Lr2021::get_rssi_filtered))
+//! or with the average level of successive CCA ([`cca_filtered`](Lr2021::cca_filtered)).
+//! Note that a lower RSSI value means a stronger signal.
+//!
+//! ## Quick Start
+//!
+//! ```rust,no_run
+//! use lr2021::rssi::{RssiFilter, RssiMedian};
+//!
+//! // Chip in RX: median over the last 8 instantaneous measurements
+//! let mut filter = RssiMedian::<8>::new();
+//! let rssi = lr2021.get_rssi_filtered(&mut filter, 8).await.expect("RssiFiltered");
+//! info!("RSSI = -{}dBm", rssi >> 1);
+//! ```
+//!
+//! ## Available Methods
+//! - [`get_rssi_filtered`](Lr2021::get_rssi_filtered) - Feed instantaneous RSSI measurements to a filter
+//! - [`cca_filtered`](Lr2021::cca_filtered) - Run a CCA and feed its average RSSI to a filter
+
+use embedded_hal::digital::OutputPin;
+use embedded_hal_async::spi::SpiBus;
+
+use super::{BusyPin, Lr2021, Lr2021Error};
+
+/// RSSI filter (values in -0.5dBm)
+pub trait RssiFilter {
+    /// Add a sample and return the filtered value
+    fn update(&mut self, rssi: u16) -> u16;
+
+    /// Filtered value (None if no sample was added)
+    fn value(&self) -> Option<u16>;
+
+    /// Clear all samples
+    fn reset(&mut self);
+}
+
+/// Exponential moving average: each new sample has a weight of 1/2^shift
+#[derive(Debug, Default, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct RssiEwma {
+    /// Smoothing factor
+    shift: u8,
+    /// Average with 8 fractional bits
+    avg: Option<u32>,
+}
+
+impl RssiEwma {
+    /// Create an average with a smoothing factor (max 7)
+    pub fn new(shift: u8) -> Self {
+        Self { shift: shift.min(7), avg: None }
+    }
+}
+
+impl RssiFilter for RssiEwma {
+    fn update(&mut self, rssi: u16) -> u16 {
+        let sample = (rssi as u32) << 8;
+        let avg = match self.avg {
+            Some(avg) => avg - (avg >> self.shift) + (sample >> self.shift),
+            None => sample,
+        };
+        self.avg = Some(avg);
+        ((avg + 0x80) >> 8) as u16
+    }
+
+    fn value(&self) -> Option<u16> {
+        self.avg.map(|avg| ((avg + 0x80) >> 8) as u16)
+    }
+
+    fn reset(&mut self) {
+        self.avg = None;
+    }
+}
+
+/// Median of the last N samples
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct RssiMedian<const N: usize> {
+    samples: [u16; N],
+    /// Index of the next sample
+    idx: usize,
+    /// Number of samples (up to N)
+    count: usize,
+}
+
+impl<const N: usize> RssiMedian<N> {
+    /// Create an empty median filter
+    pub fn new() -> Self {
+        Self { samples: [0; N], idx: 0, count: 0 }
+    }
+}
+
+impl<const N: usize> Default for RssiMedian<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> RssiFilter for RssiMedian<N> {
+    fn update(&mut self, rssi: u16) -> u16 {
+        if N == 0 {
+            return rssi;
+        }
+        self.samples[self.idx] = rssi;
+        self.idx = (self.idx + 1) % N;
+        self.count = (self.count + 1).min(N);
+        self.value().unwrap_or(rssi)
+    }
+
+    fn value(&self) -> Option<u16> {
+        if self.count == 0 {
+            return None;
+        }
+        let mut sorted = self.samples;
+        let sorted = &mut sorted[..self.count];
+        sorted.sort_unstable();
+        Some(sorted[self.count / 2])
+    }
+
+    fn reset(&mut self) {
+        self.idx = 0;
+        self.count = 0;
+    }
+}
+
+/// Peak hold: strongest level seen (lowest RSSI value), decaying by a fixed step on each sample
+#[derive(Debug, Default, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct RssiPeakHold {
+    /// Decay per sample in 0.5dB
+    decay: u16,
+    /// Level held
+    peak: Option<u16>,
+}
+
+impl RssiPeakHold {
+    /// Create a peak hold with a decay per sample (in 0.5dB, 0 to hold forever)
+    pub fn new(decay: u16) -> Self {
+        Self { decay, peak: None }
+    }
+}
+
+impl RssiFilter for RssiPeakHold {
+    fn update(&mut self, rssi: u16) -> u16 {
+        let peak = match self.peak {
+            Some(peak) => peak.saturating_add(self.decay).min(rssi),
+            None => rssi,
+        };
+        self.peak = Some(peak);
+        peak
+    }
+
+    fn value(&self) -> Option<u16> {
+        self.peak
+    }
+
+    fn reset(&mut self) {
+        self.peak = None;
+    }
+}
+
+impl<O,SPI, M> Lr2021<O,SPI, M> where
+    O: OutputPin, SPI: SpiBus<u8>, M: BusyPin
+{
+    /// Feed nb_meas instantaneous RSSI measurements (calibrated, see `set_rssi_calibration`) to a filter
+    /// and return the filtered value (in -0.5dBm). The chip must be in RX
+    pub async fn get_rssi_filtered<F: RssiFilter>(&mut self, filter: &mut F, nb_meas: u16) -> Result<u16, Lr2021Error> {
+        for _ in 0..nb_meas {
+            let rssi = self.get_rssi_inst().await?;
+            filter.update(rssi);
+        }
+        filter.value().ok_or(Lr2021Error::InvalidParam)
+    }
+
+    /// Run a Clear Channel Assesment for duration (31.25ns), feed its average RSSI (calibrated) to a filter
+    /// and return the filtered value (in -0.5dBm)
+    /// Note: Chip must be standby or FS before issuing the command
+    pub async fn cca_filtered<F: RssiFilter>(&mut self, filter: &mut F, duration: u32, gain: Option<u8>) -> Result<u16, Lr2021Error> {
+        let rsp = self.set_and_get_cca(duration, gain).await?;
+        let rssi = self.rssi_calibrate(rsp.rssi_avg());
+        Ok(filter.update(rssi))
+    }
+}
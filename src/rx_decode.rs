@@ -0,0 +1,232 @@
+//! # Host-side decoding of captured receptions
+//!
+//! This module provides the decoding of the chip responses and RX FIFO content as pure functions (no SPI access),
+//! so that protocol decoders built on this crate can be unit-tested on a host machine with captured dumps:
+//!  - [`decode_packet_status`] / [`decode_rx_stats`]: raw response of the GetPacketStatus / GetRxStats command
+//!    of a packet type (including the 2 status bytes), decoded as by [`get_packet_status`](crate::Lr2021::get_packet_status)
+//!    (without the RSSI calibration) and [`get_rx_stats`](crate::Lr2021::get_rx_stats)
+//!  - [`decode_irq`]: raw response of the GetStatus command
+//!  - [`RxCapture`]: one reception (interrupts, packet status, FIFO bytes with an optional CRC appended by
+//!    [`force_crc_out`](crate::Lr2021::force_crc_out)) decoded into an [`RxFrame`]
+//!  - [`FifoPackets`]: split a FIFO dump holding several packets using their recorded lengths
+//!
+//! The raw responses are the bytes received on the SPI bus when reading the response, status bytes included,
+//! i.e. the content of the response types returned by the protocol getters (e.g. `get_lora_packet_status().as_mut()`).
+//!
+//! ## Quick Start
+//!
+//! ```rust,no_run
+//! use lr2021::rx_decode::RxCapture;
+//! use lr2021::crc_out::CrcFormat;
+//!
+//! // Unit test of a decoder with a LoRa reception captured on the target (FIFO with CRC)
+//! let irq = [0x02, 0x00, 0x00, 0x04, 0x00, 0x00];
+//! let status = [0x02, 0x00, 0x05, 0x04, 0x14, 0x50, 0x00, 0x50];
+//! let fifo = [0x48, 0x65, 0x6C, 0x6C, 0x6F, 0x12, 0x34];
+//! let frame = RxCapture::new(PacketType::Lora, &status, &fifo)
+//!     .with_irq(&irq)
+//!     .with_crc(CrcFormat::lora())
+//!     .decode()
+//!     .expect("Decode");
+//! assert_eq!(frame.payload, b"Hello");
+//! ```
+//!
+//! ## Available Methods
+//! - [`decode_packet_status`] - Decode a GetPacketStatus response
+//! - [`decode_rx_stats`] - Decode a GetRxStats response
+//! - [`decode_irq`] - Decode a GetStatus response
+//! - [`RxCapture::decode`] - Decode a captured reception
+//! - [`FifoPackets::new`] - Iterate over the packets of a FIFO dump
+
+use super::autostats::RxStats;
+use super::ble::{BlePacketStatusRsp, BleRxStatsRsp};
+use super::crc_out::{CrcCheck, CrcFormat};
+use super::flrc::{FlrcPacketStatusRsp, FlrcRxStatsRsp};
+use super::fsk::{FskPacketStatusRsp, FskRxStatsRsp};
+use super::lora::{LoraPacketStatusRsp, LoraRxStatsRsp};
+use super::ook::{OokPacketStatusRsp, OokRxStatsRsp};
+use super::radio::{PacketType, PktStatus};
+use super::status::{Intr, Status};
+use super::system::StatusRsp;
+use super::wisun::{WisunPacketStatusRsp, WisunRxStatsRsp};
+use super::wmbus::{WmbusPacketStatusRsp, WmbusRxStatsRsp};
+use super::zigbee::{ZigbeePacketStatusRsp, ZigbeeRxStatsRsp};
+use super::zwave::{ZwavePacketStatusRsp, ZwaveRxStatsRsp};
+
+/// Error decoding a captured reception
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum RxDecodeError {
+    /// Packet type without packet status
+    UnsupportedPacketType,
+    /// Response shorter than expected for the packet type
+    ResponseTooShort,
+    /// FIFO content not matching the length reported in the packet status
+    LengthMismatch,
+}
+
+/// Copy a raw response into a response type. Return None if the response is too short
+fn rsp_from<R: Default + AsMut<[u8]>>(bytes: &[u8]) -> Option<R> {
+    let mut rsp = R::default();
+    let buf = rsp.as_mut();
+    buf.copy_from_slice(bytes.get(..buf.len())?);
+    Some(rsp)
+}
+
+/// Decode the raw response of the GetPacketStatus command of a packet type (RSSI not calibrated)
+/// Return None if the packet type has no packet status or if the response is too short
+pub fn decode_packet_status(packet_type: PacketType, rsp: &[u8]) -> Option<PktStatus> {
+    let status = match packet_type {
+        PacketType::Lora => PktStatus::from(&rsp_from::<LoraPacketStatusRsp>(rsp)?),
+        PacketType::FskGeneric | PacketType::FskLegacy => PktStatus::from(&rsp_from::<FskPacketStatusRsp>(rsp)?),
+        PacketType::Flrc => PktStatus::from(&rsp_from::<FlrcPacketStatusRsp>(rsp)?),
+        PacketType::Ble => PktStatus::from(&rsp_from::<BlePacketStatusRsp>(rsp)?),
+        PacketType::Ook => PktStatus::from(&rsp_from::<OokPacketStatusRsp>(rsp)?),
+        PacketType::Zigbee => PktStatus::from(&rsp_from::<ZigbeePacketStatusRsp>(rsp)?),
+        PacketType::Zwave => PktStatus::from(&rsp_from::<ZwavePacketStatusRsp>(rsp)?),
+        PacketType::Wmbus => PktStatus::from(&rsp_from::<WmbusPacketStatusRsp>(rsp)?),
+        PacketType::Wisun => PktStatus::from(&rsp_from::<WisunPacketStatusRsp>(rsp)?),
+        _ => return None,
+    };
+    Some(status)
+}
+
+/// Decode the raw response of the GetRxStats command of a packet type
+/// Return None if the packet type has no RX statistics or if the response is too short
+pub fn decode_rx_stats(packet_type: PacketType, rsp: &[u8]) -> Option<RxStats> {
+    let stats = match packet_type {
+        PacketType::Lora => RxStats::from(&rsp_from::<LoraRxStatsRsp>(rsp)?),
+        PacketType::FskGeneric | PacketType::FskLegacy => RxStats::from(&rsp_from::<FskRxStatsRsp>(rsp)?),
+        PacketType::Flrc => RxStats::from(&rsp_from::<FlrcRxStatsRsp>(rsp)?),
+        PacketType::Ble => RxStats::from(&rsp_from::<BleRxStatsRsp>(rsp)?),
+        PacketType::Ook => RxStats::from(&rsp_from::<OokRxStatsRsp>(rsp)?),
+        PacketType::Zigbee => RxStats::from(&rsp_from::<ZigbeeRxStatsRsp>(rsp)?),
+        PacketType::Zwave => RxStats::from(&rsp_from::<ZwaveRxStatsRsp>(rsp)?),
+        PacketType::Wmbus => RxStats::from(&rsp_from::<WmbusRxStatsRsp>(rsp)?),
+        PacketType::Wisun => RxStats::from(&rsp_from::<WisunRxStatsRsp>(rsp)?),
+        _ => return None,
+    };
+    Some(stats)
+}
+
+/// Decode the raw response of the GetStatus command (status and interrupts)
+/// Return None if the response is too short
+pub fn decode_irq(rsp: &[u8]) -> Option<(Status, Intr)> {
+    let mut rsp = rsp_from::<StatusRsp>(rsp)?;
+    Some((rsp.status(), rsp.intr()))
+}
+
+/// Captured reception: raw chip responses and FIFO content of one packet
+#[derive(Debug, Clone, Copy)]
+pub struct RxCapture<'a> {
+    /// Packet type used for the reception
+    pub packet_type: PacketType,
+    /// Raw response of GetStatus read on the interrupt (None if not captured)
+    pub irq: Option<&'a [u8]>,
+    /// Raw response of the GetPacketStatus command of the packet type
+    pub pkt_status: &'a [u8],
+    /// Bytes read from the RX FIFO for this packet
+    pub fifo: &'a [u8],
+    /// Format of the CRC appended in the FIFO (None when `force_crc_out` is not used)
+    pub crc: Option<CrcFormat>,
+}
+
+/// Decoded reception
+#[derive(Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct RxFrame<'a> {
+    /// Payload (FIFO content without CRC)
+    pub payload: &'a [u8],
+    /// Packet status (RSSI not calibrated)
+    pub status: PktStatus,
+    /// Interrupts captured with the packet
+    pub intr: Option<Intr>,
+    /// Verification of the CRC appended in the FIFO (None when the FIFO holds only the payload)
+    pub crc: Option<CrcCheck>,
+}
+
+impl RxFrame<'_> {
+    /// Flag if the packet is valid: no CRC/length/address error reported and CRC in FIFO (if any) matching
+    pub fn is_valid(&self) -> bool {
+        let intr_ok = self.intr.is_none_or(|i| !i.crc_error() && !i.len_error() && !i.addr_error());
+        let crc_ok = self.crc.is_none_or(|c| c.is_valid());
+        intr_ok && crc_ok
+    }
+}
+
+impl<'a> RxCapture<'a> {
+    /// Capture of a packet status and its FIFO content
+    pub fn new(packet_type: PacketType, pkt_status: &'a [u8], fifo: &'a [u8]) -> Self {
+        Self { packet_type, irq: None, pkt_status, fifo, crc: None }
+    }
+
+    /// Add the raw GetStatus response read on the interrupt
+    pub fn with_irq(self, irq: &'a [u8]) -> Self {
+        Self { irq: Some(irq), ..self }
+    }
+
+    /// Set the format of the CRC appended in the FIFO
+    pub fn with_crc(self, crc: CrcFormat) -> Self {
+        Self { crc: Some(crc), ..self }
+    }
+
+    /// Decode the capture. The FIFO length must match the packet length of the status,
+    /// or the packet length plus the CRC bytes when a CRC format is set: the CRC is only split and verified in this case
+    pub fn decode(&self) -> Result<RxFrame<'a>, RxDecodeError> {
+        if !matches!(self.packet_type,
+            PacketType::Lora | PacketType::FskGeneric | PacketType::FskLegacy | PacketType::Flrc | PacketType::Ble |
+            PacketType::Ook | PacketType::Zigbee | PacketType::Zwave | PacketType::Wmbus | PacketType::Wisun) {
+            return Err(RxDecodeError::UnsupportedPacketType);
+        }
+        let status = decode_packet_status(self.packet_type, self.pkt_status).ok_or(RxDecodeError::ResponseTooShort)?;
+        let intr = match self.irq {
+            Some(irq) => Some(decode_irq(irq).ok_or(RxDecodeError::ResponseTooShort)?.1),
+            None => None,
+        };
+        let len = status.len as usize;
+        // CRC split only when the FIFO holds the payload followed by the CRC bytes
+        let (payload, crc) = match self.crc {
+            _ if self.fifo.len() == len => (self.fifo, None),
+            Some(fmt) if self.fifo.len() == len + fmt.len as usize => {
+                let check = fmt.verify(self.fifo).ok_or(RxDecodeError::LengthMismatch)?;
+                (&self.fifo[..check.payload_len], Some(check))
+            }
+            _ => return Err(RxDecodeError::LengthMismatch),
+        };
+        Ok(RxFrame { payload, status, intr, crc })
+    }
+}
+
+/// Iterator over the packets of a FIFO dump, using the packet lengths recorded on each RxDone
+/// (see [`rx_fifo_push_pkt`](crate::Lr2021::rx_fifo_push_pkt)). Stops when the dump is too short for the next packet
+#[derive(Debug, Clone)]
+pub struct FifoPackets<'a> {
+    dump: &'a [u8],
+    lens: core::slice::Iter<'a, u16>,
+}
+
+impl<'a> FifoPackets<'a> {
+    /// Split a FIFO dump with the length of each packet
+    pub fn new(dump: &'a [u8], lens: &'a [u16]) -> Self {
+        Self { dump, lens: lens.iter() }
+    }
+
+    /// Bytes of the dump not consumed yet
+    pub fn remaining(&self) -> &'a [u8] {
+        self.dump
+    }
+}
+
+impl<'a> Iterator for FifoPackets<'a> {
+    type Item = &'a [u8];
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let len = *self.lens.next()? as usize;
+        if len > self.dump.len() {
+            return None;
+        }
+        let (pkt, rest) = self.dump.split_at(len);
+        self.dump = rest;
+        Some(pkt)
+    }
+}
@@ -0,0 +1,213 @@
+//! # Queue of received packets
+//!
+//! This module provides a fixed-size, allocation free queue (based on `heapless`) to store received packets
+//! with some metadata. It allows to decouple the task servicing the radio (handling interrupts and reading the FIFO)
+//! from the tasks consuming the packets.
+//!
+//! The queue is split into a producer, used by the radio task, and a consumer, used by the application task.
+//! Packets which cannot be stored (queue full) or which are larger than the maximum length are counted in the producer statistics.
+//!
+//! This module is only available with the `heapless` feature.
+//!
+//! ## Quick Start
+//!
+//! ```rust,no_run
+//! use lr2021::rx_queue::{RxQueue, RxMeta};
+//!
+//! // Queue of up to 7 packets of 64 bytes
+//! let mut queue: RxQueue<8, 64> = RxQueue::new();
+//! let (mut producer, mut consumer) = queue.split();
+//!
+//! // Radio task: on RxDone, move the packet from the RX FIFO to the queue
+//! let intr = lr2021.get_and_clear_irq().await.expect("GetIrq");
+//! if intr.rx_done() {
+//!     lr2021.rd_rx_fifo_to_queue(&mut producer, RxMeta::new(intr)).await.expect("RxQueue");
+//! }
+//!
+//! // Application task
+//! while let Some(pkt) = consumer.dequeue() {
+//!     info!("Received {:02x}", pkt.data());
+//! }
+//! ```
+//!
+//! ## Available Methods
+//! - [`rd_rx_fifo_to_queue`](Lr2021::rd_rx_fifo_to_queue) - Read the content of the RX FIFO and push it in a queue
+
+use embassy_time::Instant;
+use embedded_hal::digital::OutputPin;
+use embedded_hal_async::spi::SpiBus;
+use heapless::{spsc, Vec};
+
+use super::status::Intr;
+use super::{BusyPin, Lr2021, Lr2021Error};
+
+/// Metadata associated to a received packet
+#[derive(Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct RxMeta {
+    /// Interrupt flags captured when the packet was received
+    pub intr: Intr,
+    /// Time at which the packet was retrieved from the chip
+    pub timestamp: Instant,
+    /// Packet RSSI (in -0.5dBm unit) if available
+    pub rssi: Option<u16>,
+}
+
+impl RxMeta {
+    /// Create metadata from interrupt flags, timestamped now
+    pub fn new(intr: Intr) -> Self {
+        Self { intr, timestamp: Instant::now(), rssi: None }
+    }
+
+    /// Set the packet RSSI (in -0.5dBm unit)
+    pub fn with_rssi(self, rssi: u16) -> Self {
+        Self { rssi: Some(rssi), ..self }
+    }
+}
+
+/// Packet stored in the RX queue
+#[derive(Clone)]
+pub struct RxPacket<const MAX_LEN: usize> {
+    /// Packet content
+    pub data: Vec<u8, MAX_LEN>,
+    /// Packet metadata
+    pub meta: RxMeta,
+}
+
+impl<const MAX_LEN: usize> RxPacket<MAX_LEN> {
+    /// Packet content
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+}
+
+#[cfg(feature = "defmt")]
+impl<const MAX_LEN: usize> defmt::Format for RxPacket<MAX_LEN> {
+    fn format(&self, fmt: defmt::Formatter) {
+        defmt::write!(fmt, "RxPacket {{ data: {:02x}, meta: {} }}", self.data(), self.meta);
+    }
+}
+
+/// Statistics of the queue producer
+#[derive(Debug, Default, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct RxQueueStats {
+    /// Number of packets pushed in the queue
+    pub pushed: u32,
+    /// Number of packets dropped because the queue was full
+    pub overflow: u32,
+    /// Number of packets dropped because they were larger than the maximum packet length
+    pub oversize: u32,
+}
+
+/// Queue of received packets
+/// Can store up to N-1 packets of MAX_LEN bytes
+pub struct RxQueue<const N: usize, const MAX_LEN: usize> {
+    queue: spsc::Queue<RxPacket<MAX_LEN>, N>,
+}
+
+impl<const N: usize, const MAX_LEN: usize> Default for RxQueue<N, MAX_LEN> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize, const MAX_LEN: usize> RxQueue<N, MAX_LEN> {
+    /// Create an empty queue
+    pub const fn new() -> Self {
+        Self { queue: spsc::Queue::new() }
+    }
+
+    /// Split the queue into a producer (radio task) and a consumer (application task)
+    pub fn split(&mut self) -> (RxProducer<'_, N, MAX_LEN>, RxConsumer<'_, N, MAX_LEN>) {
+        let (producer, consumer) = self.queue.split();
+        (RxProducer { producer, stats: RxQueueStats::default() }, RxConsumer { consumer })
+    }
+}
+
+/// Producer side of the RX queue
+pub struct RxProducer<'a, const N: usize, const MAX_LEN: usize> {
+    producer: spsc::Producer<'a, RxPacket<MAX_LEN>, N>,
+    stats: RxQueueStats,
+}
+
+impl<const N: usize, const MAX_LEN: usize> RxProducer<'_, N, MAX_LEN> {
+    /// Push a packet in the queue
+    /// Return false if the packet was dropped (queue full or packet too large)
+    pub fn push(&mut self, data: &[u8], meta: RxMeta) -> bool {
+        let Ok(data) = Vec::from_slice(data) else {
+            self.stats.oversize = self.stats.oversize.wrapping_add(1);
+            return false;
+        };
+        match self.producer.enqueue(RxPacket { data, meta }) {
+            Ok(_) => {
+                self.stats.pushed = self.stats.pushed.wrapping_add(1);
+                true
+            }
+            Err(_) => {
+                self.stats.overflow = self.stats.overflow.wrapping_add(1);
+                false
+            }
+        }
+    }
+
+    /// Flag if the queue can accept a new packet
+    pub fn ready(&self) -> bool {
+        self.producer.ready()
+    }
+
+    /// Producer statistics
+    pub fn stats(&self) -> RxQueueStats {
+        self.stats
+    }
+
+    /// Reset producer statistics
+    pub fn clear_stats(&mut self) {
+        self.stats = RxQueueStats::default();
+    }
+}
+
+/// Consumer side of the RX queue
+pub struct RxConsumer<'a, const N: usize, const MAX_LEN: usize> {
+    consumer: spsc::Consumer<'a, RxPacket<MAX_LEN>, N>,
+}
+
+impl<const N: usize, const MAX_LEN: usize> RxConsumer<'_, N, MAX_LEN> {
+    /// Retrieve the oldest packet from the queue
+    pub fn dequeue(&mut self) -> Option<RxPacket<MAX_LEN>> {
+        self.consumer.dequeue()
+    }
+
+    /// Access the oldest packet without removing it from the queue
+    pub fn peek(&self) -> Option<&RxPacket<MAX_LEN>> {
+        self.consumer.peek()
+    }
+
+    /// Number of packets available in the queue
+    pub fn len(&self) -> usize {
+        self.consumer.len()
+    }
+
+    /// Flag if no packet is available
+    pub fn is_empty(&self) -> bool {
+        !self.consumer.ready()
+    }
+}
+
+impl<O,SPI, M> Lr2021<O,SPI, M> where
+    O: OutputPin, SPI: SpiBus<u8>, M: BusyPin
+{
+    /// Read the content of the RX FIFO and push it in a queue with its metadata
+    /// The RX FIFO is cleared if the packet is too large for the queue
+    /// Return false if the packet was dropped
+    pub async fn rd_rx_fifo_to_queue<const N: usize, const MAX_LEN: usize>(&mut self, producer: &mut RxProducer<'_, N, MAX_LEN>, meta: RxMeta) -> Result<bool, Lr2021Error> {
+        let len = self.get_rx_fifo_lvl().await? as usize;
+        if len > MAX_LEN || len > crate::BUFFER_SIZE {
+            self.clear_rx_fifo().await?;
+            producer.stats.oversize = producer.stats.oversize.wrapping_add(1);
+            return Ok(false);
+        }
+        self.rd_rx_fifo(len).await?;
+        Ok(producer.push(&self.buffer()[..len], meta))
+    }
+}
@@ -0,0 +1,139 @@
+//! # RX boost management
+//!
+//! The RX boost set with [`set_rx_path`](Lr2021::set_rx_path) improves the noise figure of the receiver
+//! (i.e. the sensitivity) at the cost of a higher current consumption in RX.
+//! This module provides a few [`BoostProfile`] to select a trade-off without knowing the raw boost levels,
+//! and a [`BoostPolicy`] enabling the boost only when the link is weak:
+//! the RSSI of the packets received is averaged and the boost is enabled when the average falls below a threshold,
+//! then disabled again once the link is strong, with an hysteresis to avoid toggling on every packet.
+//!
+//! Boost only helps for packets close to the sensitivity limit: on a strong link it only costs current.
+//!
+//! ## Quick Start
+//!
+//! ```rust,no_run
+//! use lr2021::rxboost::{BoostPolicy, BoostProfile};
+//!
+//! lr2021.set_rx_path(RxPath::LfPath, RxBoost::Off).await.expect("SetRxPath");
+//! // Boost when the average RSSI is below -110dBm, back to nominal gain above -100dBm
+//! let mut policy = BoostPolicy::new(BoostProfile::Nominal, BoostProfile::Sensitivity, 220, 200);
+//! loop {
+//!     // Wait for a packet ...
+//!     let status = lr2021.get_packet_status().await.expect("PacketStatus");
+//!     lr2021.rx_boost_update(&mut policy, status.rssi_avg).await.expect("BoostUpdate");
+//! }
+//! ```
+//!
+//! ## Available Methods
+//! - [`set_rx_boost_profile`](Lr2021::set_rx_boost_profile) - Set a boost profile on the current RX path
+//! - [`rx_boost_update`](Lr2021::rx_boost_update) - Feed the RSSI of a packet to a policy and update the boost when needed
+
+use embedded_hal::digital::OutputPin;
+use embedded_hal_async::spi::SpiBus;
+
+use super::radio::RxBoost;
+use super::rssi::{RssiEwma, RssiFilter};
+use super::{BusyPin, Lr2021, Lr2021Error};
+
+/// RX boost profile: trade-off between current consumption and sensitivity
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum BoostProfile {
+    /// No boost: lowest current consumption
+    Nominal,
+    /// Intermediate boost: most of the sensitivity gain for part of the extra current
+    Balanced,
+    /// Maximum boost: best sensitivity, highest current consumption
+    Sensitivity,
+}
+
+impl BoostProfile {
+    /// RX boost level of the profile
+    pub fn boost(&self) -> RxBoost {
+        match self {
+            BoostProfile::Nominal => RxBoost::Off,
+            BoostProfile::Balanced => RxBoost::B4,
+            BoostProfile::Sensitivity => RxBoost::Max,
+        }
+    }
+}
+
+/// Policy enabling the boost on weak links, based on the average RSSI of the packets received
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct BoostPolicy {
+    /// Profile used on strong links
+    pub strong: BoostProfile,
+    /// Profile used on weak links
+    pub weak: BoostProfile,
+    /// Average RSSI (in -0.5dBm) above which (i.e. weaker) the weak profile is selected
+    pub rssi_weak: u16,
+    /// Average RSSI (in -0.5dBm) below which (i.e. stronger) the strong profile is selected again
+    pub rssi_strong: u16,
+    /// Average RSSI of the last packets
+    rssi: RssiEwma,
+    /// Profile currently selected
+    active: BoostProfile,
+}
+
+impl BoostPolicy {
+    /// Create a policy starting with the strong profile.
+    /// Thresholds are in -0.5dBm: rssi_strong must be lower than rssi_weak to provide an hysteresis
+    pub fn new(strong: BoostProfile, weak: BoostProfile, rssi_weak: u16, rssi_strong: u16) -> Self {
+        Self { strong, weak, rssi_weak, rssi_strong: rssi_strong.min(rssi_weak), rssi: RssiEwma::new(2), active: strong }
+    }
+
+    /// Profile currently selected
+    pub fn active(&self) -> BoostProfile {
+        self.active
+    }
+
+    /// Average RSSI (in -0.5dBm) of the packets received
+    pub fn rssi(&self) -> Option<u16> {
+        self.rssi.value()
+    }
+
+    /// Add the RSSI (in -0.5dBm) of a packet and return the new profile if it changed
+    pub fn update(&mut self, rssi: u16) -> Option<BoostProfile> {
+        let avg = self.rssi.update(rssi);
+        let next = if avg > self.rssi_weak {
+            self.weak
+        } else if avg < self.rssi_strong {
+            self.strong
+        } else {
+            self.active
+        };
+        if next == self.active {
+            return None;
+        }
+        self.active = next;
+        Some(next)
+    }
+
+    /// Forget the RSSI history and go back to the strong profile (e.g. after a channel change)
+    pub fn reset(&mut self) {
+        self.rssi.reset();
+        self.active = self.strong;
+    }
+}
+
+impl<O,SPI, M> Lr2021<O,SPI, M> where
+    O: OutputPin, SPI: SpiBus<u8>, M: BusyPin
+{
+    /// Set a boost profile on the RX path configured with `set_rx_path`
+    /// Return InvalidParam if no RX path was set
+    pub async fn set_rx_boost_profile(&mut self, profile: BoostProfile) -> Result<(), Lr2021Error> {
+        let rx_path = self.cache.rx_path.ok_or(Lr2021Error::InvalidParam)?;
+        self.set_rx_path(rx_path, profile.boost()).await
+    }
+
+    /// Feed the RSSI (in -0.5dBm) of a received packet to a policy and apply the new boost profile when it changes.
+    /// Return the new profile when a change occurred
+    pub async fn rx_boost_update(&mut self, policy: &mut BoostPolicy, rssi: u16) -> Result<Option<BoostProfile>, Lr2021Error> {
+        let Some(profile) = policy.update(rssi) else {
+            return Ok(None);
+        };
+        self.set_rx_boost_profile(profile).await?;
+        Ok(Some(profile))
+    }
+}
@@ -0,0 +1,187 @@
+//! # RX bandwidth and modulation parameter checks
+//!
+//! The chip answers an impossible combination of FSK/OOK modulation parameters with a generic command error,
+//! without indicating which parameter was wrong. The helpers of this module check the combination on the host
+//! and return a typed [`ModParamError`]:
+//!  - the bitrate must fit in the RX bandwidth
+//!  - the FSK occupied bandwidth (Carson's rule: 2*fdev + bitrate) must fit in the RX bandwidth
+//!
+//! All checks are `const fn`, allowing to validate a fixed configuration at compile time.
+//! They are also applied by [`set_fsk_modulation`](crate::Lr2021::set_fsk_modulation) and [`set_ook_modulation`](crate::Lr2021::set_ook_modulation),
+//! which return `InvalidParam` without sending the command.
+//!
+//! ## Quick Start
+//!
+//! ```rust,no_run
+//! use lr2021::RxBw;
+//! use lr2021::rxbw::validate_fsk_modulation;
+//!
+//! // Compile time check: fails to build if the combination is invalid
+//! const _: () = assert!(validate_fsk_modulation(250_000, RxBw::Bw444, 62_500).is_ok());
+//!
+//! // Runtime check with the reason
+//! if let Err(e) = validate_fsk_modulation(bitrate, rx_bw, fdev) {
+//!     warn!("Invalid FSK modulation: {}", e);
+//! }
+//! ```
+
+use super::{Lr2021Error, RxBw};
+
+/// Bit of the raw bitrate selecting the fractional format (bitrate in 1/256 b/s)
+pub const BITRATE_FRAC_BIT: u32 = 1 << 31;
+
+/// Invalid combination of modulation parameters
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ModParamError {
+    /// Null bitrate
+    BitrateNull,
+    /// Bitrate larger than the RX bandwidth
+    BitrateAboveBw,
+    /// Null frequency deviation
+    FdevNull,
+    /// Occupied bandwidth (2*fdev + bitrate) larger than the RX bandwidth
+    FdevAboveBw,
+}
+
+impl From<ModParamError> for Lr2021Error {
+    fn from(_: ModParamError) -> Self {
+        Lr2021Error::InvalidParam
+    }
+}
+
+impl RxBw {
+    /// Bandwidth in Hz (None for automatic selection)
+    pub const fn to_hz(&self) -> Option<u32> {
+        match self {
+            RxBw::BwAuto => None,
+            RxBw::Bw3076 => Some(3_076_000),
+            RxBw::Bw2857 => Some(2_857_000),
+            RxBw::Bw2666 => Some(2_666_000),
+            RxBw::Bw2222 => Some(2_222_000),
+            RxBw::Bw1333 => Some(1_333_000),
+            RxBw::Bw1111 => Some(1_111_000),
+            RxBw::Bw888 => Some(888_000),
+            RxBw::Bw769 => Some(769_000),
+            RxBw::Bw740 => Some(740_000),
+            RxBw::Bw714 => Some(714_000),
+            RxBw::Bw666 => Some(666_000),
+            RxBw::Bw615 => Some(615_000),
+            RxBw::Bw571 => Some(571_000),
+            RxBw::Bw555 => Some(555_000),
+            RxBw::Bw533 => Some(533_000),
+            RxBw::Bw512 => Some(512_000),
+            RxBw::Bw476 => Some(476_000),
+            RxBw::Bw444 => Some(444_000),
+            RxBw::Bw384 => Some(384_000),
+            RxBw::Bw370 => Some(370_000),
+            RxBw::Bw357 => Some(357_000),
+            RxBw::Bw333 => Some(333_000),
+            RxBw::Bw307 => Some(307_000),
+            RxBw::Bw285 => Some(285_000),
+            RxBw::Bw277 => Some(277_000),
+            RxBw::Bw266 => Some(266_000),
+            RxBw::Bw256 => Some(256_000),
+            RxBw::Bw238 => Some(238_000),
+            RxBw::Bw222 => Some(222_000),
+            RxBw::Bw192 => Some(192_000),
+            RxBw::Bw185 => Some(185_000),
+            RxBw::Bw178 => Some(178_000),
+            RxBw::Bw166 => Some(166_000),
+            RxBw::Bw153 => Some(153_000),
+            RxBw::Bw142 => Some(142_000),
+            RxBw::Bw138 => Some(138_000),
+            RxBw::Bw133 => Some(133_000),
+            RxBw::Bw128 => Some(128_000),
+            RxBw::Bw119 => Some(119_000),
+            RxBw::Bw111 => Some(111_000),
+            RxBw::Bw96 => Some(96_000),
+            RxBw::Bw92 => Some(92_000),
+            RxBw::Bw89 => Some(89_000),
+            RxBw::Bw83 => Some(83_000),
+            RxBw::Bw76 => Some(76_000),
+            RxBw::Bw71 => Some(71_000),
+            RxBw::Bw69 => Some(69_000),
+            RxBw::Bw66 => Some(66_000),
+            RxBw::Bw64 => Some(64_000),
+            RxBw::Bw59 => Some(59_000),
+            RxBw::Bw55 => Some(55_000),
+            RxBw::Bw48 => Some(48_000),
+            RxBw::Bw46 => Some(46_000),
+            RxBw::Bw44 => Some(44_000),
+            RxBw::Bw41 => Some(41_000),
+            RxBw::Bw38 => Some(38_000),
+            RxBw::Bw35 => Some(35_000),
+            RxBw::Bw34 => Some(34_000),
+            RxBw::Bw33 => Some(33_000),
+            RxBw::Bw32 => Some(32_000),
+            RxBw::Bw29 => Some(29_000),
+            RxBw::Bw27 => Some(27_000),
+            RxBw::Bw24 => Some(24_000),
+            RxBw::Bw23 => Some(23_000),
+            RxBw::Bw22 => Some(22_000),
+            RxBw::Bw20 => Some(20_000),
+            RxBw::Bw19 => Some(19_000),
+            RxBw::Bw17 => Some(17_000),
+            RxBw::Bw16 => Some(16_000),
+            RxBw::Bw14 => Some(14_000),
+            RxBw::Bw13 => Some(13_000),
+            RxBw::Bw12 => Some(12_000),
+            RxBw::Bw11 => Some(11_000),
+            RxBw::Bw10 => Some(10_000),
+            RxBw::Bw9p6 => Some(9_600),
+            RxBw::Bw8p9 => Some(8_900),
+            RxBw::Bw8p7 => Some(8_700),
+            RxBw::Bw8p3 => Some(8_300),
+            RxBw::Bw8 => Some(8_000),
+            RxBw::Bw7p4 => Some(7_400),
+            RxBw::Bw6p9 => Some(6_900),
+            RxBw::Bw6 => Some(6_000),
+            RxBw::Bw5p8 => Some(5_800),
+            RxBw::Bw5p6 => Some(5_600),
+            RxBw::Bw5p2 => Some(5_200),
+            RxBw::Bw4p8 => Some(4_800),
+            RxBw::Bw4p5 => Some(4_500),
+            RxBw::Bw4p3 => Some(4_300),
+            RxBw::Bw4p2 => Some(4_200),
+            RxBw::Bw3p5 => Some(3_500),
+        }
+    }
+}
+
+/// Bitrate in b/s from the raw bitrate (integer part when using the fractional format)
+pub const fn bitrate_bps(bitrate: u32) -> u32 {
+    if bitrate & BITRATE_FRAC_BIT != 0 {
+        (bitrate & !BITRATE_FRAC_BIT) >> 8
+    } else {
+        bitrate
+    }
+}
+
+/// Check an OOK modulation: the bitrate (raw format) must fit in the RX bandwidth
+/// The automatic bandwidth is always accepted
+pub const fn validate_ook_modulation(bitrate: u32, rx_bw: RxBw) -> Result<(), ModParamError> {
+    let bps = bitrate_bps(bitrate);
+    if bps == 0 && bitrate & !BITRATE_FRAC_BIT == 0 {
+        return Err(ModParamError::BitrateNull);
+    }
+    match rx_bw.to_hz() {
+        Some(bw) if bps > bw => Err(ModParamError::BitrateAboveBw),
+        _ => Ok(()),
+    }
+}
+
+/// Check an FSK modulation: the bitrate (raw format) and the occupied bandwidth (2*fdev + bitrate) must fit in the RX bandwidth
+/// The automatic bandwidth is always accepted
+pub const fn validate_fsk_modulation(bitrate: u32, rx_bw: RxBw, fdev: u32) -> Result<(), ModParamError> {
+    if let Err(e) = validate_ook_modulation(bitrate, rx_bw) {
+        return Err(e);
+    }
+    if fdev == 0 {
+        return Err(ModParamError::FdevNull);
+    }
+    match rx_bw.to_hz() {
+        Some(bw) if 2 * fdev as u64 + bitrate_bps(bitrate) as u64 > bw as u64 => Err(ModParamError::FdevAboveBw),
+        _ => Ok(()),
+    }
+}
@@ -0,0 +1,185 @@
+//! # Driver shared between tasks
+//!
+//! This module provides [`SharedLr2021`], a wrapper around the driver protected by an `embassy-sync` mutex,
+//! so that several tasks can use the same chip with two priority levels:
+//!  - high priority (e.g. sending an ACK): [`lock_high`](SharedLr2021::lock_high) gives a full access to the driver
+//!    and is granted before any pending low priority request
+//!  - low priority (telemetry, statistics polling, ...): a restricted API where each method holds the driver
+//!    for a single short operation only, so that a high priority task waits at most for the command in progress
+//!
+//! Low priority requests wait while a high priority task holds or requests the driver:
+//! this wait is bounded by a maximum duration, after which the request fails with `Timeout`.
+//!
+//! This module is only available with the `embassy-sync` feature.
+//!
+//! ## Quick Start
+//!
+//! ```rust,no_run
+//! use embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex;
+//! use lr2021::shared::SharedLr2021;
+//! use lr2021::system::{AdcRes, TempSrc};
+//!
+//! static RADIO: StaticCell<SharedLr2021<CriticalSectionRawMutex, Output, Spi, BusyAsync<ExtiInput>>> = StaticCell::new();
+//! let radio = RADIO.init(SharedLr2021::new(lr2021, Duration::from_millis(500)));
+//!
+//! // MAC task
+//! let mut lr2021 = radio.lock_high().await;
+//! lr2021.set_tx(0).await.expect("SetTx");
+//! drop(lr2021);
+//!
+//! // Housekeeping task
+//! let temp = radio.get_temperature(TempSrc::Vbe, AdcRes::Res10bit).await.expect("Temperature");
+//! ```
+//!
+//! ## Available Methods
+//! - [`lock_high`](SharedLr2021::lock_high) - Get a full access to the driver, before any low priority request
+//! - [`get_status`](SharedLr2021::get_status) - Read status and interrupts (low priority)
+//! - [`get_temperature`](SharedLr2021::get_temperature) - Measure the temperature (low priority)
+//! - [`get_vbat`](SharedLr2021::get_vbat) - Measure the battery voltage (low priority)
+//! - [`get_rssi_inst`](SharedLr2021::get_rssi_inst) - Read the instantaneous RSSI (low priority)
+//! - [`get_random_number`](SharedLr2021::get_random_number) - Read a random number from the chip (low priority)
+//! - [`telemetry_poll`](SharedLr2021::telemetry_poll) - Poll a telemetry sampler (low priority)
+
+use core::cell::Cell;
+use core::ops::{Deref, DerefMut};
+
+use embassy_sync::blocking_mutex::Mutex as BlockingMutex;
+use embassy_sync::blocking_mutex::raw::RawMutex;
+use embassy_sync::mutex::{Mutex, MutexGuard};
+use embassy_sync::signal::Signal;
+use embassy_time::{with_timeout, Duration};
+use embedded_hal::digital::OutputPin;
+use embedded_hal_async::spi::SpiBus;
+
+use super::status::{Intr, Status};
+use super::system::{AdcRes, TempSrc};
+use super::telemetry::TelemetrySampler;
+use super::{BusyPin, Lr2021, Lr2021Error};
+
+/// Driver shared between tasks with two priority levels
+pub struct SharedLr2021<MX: RawMutex, O, SPI, M: BusyPin> {
+    /// Driver
+    dev: Mutex<MX, Lr2021<O,SPI, M>>,
+    /// Number of high priority tasks holding or waiting for the driver
+    high_pending: BlockingMutex<MX, Cell<u8>>,
+    /// Signaled when the last high priority task releases the driver
+    high_done: Signal<MX, ()>,
+    /// Serialize low priority requests (only one waits for the high priority tasks)
+    low_gate: Mutex<MX, ()>,
+    /// Maximum wait of a low priority request
+    low_max_wait: Duration,
+}
+
+/// Full access to the shared driver for a high priority task: release it by dropping the guard
+pub struct HighPriorityGuard<'a, MX: RawMutex, O, SPI, M: BusyPin> {
+    guard: MutexGuard<'a, MX, Lr2021<O,SPI, M>>,
+    _pending: HighPending<'a, MX, O, SPI, M>,
+}
+
+/// Registration of a high priority task, removed on drop (also when the lock request is cancelled)
+struct HighPending<'a, MX: RawMutex, O, SPI, M: BusyPin> {
+    shared: &'a SharedLr2021<MX, O, SPI, M>,
+}
+
+impl<MX: RawMutex, O, SPI, M: BusyPin> Drop for HighPending<'_, MX, O, SPI, M> {
+    fn drop(&mut self) {
+        let remaining = self.shared.high_pending.lock(|n| {
+            n.set(n.get().saturating_sub(1));
+            n.get()
+        });
+        if remaining == 0 {
+            self.shared.high_done.signal(());
+        }
+    }
+}
+
+impl<MX: RawMutex, O, SPI, M: BusyPin> Deref for HighPriorityGuard<'_, MX, O, SPI, M> {
+    type Target = Lr2021<O,SPI, M>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.guard
+    }
+}
+
+impl<MX: RawMutex, O, SPI, M: BusyPin> DerefMut for HighPriorityGuard<'_, MX, O, SPI, M> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.guard
+    }
+}
+
+impl<MX: RawMutex, O, SPI, M: BusyPin> SharedLr2021<MX, O, SPI, M> {
+    /// Share a driver: low priority requests fail after waiting low_max_wait for the high priority tasks
+    pub fn new(dev: Lr2021<O,SPI, M>, low_max_wait: Duration) -> Self {
+        Self {
+            dev: Mutex::new(dev),
+            high_pending: BlockingMutex::new(Cell::new(0)),
+            high_done: Signal::new(),
+            low_gate: Mutex::new(()),
+            low_max_wait,
+        }
+    }
+
+    /// Get a full access to the driver, granted before any pending low priority request.
+    /// The wait is bounded by the operation in progress (a single command for low priority requests)
+    pub async fn lock_high(&self) -> HighPriorityGuard<'_, MX, O, SPI, M> {
+        self.high_pending.lock(|n| n.set(n.get().saturating_add(1)));
+        let pending = HighPending { shared: self };
+        let guard = self.dev.lock().await;
+        HighPriorityGuard { guard, _pending: pending }
+    }
+
+    /// Get the driver for a low priority request once no high priority task holds or requests it
+    async fn lock_low(&self) -> Result<MutexGuard<'_, MX, Lr2021<O,SPI, M>>, Lr2021Error> {
+        let wait = async {
+            let _gate = self.low_gate.lock().await;
+            loop {
+                self.high_done.reset();
+                if self.high_pending.lock(|n| n.get()) == 0 {
+                    let guard = self.dev.lock().await;
+                    // A high priority task may have arrived while waiting for the lock
+                    if self.high_pending.lock(|n| n.get()) == 0 {
+                        return guard;
+                    }
+                    drop(guard);
+                }
+                self.high_done.wait().await;
+            }
+        };
+        with_timeout(self.low_max_wait, wait).await.map_err(|_| Lr2021Error::Timeout)
+    }
+}
+
+impl<MX: RawMutex, O, SPI, M> SharedLr2021<MX, O, SPI, M> where
+    O: OutputPin, SPI: SpiBus<u8>, M: BusyPin
+{
+    /// Read status and interrupts (low priority)
+    pub async fn get_status(&self) -> Result<(Status,Intr), Lr2021Error> {
+        self.lock_low().await?.get_status().await
+    }
+
+    /// Measure the temperature in °C with 5 fractional bits (low priority)
+    pub async fn get_temperature(&self, src: TempSrc, res: AdcRes) -> Result<i16, Lr2021Error> {
+        self.lock_low().await?.get_temperature(src, res).await
+    }
+
+    /// Measure the battery voltage in mV (low priority)
+    pub async fn get_vbat(&self, res: AdcRes) -> Result<u16, Lr2021Error> {
+        self.lock_low().await?.get_vbat(res).await
+    }
+
+    /// Read the instantaneous RSSI (low priority)
+    pub async fn get_rssi_inst(&self) -> Result<u16, Lr2021Error> {
+        self.lock_low().await?.get_rssi_inst().await
+    }
+
+    /// Read a random number from the chip (low priority)
+    pub async fn get_random_number(&self) -> Result<u32, Lr2021Error> {
+        self.lock_low().await?.get_random_number().await
+    }
+
+    /// Poll a telemetry sampler (low priority)
+    /// The driver is held for the whole poll: up to three commands (status, temperature and battery voltage)
+    pub async fn telemetry_poll<const N: usize>(&self, sampler: &mut TelemetrySampler<N>) -> Result<bool, Lr2021Error> {
+        self.lock_low().await?.telemetry_poll(sampler).await
+    }
+}
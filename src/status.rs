@@ -11,6 +11,7 @@
 //! with the command [`set_dio_irq`](crate::Lr2021::set_dio_irq) and easily get which interrupt is currently raised
 //! after a [`get_status`](crate::Lr2021::get_status) or [`get_and_clear_irq`](crate::Lr2021::get_and_clear_irq).
 
+use super::radio::PacketType;
 use super::Lr2021Error;
 
 /// Status sent at the beginning of each SPI command
@@ -257,6 +258,72 @@ pub const IRQ_MASK_FSK_TXRX : u32 =
     IRQ_MASK_LEN_ERROR |
     IRQ_MASK_TIMEOUT | IRQ_MASK_CRC_ERROR;
 
+/// Mask to enable all interrupt usefull for BLE TX/RX (preamble detected, sync valid, tx/rx done, timeout, CRC/Length error)
+pub const IRQ_MASK_BLE_TXRX : u32 =
+    IRQ_MASK_PREAMBLE_DETECTED | IRQ_MASK_HEADER_VALID |
+    IRQ_MASK_RX_DONE | IRQ_MASK_TX_DONE |
+    IRQ_MASK_LEN_ERROR |
+    IRQ_MASK_TIMEOUT | IRQ_MASK_CRC_ERROR;
+
+/// Mask to enable all interrupt usefull for FLRC TX/RX (preamble detected, sync valid, tx/rx done, timeout, CRC/Length error)
+pub const IRQ_MASK_FLRC_TXRX : u32 =
+    IRQ_MASK_PREAMBLE_DETECTED | IRQ_MASK_HEADER_VALID |
+    IRQ_MASK_RX_DONE | IRQ_MASK_TX_DONE |
+    IRQ_MASK_LEN_ERROR |
+    IRQ_MASK_TIMEOUT | IRQ_MASK_CRC_ERROR;
+
+/// Mask to enable all interrupt usefull for OOK TX/RX (preamble detected, tx/rx done, timeout, CRC/Length/Address error)
+pub const IRQ_MASK_OOK_TXRX : u32 =
+    IRQ_MASK_PREAMBLE_DETECTED |
+    IRQ_MASK_RX_DONE | IRQ_MASK_TX_DONE |
+    IRQ_MASK_LEN_ERROR | IRQ_MASK_ADDR_ERROR |
+    IRQ_MASK_TIMEOUT | IRQ_MASK_CRC_ERROR;
+
+/// Mask to enable all interrupt usefull for Zigbee TX/RX (preamble detected, tx/rx done, timeout, FCS/Length/Address error)
+pub const IRQ_MASK_ZIGBEE_TXRX : u32 =
+    IRQ_MASK_PREAMBLE_DETECTED |
+    IRQ_MASK_RX_DONE | IRQ_MASK_TX_DONE |
+    IRQ_MASK_LEN_ERROR | IRQ_MASK_ADDR_ERROR |
+    IRQ_MASK_TIMEOUT | IRQ_MASK_CRC_ERROR;
+
+/// Mask to enable all interrupt usefull for Z-Wave TX/RX (preamble detected, tx/rx done, timeout, CRC/Length/Address error)
+pub const IRQ_MASK_ZWAVE_TXRX : u32 =
+    IRQ_MASK_PREAMBLE_DETECTED |
+    IRQ_MASK_RX_DONE | IRQ_MASK_TX_DONE |
+    IRQ_MASK_LEN_ERROR | IRQ_MASK_ADDR_ERROR |
+    IRQ_MASK_TIMEOUT | IRQ_MASK_CRC_ERROR;
+
+/// Mask to enable all interrupt usefull for a Z-Wave multi-channel scan (preamble detected, rx done, CRC/Length/Address error,
+/// and tx done for the transmissions while the scan is paused). The scan runs continuously so timeout is not enabled
+pub const IRQ_MASK_ZWAVE_SCAN : u32 =
+    IRQ_MASK_PREAMBLE_DETECTED |
+    IRQ_MASK_RX_DONE | IRQ_MASK_TX_DONE |
+    IRQ_MASK_LEN_ERROR | IRQ_MASK_ADDR_ERROR |
+    IRQ_MASK_CRC_ERROR;
+
+/// Mask to enable all interrupt usefull for W-MBus TX/RX (preamble detected, sync valid, tx/rx done, timeout, CRC/Length/Address error)
+pub const IRQ_MASK_WMBUS_TXRX : u32 =
+    IRQ_MASK_PREAMBLE_DETECTED | IRQ_MASK_HEADER_VALID |
+    IRQ_MASK_RX_DONE | IRQ_MASK_TX_DONE |
+    IRQ_MASK_LEN_ERROR | IRQ_MASK_ADDR_ERROR |
+    IRQ_MASK_TIMEOUT | IRQ_MASK_CRC_ERROR;
+
+/// Mask to enable all interrupt usefull for Wi-SUN TX/RX (preamble detected, sync valid, tx/rx done, timeout, FCS/Length error)
+pub const IRQ_MASK_WISUN_TXRX : u32 =
+    IRQ_MASK_PREAMBLE_DETECTED | IRQ_MASK_HEADER_VALID |
+    IRQ_MASK_RX_DONE | IRQ_MASK_TX_DONE |
+    IRQ_MASK_LEN_ERROR |
+    IRQ_MASK_TIMEOUT | IRQ_MASK_CRC_ERROR;
+
+/// Mask to enable all interrupt usefull for LR-FHSS TX (tx done, hopping table update, timeout)
+pub const IRQ_MASK_LRFHSS_TX : u32 =
+    IRQ_MASK_TX_DONE | IRQ_MASK_FHSS | IRQ_MASK_TIMEOUT;
+
+/// Mask to enable all interrupt usefull for ranging (exchange valid, response done, request discarded, timeout)
+pub const IRQ_MASK_RANGING : u32 =
+    IRQ_MASK_RNG_EXCH_VLD | IRQ_MASK_RNG_RESP_DONE | IRQ_MASK_RNG_REQ_DIS |
+    IRQ_MASK_TIMEOUT | IRQ_MASK_RNG_TIMEOUT;
+
 #[derive(Default, Clone, Copy)]
 pub struct Intr(u32);
 
@@ -275,7 +342,7 @@ impl Intr {
 
     /// Create a new interrupt using a mask value
     /// Use IRQ_MASK_* constant to build it
-    pub fn new(value: u32) -> Intr {
+    pub const fn new(value: u32) -> Intr {
         Intr(value)
     }
 
@@ -287,11 +354,29 @@ impl Intr {
     /// Create a new interrupt for LoRa Ranging operations
     /// Enable Ranging exchange valid, response done, request discarded and timeout
     pub fn new_ranging() -> Intr {
-        Intr(IRQ_MASK_RNG_EXCH_VLD|IRQ_MASK_RNG_RESP_DONE|IRQ_MASK_RNG_REQ_DIS|IRQ_MASK_TIMEOUT|IRQ_MASK_RNG_TIMEOUT)
+        Intr(IRQ_MASK_RANGING)
+    }
+
+    /// Create a new interrupt with the default mask of a packet type (see `IRQ_MASK_*_TXRX`)
+    pub fn for_packet_type(packet_type: PacketType) -> Intr {
+        Intr(match packet_type {
+            PacketType::Lora => IRQ_MASK_LORA_TXRX,
+            PacketType::FskGeneric | PacketType::FskLegacy | PacketType::Raw => IRQ_MASK_FSK_TXRX,
+            PacketType::Ble => IRQ_MASK_BLE_TXRX,
+            PacketType::Ranging => IRQ_MASK_RANGING,
+            PacketType::Flrc => IRQ_MASK_FLRC_TXRX,
+            PacketType::Bpsk => IRQ_MASK_TX_DONE | IRQ_MASK_TIMEOUT,
+            PacketType::LrFhss => IRQ_MASK_LRFHSS_TX,
+            PacketType::Wmbus => IRQ_MASK_WMBUS_TXRX,
+            PacketType::Wisun => IRQ_MASK_WISUN_TXRX,
+            PacketType::Ook => IRQ_MASK_OOK_TXRX,
+            PacketType::Zwave => IRQ_MASK_ZWAVE_TXRX,
+            PacketType::Zigbee => IRQ_MASK_ZIGBEE_TXRX,
+        })
     }
 
     /// Return the interrupt status as u32
-    pub fn value(&self) -> u32 {
+    pub const fn value(&self) -> u32 {
         self.0
     }
 
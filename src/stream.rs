@@ -0,0 +1,134 @@
+//! # Stream of received packets
+//!
+//! This module provides an adapter implementing `futures_core::Stream` for the packets received by the radio,
+//! so that application code can use `while let Some(pkt) = rx.next().await` and the stream combinators
+//! of higher-level async frameworks.
+//!
+//! Packets are transferred from the task owning the driver to the application through an `embassy-sync` channel:
+//!  - the radio task pushes packets with [`rd_rx_fifo_to_sink`](Lr2021::rd_rx_fifo_to_sink) through an [`RxSink`]
+//!  - the application consumes them with an [`RxStream`]
+//!
+//! This module is only available with the `stream` feature (which enables `embassy-sync` and `heapless`).
+//!
+//! ## Quick Start
+//!
+//! ```rust,no_run
+//! use embassy_sync::{blocking_mutex::raw::CriticalSectionRawMutex, channel::Channel};
+//! use futures_util::StreamExt;
+//! use lr2021::rx_queue::{RxMeta, RxPacket};
+//! use lr2021::stream::{RxSink, RxStream};
+//!
+//! static RX_PACKETS: Channel<CriticalSectionRawMutex, RxPacket<64>, 4> = Channel::new();
+//!
+//! // Radio task
+//! let mut sink = RxSink::new(&RX_PACKETS);
+//! loop {
+//!     irq.wait_for_high().await;
+//!     let intr = lr2021.get_and_clear_irq().await.expect("GetIrq");
+//!     if intr.rx_done() {
+//!         lr2021.rd_rx_fifo_to_sink(&mut sink, RxMeta::new(intr)).await.expect("RxSink");
+//!     }
+//! }
+//!
+//! // Application task
+//! let mut rx = RxStream::new(&RX_PACKETS);
+//! while let Some(pkt) = rx.next().await {
+//!     info!("Received {:02x}", pkt.data());
+//! }
+//! ```
+//!
+//! ## Available Methods
+//! - [`rd_rx_fifo_to_sink`](Lr2021::rd_rx_fifo_to_sink) - Read the content of the RX FIFO and send it to the stream
+
+use core::pin::Pin;
+use core::task::{Context, Poll};
+
+use embassy_sync::blocking_mutex::raw::RawMutex;
+use embassy_sync::channel::{Channel, Receiver, Sender};
+use embedded_hal::digital::OutputPin;
+use embedded_hal_async::spi::SpiBus;
+use futures_core::Stream;
+use heapless::Vec;
+
+use super::rx_queue::{RxMeta, RxPacket, RxQueueStats};
+use super::{BusyPin, Lr2021, Lr2021Error};
+
+/// Radio side of the packet stream
+pub struct RxSink<'a, M: RawMutex, const N: usize, const MAX_LEN: usize> {
+    sender: Sender<'a, M, RxPacket<MAX_LEN>, N>,
+    stats: RxQueueStats,
+}
+
+impl<'a, M: RawMutex, const N: usize, const MAX_LEN: usize> RxSink<'a, M, N, MAX_LEN> {
+    /// Create a sink sending packets to a channel
+    pub fn new(channel: &'a Channel<M, RxPacket<MAX_LEN>, N>) -> Self {
+        Self { sender: channel.sender(), stats: RxQueueStats::default() }
+    }
+
+    /// Send a packet to the stream without waiting
+    /// Return false if the packet was dropped (channel full or packet too large)
+    pub fn push(&mut self, data: &[u8], meta: RxMeta) -> bool {
+        let Ok(data) = Vec::from_slice(data) else {
+            self.stats.oversize = self.stats.oversize.wrapping_add(1);
+            return false;
+        };
+        match self.sender.try_send(RxPacket { data, meta }) {
+            Ok(_) => {
+                self.stats.pushed = self.stats.pushed.wrapping_add(1);
+                true
+            }
+            Err(_) => {
+                self.stats.overflow = self.stats.overflow.wrapping_add(1);
+                false
+            }
+        }
+    }
+
+    /// Sink statistics
+    pub fn stats(&self) -> RxQueueStats {
+        self.stats
+    }
+
+    /// Reset sink statistics
+    pub fn clear_stats(&mut self) {
+        self.stats = RxQueueStats::default();
+    }
+}
+
+/// Application side of the packet stream: never terminates
+pub struct RxStream<'a, M: RawMutex, const N: usize, const MAX_LEN: usize> {
+    receiver: Receiver<'a, M, RxPacket<MAX_LEN>, N>,
+}
+
+impl<'a, M: RawMutex, const N: usize, const MAX_LEN: usize> RxStream<'a, M, N, MAX_LEN> {
+    /// Create a stream receiving packets from a channel
+    pub fn new(channel: &'a Channel<M, RxPacket<MAX_LEN>, N>) -> Self {
+        Self { receiver: channel.receiver() }
+    }
+}
+
+impl<M: RawMutex, const N: usize, const MAX_LEN: usize> Stream for RxStream<'_, M, N, MAX_LEN> {
+    type Item = RxPacket<MAX_LEN>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.receiver.poll_receive(cx).map(Some)
+    }
+}
+
+impl<O,SPI, M> Lr2021<O,SPI, M> where
+    O: OutputPin, SPI: SpiBus<u8>, M: BusyPin
+{
+    /// Read the content of the RX FIFO and send it to the stream with its metadata
+    /// The RX FIFO is cleared if the packet is too large for the stream
+    /// Return false if the packet was dropped
+    pub async fn rd_rx_fifo_to_sink<R: RawMutex, const N: usize, const MAX_LEN: usize>(&mut self, sink: &mut RxSink<'_, R, N, MAX_LEN>, meta: RxMeta) -> Result<bool, Lr2021Error> {
+        let len = self.get_rx_fifo_lvl().await? as usize;
+        if len > MAX_LEN || len > crate::BUFFER_SIZE {
+            self.clear_rx_fifo().await?;
+            sink.stats.oversize = sink.stats.oversize.wrapping_add(1);
+            return Ok(false);
+        }
+        self.rd_rx_fifo(len).await?;
+        Ok(sink.push(&self.buffer()[..len], meta))
+    }
+}
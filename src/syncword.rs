@@ -0,0 +1,96 @@
+//! # Syncword representation
+//!
+//! Protocol specifications and legacy stacks describe syncwords with different conventions:
+//! MSB-first value, LSB-first value (e.g. BLE access address), list of bytes, byte-reversed word, ...
+//! The [`Syncword`] type stores the syncword as the sequence of bits sent over the air and provides
+//! explicit constructors for each convention, so that the same value can be given to any `set_*_syncword` method,
+//! which takes care of the conversion to the native representation of each modem.
+//!
+//! LoRa syncword is not a bit pattern and is configured directly with [`set_lora_syncword`](crate::Lr2021::set_lora_syncword).
+//!
+//! ## Quick Start
+//!
+//! ```rust,no_run
+//! use lr2021::syncword::Syncword;
+//!
+//! // Same 16-bit syncword described in different ways
+//! let sw = Syncword::from_msb_bits(0x2DD4, 16);
+//! assert_eq!(sw, Syncword::from_msb_bytes(&[0x2D, 0xD4]));
+//! assert_eq!(sw, Syncword::from_lsb_bits(0x2BB4, 16));
+//! assert_eq!(sw, Syncword::from_msb_bits(0xD42D, 16).byte_reversed());
+//!
+//! lr2021.set_fsk_syncword(sw).await.expect("SetSyncword");
+//! lr2021.set_flrc_syncword(1, sw).await.expect("SetSyncword");
+//! ```
+
+/// Syncword as the sequence of bits sent over the air
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Syncword {
+    /// Syncword bits: bit nb_bits-1 is sent first
+    bits: u64,
+    /// Syncword length in bits (max 64)
+    nb_bits: u8,
+}
+
+impl Syncword {
+    /// Empty syncword
+    pub const NONE: Syncword = Syncword { bits: 0, nb_bits: 0 };
+
+    /// Create a syncword from a value sent MSB first (bit nb_bits-1 sent first)
+    pub const fn from_msb_bits(value: u64, nb_bits: u8) -> Self {
+        let nb_bits = if nb_bits > 64 {64} else {nb_bits};
+        let mask = if nb_bits == 64 {u64::MAX} else {(1 << nb_bits) - 1};
+        Self { bits: value & mask, nb_bits }
+    }
+
+    /// Create a syncword from a value sent LSB first (bit 0 sent first)
+    pub const fn from_lsb_bits(value: u64, nb_bits: u8) -> Self {
+        let nb_bits = if nb_bits > 64 {64} else {nb_bits};
+        let bits = if nb_bits == 0 {0} else {value.reverse_bits() >> (64 - nb_bits)};
+        Self { bits, nb_bits }
+    }
+
+    /// Create a syncword from a list of bytes sent in order, each byte MSB first
+    /// Only the first 8 bytes are used
+    pub fn from_msb_bytes(bytes: &[u8]) -> Self {
+        let bytes = &bytes[..bytes.len().min(8)];
+        let bits = bytes.iter().fold(0u64, |acc, &b| (acc << 8) | b as u64);
+        Self { bits, nb_bits: (bytes.len() * 8) as u8 }
+    }
+
+    /// Create a syncword from a list of bytes sent in order, each byte LSB first
+    /// Only the first 8 bytes are used
+    pub fn from_lsb_bytes(bytes: &[u8]) -> Self {
+        let bytes = &bytes[..bytes.len().min(8)];
+        let bits = bytes.iter().fold(0u64, |acc, &b| (acc << 8) | b.reverse_bits() as u64);
+        Self { bits, nb_bits: (bytes.len() * 8) as u8 }
+    }
+
+    /// Reverse the byte order of the syncword, keeping the bit order inside each byte
+    /// Incomplete bytes (when the length is not a multiple of 8) are dropped
+    pub const fn byte_reversed(self) -> Self {
+        let nb_bytes = self.nb_bits / 8;
+        if nb_bytes == 0 {
+            return Self::NONE;
+        }
+        let bits = (self.bits >> (self.nb_bits % 8)).swap_bytes() >> (64 - 8 * nb_bytes as u32);
+        Self { bits, nb_bits: nb_bytes * 8 }
+    }
+
+    /// Syncword length in bits
+    pub const fn nb_bits(&self) -> u8 {
+        self.nb_bits
+    }
+
+    /// Syncword value with the first bit sent as MSB (bit nb_bits-1)
+    pub const fn msb_bits(&self) -> u64 {
+        self.bits
+    }
+
+    /// Syncword value with the first bit sent as LSB (bit 0)
+    /// This is the convention used for the BLE access address
+    pub const fn lsb_bits(&self) -> u64 {
+        if self.nb_bits == 0 {0} else {self.bits.reverse_bits() >> (64 - self.nb_bits)}
+    }
+}
@@ -19,21 +19,34 @@
 //! - [`patch_simo`](Lr2021::patch_simo) - Update SIMO configuration for optimal performances
 //! - [`add_register_to_retention`](Lr2021::add_register_to_retention) - Add a register to the retention list (i.e. the value is restored on wake-up)
 //! - [`setup_retention`](Lr2021::setup_retention) - Setup which registers to add to retention
+//! - [`reboot`](Lr2021::reboot) - Reset the chip, clear the driver state and optionally restore the cached configuration
 //!
 //! ### Calibration
 //! - [`calibrate`](Lr2021::calibrate) - Run calibration of different blocks
 //! - [`calib_fe`](Lr2021::calib_fe) - Run front-end calibration on specified frequencies
+//! - [`clear_errors`](Lr2021::clear_errors) - Clear all error flags
 //!
 //! ### Clock Management
 //! - [`set_lf_clk`](Lr2021::set_lf_clk) - Configure the LF clock
+//! - [`measure_lf_clock_ppm`](Lr2021::measure_lf_clock_ppm) - Measure the LF clock frequency error against the HF clock
+//! - [`set_lf_clock_ppm`](Lr2021::set_lf_clock_ppm) - Set the LF clock error compensated on sleep and RX duty-cycle durations
+//! - [`set_xtal_ppm`](Lr2021::set_xtal_ppm) - Set the crystal tolerance used to select the LoRa detection frequency range
+//! - [`lf_ticks_compensated`](Lr2021::lf_ticks_compensated) - Convert a nominal number of LF ticks to the compensated value
 //! - [`set_tcxo`](Lr2021::set_tcxo) - Configure the chip to use a TCXO
+//! - [`init_with_tcxo`](Lr2021::init_with_tcxo) - Reset and bring-up sequence for boards using a TCXO
 //! - [`set_xosc_trim`](Lr2021::set_xosc_trim) - Configure XOsc foot capacitor
 //!
 //! ### I/O Management
 //! - [`set_dio_function`](Lr2021::set_dio_function) - Configure a DIO pin function
 //! - [`set_dio_irq`](Lr2021::set_dio_irq) - Configure a DIO pin for interrupt generation
+//! - [`setup_irq_for_current_protocol`](Lr2021::setup_irq_for_current_protocol) - Configure a DIO pin with the default interrupts of the current packet type
 //! - [`set_dio_rf_switch`](Lr2021::set_dio_rf_switch) - Configure a DIO pin to control an RF Switch
 //! - [`set_dio_clk_scaling`](Lr2021::set_dio_clk_scaling) - Configure the clock scaling when output on a DIO
+//! - [`release_dio`](Lr2021::release_dio) - Release a DIO so that it can be assigned to another function
+//! - [`dio_configuration`](Lr2021::dio_configuration) - Inspect the DIO assignments (conflicting assignments are rejected)
+//! - [`set_irq_routing`](Lr2021::set_irq_routing) - Route interrupts to several DIOs in one call
+//! - [`route_irq`](Lr2021::route_irq) - Move interrupts to a DIO at runtime
+//! - [`irq_routing`](Lr2021::irq_routing) - Read back the interrupts routed to each DIO
 //!
 //! ### Register and Memory Access
 //! - [`rd_reg`](Lr2021::rd_reg) - Read a 32-bit register value
@@ -41,6 +54,8 @@
 //! - [`wr_reg_mask`](Lr2021::wr_reg_mask) - Write a 32-bit register value with a mask
 //! - [`wr_field`](Lr2021::wr_field) - Write to specific bit field in a register
 //! - [`rd_mem`](Lr2021::rd_mem) - Read multiple 32-bit words from memory to internal buffer
+//! - [`rd_mem_into`](Lr2021::rd_mem_into) - Read a block of 32-bit words of any size from memory
+//! - [`wr_mem`](Lr2021::wr_mem) - Write a block of bytes of any size to memory with read-back verification
 //!
 //! ### Measurements
 //! - [`get_temperature`](Lr2021::get_temperature) -  Return temperature in degree Celsius with 5 fractional bits
@@ -48,18 +63,19 @@
 //! - [`get_vbat`](Lr2021::get_vbat) -  Return the battery voltage in mV
 //! - [`get_random_number`](Lr2021::get_random_number) -  Return a random number using entropy from PLL and ADC
 
-use embassy_time::Duration;
+use embassy_time::{Duration, Instant, Timer};
 use embedded_hal::digital::OutputPin;
 use embedded_hal_async::spi::SpiBus;
 
 use crate::cmd::cmd_regmem::{read_reg_mem32_req, write_reg_mem32_cmd, write_reg_mem_mask32_cmd, ReadRegMem32Rsp};
 use crate::constants::*;
 
-use super::{BusyPin, Lr2021, Lr2021Error};
-use super::status::{Intr, Status};
+use super::{BusyPin, CfgCache, Lr2021, Lr2021Error};
+use super::status::{Intr, Status, IRQ_MASK_TIMEOUT, IRQ_MASK_ZWAVE_SCAN};
+use super::quirks::{FwVersion, Quirks};
 
 pub use super::cmd::cmd_system::*;
-use super::radio::{set_rx_cmd, set_tx_cmd};
+use super::radio::{set_rx_cmd, set_tx_cmd, PacketType, ScanState, TimestampIndex};
 
 /// Chip Mode: Sleep/Standby/Fs/...
 #[derive(Clone, Debug, PartialEq)]
@@ -85,6 +101,9 @@ pub enum ChipMode {
     Rx,
 }
 
+/// Maximum number of words read by a single memory read command
+pub const RD_MEM_MAX_WORDS: usize = 40;
+
 /// SIMO frequency for low bandwidth in pll step (4.30MHz)
 const SIMO_FREQ_LBW : u32 = 4_508_877;
 /// SIMO frequency for high bandwidth in pll step (2.80MHz)
@@ -100,6 +119,121 @@ pub fn pllstep_to_hz(val_step: u32) -> u32 {
     (val_scaled >> 14) as u32
 }
 
+/// DIO assignments made through the driver
+/// A DIO can only be re-assigned to a different function after being released (function set to None)
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct DioConfiguration {
+    func: [Option<DioFunc>; 7],
+    irq: [u32; 7],
+}
+
+/// List of all DIOs
+const DIO_ALL: [DioNum; 7] = [DioNum::Dio5, DioNum::Dio6, DioNum::Dio7, DioNum::Dio8, DioNum::Dio9, DioNum::Dio10, DioNum::Dio11];
+
+fn dio_idx(dio: DioNum) -> usize {
+    dio as usize - DioNum::Dio5 as usize
+}
+
+impl DioConfiguration {
+    /// Function assigned to a DIO (None if not assigned)
+    pub fn get(&self, dio: DioNum) -> Option<DioFunc> {
+        self.func[dio_idx(dio)]
+    }
+
+    /// Interrupts routed to a DIO (none if the DIO is not an IRQ line)
+    pub fn irq(&self, dio: DioNum) -> Intr {
+        Intr::new(self.irq[dio_idx(dio)])
+    }
+
+    /// Check if a DIO can be assigned to a function: free or already assigned to the same function
+    pub fn can_assign(&self, dio: DioNum, func: DioFunc) -> bool {
+        func == DioFunc::None || self.get(dio).is_none_or(|f| f == func)
+    }
+
+    /// List of DIO assigned to a function
+    pub fn assigned(&self) -> impl Iterator<Item = (DioNum, DioFunc)> + '_ {
+        DIO_ALL.into_iter().filter_map(|dio| self.get(dio).map(|f| (dio, f)))
+    }
+
+    /// Interrupt routing of all DIOs configured as IRQ line
+    pub fn irq_routing(&self) -> IrqRouting {
+        IrqRouting(self.irq)
+    }
+
+    fn set(&mut self, dio: DioNum, func: DioFunc) {
+        self.func[dio_idx(dio)] = if func == DioFunc::None {None} else {Some(func)};
+        if func != DioFunc::Irq {
+            self.irq[dio_idx(dio)] = 0;
+        }
+    }
+}
+
+/// Routing of interrupts to the DIOs: each DIO used as IRQ line has its own interrupt mask
+/// (e.g. RxDone on DIO5 and reception errors on DIO6)
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct IrqRouting([u32; 7]);
+
+impl IrqRouting {
+    /// Empty routing (no interrupt on any DIO)
+    pub const fn new() -> Self {
+        Self([0; 7])
+    }
+
+    /// Add interrupts to a DIO (an interrupt can be routed on several DIOs)
+    pub const fn with(self, dio: DioNum, intr: Intr) -> Self {
+        let mut masks = self.0;
+        masks[dio as usize - DioNum::Dio5 as usize] |= intr.value();
+        Self(masks)
+    }
+
+    /// Interrupts routed to a DIO
+    pub fn get(&self, dio: DioNum) -> Intr {
+        Intr::new(self.0[dio_idx(dio)])
+    }
+
+    /// List of DIOs with at least one of the interrupts
+    pub fn dios_for(&self, intr: Intr) -> impl Iterator<Item = DioNum> + '_ {
+        DIO_ALL.into_iter().filter(move |dio| self.0[dio_idx(*dio)] & intr.value() != 0)
+    }
+
+    /// List of DIOs with their interrupts (only DIOs with at least one interrupt)
+    pub fn routes(&self) -> impl Iterator<Item = (DioNum, Intr)> + '_ {
+        DIO_ALL.into_iter()
+            .filter(|dio| self.0[dio_idx(*dio)] != 0)
+            .map(|dio| (dio, self.get(dio)))
+    }
+}
+
+/// Result of a [`reboot`](Lr2021::reboot): firmware version and configuration restored
+#[derive(Debug, Default, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct RebootReport {
+    /// Firmware version read after the reset
+    pub version: FwVersion,
+    /// Firmware version differs from the one known before the reboot (quirks reloaded)
+    pub version_changed: bool,
+    /// TCXO configured and chip re-calibrated
+    pub tcxo: bool,
+    /// Packet type restored
+    pub packet_type: bool,
+    /// LoRa modulation parameters restored
+    pub lora_modulation: bool,
+    /// LoRa packet parameters restored
+    pub lora_packet: bool,
+    /// RX path and boost restored
+    pub rx_path: bool,
+    /// Manual RX gain restored
+    pub rx_gain: bool,
+    /// W-MBus packet parameters restored
+    pub wmbus_packet: bool,
+    /// Z-Wave scan configuration restored (scan not started)
+    pub zwave_scan: bool,
+    /// DIO assigned before the reboot: their configuration is not cached and must be set again
+    pub dio_lost: DioConfiguration,
+}
+
 #[derive(Default, Clone, Copy)]
 /// List of additional registers to keep in retention
 pub struct RetentionCfg(u8);
@@ -210,6 +344,13 @@ impl<O,SPI, M> Lr2021<O,SPI, M> where
         self.cmd_wr(&req).await
     }
 
+    /// Clear all error flags
+    /// The error interrupt is not cleared and must be cleared with [`clear_irqs`](Lr2021::clear_irqs)
+    pub async fn clear_errors(&mut self) -> Result<(), Lr2021Error> {
+        let req = clear_errors_cmd();
+        self.cmd_wr(&req).await
+    }
+
     /// Run calibration on up to 3 frequencies on 16b (MSB encode RX Path)
     /// If none, use current frequency
     pub async fn calib_fe(&mut self, freqs_4m: &[u16]) -> Result<(), Lr2021Error> {
@@ -226,8 +367,8 @@ impl<O,SPI, M> Lr2021<O,SPI, M> where
         match chip_mode {
             ChipMode::DeepSleep      => self.cmd_wr(&set_sleep_cmd(false, 0)).await,
             ChipMode::DeepRetention  => self.cmd_wr(&set_sleep_adv_cmd(false, 1, 0)).await,
-            ChipMode::Sleep(t)       => self.cmd_wr(&set_sleep_adv_cmd(true, 0, t)).await,
-            ChipMode::Retention(t)   => self.cmd_wr(&set_sleep_adv_cmd(true, 1, t)).await,
+            ChipMode::Sleep(t)       => self.cmd_wr(&set_sleep_adv_cmd(true, 0, self.lf_ticks_compensated(t))).await,
+            ChipMode::Retention(t)   => self.cmd_wr(&set_sleep_adv_cmd(true, 1, self.lf_ticks_compensated(t))).await,
             ChipMode::StandbyRc      => self.cmd_wr(&set_standby_cmd(StandbyMode::Rc)).await,
             ChipMode::StandbyXosc    => self.cmd_wr(&set_standby_cmd(StandbyMode::Xosc)).await,
             ChipMode::Fs => self.cmd_wr(&set_fs_cmd()).await,
@@ -236,6 +377,78 @@ impl<O,SPI, M> Lr2021<O,SPI, M> where
         }
     }
 
+    /// Reset the chip and clear the driver state (cached configuration, pending packets, status)
+    /// The firmware version is read back to check the chip is responding, and the quirks are reloaded if it changed
+    /// When `reload_config` is set, the configuration cached before the reset is replayed:
+    /// TCXO (with calibration), packet type, LoRa/W-MBus parameters, RX path and gain, Z-Wave scan configuration.
+    /// RF frequency, PA and TX parameters and DIO are not restored and must be configured again
+    pub async fn reboot(&mut self, reload_config: bool) -> Result<RebootReport, Lr2021Error> {
+        let prev = self.cache;
+        self.reset().await?;
+        self.wait_ready(Duration::from_millis(100)).await?;
+        let rsp = self.get_version().await?;
+        let version = FwVersion::new(rsp.major(), rsp.minor());
+        let version_changed = version != prev.quirks.version();
+        // Clear all driver state, keeping manual quirks if the firmware did not change
+        self.cache = CfgCache {
+            quirks: if version_changed {Quirks::for_version(version)} else {prev.quirks},
+            // Crystal tolerance is a board property: keep it to select the LoRa frequency range
+            xtal_ppm: prev.xtal_ppm,
+            ..CfgCache::default()
+        };
+        self.rx_pkt_lens.clear();
+        self.buffer.nop();
+        let mut report = RebootReport { version, version_changed, dio_lost: prev.dio, ..RebootReport::default() };
+        if !reload_config {
+            return Ok(report);
+        }
+        if let Some((volt, start_time)) = prev.tcxo {
+            self.set_tcxo(volt, start_time).await?;
+            self.clear_errors().await?;
+            self.calibrate(true, true, true, true, true, true).await?;
+            report.tcxo = true;
+        }
+        if let Some(packet_type) = prev.packet_type {
+            self.set_packet_type(packet_type).await?;
+            report.packet_type = true;
+        }
+        // Protocol parameters are only accepted by the chip for the matching packet type
+        match prev.packet_type {
+            Some(PacketType::Lora) => {
+                if let Some(params) = prev.lora_modulation {
+                    self.set_lora_modulation(&params).await?;
+                    report.lora_modulation = true;
+                }
+                if let Some(params) = prev.lora_packet {
+                    self.set_lora_packet(&params).await?;
+                    report.lora_packet = true;
+                }
+            }
+            Some(PacketType::Wmbus) => {
+                if let Some(params) = prev.wmbus_packet {
+                    self.set_wmbus_packet(params).await?;
+                    report.wmbus_packet = true;
+                }
+            }
+            Some(PacketType::Zwave) => {
+                if let Some(cfg) = prev.zwave_scan {
+                    self.set_zwave_scan_config(&cfg).await?;
+                    report.zwave_scan = true;
+                }
+            }
+            _ => {}
+        }
+        if let (Some(rx_path), Some(rx_boost)) = (prev.rx_path, prev.rx_boost) {
+            self.set_rx_path(rx_path, rx_boost).await?;
+            report.rx_path = true;
+        }
+        if prev.rx_gain != 0 {
+            self.set_rx_gain(prev.rx_gain).await?;
+            report.rx_gain = true;
+        }
+        Ok(report)
+    }
+
     /// Configure regulator (LDO or SIMO)
     /// Shall only be called while in Standby RC
     pub async fn set_regulator_mode(&mut self, simo_en: bool) -> Result<(), Lr2021Error> {
@@ -297,8 +510,7 @@ impl<O,SPI, M> Lr2021<O,SPI, M> where
         if curr_freq != new_freq {
             self.wr_reg(ADDR_SIMO_FREQ, new_freq).await?;
             // Need to call set_rf to be sure this is taken into account
-            let rf_step = self.rd_reg(ADDR_FREQ_RF).await?;
-            let rf_hz = pllstep_to_hz(rf_step);
+            let rf_hz = self.get_rf().await?;
             self.set_rf(rf_hz).await?;
         }
         if let Some(slot) = ret_en {
@@ -307,27 +519,109 @@ impl<O,SPI, M> Lr2021<O,SPI, M> where
         Ok(())
     }
 
+    /// Check that a DIO can be assigned to a function
+    fn check_dio(&self, dio: DioNum, func: DioFunc) -> Result<(), Lr2021Error> {
+        let lf_clk_invalid = func == DioFunc::LfClkOut && matches!(dio, DioNum::Dio5 | DioNum::Dio6);
+        if lf_clk_invalid || !self.cache.dio.can_assign(dio, func) {
+            return Err(Lr2021Error::InvalidParam);
+        }
+        Ok(())
+    }
+
     /// Configure a DIO function (IRQ, RF Switch, Clock, ...)
     /// Note: LF clock can only be output on DIO 7 to 11
+    /// Return InvalidParam if the DIO is already assigned to another function: use DioFunc::None to release it first
     pub async fn set_dio_function(&mut self, dio: DioNum, func: DioFunc, pull_drive: PullDrive) -> Result<(), Lr2021Error> {
+        self.check_dio(dio, func)?;
         let req = set_dio_function_cmd(dio, func, pull_drive);
-        self.cmd_wr(&req).await
+        self.cmd_wr(&req).await?;
+        self.cache.dio.set(dio, func);
+        Ok(())
     }
 
     /// Configure a pin as An RF Switch
     /// Each args flags when the IO should be high
+    /// Return InvalidParam if the DIO is already assigned to another function
     pub async fn set_dio_rf_switch(&mut self, dio_num: DioNum, tx_hf: bool, rx_hf: bool, tx_lf: bool, rx_lf: bool, standby: bool) -> Result<(), Lr2021Error> {
+        self.check_dio(dio_num, DioFunc::RfSwitch)?;
         let req = set_dio_rf_switch_config_cmd(dio_num, tx_hf, rx_hf, tx_lf, rx_lf, standby);
-        self.cmd_wr(&req).await
+        self.cmd_wr(&req).await?;
+        self.cache.dio.set(dio_num, DioFunc::RfSwitch);
+        Ok(())
     }
 
     /// Configure a pin as IRQ and enable interrupts for this pin
+    /// Return InvalidParam if the DIO is already assigned to another function
     pub async fn set_dio_irq(&mut self, dio: DioNum, intr_en: Intr) -> Result<(), Lr2021Error> {
+        self.check_dio(dio, DioFunc::Irq)?;
         let sleep_pull = if dio==DioNum::Dio5 || dio==DioNum::Dio6 {PullDrive::PullAuto} else {PullDrive::PullUp};
         let req = set_dio_function_cmd(dio, DioFunc::Irq, sleep_pull);
         self.cmd_wr(&req).await?;
+        self.cache.dio.set(dio, DioFunc::Irq);
+        self.wr_dio_irq_mask(dio, intr_en).await
+    }
+
+    /// Configure a pin as IRQ with the default interrupts of the packet type set (see [`Intr::for_packet_type`]),
+    /// using the Z-Wave scan mask while a scan is running or paused.
+    /// Return the interrupts enabled, or InvalidParam if no packet type was set or the DIO is already assigned to another function
+    pub async fn setup_irq_for_current_protocol(&mut self, dio: DioNum) -> Result<Intr, Lr2021Error> {
+        let packet_type = self.cache.packet_type.ok_or(Lr2021Error::InvalidParam)?;
+        let intr = match packet_type {
+            PacketType::Zwave if self.cache.scan_state != ScanState::Idle => Intr::new(IRQ_MASK_ZWAVE_SCAN),
+            _ => Intr::for_packet_type(packet_type),
+        };
+        self.set_dio_irq(dio, intr).await?;
+        Ok(intr)
+    }
+
+    /// Update the interrupts of a DIO already configured as IRQ line
+    async fn wr_dio_irq_mask(&mut self, dio: DioNum, intr_en: Intr) -> Result<(), Lr2021Error> {
         let req = set_dio_irq_config_cmd(dio, intr_en.value());
-        self.cmd_wr(&req).await
+        self.cmd_wr(&req).await?;
+        self.cache.dio.irq[dio_idx(dio)] = intr_en.value();
+        Ok(())
+    }
+
+    /// Apply an interrupt routing: each DIO with interrupts is configured as IRQ line,
+    /// and the DIOs previously used as IRQ line and absent from the routing get an empty mask (still assigned to IRQ).
+    /// Only DIOs whose mask changed are programmed.
+    /// Return InvalidParam if a DIO is already assigned to another function (no DIO is modified in this case)
+    pub async fn set_irq_routing(&mut self, routing: &IrqRouting) -> Result<(), Lr2021Error> {
+        for (dio, _) in routing.routes() {
+            self.check_dio(dio, DioFunc::Irq)?;
+        }
+        for dio in DIO_ALL {
+            let intr = routing.get(dio);
+            match self.cache.dio.get(dio) {
+                Some(DioFunc::Irq) if self.cache.dio.irq(dio).value() != intr.value() => self.wr_dio_irq_mask(dio, intr).await?,
+                None if !intr.none() => self.set_dio_irq(dio, intr).await?,
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Route interrupts to a DIO at runtime: the interrupts are removed from all other IRQ lines and added to the DIO
+    pub async fn route_irq(&mut self, intr: Intr, dio: DioNum) -> Result<(), Lr2021Error> {
+        let mut masks = self.cache.dio.irq;
+        masks.iter_mut().for_each(|m| *m &= !intr.value());
+        masks[dio_idx(dio)] |= intr.value();
+        self.set_irq_routing(&IrqRouting(masks)).await
+    }
+
+    /// Interrupt routing programmed through the driver
+    pub fn irq_routing(&self) -> IrqRouting {
+        self.cache.dio.irq_routing()
+    }
+
+    /// Release a DIO (function set to None) so that it can be assigned to another function
+    pub async fn release_dio(&mut self, dio: DioNum, pull_drive: PullDrive) -> Result<(), Lr2021Error> {
+        self.set_dio_function(dio, DioFunc::None, pull_drive).await
+    }
+
+    /// DIO assignments made through the driver
+    pub fn dio_configuration(&self) -> &DioConfiguration {
+        &self.cache.dio
     }
 
     /// Configure the clock scaling when output on a DIO
@@ -337,15 +631,118 @@ impl<O,SPI, M> Lr2021<O,SPI, M> where
     }
 
     /// Configure the LF clock
+    /// The LF clock compensation is cleared since it is only valid for the clock it was measured on
     pub async fn set_lf_clk(&mut self, sel: LfClock) -> Result<(), Lr2021Error> {
         let req = config_lf_clock_cmd(sel);
-        self.cmd_wr(&req).await
+        self.cmd_wr(&req).await?;
+        self.cache.lf_ppm = 0;
+        Ok(())
+    }
+
+    /// Measure the LF clock frequency error (in ppm, positive when the LF clock is fast) against the HF clock.
+    /// Two RX with a timeout of `lf_ticks` and `2*lf_ticks` are started and their duration is measured
+    /// in HF ticks by reading the timestamp `index` before starting RX and after the timeout:
+    /// only the difference between the two reads is used, so the timestamp event does not matter,
+    /// and the difference between the two measurements removes the command and polling latency.
+    /// Longer measurement gives better accuracy (e.g. 32768 ticks gives an accuracy of a few ppm).
+    /// The chip must be in standby with the RX configured (frequency, packet type, ...): it goes back to its fallback mode after each timeout.
+    /// The result is stored as the LF clock compensation (see [`set_lf_clock_ppm`](Lr2021::set_lf_clock_ppm))
+    /// Return Timeout if a RX does not end within twice its nominal duration (the chip is then set in Standby)
+    pub async fn measure_lf_clock_ppm(&mut self, index: TimestampIndex, lf_ticks: u32) -> Result<i32, Lr2021Error> {
+        if lf_ticks == 0 || lf_ticks > 0x7FFFFF {
+            return Err(Lr2021Error::InvalidParam);
+        }
+        let hf_short = self.measure_rx_timeout(index, lf_ticks).await?;
+        let hf_long = self.measure_rx_timeout(index, 2*lf_ticks).await?;
+        let hf = hf_long.wrapping_sub(hf_short) as i64;
+        if hf <= 0 {
+            return Err(Lr2021Error::Unknown);
+        }
+        // One LF tick (32.768kHz) is 15625/16 HF ticks (32MHz)
+        let expected = lf_ticks as i64 * 15625 * 1_000_000 / 16;
+        let ppm = ((expected - hf * 1_000_000) / hf) as i32;
+        self.cache.lf_ppm = ppm;
+        Ok(ppm)
+    }
+
+    /// Run a RX with a timeout in LF ticks and return its duration in HF ticks
+    async fn measure_rx_timeout(&mut self, index: TimestampIndex, lf_ticks: u32) -> Result<u32, Lr2021Error> {
+        self.clear_irqs(Intr::new(IRQ_MASK_TIMEOUT)).await?;
+        let start = self.get_timestamp(index).await?;
+        self.set_rx(lf_ticks, false).await?;
+        // Timeout of the host polling: twice the nominal duration
+        let deadline = Instant::now() + Duration::from_micros(2 * lf_ticks as u64 * 1_000_000 / 32768 + 100_000);
+        loop {
+            let (_, intr) = self.get_status().await?;
+            if intr.timeout() {
+                break;
+            }
+            if Instant::now() >= deadline {
+                self.set_chip_mode(ChipMode::StandbyRc).await?;
+                return Err(Lr2021Error::Timeout);
+            }
+            Timer::after_micros(100).await;
+        }
+        let end = self.get_timestamp(index).await?;
+        self.clear_irqs(Intr::new(IRQ_MASK_TIMEOUT)).await?;
+        Ok(end.wrapping_sub(start))
+    }
+
+    /// Set the LF clock frequency error (in ppm, positive when the LF clock is fast)
+    /// compensated on sleep durations and RX duty-cycle period
+    pub fn set_lf_clock_ppm(&mut self, ppm: i32) {
+        self.cache.lf_ppm = ppm;
+    }
+
+    /// Return the LF clock frequency error compensated (in ppm)
+    pub fn lf_clock_ppm(&self) -> i32 {
+        self.cache.lf_ppm
+    }
+
+    /// Set the crystal tolerance of the link (in ppm, sum of the tolerance of both ends)
+    /// used to select the LoRa detection frequency range (see [`set_lora_freq_range_auto`](Lr2021::set_lora_freq_range_auto)).
+    /// The range is updated on the next LoRa modulation configuration
+    pub fn set_xtal_ppm(&mut self, ppm: u16) {
+        self.cache.xtal_ppm = Some(ppm);
+    }
+
+    /// Return the crystal tolerance of the link (in ppm) if set
+    pub fn xtal_ppm(&self) -> Option<u16> {
+        self.cache.xtal_ppm
+    }
+
+    /// Convert a nominal duration timed by the LF clock (in LF ticks, or any unit) to the value compensated by the LF clock error
+    pub fn lf_ticks_compensated(&self, ticks: u32) -> u32 {
+        let ticks = ticks as i64 + ticks as i64 * self.cache.lf_ppm as i64 / 1_000_000;
+        ticks.clamp(0, u32::MAX as i64) as u32
     }
 
     /// Configure the chip to use a TCXO
+    /// Temperature compensation cannot be enabled while a TCXO is configured
     pub async fn set_tcxo(&mut self, volt: TcxoVoltage, start_time: u32) -> Result<(), Lr2021Error> {
         let req = set_tcxo_mode_cmd(volt, start_time);
-        self.cmd_wr(&req).await
+        self.cmd_wr(&req).await?;
+        self.cache.tcxo = Some((volt, start_time));
+        Ok(())
+    }
+
+    /// Reset the chip and run the bring-up sequence for a board using a TCXO:
+    ///  - configure the TCXO before any calibration, since calibrations are run on the crystal clock
+    ///  - clear the crystal start error flagged while the chip was booting without the TCXO supply
+    ///  - calibrate all blocks with the TCXO running and check the crystal started correctly
+    ///
+    /// Return the remaining errors after calibration, with InvalidParam on a hf_xosc_start error
+    /// (TCXO not starting: check the voltage and increase the start time)
+    pub async fn init_with_tcxo(&mut self, volt: TcxoVoltage, start_time: u32) -> Result<ErrorsRsp, Lr2021Error> {
+        self.reset().await?;
+        self.set_tcxo(volt, start_time).await?;
+        self.clear_errors().await?;
+        self.calibrate(true, true, true, true, true, true).await?;
+        let errors = self.get_errors().await?;
+        if errors.hf_xosc_start() {
+            return Err(Lr2021Error::InvalidParam);
+        }
+        Ok(errors)
     }
 
     /// Configure XOsc foot capacitor
@@ -379,10 +776,14 @@ impl<O,SPI, M> Lr2021<O,SPI, M> where
     }
 
     /// Configure Temperature compensation
-    /// Command will fail if a TCXO is configured
+    /// Command is rejected by the chip if a TCXO is configured: return InvalidParam without sending it
     /// External NTC is important when the board does not have sufficient thermal break
     /// and/or when transmission is particularly long (more than 2s)
     pub async fn set_temp_comp(&mut self, mode: CompMode, ntc: bool) -> Result<(), Lr2021Error> {
+        if let Some((_volt, _)) = self.cache.tcxo && mode != CompMode::Disabled {
+            #[cfg(feature = "defmt")]{defmt::warn!("Temperature compensation not available with TCXO ({})", _volt);}
+            return Err(Lr2021Error::InvalidParam);
+        }
         let req = set_temp_comp_cfg_cmd(ntc, mode);
         self.cmd_wr(&req).await
     }
@@ -414,23 +815,39 @@ impl<O,SPI, M> Lr2021<O,SPI, M> where
     }
 
     /// Read nb32 qword (max 40) from memory and save them inside local buffer
+    /// Words are available big-endian in the local buffer after the two status bytes
     pub async fn rd_mem(&mut self, addr: u32, nb32: u8) -> Result<(), Lr2021Error> {
-        if nb32 > 40 {
+        if nb32 as usize > RD_MEM_MAX_WORDS {
             return Err(Lr2021Error::CmdErr);
         }
         let req = read_reg_mem32_req(addr, nb32);
         self.cmd_wr(&req).await?;
         self.wait_ready(Duration::from_millis(1)).await?;
-        self.nss.set_low().map_err(|_| Lr2021Error::Pin)?;
+        self.nss.set_low().map_err(Lr2021Error::pin)?;
         self.buffer.nop();
-        let rsp_buf = &mut self.buffer.0[..4*nb32 as usize];
+        let rsp_buf = self.buffer.frame_mut(2+4*nb32 as usize);
+        #[cfg(feature = "bus-stats")]
+        self.bus_stats.add_bytes(rsp_buf.len());
         self.spi
             .transfer_in_place(rsp_buf).await
-            .map_err(|_| Lr2021Error::Spi)?;
-        self.nss.set_high().map_err(|_| Lr2021Error::Pin)?;
+            .map_err(Lr2021Error::spi)?;
+        self.nss.set_high().map_err(Lr2021Error::pin)?;
         self.buffer.cmd_status().check()
     }
 
+    /// Read a block of 32-bit words from memory of any size, starting at addr
+    /// The read is split in chunks of 40 words and each word is converted from the big-endian SPI format
+    pub async fn rd_mem_into(&mut self, addr: u32, out: &mut [u32]) -> Result<(), Lr2021Error> {
+        for (i, chunk) in out.chunks_mut(RD_MEM_MAX_WORDS).enumerate() {
+            let chunk_addr = addr.wrapping_add((4 * i * RD_MEM_MAX_WORDS) as u32);
+            self.rd_mem(chunk_addr, chunk.len() as u8).await?;
+            for (w, bytes) in chunk.iter_mut().zip(self.buffer.data().chunks_exact(4)) {
+                *w = u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+            }
+        }
+        Ok(())
+    }
+
     /// Write a register value
     pub async fn wr_reg(&mut self, addr: u32, value: u32) -> Result<(), Lr2021Error> {
         let req = write_reg_mem32_cmd(addr, value);
@@ -443,6 +860,50 @@ impl<O,SPI, M> Lr2021<O,SPI, M> where
         self.cmd_wr(&req).await
     }
 
+    /// Write a block of bytes of any size and alignment to memory, starting at addr, and verify it by reading it back.
+    /// Memory is little-endian: the byte at addr+k is bits 8k..8k+7 of the word at addr.
+    /// Each word is written with a mask (partial words at the edges keep their other bytes)
+    /// and each chunk of 40 words is read back after being written.
+    /// Return CmdFail if the read-back differs from the data written
+    pub async fn wr_mem(&mut self, addr: u32, data: &[u8]) -> Result<(), Lr2021Error> {
+        let offset = (addr & 3) as usize;
+        let base = addr & !3;
+        let nb_words = (offset + data.len()).div_ceil(4);
+        let mut words = [(0u32, 0u32); RD_MEM_MAX_WORDS];
+        for chunk_start in (0..nb_words).step_by(RD_MEM_MAX_WORDS) {
+            let chunk_len = (nb_words - chunk_start).min(RD_MEM_MAX_WORDS);
+            let chunk_addr = base.wrapping_add(4 * chunk_start as u32);
+            // Build value and mask of each word
+            for (i, word) in words[..chunk_len].iter_mut().enumerate() {
+                *word = (0, 0);
+                for k in 0..4 {
+                    let pos = 4 * (chunk_start + i) + k;
+                    if let Some(&b) = pos.checked_sub(offset).and_then(|idx| data.get(idx)) {
+                        word.0 |= (b as u32) << (8 * k);
+                        word.1 |= 0xFF << (8 * k);
+                    }
+                }
+            }
+            for (i, &(value, mask)) in words[..chunk_len].iter().enumerate() {
+                let word_addr = chunk_addr.wrapping_add(4 * i as u32);
+                if mask == 0xFFFFFFFF {
+                    self.wr_reg(word_addr, value).await?;
+                } else {
+                    self.wr_reg_mask(word_addr, mask, value).await?;
+                }
+            }
+            // Verification read-back
+            self.rd_mem(chunk_addr, chunk_len as u8).await?;
+            for (&(value, mask), bytes) in words[..chunk_len].iter().zip(self.buffer.data().chunks_exact(4)) {
+                let rd = u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+                if (rd ^ value) & mask != 0 {
+                    return Err(Lr2021Error::CmdFail);
+                }
+            }
+        }
+        Ok(())
+    }
+
     /// Write a field value
     pub async fn wr_field(&mut self, addr: u32, value: u32, pos: u8, width: u8) -> Result<(), Lr2021Error> {
         let mask =
@@ -0,0 +1,186 @@
+//! # Telemetry sampler
+//!
+//! This module schedules periodic temperature and battery voltage measurements without interfering with the radio:
+//! a measurement is only done when the chip is not in RX or TX, so that housekeeping never delays packet handling.
+//!
+//! Samples are taken on a regular time grid: when the radio is busy at the scheduled time, the measurement is postponed
+//! until the radio is idle, up to a maximum jitter. Past this jitter the sample is skipped (and counted) and the
+//! next one is scheduled on the grid. Samples are buffered with their timestamp in a ring buffer
+//! (the oldest samples are overwritten when the application does not read them).
+//!
+//! ## Quick Start
+//!
+//! ```rust,no_run
+//! use lr2021::telemetry::TelemetrySampler;
+//! use lr2021::system::TempSrc;
+//!
+//! // One sample per minute, up to 5s late
+//! let mut sampler: TelemetrySampler<8> = TelemetrySampler::new(TempSrc::Vbe, Duration::from_secs(60), Duration::from_secs(5));
+//! loop {
+//!     // Radio handling ...
+//!     lr2021.telemetry_poll(&mut sampler).await.expect("Telemetry");
+//!     while let Some(s) = sampler.pop() {
+//!         info!("{}: {}°C, {}mV", s.time.as_millis(), s.temp >> 5, s.vbat_mv);
+//!     }
+//! }
+//! ```
+//!
+//! ## Available Methods
+//! - [`telemetry_poll`](Lr2021::telemetry_poll) - Take a sample if one is due and the radio is idle
+
+use embassy_time::{Duration, Instant};
+use embedded_hal::digital::OutputPin;
+use embedded_hal_async::spi::SpiBus;
+
+use super::status::ChipModeStatus;
+use super::system::{AdcRes, TempSrc};
+use super::{BusyPin, Lr2021, Lr2021Error};
+
+/// Telemetry sample
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct TelemetrySample {
+    /// Time of the measurement
+    pub time: Instant,
+    /// Temperature in °C with 5 fractional bits
+    pub temp: i16,
+    /// Battery voltage in mV
+    pub vbat_mv: u16,
+}
+
+/// Periodic telemetry sampler buffering up to N samples
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct TelemetrySampler<const N: usize> {
+    /// Temperature sensor
+    src: TempSrc,
+    /// ADC resolution
+    res: AdcRes,
+    /// Period between two samples
+    period: Duration,
+    /// Maximum delay of a sample when the radio is busy
+    max_jitter: Duration,
+    /// Scheduled time of the next sample
+    next: Instant,
+    /// Samples ring buffer
+    samples: [Option<TelemetrySample>; N],
+    /// Index of the oldest sample
+    head: usize,
+    /// Number of samples in the buffer
+    len: usize,
+    /// Number of samples skipped because the radio was busy
+    nb_skipped: u32,
+    /// Number of samples overwritten before being read
+    nb_overwritten: u32,
+}
+
+impl<const N: usize> TelemetrySampler<N> {
+    /// Create a sampler with a period and a maximum jitter: the first sample is due immediately
+    pub fn new(src: TempSrc, period: Duration, max_jitter: Duration) -> Self {
+        Self {
+            src, res: AdcRes::Res10bit, period, max_jitter,
+            next: Instant::now(),
+            samples: [None; N], head: 0, len: 0,
+            nb_skipped: 0, nb_overwritten: 0,
+        }
+    }
+
+    /// Set the ADC resolution used for the measurements (10 bits by default)
+    pub fn with_resolution(self, res: AdcRes) -> Self {
+        Self { res, ..self }
+    }
+
+    /// Scheduled time of the next sample: can be used to wake up the task polling the sampler
+    pub fn next_due(&self) -> Instant {
+        self.next
+    }
+
+    /// Number of samples buffered
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// No sample buffered
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Number of samples skipped because the radio stayed busy longer than the maximum jitter
+    pub fn nb_skipped(&self) -> u32 {
+        self.nb_skipped
+    }
+
+    /// Number of samples overwritten before being read
+    pub fn nb_overwritten(&self) -> u32 {
+        self.nb_overwritten
+    }
+
+    /// Most recent sample (kept in the buffer)
+    pub fn latest(&self) -> Option<TelemetrySample> {
+        if self.len == 0 || N == 0 {
+            return None;
+        }
+        self.samples[(self.head + self.len - 1) % N]
+    }
+
+    /// Remove and return the oldest sample
+    pub fn pop(&mut self) -> Option<TelemetrySample> {
+        if self.len == 0 {
+            return None;
+        }
+        let sample = self.samples[self.head].take();
+        self.head = (self.head + 1) % N;
+        self.len -= 1;
+        sample
+    }
+
+    /// Add a sample, overwriting the oldest one when the buffer is full
+    fn push(&mut self, sample: TelemetrySample) {
+        if N == 0 {
+            return;
+        }
+        if self.len == N {
+            self.head = (self.head + 1) % N;
+            self.len -= 1;
+            self.nb_overwritten = self.nb_overwritten.wrapping_add(1);
+        }
+        self.samples[(self.head + self.len) % N] = Some(sample);
+        self.len += 1;
+    }
+
+    /// Schedule the next sample on the time grid after now
+    fn schedule_next(&mut self, now: Instant) {
+        self.next += self.period;
+        if self.next <= now {
+            let late = (now - self.next).as_ticks() / self.period.as_ticks().max(1) + 1;
+            self.next += Duration::from_ticks(self.period.as_ticks() * late);
+        }
+    }
+}
+
+impl<O,SPI, M> Lr2021<O,SPI, M> where
+    O: OutputPin, SPI: SpiBus<u8>, M: BusyPin
+{
+    /// Take a temperature and battery voltage sample if one is due and the chip is not in RX or TX.
+    /// A sample delayed by more than the maximum jitter is skipped.
+    /// Return true when a sample was added
+    pub async fn telemetry_poll<const N: usize>(&mut self, sampler: &mut TelemetrySampler<N>) -> Result<bool, Lr2021Error> {
+        let now = Instant::now();
+        if now < sampler.next {
+            return Ok(false);
+        }
+        let (status, _) = self.get_status().await?;
+        if matches!(status.chip_mode(), ChipModeStatus::Rx | ChipModeStatus::Tx) {
+            if now - sampler.next > sampler.max_jitter {
+                sampler.nb_skipped = sampler.nb_skipped.wrapping_add(1);
+                sampler.schedule_next(now);
+            }
+            return Ok(false);
+        }
+        let temp = self.get_temperature(sampler.src, sampler.res).await?;
+        let vbat_mv = self.get_vbat(sampler.res).await?;
+        sampler.push(TelemetrySample { time: now, temp, vbat_mv });
+        sampler.schedule_next(now);
+        Ok(true)
+    }
+}
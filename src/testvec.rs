@@ -0,0 +1,317 @@
+//! # Test vectors for bench instruments
+//!
+//! This module generates the expected on-air content of a packet from a configuration and a payload,
+//! so that the demodulated output of a signal analyzer can be compared against it:
+//!  - FSK: the exact bit sequence (preamble, syncword, length header, payload and CRC, with whitening),
+//!    as written MSB first in a [`BitSeq`]
+//!  - LoRa: the nibble sequence before the forward error correction (explicit header with its checksum,
+//!    whitened payload and payload CRC). Hamming coding, interleaving and Gray mapping are not generated.
+//!
+//! All functions are pure computations: no command is sent to the chip.
+//!
+//! ## Quick Start
+//!
+//! ```rust,no_run
+//! use lr2021::testvec::{fsk_test_vector, lora_test_vector, FskVectorCfg};
+//! use lr2021::fsk::{Crc, WhitenType};
+//! use lr2021::syncword::Syncword;
+//!
+//! // FSK with a 16 bits syncword, CRC-16 CCITT and SX126x whitening
+//! let cfg = FskVectorCfg::new(Syncword::from_msb_bits(0xC194, 16))
+//!     .with_crc(Crc::Crc2ByteInv, 0x1021, 0x1D0F)
+//!     .with_whitening(WhitenType::Sx126xLr11xx, 0x01FF);
+//! let mut bits = [0u8; 64];
+//! let seq = fsk_test_vector(&cfg, b"hello", &mut bits).expect("Buffer too small");
+//! info!("{} bits: {:02x}", seq.nb_bits(), seq.as_bytes());
+//!
+//! // LoRa nibbles before FEC
+//! let mut nibbles = [0u8; 64];
+//! let nb = lora_test_vector(LoraCr::Cr1Ham45Si, HeaderType::Explicit, true, b"hello", &mut nibbles).expect("Invalid");
+//! ```
+//!
+//! ## Available Methods
+//! - [`fsk_test_vector`] - Expected FSK bit sequence of a packet
+//! - [`lora_test_vector`] - Expected LoRa nibble sequence of a packet (before FEC)
+//! - [`crc_msb`] - Generic MSB-first CRC (FSK/OOK CRC configuration)
+//! - [`lora_whiten`] - Apply the LoRa payload whitening
+//! - [`lora_header`] - LoRa explicit header nibbles with checksum
+//! - [`lora_payload_crc`] - LoRa payload CRC
+
+use super::ble::ble_whiten;
+use super::fsk::{Crc, FskPktFormat, WhitenType};
+use super::lora::{HeaderType, LoraCr};
+use super::syncword::Syncword;
+
+/// Sequence of bits stored MSB first in a byte buffer
+#[derive(Debug)]
+pub struct BitSeq<'a> {
+    buf: &'a mut [u8],
+    nb_bits: usize,
+}
+
+impl<'a> BitSeq<'a> {
+    /// Create an empty sequence using a buffer
+    pub fn new(buf: &'a mut [u8]) -> Self {
+        buf.fill(0);
+        Self { buf, nb_bits: 0 }
+    }
+
+    /// Number of bits in the sequence
+    pub fn nb_bits(&self) -> usize {
+        self.nb_bits
+    }
+
+    /// Bytes containing the sequence (the last byte is padded with 0)
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.buf[..self.nb_bits.div_ceil(8)]
+    }
+
+    /// Return bit at index i (first bit sent at index 0)
+    pub fn bit(&self, i: usize) -> Option<bool> {
+        if i >= self.nb_bits {
+            return None;
+        }
+        Some(self.buf[i / 8] & (0x80 >> (i % 8)) != 0)
+    }
+
+    /// Append the nb LSB of a value, MSB first. Return None if the buffer is full
+    pub fn push_bits(&mut self, value: u64, nb: u8) -> Option<()> {
+        if self.nb_bits + nb as usize > self.buf.len() * 8 {
+            return None;
+        }
+        for i in (0..nb).rev() {
+            if (value >> i) & 1 != 0 {
+                self.buf[self.nb_bits / 8] |= 0x80 >> (self.nb_bits % 8);
+            }
+            self.nb_bits += 1;
+        }
+        Some(())
+    }
+
+    /// Append bytes, MSB first. Return None if the buffer is full
+    pub fn push_bytes(&mut self, bytes: &[u8]) -> Option<()> {
+        bytes.iter().try_for_each(|&b| self.push_bits(b as u64, 8))
+    }
+}
+
+/// Generic CRC computed MSB first on width bits (8 to 32), without final inversion
+pub fn crc_msb(poly: u32, init: u32, width: u8, data: &[u8]) -> u32 {
+    let width = width.clamp(1, 32);
+    let mask = (1u64 << width) - 1;
+    let top = 1u64 << (width - 1);
+    let mut crc = init as u64 & mask;
+    for &byte in data {
+        for bit in (0..8).rev() {
+            let fb = (crc & top != 0) ^ ((byte >> bit) & 1 != 0);
+            crc = (crc << 1) & mask;
+            if fb {
+                crc ^= poly as u64 & mask;
+            }
+        }
+    }
+    crc as u32
+}
+
+/// Number of bytes and inversion of a FSK CRC configuration
+fn fsk_crc_len(crc: Crc) -> (u8, bool) {
+    let val = crc as u8;
+    (val & 0x7, val & 0x8 != 0)
+}
+
+/// Apply the SX126x/LR11xx compatible whitening (PN9: x^9 + x^5 + 1) on a buffer from a 9 bits seed
+fn pn9_whiten(init: u16, data: &mut [u8]) {
+    let mut lfsr = init & 0x1FF;
+    for byte in data.iter_mut() {
+        *byte ^= lfsr as u8;
+        for _ in 0..8 {
+            let fb = (lfsr ^ (lfsr >> 5)) & 1;
+            lfsr = (lfsr >> 1) | (fb << 8);
+        }
+    }
+}
+
+/// FSK packet configuration used to generate a test vector
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct FskVectorCfg {
+    /// Preamble length in bits (0x55 pattern)
+    pub pbl_len: u16,
+    /// Syncword
+    pub syncword: Syncword,
+    /// Length header format (Variable9bit is not supported)
+    pub pkt_format: FskPktFormat,
+    /// CRC length and inversion
+    pub crc: Crc,
+    /// CRC polynomial
+    pub crc_poly: u32,
+    /// CRC initial value
+    pub crc_init: u32,
+    /// Whitening type and initial value (None when disabled)
+    pub whitening: Option<(WhitenType, u16)>,
+}
+
+impl FskVectorCfg {
+    /// Configuration with a 32 bits preamble, a 8 bits length header, no CRC and no whitening
+    pub fn new(syncword: Syncword) -> Self {
+        Self {
+            pbl_len: 32, syncword,
+            pkt_format: FskPktFormat::Variable8bit,
+            crc: Crc::CrcOff, crc_poly: 0x1021, crc_init: 0x1D0F,
+            whitening: None,
+        }
+    }
+
+    /// Set the preamble length (in bits)
+    pub fn with_preamble(self, pbl_len: u16) -> Self {
+        Self { pbl_len, ..self }
+    }
+
+    /// Set the length header format
+    pub fn with_format(self, pkt_format: FskPktFormat) -> Self {
+        Self { pkt_format, ..self }
+    }
+
+    /// Enable the CRC with its polynomial and initial value
+    pub fn with_crc(self, crc: Crc, crc_poly: u32, crc_init: u32) -> Self {
+        Self { crc, crc_poly, crc_init, ..self }
+    }
+
+    /// Enable the whitening with its initial value
+    pub fn with_whitening(self, whiten_type: WhitenType, init: u16) -> Self {
+        Self { whitening: Some((whiten_type, init)), ..self }
+    }
+}
+
+/// Maximum number of bytes covered by the FSK whitening: 2 bytes header, payload and 4 bytes CRC
+const FSK_VECTOR_MAX_BYTES: usize = 2 + 255 + 4;
+
+/// Generate the expected FSK bit sequence of a packet (up to 255 bytes of payload):
+/// preamble, syncword, then the length header, payload and CRC (whitened when enabled).
+/// The CRC covers the header and payload and is computed before whitening.
+/// Return None if the buffer is too small or the configuration is not supported
+pub fn fsk_test_vector<'a>(cfg: &FskVectorCfg, payload: &[u8], buf: &'a mut [u8]) -> Option<BitSeq<'a>> {
+    if payload.len() > 255 {
+        return None;
+    }
+    let mut frame = [0u8; FSK_VECTOR_MAX_BYTES];
+    let len = payload.len();
+    let hdr_len = match cfg.pkt_format {
+        FskPktFormat::FixedLength => 0,
+        FskPktFormat::Variable8bit => {
+            frame[0] = len as u8;
+            1
+        }
+        FskPktFormat::Variable16bit => {
+            frame[..2].copy_from_slice(&(len as u16).to_be_bytes());
+            2
+        }
+        FskPktFormat::Variable9bit => return None,
+    };
+    frame[hdr_len..hdr_len + len].copy_from_slice(payload);
+    let mut nb = hdr_len + len;
+    let (crc_len, crc_inv) = fsk_crc_len(cfg.crc);
+    if crc_len > 0 {
+        let width = crc_len * 8;
+        let mut crc = crc_msb(cfg.crc_poly, cfg.crc_init, width, &frame[..nb]);
+        if crc_inv {
+            crc ^= ((1u64 << width) - 1) as u32;
+        }
+        let crc_bytes = crc.to_be_bytes();
+        frame[nb..nb + crc_len as usize].copy_from_slice(&crc_bytes[4 - crc_len as usize..]);
+        nb += crc_len as usize;
+    }
+    match cfg.whitening {
+        Some((WhitenType::Sx126xLr11xx, init)) => pn9_whiten(init, &mut frame[..nb]),
+        Some((WhitenType::Sx128x, init)) => ble_whiten(init as u8, &mut frame[..nb]),
+        None => {}
+    }
+    let mut seq = BitSeq::new(buf);
+    for i in 0..cfg.pbl_len {
+        seq.push_bits(if i % 2 == 0 {0} else {1}, 1)?;
+    }
+    seq.push_bits(cfg.syncword.msb_bits(), cfg.syncword.nb_bits())?;
+    seq.push_bytes(&frame[..nb])?;
+    Some(seq)
+}
+
+/// Apply the LoRa payload whitening (LFSR x^8 + x^6 + x^5 + x^4 + 1, seed 0xFF)
+/// Whitening is its own inverse: the same function de-whitens a received payload
+pub fn lora_whiten(data: &mut [u8]) {
+    let mut lfsr: u8 = 0xFF;
+    for byte in data.iter_mut() {
+        *byte ^= lora_whit_next(&mut lfsr);
+    }
+}
+
+/// Return the current LoRa whitening byte and advance the LFSR
+fn lora_whit_next(lfsr: &mut u8) -> u8 {
+    let cur = *lfsr;
+    let fb = ((cur >> 7) ^ (cur >> 5) ^ (cur >> 4) ^ (cur >> 3)) & 1;
+    *lfsr = (cur << 1) | fb;
+    cur
+}
+
+/// LoRa explicit header nibbles: payload length (MSB nibble first), coding rate and CRC flag, 5 bits checksum
+/// Return None for coding rates without a standard header value (long interleaving and convolutional codes)
+pub fn lora_header(payload_len: u8, cr: LoraCr, crc_en: bool) -> Option<[u8; 5]> {
+    if !matches!(cr, LoraCr::Cr1Ham45Si | LoraCr::Cr2Ham23Si | LoraCr::Cr3Ham47Si | LoraCr::Cr4Ham12Si) {
+        return None;
+    }
+    let n0 = payload_len >> 4;
+    let n1 = payload_len & 0xF;
+    let n2 = ((cr as u8) << 1) | crc_en as u8;
+    let b = |n: u8, i: u8| (n >> i) & 1;
+    let c4 = b(n0, 3) ^ b(n0, 2) ^ b(n0, 1) ^ b(n0, 0);
+    let c3 = b(n0, 3) ^ b(n1, 3) ^ b(n1, 2) ^ b(n1, 1) ^ b(n2, 0);
+    let c2 = b(n0, 2) ^ b(n1, 3) ^ b(n1, 0) ^ b(n2, 3) ^ b(n2, 1);
+    let c1 = b(n0, 1) ^ b(n1, 2) ^ b(n1, 0) ^ b(n2, 2) ^ b(n2, 1) ^ b(n2, 0);
+    let c0 = b(n0, 0) ^ b(n1, 1) ^ b(n2, 3) ^ b(n2, 2) ^ b(n2, 1) ^ b(n2, 0);
+    Some([n0, n1, n2, c4, (c3 << 3) | (c2 << 2) | (c1 << 1) | c0])
+}
+
+/// LoRa payload CRC: CRC-16 CCITT (init 0) on the payload minus its last two bytes,
+/// XORed with these two bytes. The result is sent LSB first after the payload
+pub fn lora_payload_crc(payload: &[u8]) -> u16 {
+    let n = payload.len();
+    match n {
+        0 => 0,
+        1 => payload[0] as u16,
+        _ => {
+            let crc = crc_msb(0x1021, 0, 16, &payload[..n - 2]) as u16;
+            crc ^ u16::from_be_bytes([payload[n - 2], payload[n - 1]])
+        }
+    }
+}
+
+/// Generate the expected LoRa nibble sequence of a packet before forward error correction:
+/// header nibbles (explicit header only), whitened payload (LSB nibble first) and payload CRC (when enabled, not whitened).
+/// Return the number of nibbles written, or None if the buffer is too small or the coding rate is not supported
+pub fn lora_test_vector(cr: LoraCr, header_type: HeaderType, crc_en: bool, payload: &[u8], nibbles: &mut [u8]) -> Option<usize> {
+    if payload.len() > 255 {
+        return None;
+    }
+    let mut nb = 0;
+    let mut push = |n: u8, nb: &mut usize| -> Option<()> {
+        *nibbles.get_mut(*nb)? = n & 0xF;
+        *nb += 1;
+        Some(())
+    };
+    if header_type == HeaderType::Explicit {
+        for n in lora_header(payload.len() as u8, cr, crc_en)? {
+            push(n, &mut nb)?;
+        }
+    }
+    let mut lfsr: u8 = 0xFF;
+    for &byte in payload {
+        let w = byte ^ lora_whit_next(&mut lfsr);
+        push(w & 0xF, &mut nb)?;
+        push(w >> 4, &mut nb)?;
+    }
+    if crc_en {
+        for b in lora_payload_crc(payload).to_le_bytes() {
+            push(b & 0xF, &mut nb)?;
+            push(b >> 4, &mut nb)?;
+        }
+    }
+    Some(nb)
+}
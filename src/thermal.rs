@@ -0,0 +1,180 @@
+//! # Thermal protection
+//!
+//! This module provides a guard for sustained transmissions (LR-FHSS, long packets, test modes, ...)
+//! in enclosures with poor heat dissipation.
+//! The [`ThermalGuard`] tracks the TX on-time and periodically samples the temperature during transmission:
+//!  - above the warning temperature, the TX power is reduced by a configurable step (down to a minimum power)
+//!  - above the maximum temperature, the transmission is aborted (chip set in standby)
+//!
+//! Each decision is reported to the application as a [`ThermalEvent`].
+//! The new TX power is configured immediately but the chip only uses it for the next transmission.
+//!
+//! ## Quick Start
+//!
+//! ```rust,no_run
+//! use lr2021::thermal::{ThermalGuard, ThermalEvent};
+//! use lr2021::system::TempSrc;
+//!
+//! // Reduce power by 1dB above 85°C, abort above 105°C, check every 100ms
+//! let mut guard = ThermalGuard::new(TempSrc::Vbe, 85, 105, Duration::from_millis(100)).with_power_step(2, 0);
+//! lr2021.thermal_tx_start(&mut guard, 44, RampTime::Ramp16u).await.expect("SetTx");
+//! lr2021.set_tx(0).await.expect("SetTx");
+//! match lr2021.thermal_wait_tx_done(&mut guard).await.expect("WaitTx") {
+//!     ThermalEvent::Aborted(t) => warn!("TX aborted at {}°C", t),
+//!     evt => info!("TX done: {} (on-time {}ms)", evt, guard.on_time().as_millis()),
+//! }
+//! ```
+//!
+//! ## Available Methods
+//! - [`thermal_tx_start`](Lr2021::thermal_tx_start) - Configure the TX power and start tracking the TX on-time
+//! - [`thermal_check`](Lr2021::thermal_check) - Sample the temperature and apply the protection
+//! - [`thermal_wait_tx_done`](Lr2021::thermal_wait_tx_done) - Wait for the end of transmission while applying the protection
+
+use embassy_time::{Duration, Instant, Timer};
+use embedded_hal::digital::OutputPin;
+use embedded_hal_async::spi::SpiBus;
+
+use super::radio::RampTime;
+use super::system::{AdcRes, ChipMode, TempSrc};
+use super::{BusyPin, Lr2021, Lr2021Error};
+
+/// Decision of the thermal guard
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ThermalEvent {
+    /// Temperature (in °C) below the warning threshold
+    Normal(i16),
+    /// TX power reduced (new power in half-dB) because temperature (in °C) exceeded the warning threshold
+    PowerReduced(i8, i16),
+    /// TX aborted because temperature (in °C) exceeded the maximum
+    Aborted(i16),
+}
+
+/// Thermal protection for sustained transmissions
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ThermalGuard {
+    /// Temperature sensor
+    src: TempSrc,
+    /// Temperature (in °C) above which the TX power is reduced
+    warn_temp: i16,
+    /// Temperature (in °C) above which the transmission is aborted
+    max_temp: i16,
+    /// Period between two temperature checks
+    period: Duration,
+    /// TX power reduction step (in half-dB)
+    power_step: u8,
+    /// Minimum TX power (in half-dB)
+    min_power: i8,
+    /// Current TX power (in half-dB)
+    tx_power: i8,
+    /// Ramp time used for the TX power configuration
+    ramp_time: RampTime,
+    /// Start of the current transmission
+    tx_start: Option<Instant>,
+    /// Accumulated TX on-time
+    on_time: Duration,
+    /// Maximum temperature measured (in °C)
+    peak_temp: i16,
+}
+
+impl ThermalGuard {
+    /// Create a thermal guard with a warning and maximum temperature in °C, checked periodically
+    /// By default the power is reduced by 1dB steps without minimum
+    pub fn new(src: TempSrc, warn_temp: i16, max_temp: i16, period: Duration) -> Self {
+        Self {
+            src, warn_temp, max_temp: max_temp.max(warn_temp), period,
+            power_step: 2, min_power: i8::MIN,
+            tx_power: 0, ramp_time: RampTime::Ramp208u,
+            tx_start: None, on_time: Duration::from_ticks(0), peak_temp: i16::MIN,
+        }
+    }
+
+    /// Set the TX power reduction step and the minimum TX power (in half-dB)
+    pub fn with_power_step(self, power_step: u8, min_power: i8) -> Self {
+        Self { power_step, min_power, ..self }
+    }
+
+    /// Current TX power (in half-dB)
+    pub fn tx_power(&self) -> i8 {
+        self.tx_power
+    }
+
+    /// Accumulated TX on-time (including the ongoing transmission)
+    pub fn on_time(&self) -> Duration {
+        match self.tx_start {
+            Some(start) => self.on_time + start.elapsed(),
+            None => self.on_time,
+        }
+    }
+
+    /// Stop tracking the TX on-time: must be called at the end of transmission if not using `thermal_wait_tx_done`
+    pub fn tx_done(&mut self) {
+        if let Some(start) = self.tx_start.take() {
+            self.on_time += start.elapsed();
+        }
+    }
+
+    /// Reset the accumulated TX on-time
+    pub fn clear_on_time(&mut self) {
+        self.on_time = Duration::from_ticks(0);
+    }
+
+    /// Maximum temperature measured (in °C)
+    pub fn peak_temp(&self) -> Option<i16> {
+        if self.peak_temp == i16::MIN {None} else {Some(self.peak_temp)}
+    }
+}
+
+impl<O,SPI, M> Lr2021<O,SPI, M> where
+    O: OutputPin, SPI: SpiBus<u8>, M: BusyPin
+{
+    /// Configure the TX power (in half-dB) and start tracking the TX on-time
+    /// Must be called just before starting the transmission
+    pub async fn thermal_tx_start(&mut self, guard: &mut ThermalGuard, tx_power: i8, ramp_time: RampTime) -> Result<(), Lr2021Error> {
+        self.set_tx_params(tx_power, ramp_time).await?;
+        guard.tx_power = tx_power;
+        guard.ramp_time = ramp_time;
+        guard.tx_start = Some(Instant::now());
+        Ok(())
+    }
+
+    /// Sample the temperature and apply the protection: reduce the TX power or abort the transmission
+    pub async fn thermal_check(&mut self, guard: &mut ThermalGuard) -> Result<ThermalEvent, Lr2021Error> {
+        let temp = self.get_temperature(guard.src, AdcRes::Res10bit).await? >> 5;
+        guard.peak_temp = guard.peak_temp.max(temp);
+        if temp >= guard.max_temp {
+            self.set_chip_mode(ChipMode::StandbyRc).await?;
+            guard.tx_done();
+            return Ok(ThermalEvent::Aborted(temp));
+        }
+        if temp >= guard.warn_temp && guard.tx_power > guard.min_power {
+            let power = guard.tx_power.saturating_sub(guard.power_step as i8).max(guard.min_power);
+            self.set_tx_params(power, guard.ramp_time).await?;
+            guard.tx_power = power;
+            return Ok(ThermalEvent::PowerReduced(power, temp));
+        }
+        Ok(ThermalEvent::Normal(temp))
+    }
+
+    /// Wait for the end of the transmission, checking the temperature periodically
+    /// Return the last decision of the guard (the TX done interrupt is not cleared)
+    pub async fn thermal_wait_tx_done(&mut self, guard: &mut ThermalGuard) -> Result<ThermalEvent, Lr2021Error> {
+        let mut event = ThermalEvent::Normal(guard.peak_temp().unwrap_or(0));
+        loop {
+            let (_, intr) = self.get_status().await?;
+            if intr.tx_done() {
+                guard.tx_done();
+                return Ok(event);
+            }
+            let check = self.thermal_check(guard).await?;
+            match (check, event) {
+                (ThermalEvent::Aborted(_), _) => return Ok(check),
+                // Keep the information of power reduction until the end of transmission
+                (ThermalEvent::Normal(t), ThermalEvent::PowerReduced(p, _)) => event = ThermalEvent::PowerReduced(p, t),
+                _ => event = check,
+            }
+            Timer::after(guard.period).await;
+        }
+    }
+}
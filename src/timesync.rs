@@ -0,0 +1,221 @@
+//! # Time distribution over LoRa
+//!
+//! This module provides a simple time distribution service between a master node and one or more slave nodes.
+//! The master periodically sends a small sync packet and the slaves estimate the offset and drift
+//! between their local time base and the master one.
+//!
+//! The precise time of transmission/reception is obtained from the chip timestamp (HF clock, 32MHz):
+//! the event time is computed from the current time minus the number of ticks elapsed since the TxDone/RxDone event,
+//! making the estimation independent of the latency of the interrupt handling.
+//! Since the exact TX time of a packet is only known once it has been sent, each sync packet carries
+//! the TX time of the previous one (two-step synchronization).
+//!
+//! For hardware-level synchronization (DIO pulse on the responder a fixed delay after reception),
+//! use [`set_lora_timing_sync`](Lr2021::set_lora_timing_sync) and [`set_lora_timing_sync_pulse`](Lr2021::set_lora_timing_sync_pulse).
+//!
+//! ## Quick Start
+//!
+//! ```rust,no_run
+//! use lr2021::timesync::{TimeSyncMaster, TimeSyncSlave};
+//! use lr2021::radio::{TimestampIndex, TimestampSource};
+//!
+//! // Master: send a sync packet periodically
+//! let mut master = TimeSyncMaster::new(TimestampIndex::Ts0);
+//! lr2021.set_timestamp_source(TimestampIndex::Ts0, TimestampSource::TxDone).await.expect("SetTs");
+//! loop {
+//!     lr2021.time_sync_send(&mut master, 0).await.expect("TimeSyncSend");
+//!     // Wait for TxDone interrupt ...
+//!     lr2021.time_sync_tx_done(&mut master).await.expect("TimeSyncTxDone");
+//!     Timer::after_secs(10).await;
+//! }
+//!
+//! // Slave: on each RxDone, update the estimation
+//! let mut slave = TimeSyncSlave::new(TimestampIndex::Ts0);
+//! lr2021.set_timestamp_source(TimestampIndex::Ts0, TimestampSource::RxDone).await.expect("SetTs");
+//! // Wait for RxDone interrupt ...
+//! if lr2021.time_sync_rx_done(&mut slave).await.expect("TimeSyncRxDone") {
+//!     info!("Offset = {}us, drift = {}ppb", slave.offset_us(), slave.drift_ppb());
+//! }
+//! ```
+//!
+//! ## Available Methods
+//! - [`get_timestamp_instant`](Lr2021::get_timestamp_instant) - Return the local time at which a timestamp event occurred
+//! - [`time_sync_send`](Lr2021::time_sync_send) - Send a time synchronization packet (master)
+//! - [`time_sync_tx_done`](Lr2021::time_sync_tx_done) - Capture the TX time of the last sync packet (master)
+//! - [`time_sync_rx_done`](Lr2021::time_sync_rx_done) - Process a received sync packet (slave)
+
+use embassy_time::{Duration, Instant};
+use embedded_hal::digital::OutputPin;
+use embedded_hal_async::spi::SpiBus;
+
+use super::radio::TimestampIndex;
+use super::{BusyPin, Lr2021, Lr2021Error};
+
+/// Identifier of a time synchronization packet
+pub const TIME_SYNC_ID: u8 = 0x54;
+/// Length of a time synchronization packet: ID, sequence number and TX time of previous packet (u64 in us)
+pub const TIME_SYNC_LEN: usize = 10;
+
+/// Number of HF clock ticks per microsecond
+const HF_TICKS_PER_US: u32 = 32;
+
+/// Master side of the time synchronization
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct TimeSyncMaster {
+    /// Timestamp index configured with source TxDone
+    ts_index: TimestampIndex,
+    /// Sequence number of the next sync packet
+    seq: u8,
+    /// TX time of the last sync packet (in us)
+    last_tx: Option<u64>,
+}
+
+impl TimeSyncMaster {
+    /// Create the master, using a timestamp index configured with source TxDone
+    pub fn new(ts_index: TimestampIndex) -> Self {
+        Self { ts_index, seq: 0, last_tx: None }
+    }
+
+    /// Build the next sync packet
+    pub fn packet(&self) -> [u8; TIME_SYNC_LEN] {
+        let mut pkt = [0; TIME_SYNC_LEN];
+        pkt[0] = TIME_SYNC_ID;
+        pkt[1] = self.seq;
+        pkt[2..].copy_from_slice(&self.last_tx.unwrap_or(0).to_le_bytes());
+        pkt
+    }
+
+    /// Record the TX time of the sync packet just sent
+    pub fn tx_done(&mut self, tx_time: Instant) {
+        self.last_tx = Some(tx_time.as_micros());
+        self.seq = self.seq.wrapping_add(1);
+    }
+}
+
+/// Slave side of the time synchronization
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct TimeSyncSlave {
+    /// Timestamp index configured with source RxDone
+    ts_index: TimestampIndex,
+    /// Fixed latency (in us) between TxDone on the master and RxDone on the slave
+    latency: i32,
+    /// Sequence number and RX time (in us) of the last sync packet
+    last_rx: Option<(u8, u64)>,
+    /// Last estimation: local time (in us) and offset to the master (in us)
+    estimate: Option<(u64, i64)>,
+    /// Drift estimation in part-per-billion
+    drift: i32,
+    /// Number of estimation updates
+    nb_update: u32,
+}
+
+impl TimeSyncSlave {
+    /// Create the slave, using a timestamp index configured with source RxDone
+    pub fn new(ts_index: TimestampIndex) -> Self {
+        Self { ts_index, latency: 0, last_rx: None, estimate: None, drift: 0, nb_update: 0 }
+    }
+
+    /// Set the fixed latency (in us) between the end of transmission on the master and RxDone on the slave
+    /// This is mainly the demodulation latency which depends on the modulation parameters
+    pub fn with_latency(self, latency: i32) -> Self {
+        Self { latency, ..self }
+    }
+
+    /// Process a received sync packet
+    /// Return true if the offset estimation was updated
+    pub fn rx_done(&mut self, pkt: &[u8], rx_time: Instant) -> bool {
+        if pkt.len() != TIME_SYNC_LEN || pkt[0] != TIME_SYNC_ID {
+            return false;
+        }
+        let seq = pkt[1];
+        let master_tx = u64::from_le_bytes([pkt[2], pkt[3], pkt[4], pkt[5], pkt[6], pkt[7], pkt[8], pkt[9]]);
+        let rx_time = rx_time.as_micros();
+        let prev = self.last_rx.replace((seq, rx_time));
+        // The packet carries the TX time of the previous packet: only valid if no packet was lost
+        let Some((prev_seq, prev_rx)) = prev else {
+            return false;
+        };
+        if master_tx == 0 || prev_seq.wrapping_add(1) != seq {
+            return false;
+        }
+        let offset = master_tx as i64 + self.latency as i64 - prev_rx as i64;
+        if let Some((est_time, est_offset)) = self.estimate && prev_rx > est_time {
+            let drift = (offset - est_offset) * 1_000_000_000 / (prev_rx - est_time) as i64;
+            self.drift = drift.clamp(i32::MIN as i64, i32::MAX as i64) as i32;
+        }
+        self.estimate = Some((prev_rx, offset));
+        self.nb_update += 1;
+        true
+    }
+
+    /// Flag if an offset estimation is available
+    pub fn is_synced(&self) -> bool {
+        self.estimate.is_some()
+    }
+
+    /// Offset (in us) to add to the local time to get the master time, at the time of the last estimation
+    pub fn offset_us(&self) -> i64 {
+        self.estimate.map(|(_, o)| o).unwrap_or(0)
+    }
+
+    /// Drift of the master clock relative to the local clock in part-per-billion
+    /// Only valid after at least two estimations
+    pub fn drift_ppb(&self) -> i32 {
+        self.drift
+    }
+
+    /// Number of offset estimation updates
+    pub fn nb_update(&self) -> u32 {
+        self.nb_update
+    }
+
+    /// Convert a local time to the master time (in us), compensating the drift since the last estimation
+    pub fn to_master_us(&self, local: Instant) -> Option<u64> {
+        let (est_time, offset) = self.estimate?;
+        let local = local.as_micros();
+        let elapsed = local as i64 - est_time as i64;
+        let drift = elapsed * self.drift as i64 / 1_000_000_000;
+        Some((local as i64 + offset + drift) as u64)
+    }
+}
+
+impl<O,SPI, M> Lr2021<O,SPI, M> where
+    O: OutputPin, SPI: SpiBus<u8>, M: BusyPin
+{
+    /// Return the local time at which the event associated to a timestamp occurred
+    pub async fn get_timestamp_instant(&mut self, index: TimestampIndex) -> Result<Instant, Lr2021Error> {
+        let now = Instant::now();
+        let ticks = self.get_timestamp(index).await?;
+        Ok(now - Duration::from_micros((ticks / HF_TICKS_PER_US) as u64))
+    }
+
+    /// Send a time synchronization packet
+    /// Packet type and modulation must be configured before
+    pub async fn time_sync_send(&mut self, master: &mut TimeSyncMaster, tx_timeout: u32) -> Result<(), Lr2021Error> {
+        let pkt = master.packet();
+        self.tx_payload(&pkt, tx_timeout).await
+    }
+
+    /// Capture the TX time of the sync packet: must be called after TxDone and before the next transmission
+    pub async fn time_sync_tx_done(&mut self, master: &mut TimeSyncMaster) -> Result<(), Lr2021Error> {
+        let tx_time = self.get_timestamp_instant(master.ts_index).await?;
+        master.tx_done(tx_time);
+        Ok(())
+    }
+
+    /// Read a received packet and update the time synchronization estimation: must be called after RxDone
+    /// Return true if the estimation was updated
+    pub async fn time_sync_rx_done(&mut self, slave: &mut TimeSyncSlave) -> Result<bool, Lr2021Error> {
+        let rx_time = self.get_timestamp_instant(slave.ts_index).await?;
+        let len = self.get_rx_fifo_lvl().await? as usize;
+        if len != TIME_SYNC_LEN {
+            self.clear_rx_fifo().await?;
+            return Ok(false);
+        }
+        let mut pkt = [0; TIME_SYNC_LEN];
+        self.rd_rx_fifo_to(&mut pkt).await?;
+        Ok(slave.rx_done(&pkt, rx_time))
+    }
+}
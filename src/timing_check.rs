@@ -0,0 +1,163 @@
+//! # Protocol timing conformance checks
+//!
+//! This module (feature `timing-check`) measures the time between two radio events with the chip timestamps
+//! and checks it against the requirement of a protocol: BLE inter-frame spacing (T_IFS),
+//! Zigbee acknowledgment turnaround or Z-Wave acknowledgment turnaround.
+//! This is mainly used to validate that the host side of a responder (automatic RX/TX, ACK sent by the MCU, ...)
+//! meets the specification on a given MCU.
+//!
+//! The measure uses two timestamps (by default RxDone on Ts0 and TxDone on Ts1): the duration of the response
+//! is given by the application to get the time between the end of the reception and the start of the transmission.
+//! The first timestamp is read twice around the second one to compensate the time between the two SPI reads,
+//! giving a resolution of a few HF clock cycles.
+//!
+//! Each violation is logged (with the `defmt` feature) and counted.
+//!
+//! ## Quick Start
+//!
+//! ```rust,no_run
+//! use lr2021::timing_check::{TimingCheck, BLE_T_IFS};
+//!
+//! let mut check = TimingCheck::rx_to_tx(BLE_T_IFS);
+//! lr2021.timing_check_start(&check).await.expect("TimingStart");
+//! // Receive a packet and answer with a response lasting 376us ...
+//! let t = lr2021.timing_check_measure(&mut check, 376).await.expect("TimingMeasure");
+//! info!("T_IFS = {}ns (ok={})", t.measured_ns, t.ok);
+//! ```
+//!
+//! ## Available Methods
+//! - [`timing_check_start`](Lr2021::timing_check_start) - Configure the timestamps used by a check
+//! - [`timing_check_measure`](Lr2021::timing_check_measure) - Measure the time between the two events and check it
+
+use embedded_hal::digital::OutputPin;
+use embedded_hal_async::spi::SpiBus;
+
+use super::radio::{TimestampIndex, TimestampSource};
+use super::{BusyPin, Lr2021, Lr2021Error};
+
+/// Number of HF clock ticks per microsecond
+const HF_TICKS_PER_US: i64 = 32;
+
+/// Timing requirement of a protocol
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct TimingSpec {
+    /// Name of the requirement (for logging)
+    pub name: &'static str,
+    /// Minimum duration (in us)
+    pub min_us: u32,
+    /// Maximum duration (in us)
+    pub max_us: u32,
+}
+
+/// BLE inter-frame spacing: 150us +/- 2us
+pub const BLE_T_IFS: TimingSpec = TimingSpec { name: "BLE T_IFS", min_us: 148, max_us: 152 };
+/// Zigbee (802.15.4 O-QPSK) acknowledgment: starts aTurnaroundTime (12 symbols) after the frame,
+/// and before the end of the backoff period (32 symbols)
+pub const ZIGBEE_ACK_TURNAROUND: TimingSpec = TimingSpec { name: "Zigbee ACK turnaround", min_us: 192, max_us: 512 };
+/// Z-Wave acknowledgment: RX to TX turnaround below 1ms
+pub const ZWAVE_ACK_TURNAROUND: TimingSpec = TimingSpec { name: "Z-Wave ACK turnaround", min_us: 0, max_us: 1000 };
+
+/// Result of a timing measure
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct TimingResult {
+    /// Duration measured (in ns)
+    pub measured_ns: i32,
+    /// Duration within the requirement
+    pub ok: bool,
+}
+
+/// Timing check between two events
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct TimingCheck {
+    /// Requirement checked
+    pub spec: TimingSpec,
+    /// Timestamp and source of the first event
+    pub first: (TimestampIndex, TimestampSource),
+    /// Timestamp and source of the second event
+    pub second: (TimestampIndex, TimestampSource),
+    /// Number of measures
+    nb_checked: u32,
+    /// Number of violations
+    nb_violations: u32,
+    /// Minimum and maximum duration measured (in ns)
+    range_ns: Option<(i32, i32)>,
+}
+
+impl TimingCheck {
+    /// Check between two events, each one using a different timestamp index
+    pub fn new(spec: TimingSpec, first: (TimestampIndex, TimestampSource), second: (TimestampIndex, TimestampSource)) -> Self {
+        Self { spec, first, second, nb_checked: 0, nb_violations: 0, range_ns: None }
+    }
+
+    /// Check between the end of a reception (RxDone on Ts0) and the start of the response (TxDone on Ts1)
+    pub fn rx_to_tx(spec: TimingSpec) -> Self {
+        Self::new(spec, (TimestampIndex::Ts0, TimestampSource::RxDone), (TimestampIndex::Ts1, TimestampSource::TxDone))
+    }
+
+    /// Number of measures
+    pub fn nb_checked(&self) -> u32 {
+        self.nb_checked
+    }
+
+    /// Number of violations
+    pub fn nb_violations(&self) -> u32 {
+        self.nb_violations
+    }
+
+    /// Minimum and maximum duration measured (in ns)
+    pub fn range_ns(&self) -> Option<(i32, i32)> {
+        self.range_ns
+    }
+
+    /// Reset the statistics
+    pub fn clear(&mut self) {
+        self.nb_checked = 0;
+        self.nb_violations = 0;
+        self.range_ns = None;
+    }
+
+    /// Check a duration (in ns) against the requirement and update the statistics
+    pub fn check(&mut self, measured_ns: i32) -> TimingResult {
+        let ok = measured_ns >= self.spec.min_us as i32 * 1000 && measured_ns <= self.spec.max_us as i32 * 1000;
+        self.nb_checked = self.nb_checked.wrapping_add(1);
+        self.range_ns = Some(match self.range_ns {
+            Some((min, max)) => (min.min(measured_ns), max.max(measured_ns)),
+            None => (measured_ns, measured_ns),
+        });
+        if !ok {
+            self.nb_violations = self.nb_violations.wrapping_add(1);
+            #[cfg(feature = "defmt")]{defmt::warn!("{} violation: {}ns not in [{}, {}]us", self.spec.name, measured_ns, self.spec.min_us, self.spec.max_us);}
+        }
+        TimingResult { measured_ns, ok }
+    }
+}
+
+impl<O,SPI, M> Lr2021<O,SPI, M> where
+    O: OutputPin, SPI: SpiBus<u8>, M: BusyPin
+{
+    /// Configure the timestamps used by a check
+    pub async fn timing_check_start(&mut self, check: &TimingCheck) -> Result<(), Lr2021Error> {
+        if check.first.0 == check.second.0 {
+            return Err(Lr2021Error::InvalidParam);
+        }
+        self.set_timestamp_source(check.first.0, check.first.1).await?;
+        self.set_timestamp_source(check.second.0, check.second.1).await
+    }
+
+    /// Measure the time between the two events of a check, minus the duration (in us) of the second event
+    /// (typically the duration of the response packet when the second event is TxDone), and check it.
+    /// Must be called after the second event
+    pub async fn timing_check_measure(&mut self, check: &mut TimingCheck, duration_us: u32) -> Result<TimingResult, Lr2021Error> {
+        // Timestamps give the number of ticks elapsed until the read: reading the first one
+        // before and after the second one removes the delay between the reads
+        let first_a = self.get_timestamp(check.first.0).await? as i64;
+        let second = self.get_timestamp(check.second.0).await? as i64;
+        let first_b = self.get_timestamp(check.first.0).await? as i64;
+        let delta_ticks = (first_a + first_b) / 2 - second;
+        let measured_ns = delta_ticks * 1000 / HF_TICKS_PER_US - duration_us as i64 * 1000;
+        Ok(check.check(measured_ns.clamp(i32::MIN as i64, i32::MAX as i64) as i32))
+    }
+}
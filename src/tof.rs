@@ -0,0 +1,181 @@
+//! # Coarse ranging by time of flight
+//!
+//! This module provides a coarse two-way ranging for FSK/FLRC links (or any packet type) where the LoRa ranging
+//! is not applicable, using only the chip timestamps:
+//!  - the responder pre-loads its response and answers automatically a fixed delay after the RxDone of the request
+//!    ([`tof_respond`](Lr2021::tof_respond)), so that its turnaround does not depend on the MCU
+//!  - the initiator sends the request, switches automatically to RX after TxDone and measures the time
+//!    between its TxDone and the RxDone of the response ([`tof_request`](Lr2021::tof_request))
+//!
+//! The round-trip time minus a constant offset (responder delay, response time on air, processing delays of both chips)
+//! is twice the time of flight. The offset is best obtained by a calibration at a known distance
+//! ([`TofRanging::calibrate`]). With the HF clock resolution (31.25ns, i.e. ~4.7m) and the jitter of the
+//! packet detection, the accuracy is in the order of tens of meters: averaging many exchanges improves it.
+//!
+//! ## Quick Start
+//!
+//! ```rust,no_run
+//! use lr2021::tof::TofRanging;
+//!
+//! // Responder: answer each request 1000 ticks after RxDone
+//! lr2021.tof_respond(b"pong", 1000, 0).await.expect("TofRespond");
+//!
+//! // Initiator: calibrate once at 10m, then measure
+//! let mut tof = TofRanging::new(0);
+//! lr2021.tof_start(&tof).await.expect("TofStart");
+//! if let Some(res) = lr2021.tof_request(&mut tof, b"ping", Duration::from_millis(50)).await.expect("TofRequest") {
+//!     tof.calibrate(res.round_ns, 10);
+//! }
+//! if let Some(res) = lr2021.tof_request(&mut tof, b"ping", Duration::from_millis(50)).await.expect("TofRequest") {
+//!     info!("Distance {}m (average {}m)", res.distance_m, tof.average_m());
+//! }
+//! ```
+//!
+//! ## Available Methods
+//! - [`tof_start`](Lr2021::tof_start) - Configure the timestamps used by the initiator
+//! - [`tof_request`](Lr2021::tof_request) - Send a request, receive the response and estimate the distance (initiator)
+//! - [`tof_respond`](Lr2021::tof_respond) - Arm an automatic response a fixed delay after the next request (responder)
+//! - [`TofRanging::calibrate`] - Compute the offset from a measure at a known distance
+
+use embassy_time::{Duration, Instant, Timer};
+use embedded_hal::digital::OutputPin;
+use embedded_hal_async::spi::SpiBus;
+
+use super::radio::{AutoTxrxMode, TimestampIndex, TimestampSource};
+use super::status::{Intr, IRQ_MASK_CRC_ERROR, IRQ_MASK_LEN_ERROR, IRQ_MASK_RX_DONE, IRQ_MASK_TIMEOUT, IRQ_MASK_TX_DONE};
+use super::system::ChipMode;
+use super::{BusyPin, Lr2021, Lr2021Error};
+
+/// Speed of light in m/us
+const SPEED_OF_LIGHT_M_PER_US: i64 = 299_792_458 / 1_000_000;
+
+/// Interrupts ending the reception of the response
+const TOF_RX_MASK: u32 = IRQ_MASK_RX_DONE | IRQ_MASK_CRC_ERROR | IRQ_MASK_LEN_ERROR | IRQ_MASK_TIMEOUT;
+
+/// Result of an exchange
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct TofResult {
+    /// Time between the TxDone of the request and the RxDone of the response (in ns)
+    pub round_ns: i32,
+    /// Estimated distance (in m, 0 if the round-trip time is below the offset)
+    pub distance_m: u32,
+}
+
+/// Coarse ranging state of the initiator
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct TofRanging {
+    /// Round-trip time at distance 0 (in ns): responder delay, response time on air and processing delays
+    pub offset_ns: i32,
+    /// Timestamp capturing the TxDone of the request
+    pub ts_tx: TimestampIndex,
+    /// Timestamp capturing the RxDone of the response
+    pub ts_rx: TimestampIndex,
+    /// Sum of the distances measured
+    sum_m: u64,
+    /// Number of distances measured
+    nb: u32,
+}
+
+impl TofRanging {
+    /// Ranging with a known offset (in ns), using Ts0 for TxDone and Ts1 for RxDone
+    pub fn new(offset_ns: i32) -> Self {
+        Self { offset_ns, ts_tx: TimestampIndex::Ts0, ts_rx: TimestampIndex::Ts1, sum_m: 0, nb: 0 }
+    }
+
+    /// Use other timestamps (must be different)
+    pub fn with_timestamps(self, ts_tx: TimestampIndex, ts_rx: TimestampIndex) -> Self {
+        Self { ts_tx, ts_rx, ..self }
+    }
+
+    /// Compute the offset from a round-trip time measured at a known distance (in m), and clear the average
+    pub fn calibrate(&mut self, round_ns: i32, distance_m: u32) {
+        let tof2_ns = 2 * distance_m as i64 * 1000 / SPEED_OF_LIGHT_M_PER_US;
+        self.offset_ns = (round_ns as i64 - tof2_ns).clamp(i32::MIN as i64, i32::MAX as i64) as i32;
+        self.clear();
+    }
+
+    /// Distance (in m) corresponding to a round-trip time (in ns)
+    pub fn distance_m(&self, round_ns: i32) -> u32 {
+        let tof2_ns = (round_ns as i64 - self.offset_ns as i64).max(0);
+        (tof2_ns * SPEED_OF_LIGHT_M_PER_US / 2000) as u32
+    }
+
+    /// Average distance (in m) since the last clear, 0 if no measure
+    pub fn average_m(&self) -> u32 {
+        (self.sum_m / self.nb.max(1) as u64) as u32
+    }
+
+    /// Number of measures since the last clear
+    pub fn nb_measures(&self) -> u32 {
+        self.nb
+    }
+
+    /// Clear the average
+    pub fn clear(&mut self) {
+        self.sum_m = 0;
+        self.nb = 0;
+    }
+}
+
+impl<O,SPI, M> Lr2021<O,SPI, M> where
+    O: OutputPin, SPI: SpiBus<u8>, M: BusyPin
+{
+    /// Configure the timestamps used by the initiator
+    pub async fn tof_start(&mut self, tof: &TofRanging) -> Result<(), Lr2021Error> {
+        if tof.ts_tx == tof.ts_rx {
+            return Err(Lr2021Error::InvalidParam);
+        }
+        self.set_timestamp_source(tof.ts_tx, TimestampSource::TxDone).await?;
+        self.set_timestamp_source(tof.ts_rx, TimestampSource::RxDone).await
+    }
+
+    /// Send a request, switch automatically to RX after TxDone and wait for the response until the timeout.
+    /// The response is left in the RX FIFO. Return None if no valid response was received before the timeout
+    pub async fn tof_request(&mut self, tof: &mut TofRanging, payload: &[u8], timeout: Duration) -> Result<Option<TofResult>, Lr2021Error> {
+        self.clear_rx_fifo().await?;
+        self.clear_irqs(Intr::new(TOF_RX_MASK | IRQ_MASK_TX_DONE)).await?;
+        self.set_auto_rxtx(true, AutoTxrxMode::Always, 0, 0).await?;
+        self.tx_payload(payload, 0).await?;
+        let start = Instant::now();
+        let intr = loop {
+            let (_, intr) = self.get_status().await?;
+            if intr.intr_match(TOF_RX_MASK) {
+                break intr;
+            }
+            if start.elapsed() >= timeout {
+                self.set_chip_mode(ChipMode::StandbyRc).await?;
+                return Ok(None);
+            }
+            Timer::after_micros(100).await;
+        };
+        self.clear_irqs(Intr::new(TOF_RX_MASK | IRQ_MASK_TX_DONE)).await?;
+        if !intr.rx_done() || intr.intr_match(IRQ_MASK_CRC_ERROR | IRQ_MASK_LEN_ERROR) {
+            return Ok(None);
+        }
+        // Timestamps give the number of ticks elapsed until the read: reading the first one
+        // before and after the second one removes the delay between the reads
+        let tx_a = self.get_timestamp(tof.ts_tx).await? as i64;
+        let rx = self.get_timestamp(tof.ts_rx).await? as i64;
+        let tx_b = self.get_timestamp(tof.ts_tx).await? as i64;
+        // HF clock at 32MHz: 31.25ns per tick
+        let round_ns = ((tx_a + tx_b) / 2 - rx) * 125 / 4;
+        let round_ns = round_ns.clamp(i32::MIN as i64, i32::MAX as i64) as i32;
+        let distance_m = tof.distance_m(round_ns);
+        tof.sum_m += distance_m as u64;
+        tof.nb = tof.nb.saturating_add(1);
+        Ok(Some(TofResult { round_ns, distance_m }))
+    }
+
+    /// Arm an automatic response: the response is written in the TX FIFO and sent `delay` after the RxDone
+    /// of the next valid request (delay as in [`set_auto_rxtx`](Lr2021::set_auto_rxtx)), then RX is started.
+    /// The packet length of the response must match the packet parameters.
+    /// Timeout is given in LF clock step (1/32.768kHz ~ 30.5us)
+    pub async fn tof_respond(&mut self, response: &[u8], delay: u32, rx_timeout: u32) -> Result<(), Lr2021Error> {
+        self.clear_tx_fifo().await?;
+        self.wr_tx_fifo_from(response).await?;
+        self.set_auto_rxtx(true, AutoTxrxMode::RxOk, 0, delay).await?;
+        self.set_rx(rx_timeout, true).await
+    }
+}
@@ -0,0 +1,146 @@
+//! # Wake-on-preamble
+//!
+//! This module provides helpers for the low-power flow where the MCU deep-sleeps while the radio
+//! runs a duty-cycled reception, and a DIO wakes the MCU up when a preamble is detected.
+//!
+//! The DIO is configured as an IRQ line for PreambleDetected and the end of reception events:
+//! as any IRQ line it stays asserted until the interrupts are cleared, so the MCU can not miss the event
+//! even with a long wake-up time.
+//! On wake-up, a single call to [`wake_on_preamble_service`](Lr2021::wake_on_preamble_service) reads the status,
+//! drains the packet if it is complete, and re-arms the duty-cycled RX when the reception is over.
+//!
+//! ## Quick Start
+//!
+//! ```rust,no_run
+//! use lr2021::wake::{WakeOnPreamble, WakeEvent};
+//! use lr2021::system::DioNum;
+//!
+//! // Listen 1ms every 100ms
+//! let wop = WakeOnPreamble::new(DioNum::Dio9, 32_000, 3_200_000);
+//! lr2021.set_wake_on_preamble(&wop).await.expect("SetWakeOnPreamble");
+//! loop {
+//!     // MCU deep sleep until DIO9 rises
+//!     irq.wait_for_high().await;
+//!     match lr2021.wake_on_preamble_service(&wop).await.expect("Service") {
+//!         WakeEvent::Packet(len) => info!("Received {:02x}", &lr2021.buffer()[..len]),
+//!         // Packet still being received: sleep until RxDone asserts the DIO again
+//!         WakeEvent::InProgress => {},
+//!         WakeEvent::Error(intr) => warn!("RX error {}", intr),
+//!         WakeEvent::Spurious => {},
+//!     }
+//! }
+//! ```
+//!
+//! ## Available Methods
+//! - [`set_wake_on_preamble`](Lr2021::set_wake_on_preamble) - Configure the wake-up DIO and start the duty-cycled RX
+//! - [`wake_on_preamble_rearm`](Lr2021::wake_on_preamble_rearm) - Restart the duty-cycled RX
+//! - [`wake_on_preamble_service`](Lr2021::wake_on_preamble_service) - Handle a wake-up: read status, drain the packet or re-arm RX
+
+use embedded_hal::digital::OutputPin;
+use embedded_hal_async::spi::SpiBus;
+
+use super::status::{Intr, IRQ_MASK_ADDR_ERROR, IRQ_MASK_CRC_ERROR, IRQ_MASK_HEADER_ERR, IRQ_MASK_LEN_ERROR, IRQ_MASK_PREAMBLE_DETECTED, IRQ_MASK_RX_DONE, IRQ_MASK_TIMEOUT};
+use super::system::DioNum;
+use super::{BusyPin, Lr2021, Lr2021Error, BUFFER_SIZE};
+
+/// Interrupts asserting the wake-up DIO
+pub const IRQ_MASK_WAKE_ON_PREAMBLE : u32 =
+    IRQ_MASK_PREAMBLE_DETECTED | IRQ_MASK_RX_DONE | IRQ_MASK_TIMEOUT |
+    IRQ_MASK_HEADER_ERR | IRQ_MASK_CRC_ERROR | IRQ_MASK_LEN_ERROR | IRQ_MASK_ADDR_ERROR;
+
+/// Interrupts flagging a failed reception
+const IRQ_MASK_RX_ERROR : u32 = IRQ_MASK_HEADER_ERR | IRQ_MASK_CRC_ERROR | IRQ_MASK_LEN_ERROR | IRQ_MASK_ADDR_ERROR;
+
+/// Wake-on-preamble configuration
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct WakeOnPreamble {
+    /// DIO connected to the MCU wake-up pin
+    pub dio: DioNum,
+    /// Listen time of each RX window (in HF tick)
+    pub listen_time: u32,
+    /// Period of the RX windows (in HF tick)
+    pub cycle_time: u32,
+    /// Perform a CAD instead of a RX (LoRa only)
+    pub use_lora_cad: bool,
+    /// Bit mask of DRAM to keep in retention during sleep
+    pub dram_ret: u8,
+}
+
+impl WakeOnPreamble {
+    /// Wake-on-preamble configuration using RX (no CAD) and no DRAM retention
+    pub fn new(dio: DioNum, listen_time: u32, cycle_time: u32) -> Self {
+        Self { dio, listen_time, cycle_time, use_lora_cad: false, dram_ret: 0 }
+    }
+
+    /// Use a LoRa CAD instead of a RX to detect activity
+    pub fn with_lora_cad(self, use_lora_cad: bool) -> Self {
+        Self { use_lora_cad, ..self }
+    }
+
+    /// Set the DRAM retention mask
+    pub fn with_dram_ret(self, dram_ret: u8) -> Self {
+        Self { dram_ret, ..self }
+    }
+}
+
+/// Event handled on wake-up
+#[derive(Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum WakeEvent {
+    /// Packet received and available in the local buffer (length in bytes)
+    Packet(usize),
+    /// Preamble detected, packet still being received
+    InProgress,
+    /// Reception failed (CRC, length, header or address error) or timed out
+    Error(Intr),
+    /// No relevant interrupt pending
+    Spurious,
+}
+
+impl<O,SPI, M> Lr2021<O,SPI, M> where
+    O: OutputPin, SPI: SpiBus<u8>, M: BusyPin
+{
+    /// Configure the wake-up DIO and start the duty-cycled RX
+    /// Packet type, modulation and packet parameters must be configured before
+    pub async fn set_wake_on_preamble(&mut self, cfg: &WakeOnPreamble) -> Result<(), Lr2021Error> {
+        self.set_dio_irq(cfg.dio, Intr::new(IRQ_MASK_WAKE_ON_PREAMBLE)).await?;
+        self.clear_irqs(Intr::new(IRQ_MASK_WAKE_ON_PREAMBLE)).await?;
+        self.wake_on_preamble_rearm(cfg).await
+    }
+
+    /// Restart the duty-cycled RX
+    pub async fn wake_on_preamble_rearm(&mut self, cfg: &WakeOnPreamble) -> Result<(), Lr2021Error> {
+        self.set_rx_duty_cycle(cfg.listen_time, cfg.cycle_time, cfg.use_lora_cad, cfg.dram_ret).await
+    }
+
+    /// Handle a wake-up from the DIO: read the status, drain the packet if complete (into the local buffer)
+    /// and re-arm the duty-cycled RX once the reception is over.
+    /// All wake-up interrupts handled are cleared, releasing the DIO.
+    pub async fn wake_on_preamble_service(&mut self, cfg: &WakeOnPreamble) -> Result<WakeEvent, Lr2021Error> {
+        let (_, intr) = self.get_status().await?;
+        let pending = intr.value() & IRQ_MASK_WAKE_ON_PREAMBLE;
+        if pending != 0 {
+            self.clear_irqs(Intr::new(pending)).await?;
+        }
+        let event = if intr.intr_match(IRQ_MASK_RX_ERROR) || intr.timeout() {
+            self.clear_rx_fifo().await?;
+            WakeEvent::Error(intr)
+        } else if intr.rx_done() {
+            let len = self.get_rx_fifo_lvl().await? as usize;
+            if len > BUFFER_SIZE {
+                self.clear_rx_fifo().await?;
+                self.wake_on_preamble_rearm(cfg).await?;
+                return Err(Lr2021Error::InvalidSize);
+            }
+            self.rd_rx_fifo(len).await?;
+            WakeEvent::Packet(len)
+        } else if intr.preamble_detected() {
+            return Ok(WakeEvent::InProgress);
+        } else {
+            WakeEvent::Spurious
+        };
+        self.wake_on_preamble_rearm(cfg).await?;
+        Ok(event)
+    }
+}
@@ -15,6 +15,9 @@
 //!
 //! // Set packet type to WiSUN
 //! lr2021.set_packet_type(PacketType::Wisun).await.expect("SetPktType");
+//! // North America, operating class 2, channel 10
+//! let (mode, freq) = phy_config(WisunRegDomain::NorthAmerica, 2, 10).expect("Invalid channel");
+//! lr2021.set_rf(freq).await.expect("SetRf");
 //! lr2021.set_wisun_modulation(mode, RxBw::BwAuto).await.expect("SetModulation");
 //! let params = WisunPacketParams::new_data(12, WisunFec::Nrnsc, WisunFcsLen::Fcs16b);
 //! lr2021.set_wisun_packet(params).await.expect("SetPktParams");
@@ -26,8 +29,11 @@
 //!
 //! - [`set_wisun_modulation`](Lr2021::set_wisun_modulation) - Set Wisun packet parameters: preamble, Bandwidth, Payload length, Address filtering
 //! - [`set_wisun_packet`](Lr2021::set_wisun_packet) - Set Wisun packet parameters: preamble, Bandwidth, Payload length, Address filtering
+//! - [`set_wisun_packet_len`](Lr2021::set_wisun_packet_len) - Set only the frame length for transmission
 //! - [`get_wisun_packet_status`](Lr2021::get_wisun_packet_status) - Return info about last packet received: length, CRC error per block, RSSI, LQI
 //! - [`get_wisun_rx_stats`](Lr2021::get_wisun_rx_stats) - Return basic RX stats
+//!
+//! The functions [`phy_config`] and [`chan_plan`] map the Wi-SUN FAN operating classes to the chip mode and RF frequency.
 
 use embedded_hal::digital::OutputPin;
 use embedded_hal_async::spi::SpiBus;
@@ -85,6 +91,73 @@ impl WisunPacketParams {
     }
 }
 
+/// Wi-SUN FAN regulatory domain
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum WisunRegDomain {
+    /// North America (902-928MHz)
+    NorthAmerica = 0x01,
+    /// Japan (920-928MHz)
+    Japan = 0x02,
+    /// Europe (863-876MHz)
+    Europe = 0x03,
+    /// India (865-867MHz)
+    India = 0x05,
+    /// Brazil (902-928MHz, same channel plan as North America)
+    Brazil = 0x07,
+}
+
+/// Channel plan of a Wi-SUN FAN operating class
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct WisunChanPlan {
+    /// Center frequency of channel 0 (in Hz)
+    pub ch0_hz: u32,
+    /// Channel spacing (in Hz)
+    pub spacing_hz: u32,
+    /// Number of channels
+    pub nb_chan: u16,
+    /// PHY modes allowed in this operating class (first one is the default)
+    pub modes: &'static [WisunMode],
+}
+
+impl WisunChanPlan {
+    /// Center frequency of a channel (in Hz), None if the channel does not exist
+    pub fn freq(&self, channel: u16) -> Option<u32> {
+        (channel < self.nb_chan).then(|| self.ch0_hz + self.spacing_hz * channel as u32)
+    }
+}
+
+/// Channel plan of an operating class in a regulatory domain (Wi-SUN FAN PHY, FSK operating modes)
+/// Return None for an unknown operating class
+pub fn chan_plan(reg_domain: WisunRegDomain, operating_class: u8) -> Option<WisunChanPlan> {
+    use {WisunMode::*, WisunRegDomain::*};
+    let (ch0_hz, spacing_hz, nb_chan, modes) : (u32, u32, u16, &'static [WisunMode]) = match (reg_domain, operating_class) {
+        (NorthAmerica | Brazil, 1) => (902_200_000, 200_000, 129, &[Mode1b]),
+        (NorthAmerica | Brazil, 2) => (902_400_000, 400_000,  64, &[Mode2a, Mode3]),
+        (NorthAmerica | Brazil, 3) => (902_600_000, 600_000,  42, &[Mode5]),
+        (Japan, 1)  => (920_600_000, 200_000, 38, &[Mode1b]),
+        (Japan, 2)  => (920_900_000, 400_000, 18, &[Mode2b]),
+        (Japan, 3)  => (920_800_000, 600_000, 12, &[Mode4b]),
+        (Europe, 1) => (863_100_000, 100_000, 69, &[Mode1a]),
+        (Europe, 2) => (863_100_000, 200_000, 35, &[Mode2a, Mode3]),
+        (Europe, 3) => (870_100_000, 100_000, 55, &[Mode1a]),
+        (Europe, 4) => (870_200_000, 200_000, 27, &[Mode2a, Mode3]),
+        (India, 1)  => (865_100_000, 100_000, 19, &[Mode1a]),
+        (India, 2)  => (865_100_000, 200_000, 10, &[Mode2a, Mode3]),
+        _ => return None,
+    };
+    Some(WisunChanPlan { ch0_hz, spacing_hz, nb_chan, modes })
+}
+
+/// Return the default PHY mode and RF frequency (in Hz) of a channel in a Wi-SUN FAN operating class
+/// Return None for an unknown operating class or a channel outside the channel plan
+/// Note: channels excluded by the regulatory domain (e.g. in Brazil) are not filtered
+pub fn phy_config(reg_domain: WisunRegDomain, operating_class: u8, channel: u16) -> Option<(WisunMode, u32)> {
+    let plan = chan_plan(reg_domain, operating_class)?;
+    Some((plan.modes[0], plan.freq(channel)?))
+}
+
 impl<O,SPI, M> Lr2021<O,SPI, M> where
     O: OutputPin, SPI: SpiBus<u8>, M: BusyPin
 {
@@ -101,7 +174,14 @@ impl<O,SPI, M> Lr2021<O,SPI, M> where
         self.cmd_wr(&req).await
     }
 
+    /// Sets the frame length for TX (or header value for mode switch packets) without calling set_wisun_packet
+    pub async fn set_wisun_packet_len(&mut self, frame_len_tx: u16) -> Result<(), Lr2021Error> {
+        let req = set_wisun_packet_len_cmd(frame_len_tx);
+        self.cmd_wr(&req).await
+    }
+
     /// Return info about last packet received: length, CRC error per block, RSSI, LQI
+    /// RSSI values are raw: use [`get_packet_status`](Lr2021::get_packet_status) or [`rssi_calibrate`](Lr2021::rssi_calibrate) to apply the RSSI calibration
     pub async fn get_wisun_packet_status(&mut self) -> Result<WisunPacketStatusRsp, Lr2021Error> {
         let req = get_wisun_packet_status_req();
         let mut rsp = WisunPacketStatusRsp::new();
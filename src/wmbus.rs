@@ -27,17 +27,26 @@
 //!
 //! - [`set_wmbus_packet`](Lr2021::set_wmbus_packet) - Set Wmbus packet parameters: preamble, Bandwidth, Payload length, Address filtering
 //! - [`set_wmbus_address`](Lr2021::set_wmbus_address) - Configure the node address for address filtering
+//! - [`wmbus_tx_frame`](Lr2021::wmbus_tx_frame) - Send a frame, updating the packet type and parameters if needed
 //! - [`get_wmbus_packet_status`](Lr2021::get_wmbus_packet_status) - Return info about last packet received: length, CRC error per block, RSSI, LQI
 //! - [`get_wmbus_rx_stats`](Lr2021::get_wmbus_rx_stats) - Return basic RX stats
+//!
+//! ### Meter whitelist (feature `heapless`)
+//! The chip filters a single address: a gateway listening to many meters can instead check the received frames
+//! against a [`WmbusWhitelist`] of up to N A-fields, with counters of the frames accepted and filtered.
+//! - [`wmbus_rx_filtered`](Lr2021::wmbus_rx_filtered) - Read the last frame received and check its A-field against a whitelist
 
 use embedded_hal::digital::OutputPin;
 use embedded_hal_async::spi::SpiBus;
+#[cfg(feature = "heapless")]
+use heapless::Vec;
 
 pub use super::cmd::cmd_wmbus::*;
+use super::radio::PacketType;
 use super::{BusyPin, Lr2021, Lr2021Error, RxBw};
 
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 /// W-MBus Packet parameters
 pub struct WmbusPacketParams {
@@ -97,6 +106,120 @@ impl WmbusPacketParams {
             ..self
         }
     }
+
+    /// Set payload length (TX) or maximum L-field accepted (RX)
+    pub fn with_pld_len(self, pld_len: u8) -> Self {
+        Self {
+            pld_len,
+            ..self
+        }
+    }
+
+    /// Payload length (TX) or maximum L-field accepted (RX)
+    pub fn pld_len(&self) -> u8 {
+        self.pld_len
+    }
+}
+
+/// Offset of the A-field in a received frame (after the C-field and M-field)
+const WMBUS_A_FIELD_OFFSET: usize = 3;
+
+/// A-field (identification number, version, device type) of a frame starting with the C-field
+pub fn wmbus_a_field(frame: &[u8]) -> Option<[u8; 6]> {
+    frame.get(WMBUS_A_FIELD_OFFSET..WMBUS_A_FIELD_OFFSET+6)?.try_into().ok()
+}
+
+/// Counters of the whitelist filtering
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct WmbusFilterStats {
+    /// Frames accepted
+    pub accepted: u32,
+    /// Frames from a meter not in the whitelist
+    pub filtered: u32,
+    /// Frames too short to contain an A-field
+    pub invalid: u32,
+}
+
+/// Whitelist of meter A-fields (as transmitted: identification number LSB first, version, device type)
+#[cfg(feature = "heapless")]
+#[derive(Debug, Default, Clone)]
+pub struct WmbusWhitelist<const N: usize> {
+    addrs: Vec<[u8; 6], N>,
+    stats: WmbusFilterStats,
+}
+
+#[cfg(feature = "heapless")]
+impl<const N: usize> WmbusWhitelist<N> {
+    /// Create an empty whitelist
+    pub fn new() -> Self {
+        Self { addrs: Vec::new(), stats: WmbusFilterStats::default() }
+    }
+
+    /// Add an A-field: return false if already present, InvalidSize if the whitelist is full
+    pub fn insert(&mut self, addr: [u8; 6]) -> Result<bool, Lr2021Error> {
+        if self.contains(&addr) {
+            return Ok(false);
+        }
+        self.addrs.push(addr).map_err(|_| Lr2021Error::InvalidSize)?;
+        Ok(true)
+    }
+
+    /// Remove an A-field: return false if not present
+    pub fn remove(&mut self, addr: &[u8; 6]) -> bool {
+        match self.addrs.iter().position(|a| a == addr) {
+            Some(idx) => {
+                self.addrs.swap_remove(idx);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Check if an A-field is in the whitelist
+    pub fn contains(&self, addr: &[u8; 6]) -> bool {
+        self.addrs.contains(addr)
+    }
+
+    /// Number of A-fields in the whitelist
+    pub fn len(&self) -> usize {
+        self.addrs.len()
+    }
+
+    /// Whitelist empty
+    pub fn is_empty(&self) -> bool {
+        self.addrs.is_empty()
+    }
+
+    /// Remove all A-fields (counters are kept)
+    pub fn clear(&mut self) {
+        self.addrs.clear();
+    }
+
+    /// Check a frame (starting with the C-field) and update the counters
+    pub fn check(&mut self, frame: &[u8]) -> bool {
+        let Some(addr) = wmbus_a_field(frame) else {
+            self.stats.invalid = self.stats.invalid.wrapping_add(1);
+            return false;
+        };
+        if self.contains(&addr) {
+            self.stats.accepted = self.stats.accepted.wrapping_add(1);
+            true
+        } else {
+            self.stats.filtered = self.stats.filtered.wrapping_add(1);
+            false
+        }
+    }
+
+    /// Counters of frames accepted and filtered
+    pub fn stats(&self) -> WmbusFilterStats {
+        self.stats
+    }
+
+    /// Reset the counters
+    pub fn clear_stats(&mut self) {
+        self.stats = WmbusFilterStats::default();
+    }
 }
 
 impl<O,SPI, M> Lr2021<O,SPI, M> where
@@ -106,7 +229,28 @@ impl<O,SPI, M> Lr2021<O,SPI, M> where
     /// Set Wmbus packet parameters: preamble, Bandwidth, Payload length, Address filtering
     pub async fn set_wmbus_packet(&mut self, params: WmbusPacketParams) -> Result<(), Lr2021Error> {
         let req = set_wmbus_params_cmd(params.mode, params.rx_bw, params.pkt_format_tx, params.addr_filt_en, params.pld_len, params.pbl_len_tx, params.pbl_len_detect);
-        self.cmd_wr(&req).await
+        self.cmd_wr(&req).await?;
+        self.cache.wmbus_packet = Some(params);
+        Ok(())
+    }
+
+    /// Send a W-MBus frame: payload starts with the C-field (C, M, A, CI fields and data, without L-field and CRCs)
+    /// The packet engine generates the mode specific preamble and syncword, the L-field, the CRCs
+    /// and applies the encoding of the mode (3-out-of-6 for Mode T, NRZ/Manchester for the others)
+    /// Packet type and packet parameters are updated if needed: settings from the last `set_wmbus_packet` are kept when the mode matches
+    pub async fn wmbus_tx_frame(&mut self, mode: WmbusMode, format: WmbusFormat, payload: &[u8]) -> Result<(), Lr2021Error> {
+        let pld_len = u8::try_from(payload.len()).map_err(|_| Lr2021Error::InvalidSize)?;
+        if self.cache.packet_type != Some(PacketType::Wmbus) {
+            self.set_packet_type(PacketType::Wmbus).await?;
+        }
+        let params = match self.cache.wmbus_packet {
+            Some(params) if params.mode == mode => WmbusPacketParams { pkt_format_tx: format, ..params },
+            _ => WmbusPacketParams::new(mode, format, pld_len),
+        };
+        if self.cache.wmbus_packet.is_none_or(|p| p.mode != mode || p.pkt_format_tx != format) {
+            self.set_wmbus_packet(params).await?;
+        }
+        self.tx_payload(payload, 0).await
     }
 
     /// Configure the node address for address filtering
@@ -116,6 +260,7 @@ impl<O,SPI, M> Lr2021<O,SPI, M> where
     }
 
     /// Return info about last packet received: length, CRC error per block, RSSI, LQI
+    /// RSSI values are raw: use [`get_packet_status`](Lr2021::get_packet_status) or [`rssi_calibrate`](Lr2021::rssi_calibrate) to apply the RSSI calibration
     pub async fn get_wmbus_packet_status(&mut self) -> Result<WmbusPacketStatusRsp, Lr2021Error> {
         let req = get_wmbus_packet_status_req();
         let mut rsp = WmbusPacketStatusRsp::new();
@@ -131,4 +276,18 @@ impl<O,SPI, M> Lr2021<O,SPI, M> where
         Ok(rsp)
     }
 
+    /// Read the last frame received (starting with the C-field) and check its A-field against the whitelist
+    /// Return the frame length if accepted, None if filtered (the frame is removed from the FIFO in both cases)
+    /// An InvalidSize error is returned if the buffer is too small
+    #[cfg(feature = "heapless")]
+    pub async fn wmbus_rx_filtered<const N: usize>(&mut self, whitelist: &mut WmbusWhitelist<N>, buf: &mut [u8]) -> Result<Option<usize>, Lr2021Error> {
+        let len = self.get_rx_pkt_len().await? as usize;
+        if len > buf.len() {
+            self.clear_rx_fifo().await?;
+            return Err(Lr2021Error::InvalidSize);
+        }
+        self.rd_rx_fifo_to(&mut buf[..len]).await?;
+        Ok(whitelist.check(&buf[..len]).then_some(len))
+    }
+
 }
\ No newline at end of file
@@ -27,14 +27,350 @@
 //! - [`set_zigbee_address`](Lr2021::set_zigbee_address) - Configure the different Zigbee addresses for filtering in RX.
 //! - [`get_zigbee_packet_status`](Lr2021::get_zigbee_packet_status) - Return length of last packet received
 //! - [`get_zigbee_rx_stats`](Lr2021::get_zigbee_rx_stats) - Return basic RX stats
+//! - [`zigbee_ed`](Lr2021::zigbee_ed) - Run an energy detection and return the ED level (0-255)
+//! - [`zigbee_cca`](Lr2021::zigbee_cca) - Run a Clear Channel Assessment following IEEE 802.15.4 CCA modes
+//! - [`zigbee_energy_scan`](Lr2021::zigbee_energy_scan) - Run an energy detection scan over a list of 2.4GHz channels
+//! - [`zigbee_read_frame`](Lr2021::zigbee_read_frame) - Read a received frame with its FCS status, whatever the FCS mode
+//! - [`zigbee_fcs_stats`](Lr2021::zigbee_fcs_stats) - Return the number of frames checked and FCS errors detected by software
+//! - [`clear_zigbee_fcs_stats`](Lr2021::clear_zigbee_fcs_stats) - Reset the software FCS statistics
+//!
+//! [`MacFrame`] parses the MAC header of a received frame (frame control, sequence number, PAN IDs and addresses)
+//! and builds headers for TX: the addresses extracted from actual traffic can be used for
+//! [`set_zigbee_address`](Lr2021::set_zigbee_address) and [`MacFrame::ack`] builds the acknowledgment of a frame.
+//!
+//! With `FcsMode::FcsInFifo`, the FCS is not checked by the chip: [`zigbee_verify_fcs`] checks it on the host
+//! and [`zigbee_read_frame`](Lr2021::zigbee_read_frame) reports the result like a hardware CRC check.
 //!
 
+use embassy_time::{Duration, Timer};
 use embedded_hal::digital::OutputPin;
 use embedded_hal_async::spi::SpiBus;
 
 pub use super::cmd::cmd_zigbee::*;
+use super::radio::PktStatus;
+use super::status::{Intr, IRQ_MASK_PREAMBLE_DETECTED, IRQ_MASK_TIMEOUT};
+use super::system::ChipMode;
 use super::{BusyPin, Lr2021, Lr2021Error, RxBw};
 
+/// Power (in dBm) corresponding to an ED level of 0
+pub const ZIGBEE_ED_MIN_DBM: i16 = -100;
+/// Duration of 8 symbols (128us) in CCA steps (31.25ns)
+const ZIGBEE_CCA_DURATION: u32 = 4096;
+/// Duration of 8 symbols (128us) rounded up in LF clock steps (30.5us)
+const ZIGBEE_CS_TIMEOUT: u32 = 5;
+
+/// First 2.4GHz channel (2405MHz)
+pub const ZIGBEE_CH_FIRST: u8 = 11;
+/// Last 2.4GHz channel (2480MHz)
+pub const ZIGBEE_CH_LAST: u8 = 26;
+/// Maximum CCA duration in CCA steps (31.25ns) on 24 bits (~524ms)
+const ZIGBEE_CCA_MAX_DURATION: u32 = 0xFF_FFFF;
+
+/// Length of the 802.15.4 FCS
+pub const ZIGBEE_FCS_LEN: usize = 2;
+
+/// Compute the 802.15.4 FCS of a frame: ITU-T CRC-16 (x^16+x^12+x^5+1), initial value 0, bit-reversed (CRC-16/KERMIT)
+pub fn zigbee_fcs(data: &[u8]) -> u16 {
+    data.iter().fold(0u16, |crc, &byte| {
+        (0..8).fold(crc ^ byte as u16, |crc, _| if crc & 1 != 0 {(crc >> 1) ^ 0x8408} else {crc >> 1})
+    })
+}
+
+/// Check the FCS of a frame including its 2 FCS bytes (little-endian, as received in the FIFO)
+pub fn zigbee_verify_fcs(frame: &[u8]) -> bool {
+    let Some(pld_len) = frame.len().checked_sub(ZIGBEE_FCS_LEN) else {
+        return false;
+    };
+    let fcs = u16::from_le_bytes([frame[pld_len], frame[pld_len + 1]]);
+    zigbee_fcs(&frame[..pld_len]) == fcs
+}
+
+/// Statistics of the FCS checked by software (`FcsMode::FcsInFifo`)
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ZigbeeFcsStats {
+    /// Number of frames checked
+    pub checked: u32,
+    /// Number of FCS errors
+    pub errors: u32,
+}
+
+/// Frame read by [`zigbee_read_frame`](Lr2021::zigbee_read_frame)
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ZigbeeFrame {
+    /// Length of the MAC frame in the buffer, FCS excluded
+    pub len: usize,
+    /// FCS valid (checked by the chip or by software)
+    pub fcs_ok: bool,
+    /// Packet status
+    pub status: PktStatus,
+}
+
+/// MAC frame type (frame control bits 0-2)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum MacFrameType {
+    Beacon,
+    Data,
+    Ack,
+    Command,
+    /// Reserved or extended frame types
+    Other(u8),
+}
+
+impl MacFrameType {
+    fn from_bits(v: u16) -> Self {
+        match v & 7 {
+            0 => MacFrameType::Beacon,
+            1 => MacFrameType::Data,
+            2 => MacFrameType::Ack,
+            3 => MacFrameType::Command,
+            v => MacFrameType::Other(v as u8),
+        }
+    }
+
+    fn bits(&self) -> u16 {
+        match self {
+            MacFrameType::Beacon => 0,
+            MacFrameType::Data => 1,
+            MacFrameType::Ack => 2,
+            MacFrameType::Command => 3,
+            MacFrameType::Other(v) => (*v & 7) as u16,
+        }
+    }
+}
+
+/// MAC address: absent, short (16b) or extended (64b)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum MacAddr {
+    None,
+    Short(u16),
+    Long(u64),
+}
+
+impl MacAddr {
+    /// Addressing mode in the frame control
+    fn mode(&self) -> u16 {
+        match self {
+            MacAddr::None => 0,
+            MacAddr::Short(_) => 2,
+            MacAddr::Long(_) => 3,
+        }
+    }
+
+    /// Address value (0 when absent)
+    fn value(&self) -> u64 {
+        match self {
+            MacAddr::None => 0,
+            MacAddr::Short(a) => *a as u64,
+            MacAddr::Long(a) => *a,
+        }
+    }
+
+    /// Size of the address field
+    fn len(&self) -> usize {
+        match self {
+            MacAddr::None => 0,
+            MacAddr::Short(_) => 2,
+            MacAddr::Long(_) => 8,
+        }
+    }
+}
+
+/// IEEE 802.15.4 MAC header (addressing rules of the 2003/2006 frame versions)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct MacFrame {
+    pub frame_type: MacFrameType,
+    pub security: bool,
+    pub frame_pending: bool,
+    pub ack_request: bool,
+    /// Frame version (0: 2003, 1: 2006, 2: 2015)
+    pub version: u8,
+    pub seq_num: u8,
+    /// Destination PAN ID (present when a destination address is present)
+    pub dst_pan: Option<u16>,
+    pub dst: MacAddr,
+    /// Source PAN ID (equal to the destination PAN ID when the PAN ID compression is used)
+    pub src_pan: Option<u16>,
+    pub src: MacAddr,
+    /// Length of the MAC header: the payload starts at this offset
+    pub hdr_len: usize,
+}
+
+/// Read a little-endian field of n bytes at offset
+fn mac_field(frame: &[u8], offset: usize, n: usize) -> Option<u64> {
+    let bytes = frame.get(offset..offset + n)?;
+    Some(bytes.iter().rev().fold(0u64, |acc, &b| (acc << 8) | b as u64))
+}
+
+impl MacFrame {
+    /// Data frame with a sequence number, PAN ID and addresses: the PAN ID compression is used when both addresses are present
+    pub fn data(seq_num: u8, pan_id: u16, dst: MacAddr, src: MacAddr, ack_request: bool) -> Self {
+        let dst_pan = (dst != MacAddr::None).then_some(pan_id);
+        let src_pan = (src != MacAddr::None).then_some(pan_id);
+        let mut frame = Self {
+            frame_type: MacFrameType::Data, security: false, frame_pending: false, ack_request,
+            version: 1, seq_num, dst_pan, dst, src_pan, src, hdr_len: 0,
+        };
+        frame.hdr_len = frame.header_len();
+        frame
+    }
+
+    /// Acknowledgment of a frame (same sequence number)
+    pub fn ack(&self, frame_pending: bool) -> Self {
+        Self {
+            frame_type: MacFrameType::Ack, security: false, frame_pending, ack_request: false,
+            version: 0, seq_num: self.seq_num, dst_pan: None, dst: MacAddr::None, src_pan: None, src: MacAddr::None, hdr_len: 3,
+        }
+    }
+
+    /// PAN ID compression: both addresses present in the same PAN
+    fn pan_id_compression(&self) -> bool {
+        self.dst != MacAddr::None && self.src != MacAddr::None && self.dst_pan == self.src_pan
+    }
+
+    /// Frame control field
+    pub fn frame_control(&self) -> u16 {
+        self.frame_type.bits()
+        | (self.security as u16) << 3
+        | (self.frame_pending as u16) << 4
+        | (self.ack_request as u16) << 5
+        | (self.pan_id_compression() as u16) << 6
+        | self.dst.mode() << 10
+        | ((self.version & 3) as u16) << 12
+        | self.src.mode() << 14
+    }
+
+    /// Length of the MAC header
+    pub fn header_len(&self) -> usize {
+        let dst_pan = if self.dst != MacAddr::None {2} else {0};
+        let src_pan = if self.src != MacAddr::None && !self.pan_id_compression() {2} else {0};
+        3 + dst_pan + self.dst.len() + src_pan + self.src.len()
+    }
+
+    /// Parse the MAC header of a frame (FCS excluded)
+    /// Return None if the frame is too short or uses a reserved addressing mode
+    pub fn parse(frame: &[u8]) -> Option<Self> {
+        let fc = mac_field(frame, 0, 2)? as u16;
+        let seq_num = *frame.get(2)?;
+        let pan_id_compression = fc & (1 << 6) != 0;
+        let mut offset = 3;
+        let read_addr = |mode: u16, offset: &mut usize| -> Option<MacAddr> {
+            let addr = match mode {
+                0 => MacAddr::None,
+                2 => MacAddr::Short(mac_field(frame, *offset, 2)? as u16),
+                3 => MacAddr::Long(mac_field(frame, *offset, 8)?),
+                _ => return None,
+            };
+            *offset += addr.len();
+            Some(addr)
+        };
+        let dst_mode = (fc >> 10) & 3;
+        let dst_pan = if dst_mode != 0 {
+            let pan = mac_field(frame, offset, 2)? as u16;
+            offset += 2;
+            Some(pan)
+        } else {
+            None
+        };
+        let dst = read_addr(dst_mode, &mut offset)?;
+        let src_mode = (fc >> 14) & 3;
+        let src_pan = if src_mode == 0 {
+            None
+        } else if pan_id_compression {
+            dst_pan
+        } else {
+            let pan = mac_field(frame, offset, 2)? as u16;
+            offset += 2;
+            Some(pan)
+        };
+        let src = read_addr(src_mode, &mut offset)?;
+        Some(Self {
+            frame_type: MacFrameType::from_bits(fc),
+            security: fc & (1 << 3) != 0,
+            frame_pending: fc & (1 << 4) != 0,
+            ack_request: fc & (1 << 5) != 0,
+            version: ((fc >> 12) & 3) as u8,
+            seq_num,
+            dst_pan,
+            dst,
+            src_pan,
+            src,
+            hdr_len: offset,
+        })
+    }
+
+    /// Payload of a frame (FCS excluded) parsed with this header
+    pub fn payload<'a>(&self, frame: &'a [u8]) -> &'a [u8] {
+        frame.get(self.hdr_len..).unwrap_or(&[])
+    }
+
+    /// Write the MAC header in a buffer and return its length
+    /// Return None if the buffer is too small
+    pub fn write(&self, buf: &mut [u8]) -> Option<usize> {
+        let len = self.header_len();
+        let buf = buf.get_mut(..len)?;
+        buf[..2].copy_from_slice(&self.frame_control().to_le_bytes());
+        buf[2] = self.seq_num;
+        let mut offset = 3;
+        let mut push = |v: u64, n: usize| {
+            buf[offset..offset + n].copy_from_slice(&v.to_le_bytes()[..n]);
+            offset += n;
+        };
+        if self.dst != MacAddr::None {
+            push(self.dst_pan.unwrap_or(0xFFFF) as u64, 2);
+            push(self.dst.value(), self.dst.len());
+        }
+        if self.src != MacAddr::None {
+            if !self.pan_id_compression() {
+                push(self.src_pan.unwrap_or(0xFFFF) as u64, 2);
+            }
+            push(self.src.value(), self.src.len());
+        }
+        Some(len)
+    }
+}
+
+/// RF frequency in Hz of a 2.4GHz channel (11 to 26)
+pub fn zigbee_channel_freq(channel: u8) -> u32 {
+    2_405_000_000 + 5_000_000 * (channel.saturating_sub(ZIGBEE_CH_FIRST) as u32)
+}
+
+/// Convert an RSSI (in -0.5dBm unit) to an IEEE 802.15.4 Energy Detection level
+/// ED is linear in dB with 4 steps per dB: 0 for -100dBm and 255 for -36.25dBm or higher
+pub fn zigbee_rssi_to_ed(rssi: u16) -> u8 {
+    let ed = (2 * (-ZIGBEE_ED_MIN_DBM) as i32 - rssi as i32) * 2;
+    ed.clamp(0, 255) as u8
+}
+
+/// Convert an ED level to the corresponding power in dBm
+pub fn zigbee_ed_to_dbm(ed: u8) -> i16 {
+    ZIGBEE_ED_MIN_DBM + (ed as i16 >> 2)
+}
+
+/// Convert a power in dBm to the corresponding ED level
+pub fn zigbee_dbm_to_ed(dbm: i16) -> u8 {
+    ((dbm - ZIGBEE_ED_MIN_DBM) * 4).clamp(0, 255) as u8
+}
+
+/// Clear Channel Assessment mode as defined by IEEE 802.15.4
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum CcaMode {
+    /// Channel busy when energy is above threshold (mode 1)
+    #[default]
+    Energy,
+    /// Channel busy when an 802.15.4 signal is detected (mode 2)
+    CarrierSense,
+    /// Channel busy when an 802.15.4 signal is detected with energy above threshold (mode 3, AND)
+    EnergyAndCarrier,
+    /// Channel busy when an 802.15.4 signal is detected or energy is above threshold (mode 3, OR)
+    EnergyOrCarrier,
+}
+
 #[derive(Clone)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct ZigbeePacketParams {
@@ -72,7 +408,43 @@ impl<O,SPI, M> Lr2021<O,SPI, M> where
             params.pbl_len_tx,
             params.addr_filt_en,
             params.fcs_mode);
-        self.cmd_wr(&req).await
+        self.cmd_wr(&req).await?;
+        self.cache.zigbee_fcs_in_fifo = params.fcs_mode == FcsMode::FcsInFifo;
+        Ok(())
+    }
+
+    /// Read the last frame received in the buffer (FCS excluded) and report its FCS status.
+    /// With `FcsMode::FcsOn` the status comes from the CRC error interrupt (`intr` read on RxDone),
+    /// with `FcsMode::FcsInFifo` the FCS is read from the FIFO and checked by software (counted in `zigbee_fcs_stats`).
+    /// Return InvalidSize if the frame does not fit in the buffer (the RX FIFO is then cleared)
+    pub async fn zigbee_read_frame(&mut self, buffer: &mut [u8], intr: Intr) -> Result<ZigbeeFrame, Lr2021Error> {
+        let status = self.get_packet_status().await?;
+        let len = status.len as usize;
+        if len > buffer.len() {
+            self.clear_rx_fifo().await?;
+            return Err(Lr2021Error::InvalidSize);
+        }
+        self.rd_rx_fifo_to(&mut buffer[..len]).await?;
+        if !self.cache.zigbee_fcs_in_fifo {
+            return Ok(ZigbeeFrame { len, fcs_ok: !intr.crc_error(), status });
+        }
+        let fcs_ok = zigbee_verify_fcs(&buffer[..len]);
+        let stats = &mut self.zigbee_fcs_stats;
+        stats.checked = stats.checked.wrapping_add(1);
+        if !fcs_ok {
+            stats.errors = stats.errors.wrapping_add(1);
+        }
+        Ok(ZigbeeFrame { len: len.saturating_sub(ZIGBEE_FCS_LEN), fcs_ok, status })
+    }
+
+    /// Return the number of frames checked and FCS errors detected by software
+    pub fn zigbee_fcs_stats(&self) -> ZigbeeFcsStats {
+        self.zigbee_fcs_stats
+    }
+
+    /// Reset the software FCS statistics
+    pub fn clear_zigbee_fcs_stats(&mut self) {
+        self.zigbee_fcs_stats = ZigbeeFcsStats::default();
     }
 
     /// Sets the zigbee packet length without calling set_zigbee_packet which takes longer
@@ -92,6 +464,7 @@ impl<O,SPI, M> Lr2021<O,SPI, M> where
     }
 
     /// Return length of last packet received
+    /// RSSI values are raw: use [`get_packet_status`](Lr2021::get_packet_status) or [`rssi_calibrate`](Lr2021::rssi_calibrate) to apply the RSSI calibration
     pub async fn get_zigbee_packet_status(&mut self) -> Result<ZigbeePacketStatusRsp, Lr2021Error> {
         let req = get_zigbee_packet_status_req();
         let mut rsp = ZigbeePacketStatusRsp::new();
@@ -107,4 +480,74 @@ impl<O,SPI, M> Lr2021<O,SPI, M> where
         Ok(rsp)
     }
 
-}
\ No newline at end of file
+    /// Run an energy detection over 8 symbols (128us) and return the ED level (0-255)
+    /// Note: Chip must be standby or FS before issuing the command
+    pub async fn zigbee_ed(&mut self) -> Result<u8, Lr2021Error> {
+        let rsp = self.set_and_get_cca(ZIGBEE_CCA_DURATION, None).await?;
+        Ok(zigbee_rssi_to_ed(rsp.rssi_avg()))
+    }
+
+    /// Run a Clear Channel Assessment and return true if the channel is clear
+    /// The energy threshold is given as an ED level (see [`zigbee_dbm_to_ed`])
+    /// Carrier sense starts a reception for 8 symbols and checks for a preamble detection:
+    /// chip must be configured for Zigbee, is left in FS mode and preamble/timeout interrupts are cleared.
+    pub async fn zigbee_cca(&mut self, mode: CcaMode, ed_thr: u8) -> Result<bool, Lr2021Error> {
+        let energy = match mode {
+            CcaMode::CarrierSense => false,
+            _ => self.zigbee_ed().await? >= ed_thr,
+        };
+        let carrier = match mode {
+            CcaMode::Energy => false,
+            CcaMode::EnergyAndCarrier if !energy => false,
+            _ => self.zigbee_carrier_sense().await?,
+        };
+        let busy = match mode {
+            CcaMode::Energy => energy,
+            CcaMode::CarrierSense => carrier,
+            CcaMode::EnergyAndCarrier => energy && carrier,
+            CcaMode::EnergyOrCarrier => energy || carrier,
+        };
+        Ok(!busy)
+    }
+
+    /// Run an IEEE 802.15.4 energy detection scan: for each channel selected, tune the RF and
+    /// measure the maximum RSSI over the duration with the CCA engine.
+    /// Channels are given as a bitmask where bit 0 is channel 11 and bit 15 is channel 26.
+    /// Return the ED level per channel (index 0 for channel 11), None for channels not scanned.
+    /// Note: Chip must be standby or FS before issuing the command, and the RF is left on the last channel scanned
+    pub async fn zigbee_energy_scan(&mut self, channels: u16, duration: Duration) -> Result<[Option<u8>; 16], Lr2021Error> {
+        let mut eds = [None; 16];
+        // Duration in CCA steps (31.25ns)
+        let total = duration.as_micros().saturating_mul(32).max(1);
+        for (idx, ed) in eds.iter_mut().enumerate() {
+            if channels & (1 << idx) == 0 {
+                continue;
+            }
+            self.set_rf(zigbee_channel_freq(ZIGBEE_CH_FIRST + idx as u8)).await?;
+            // Split long duration in multiple CCA, keeping the highest power (i.e. lowest RSSI value)
+            let mut remaining = total;
+            let mut rssi_max = u16::MAX;
+            while remaining > 0 {
+                let step = remaining.min(ZIGBEE_CCA_MAX_DURATION as u64) as u32;
+                let rsp = self.set_and_get_cca(step, None).await?;
+                rssi_max = rssi_max.min(rsp.rssi_max());
+                remaining -= step as u64;
+            }
+            *ed = Some(zigbee_rssi_to_ed(rssi_max));
+        }
+        Ok(eds)
+    }
+
+    /// Listen for 8 symbols and return true if a preamble was detected
+    async fn zigbee_carrier_sense(&mut self) -> Result<bool, Lr2021Error> {
+        let mask = Intr::new(IRQ_MASK_PREAMBLE_DETECTED | IRQ_MASK_TIMEOUT);
+        self.clear_irqs(mask).await?;
+        self.set_rx(ZIGBEE_CS_TIMEOUT, true).await?;
+        Timer::after_micros(ZIGBEE_CS_TIMEOUT as u64 * 31).await;
+        let (_, intr) = self.get_status().await?;
+        self.set_chip_mode(ChipMode::Fs).await?;
+        self.clear_irqs(mask).await?;
+        Ok(intr.preamble_detected())
+    }
+
+}
@@ -37,6 +37,7 @@
 //! - [`set_zwave_packet`](Lr2021::set_zwave_packet) - Configure packet parameters (mode, bandwidth, address filtering)
 //! - [`set_zwave_scan_config`](Lr2021::set_zwave_scan_config) - Configure multi-channel scanning parameters
 //! - [`start_zwave_scan`](Lr2021::start_zwave_scan) - Start scanning across configured channels
+//! - [`zwave_scan_tx`](Lr2021::zwave_scan_tx) - Pause the scan and transmit a packet (resume with [`resume_scan`](Lr2021::resume_scan))
 //!
 //! ### Address and Filtering
 //! - [`set_zwave_home_id`](Lr2021::set_zwave_home_id) - Set home ID for network address filtering
@@ -45,13 +46,20 @@
 //! ### Status and Statistics
 //! - [`get_zwave_packet_status`](Lr2021::get_zwave_packet_status) - Get last packet status information
 //! - [`get_zwave_rx_stats`](Lr2021::get_zwave_rx_stats) - Get basic reception statistics
+//!
+//! ### Frames
+//! The [`frame`] sub-module provides parsing and building of Z-Wave MAC frames (header fields and checksum/CRC)
 
 use embedded_hal::digital::OutputPin;
 use embedded_hal_async::spi::SpiBus;
 
 pub use super::cmd::cmd_zwave::*;
+use super::quirks::Feature;
+use super::radio::ScanState;
 use super::{BusyPin, Lr2021, Lr2021Error, RxBw};
 
+pub mod frame;
+
 #[derive(Clone)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct ZwavePacketParams {
@@ -110,7 +118,7 @@ impl ZwavePacketParams {
     }
 }
 
-#[derive(Clone)]
+#[derive(Debug, Clone, Copy)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct ZwaveChanCfg {
     /// Frequency associated with this channel
@@ -164,7 +172,7 @@ impl ZwaveChanCfg {
     }
 }
 
-#[derive(Clone)]
+#[derive(Debug, Clone, Copy)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct ZwaveScanCfg {
     pub addr_comp: ZwaveAddrComp,
@@ -290,6 +298,7 @@ impl<O,SPI, M> Lr2021<O,SPI, M> where
     }
 
     /// Configure scan: number of active channel, their mode and frequency
+    /// The configuration is saved to allow resuming the scan after a transmission (see [`resume_scan`](Lr2021::resume_scan))
     pub async fn set_zwave_scan_config(&mut self, cfg: &ZwaveScanCfg) -> Result<(), Lr2021Error> {
         let req = [0x02, 0x9C,
             cfg.cmd_nb_ch(),
@@ -326,16 +335,35 @@ impl<O,SPI, M> Lr2021<O,SPI, M> where
             3 => 21,
             _ => 26,
         };
-        self.cmd_wr(&req[..len]).await
+        let res = self.cmd_wr(&req[..len]).await;
+        self.feature_check(Feature::ZwaveScan, res)?;
+        self.cache.zwave_scan = Some(*cfg);
+        Ok(())
     }
 
     /// Start the ZWave Scan: it will alternate between up to 4 channels to find an incoming packet
     pub async fn start_zwave_scan(&mut self) -> Result<(), Lr2021Error> {
         let req = set_zwave_scan_cmd();
-        self.cmd_wr(&req).await
+        let res = self.cmd_wr(&req).await;
+        self.feature_check(Feature::ZwaveScan, res)?;
+        self.cache.scan_state = ScanState::Running;
+        Ok(())
+    }
+
+    /// Pause the scan and transmit a packet (e.g. an ACK) on a given frequency and mode
+    /// The scan can be resumed with [`resume_scan`](Lr2021::resume_scan) once the transmission is done
+    pub async fn zwave_scan_tx(&mut self, rf: u32, params: &ZwavePacketParams, payload: &[u8]) -> Result<(), Lr2021Error> {
+        if payload.len() > u8::MAX as usize {
+            return Err(Lr2021Error::InvalidSize);
+        }
+        self.pause_scan().await?;
+        self.set_rf(rf).await?;
+        self.set_zwave_packet(&ZwavePacketParams {pld_len: payload.len() as u8, ..params.clone()}).await?;
+        self.tx_payload(payload, 0).await
     }
 
     /// Return length of last packet received
+    /// RSSI values are raw: use [`get_packet_status`](Lr2021::get_packet_status) or [`rssi_calibrate`](Lr2021::rssi_calibrate) to apply the RSSI calibration
     pub async fn get_zwave_packet_status(&mut self) -> Result<ZwavePacketStatusRsp, Lr2021Error> {
         let req = get_zwave_packet_status_req();
         let mut rsp = ZwavePacketStatusRsp::new();
@@ -0,0 +1,291 @@
+//! # Z-Wave MAC frames (MPDU)
+//!
+//! Parsing and building of Z-Wave MAC frames for all rates:
+//!  - R1/R2 (9.6/40kb/s): 9 bytes header, 8-bit checksum
+//!  - R3 (100kb/s): 10 bytes header with a dedicated sequence number byte, CRC-16
+//!  - LR: 12 bytes header with 12-bit node IDs, noise floor and TX power, CRC-16
+//!
+//! The frame check sequence is only present in the FIFO when the FCS mode is `FcsMode::Fifo`
+//! (always the case for LR), which is indicated by the `fcs` flag of the parse/build functions.
+//! The length field of the frame always includes the FCS.
+//!
+//! ## Quick Start
+//!
+//! ```rust,no_run
+//! use lr2021::zwave::frame::*;
+//!
+//! // Parse a frame received at R2 rate with FCS checked by the chip
+//! let len = lr2021.get_rx_pkt_len().await.expect("RxLen") as usize;
+//! lr2021.rd_rx_fifo(len).await.expect("RdFifo");
+//! let frame = ZwaveFrame::parse(ZwaveMode::R2, &lr2021.buffer()[..len], false).expect("Parse");
+//! info!("From {} to {} : {:02x}", frame.src, frame.dst, frame.payload);
+//!
+//! // Build an acknowledge
+//! let ack = ZwaveFrame::new(frame.home_id, frame.dst, frame.src, HeaderType::Ack, &[]).with_seq(frame.seq);
+//! let mut buf = [0; 16];
+//! let len = ack.build(ZwaveMode::R2, &mut buf, false).expect("Build");
+//! ```
+
+use super::ZwaveMode;
+
+/// Initial value of the R1/R2 checksum
+const CHECKSUM_INIT: u8 = 0xFF;
+/// Initial value of the R3/LR CRC-16
+const CRC16_INIT: u16 = 0x1D0F;
+/// Polynomial of the R3/LR CRC-16 (CCITT)
+const CRC16_POLY: u16 = 0x1021;
+
+/// Error while parsing/building a Z-Wave frame
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ZwaveFrameError {
+    /// Frame shorter than the header
+    TooShort,
+    /// Length field does not match the frame length
+    Length,
+    /// Checksum/CRC mismatch
+    Fcs,
+    /// Output buffer too small or payload too large
+    BufferTooSmall,
+    /// Node ID out of range for the mode (8 bits for R1/R2/R3, 12 bits for LR)
+    NodeId,
+}
+
+/// Z-Wave header type
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum HeaderType {
+    SingleCast,
+    MultiCast,
+    Ack,
+    /// Routed frame (R1/R2/R3 only)
+    Routed,
+    /// Reserved value
+    Reserved(u8),
+}
+
+impl HeaderType {
+    fn from_bits(val: u8) -> Self {
+        match val {
+            1 => HeaderType::SingleCast,
+            2 => HeaderType::MultiCast,
+            3 => HeaderType::Ack,
+            8 => HeaderType::Routed,
+            v => HeaderType::Reserved(v),
+        }
+    }
+
+    fn bits(&self) -> u8 {
+        match self {
+            HeaderType::SingleCast => 1,
+            HeaderType::MultiCast => 2,
+            HeaderType::Ack => 3,
+            HeaderType::Routed => 8,
+            HeaderType::Reserved(v) => *v,
+        }
+    }
+}
+
+/// Z-Wave MAC frame
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ZwaveFrame<'a> {
+    /// Network Home ID
+    pub home_id: u32,
+    /// Source node ID (8 bits, or 12 bits for LR)
+    pub src: u16,
+    /// Destination node ID (8 bits, or 12 bits for LR)
+    pub dst: u16,
+    /// Header type
+    pub header_type: HeaderType,
+    /// Acknowledge request
+    pub ack_req: bool,
+    /// Routed flag (R1/R2/R3 only)
+    pub routed: bool,
+    /// Low power flag (R1/R2/R3 only)
+    pub low_power: bool,
+    /// Speed modified flag (R1/R2/R3 only)
+    pub speed_modified: bool,
+    /// Beam info (R1/R2/R3 only, 3 bits)
+    pub beam_info: u8,
+    /// Sequence number (4 bits for R1/R2)
+    pub seq: u8,
+    /// Noise floor in dBm (LR only)
+    pub noise_floor: i8,
+    /// TX power in dBm (LR only)
+    pub tx_power: i8,
+    /// Frame payload
+    pub payload: &'a [u8],
+}
+
+/// Header length for a given mode
+pub fn zwave_header_len(mode: ZwaveMode) -> usize {
+    match mode {
+        ZwaveMode::R1 | ZwaveMode::R2 => 9,
+        ZwaveMode::R3 => 10,
+        ZwaveMode::Lr1 => 12,
+    }
+}
+
+/// FCS length for a given mode
+pub fn zwave_fcs_len(mode: ZwaveMode) -> usize {
+    match mode {
+        ZwaveMode::R1 | ZwaveMode::R2 => 1,
+        _ => 2,
+    }
+}
+
+/// R1/R2 checksum: XOR of all bytes
+pub fn zwave_checksum(data: &[u8]) -> u8 {
+    data.iter().fold(CHECKSUM_INIT, |acc, b| acc ^ b)
+}
+
+/// R3/LR CRC-16 (CCITT polynomial, initial value 0x1D0F)
+pub fn zwave_crc16(data: &[u8]) -> u16 {
+    let mut crc = CRC16_INIT;
+    for &b in data {
+        crc ^= (b as u16) << 8;
+        for _ in 0..8 {
+            crc = if (crc & 0x8000) != 0 {(crc << 1) ^ CRC16_POLY} else {crc << 1};
+        }
+    }
+    crc
+}
+
+impl<'a> ZwaveFrame<'a> {
+    /// Create a frame with default flags and sequence number 0
+    pub fn new(home_id: u32, src: u16, dst: u16, header_type: HeaderType, payload: &'a [u8]) -> Self {
+        Self {
+            home_id, src, dst, header_type,
+            ack_req: false, routed: false, low_power: false, speed_modified: false,
+            beam_info: 0, seq: 0, noise_floor: 0, tx_power: 0,
+            payload,
+        }
+    }
+
+    /// Set the sequence number
+    pub fn with_seq(self, seq: u8) -> Self {
+        Self { seq, ..self }
+    }
+
+    /// Set the acknowledge request flag
+    pub fn with_ack_req(self, ack_req: bool) -> Self {
+        Self { ack_req, ..self }
+    }
+
+    /// Set the noise floor and TX power (LR only)
+    pub fn with_lr_info(self, noise_floor: i8, tx_power: i8) -> Self {
+        Self { noise_floor, tx_power, ..self }
+    }
+
+    /// Parse a frame. When fcs is true, the frame ends with the checksum/CRC which is verified.
+    pub fn parse(mode: ZwaveMode, data: &'a [u8], fcs: bool) -> Result<Self, ZwaveFrameError> {
+        let hdr_len = zwave_header_len(mode);
+        let fcs_len = zwave_fcs_len(mode);
+        let min_len = hdr_len + if fcs {fcs_len} else {0};
+        if data.len() < min_len {
+            return Err(ZwaveFrameError::TooShort);
+        }
+        let home_id = u32::from_be_bytes([data[0], data[1], data[2], data[3]]);
+        let len_field = data[7] as usize;
+        let expected = data.len() + if fcs {0} else {fcs_len};
+        if len_field != expected {
+            return Err(ZwaveFrameError::Length);
+        }
+        let end = data.len() - if fcs {fcs_len} else {0};
+        if fcs {
+            let valid = match mode {
+                ZwaveMode::R1 | ZwaveMode::R2 => zwave_checksum(&data[..end]) == data[end],
+                _ => zwave_crc16(&data[..end]) == u16::from_be_bytes([data[end], data[end+1]]),
+            };
+            if !valid {
+                return Err(ZwaveFrameError::Fcs);
+            }
+        }
+        let payload = &data[hdr_len..end];
+        let frame = if mode == ZwaveMode::Lr1 {
+            Self {
+                home_id,
+                src: ((data[4] as u16) << 4) | ((data[5] as u16) >> 4),
+                dst: (((data[5] & 0xF) as u16) << 8) | data[6] as u16,
+                header_type: HeaderType::from_bits(data[8] & 0x7),
+                ack_req: (data[8] & 0x80) != 0,
+                routed: false, low_power: false, speed_modified: false, beam_info: 0,
+                seq: data[9],
+                noise_floor: data[10] as i8,
+                tx_power: data[11] as i8,
+                payload,
+            }
+        } else {
+            let fc0 = data[5];
+            let fc1 = data[6];
+            Self {
+                home_id,
+                src: data[4] as u16,
+                dst: data[hdr_len - 1] as u16,
+                header_type: HeaderType::from_bits(fc0 & 0xF),
+                ack_req: (fc0 & 0x40) != 0,
+                routed: (fc0 & 0x80) != 0,
+                low_power: (fc0 & 0x20) != 0,
+                speed_modified: (fc0 & 0x10) != 0,
+                beam_info: (fc1 >> 4) & 0x7,
+                seq: if mode == ZwaveMode::R3 {data[8]} else {fc1 & 0xF},
+                noise_floor: 0, tx_power: 0,
+                payload,
+            }
+        };
+        Ok(frame)
+    }
+
+    /// Build the frame in a buffer, appending the checksum/CRC when fcs is true
+    /// Return the number of bytes written
+    pub fn build(&self, mode: ZwaveMode, buf: &mut [u8], fcs: bool) -> Result<usize, ZwaveFrameError> {
+        let hdr_len = zwave_header_len(mode);
+        let fcs_len = zwave_fcs_len(mode);
+        let len_field = hdr_len + self.payload.len() + fcs_len;
+        let len = len_field - if fcs {0} else {fcs_len};
+        if len_field > u8::MAX as usize || buf.len() < len {
+            return Err(ZwaveFrameError::BufferTooSmall);
+        }
+        let max_id = if mode == ZwaveMode::Lr1 {0xFFF} else {0xFF};
+        if self.src > max_id || self.dst > max_id {
+            return Err(ZwaveFrameError::NodeId);
+        }
+        buf[0..4].copy_from_slice(&self.home_id.to_be_bytes());
+        if mode == ZwaveMode::Lr1 {
+            buf[4] = (self.src >> 4) as u8;
+            buf[5] = ((self.src << 4) as u8) | ((self.dst >> 8) as u8 & 0xF);
+            buf[6] = self.dst as u8;
+            buf[7] = len_field as u8;
+            buf[8] = if self.ack_req {0x80} else {0} | (self.header_type.bits() & 0x7);
+            buf[9] = self.seq;
+            buf[10] = self.noise_floor as u8;
+            buf[11] = self.tx_power as u8;
+        } else {
+            buf[4] = self.src as u8;
+            buf[5] = if self.routed {0x80} else {0}
+                | if self.ack_req {0x40} else {0}
+                | if self.low_power {0x20} else {0}
+                | if self.speed_modified {0x10} else {0}
+                | (self.header_type.bits() & 0xF);
+            buf[6] = ((self.beam_info & 0x7) << 4) | if mode == ZwaveMode::R3 {0} else {self.seq & 0xF};
+            buf[7] = len_field as u8;
+            if mode == ZwaveMode::R3 {
+                buf[8] = self.seq;
+            }
+            buf[hdr_len - 1] = self.dst as u8;
+        }
+        let end = hdr_len + self.payload.len();
+        buf[hdr_len..end].copy_from_slice(self.payload);
+        if fcs {
+            match mode {
+                ZwaveMode::R1 | ZwaveMode::R2 => buf[end] = zwave_checksum(&buf[..end]),
+                _ => {
+                    let crc = zwave_crc16(&buf[..end]);
+                    buf[end..end+2].copy_from_slice(&crc.to_be_bytes());
+                }
+            }
+        }
+        Ok(len)
+    }
+}
@@ -0,0 +1,51 @@
+//! Host environment for the tests running the driver on the mock bus
+
+use core::future::Future;
+use core::sync::atomic::{AtomicU64, Ordering};
+use core::task::Waker;
+
+use embassy_time_driver::Driver;
+
+use lr2021::mock::MockPin;
+use lr2021::{BusyAsync, Lr2021};
+
+/// Simulated time: waiting for a timer moves the time forward to its expiration
+struct SimTime(AtomicU64);
+
+impl Driver for SimTime {
+    fn now(&self) -> u64 {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    fn schedule_wake(&self, at: u64, waker: &Waker) {
+        self.0.fetch_max(at, Ordering::Relaxed);
+        waker.wake_by_ref();
+    }
+}
+
+embassy_time_driver::time_driver_impl!(static SIM_TIME: SimTime = SimTime(AtomicU64::new(0)));
+
+/// Logger discarding the defmt frames (embassy-time is built with defmt)
+#[defmt::global_logger]
+struct NoLogger;
+
+unsafe impl defmt::Logger for NoLogger {
+    fn acquire() {}
+    unsafe fn flush() {}
+    unsafe fn release() {}
+    unsafe fn write(_bytes: &[u8]) {}
+}
+
+#[defmt::panic_handler]
+fn defmt_panic() -> ! {
+    panic!("defmt panic")
+}
+
+/// Driver running on the mock bus
+#[allow(dead_code)]
+pub type MockLr2021<'b, 'a> = Lr2021<MockPin<'b, 'a>, lr2021::mock::MockSpi<'b, 'a>, BusyAsync<MockPin<'b, 'a>>>;
+
+/// Run a future to completion
+pub fn run<F: Future>(fut: F) -> F::Output {
+    embassy_futures::block_on(fut)
+}
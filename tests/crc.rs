@@ -0,0 +1,74 @@
+//! Known-answer vectors of the CRC helpers, using the check value of the CRC catalogue (CRC of "123456789")
+
+use lr2021::ble::ble_crc24;
+use lr2021::crc_out::{CrcFormat, CrcHeader};
+use lr2021::testvec::{crc_msb, lora_payload_crc};
+use lr2021::zwave::ZwaveMode;
+use lr2021::zwave::frame::{zwave_checksum, zwave_crc16};
+
+const CHECK: &[u8] = b"123456789";
+
+#[test]
+fn crc_msb_catalogue() {
+    // CRC-16/XMODEM, CRC-16/IBM-3740, CRC-8/SMBUS, CRC-32/MPEG-2
+    assert_eq!(crc_msb(0x1021, 0x0000, 16, CHECK), 0x31C3);
+    assert_eq!(crc_msb(0x1021, 0xFFFF, 16, CHECK), 0x29B1);
+    assert_eq!(crc_msb(0x07, 0x00, 8, CHECK), 0xF4);
+    assert_eq!(crc_msb(0x04C1_1DB7, 0xFFFF_FFFF, 32, CHECK), 0x0376_E6E7);
+}
+
+#[test]
+fn crc_msb_inverted() {
+    // CRC-16/GENIBUS and CRC-32/BZIP2: MSB first with final inversion
+    assert_eq!(CrcFormat::msb(2, 0x1021, 0xFFFF, true).compute(CHECK), Some(0xD64E));
+    assert_eq!(CrcFormat::msb(4, 0x04C1_1DB7, 0xFFFF_FFFF, true).compute(CHECK), Some(0xFC89_1918));
+}
+
+#[test]
+fn crc_length_header() {
+    // Header byte covered by the CRC: same as the CRC of the length followed by the payload
+    let fmt = CrcFormat::msb(2, 0x1021, 0xFFFF, true).with_header(CrcHeader::Len8);
+    assert_eq!(fmt.compute(CHECK), Some(0x0A0B));
+    let mut hdr_payload = [9u8; 10];
+    hdr_payload[1..].copy_from_slice(CHECK);
+    assert_eq!(CrcFormat::msb(2, 0x1021, 0xFFFF, true).compute(&hdr_payload), Some(0x0A0B));
+}
+
+#[test]
+fn ble_crc24_catalogue() {
+    // CRC-24/BLE
+    assert_eq!(ble_crc24(0x55_5555, CHECK), 0xC2_5A56);
+}
+
+#[test]
+fn zwave_catalogue() {
+    // CRC-16/SPI-FUJITSU (AUG-CCITT) and XOR checksum initialized to 0xFF
+    assert_eq!(zwave_crc16(CHECK), 0xE5CC);
+    assert_eq!(zwave_checksum(CHECK), 0xCE);
+}
+
+#[test]
+fn lora_payload_crc_vector() {
+    // CRC-16/XMODEM of the payload minus the last two bytes ("1234567"), XORed with them ("89")
+    assert_eq!(lora_payload_crc(CHECK), 0xBEEF);
+    assert_eq!(lora_payload_crc(b"Hello"), 0x8196);
+}
+
+#[test]
+fn crc_split_and_verify() {
+    // LoRa: CRC sent LSB first
+    let frame = [b'H', b'e', b'l', b'l', b'o', 0x96, 0x81];
+    let check = CrcFormat::lora().verify(&frame).expect("Verify");
+    assert_eq!(check.payload_len, 5);
+    assert_eq!(check.received, 0x8196);
+    assert!(check.is_valid());
+    // Z-Wave R3: CRC sent MSB first
+    let mut frame = [0u8; 11];
+    frame[..9].copy_from_slice(CHECK);
+    frame[9..].copy_from_slice(&[0xE5, 0xCD]);
+    let check = CrcFormat::zwave(ZwaveMode::R3).verify(&frame).expect("Verify");
+    assert_eq!(check.computed, 0xE5CC);
+    assert!(!check.is_valid());
+    // Frame shorter than the CRC
+    assert_eq!(CrcFormat::ble(0x55_5555).verify(&[0x00, 0x01]), None);
+}
@@ -0,0 +1,113 @@
+//! Capture and replay of the SPI transactions on the mock bus
+
+mod common;
+
+use common::run;
+use lr2021::Lr2021;
+use lr2021::mock::{CaptureMismatch, MockBus, MockReply};
+use lr2021::status::{Intr, IRQ_MASK_TX_DONE};
+use lr2021::system::RD_MEM_MAX_WORDS;
+
+/// GetStatus response without interrupt
+const STATUS_IDLE: [u8; 6] = [0x04, 0x00, 0x00, 0x00, 0x00, 0x00];
+/// GetStatus response with TxDone
+const STATUS_TX_DONE: [u8; 6] = [0x04, 0x00, 0x00, 0x08, 0x00, 0x00];
+/// ClearIrq on TxDone
+const CLEAR_TX_DONE: [u8; 6] = [0x01, 0x16, 0x00, 0x08, 0x00, 0x00];
+
+#[test]
+fn capture_commands() {
+    let mut capture = [0u8; 256];
+    let bus = MockBus::new(&mut capture, &[]);
+    let mut lr2021 = Lr2021::new(bus.pin(), bus.pin(), bus.spi(), bus.nss());
+    run(async {
+        lr2021.clear_irqs(Intr::new(IRQ_MASK_TX_DONE)).await.expect("ClearIrq");
+        // NSS toggled without transfer is not recorded
+        lr2021.wake_up().await.expect("WakeUp");
+        lr2021.get_status().await.expect("GetStatus");
+    });
+    let cap = bus.capture();
+    // Read phase without reply is captured as a command full of zeros
+    assert_eq!(cap.compare(&[&CLEAR_TX_DONE, &[0x01, 0x00], &[0; 6]]), Ok(()));
+    assert_eq!(cap.compare(&[&CLEAR_TX_DONE, &[0x01, 0x00]]), Err(CaptureMismatch::Count(3, 2)));
+    assert_eq!(cap.compare(&[&[0x01, 0x00], &[0x01, 0x00], &[0; 6]]), Err(CaptureMismatch::Frame(0)));
+    assert!(cap.frames().all(|f| !f.reply));
+}
+
+#[test]
+fn replay_replies() {
+    const STATUS: [&[u8]; 2] = [&STATUS_IDLE, &STATUS_TX_DONE];
+    let replies = [MockReply::new(0x0100, &STATUS)];
+    let mut capture = [0u8; 256];
+    let bus = MockBus::new(&mut capture, &replies);
+    let mut lr2021 = Lr2021::new(bus.pin(), bus.pin(), bus.spi(), bus.nss());
+    let intrs = run(async {
+        let mut intrs = [false; 3];
+        for done in intrs.iter_mut() {
+            *done = lr2021.get_status().await.expect("GetStatus").1.tx_done();
+        }
+        intrs
+    });
+    // Last response is repeated
+    assert_eq!(intrs, [false, true, true]);
+    assert_eq!(replies[0].nb_used(), 3);
+    let cap = bus.capture();
+    assert_eq!(cap.frames().filter(|f| f.reply).count(), 3);
+    assert_eq!(cap.compare(&[&[0x01, 0x00], &[0x01, 0x00], &[0x01, 0x00]]), Ok(()));
+}
+
+#[test]
+fn status_error_replayed() {
+    // Parameter error reported on the read phase
+    const STATUS: [&[u8]; 1] = [&[0x02, 0x00, 0x00, 0x00, 0x00, 0x00]];
+    let replies = [MockReply::new(0x0100, &STATUS)];
+    let mut capture = [0u8; 64];
+    let bus = MockBus::new(&mut capture, &replies);
+    let mut lr2021 = Lr2021::new(bus.pin(), bus.pin(), bus.spi(), bus.nss());
+    let res = run(lr2021.get_status());
+    assert!(matches!(res, Err(lr2021::Lr2021Error::CmdErr)));
+}
+
+#[test]
+fn capture_overflow() {
+    let mut capture = [0u8; 12];
+    let bus = MockBus::new(&mut capture, &[]);
+    let mut lr2021 = Lr2021::new(bus.pin(), bus.pin(), bus.spi(), bus.nss());
+    run(async {
+        lr2021.clear_irqs(Intr::new(IRQ_MASK_TX_DONE)).await.expect("ClearIrq");
+        lr2021.clear_irqs(Intr::new(IRQ_MASK_TX_DONE)).await.expect("ClearIrq");
+    });
+    {
+        let cap = bus.capture();
+        assert!(cap.overflow());
+        assert_eq!(cap.commands().count(), 1);
+        assert_eq!(cap.compare(&[&CLEAR_TX_DONE]), Err(CaptureMismatch::Overflow));
+    }
+    bus.clear();
+    run(lr2021.clear_irqs(Intr::new(IRQ_MASK_TX_DONE))).expect("ClearIrq");
+    assert_eq!(bus.capture().compare(&[&CLEAR_TX_DONE]), Ok(()));
+}
+
+#[test]
+fn rd_mem_reads_all_words() {
+    // Word i of the memory holds 0x0101_0101 * i
+    let words: Vec<u32> = (0..RD_MEM_MAX_WORDS as u32 + 2).map(|i| 0x0101_0101 * i).collect();
+    let rsp: Vec<Vec<u8>> = words.chunks(RD_MEM_MAX_WORDS)
+        .map(|c| [0x04, 0x00].into_iter().chain(c.iter().flat_map(|w| w.to_be_bytes())).collect())
+        .collect();
+    let rsps: Vec<&[u8]> = rsp.iter().map(|r| r.as_slice()).collect();
+    let replies = [MockReply::new(0x0106, &rsps)];
+    let mut capture = [0u8; 512];
+    let bus = MockBus::new(&mut capture, &replies);
+    let mut lr2021 = Lr2021::new(bus.pin(), bus.pin(), bus.spi(), bus.nss());
+    let mut out = [0u32; RD_MEM_MAX_WORDS + 2];
+    run(lr2021.rd_mem_into(0x80_0000, &mut out)).expect("RdMemInto");
+    assert_eq!(out.as_slice(), words.as_slice());
+    let cap = bus.capture();
+    // Read phase covers the status and all the words requested
+    let rd_len: Vec<usize> = cap.frames().filter(|f| f.reply).map(|f| f.mosi.len()).collect();
+    assert_eq!(rd_len, [2 + 4 * RD_MEM_MAX_WORDS, 2 + 4 * 2]);
+    let chunk_addr = 0x80_0000 + 4 * RD_MEM_MAX_WORDS as u32;
+    let [_, a2, a1, a0] = chunk_addr.to_be_bytes();
+    assert_eq!(cap.compare(&[&[0x01, 0x06, 0x80, 0x00, 0x00, RD_MEM_MAX_WORDS as u8], &[0x01, 0x06, a2, a1, a0, 2]]), Ok(()));
+}
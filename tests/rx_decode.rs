@@ -0,0 +1,102 @@
+//! Decoding of captured chip responses and FIFO dumps
+
+use lr2021::crc_out::CrcFormat;
+use lr2021::radio::PacketType;
+use lr2021::rx_decode::{decode_irq, decode_packet_status, decode_rx_stats, FifoPackets, RxCapture, RxDecodeError};
+
+/// GetStatus response with RxDone
+const IRQ_RX_DONE: [u8; 6] = [0x02, 0x00, 0x00, 0x04, 0x00, 0x00];
+/// GetStatus response with RxDone and CrcError
+const IRQ_CRC_ERROR: [u8; 6] = [0x02, 0x00, 0x00, 0x44, 0x00, 0x00];
+/// LoRa packet status: CRC on, CR 4/5, 5 bytes, SNR 5dB, RSSI -80dBm / -81dBm
+const LORA_STATUS: [u8; 8] = [0x02, 0x00, 0x11, 0x05, 0x14, 0x50, 0x51, 0x00];
+/// LoRa payload "Hello" followed by its CRC (LSB first)
+const LORA_FIFO: [u8; 7] = [b'H', b'e', b'l', b'l', b'o', 0x96, 0x81];
+
+#[test]
+fn lora_packet_status() {
+    let status = decode_packet_status(PacketType::Lora, &LORA_STATUS).expect("Status");
+    assert_eq!(status.len, 5);
+    assert_eq!(status.rssi_avg, 160);
+    assert_eq!(status.rssi_sync, Some(162));
+    assert_eq!(status.quality, Some(20));
+    assert_eq!(status.rssi_avg_dbm(), -80);
+    // Truncated response and packet type without status
+    assert!(decode_packet_status(PacketType::Lora, &LORA_STATUS[..7]).is_none());
+    assert!(decode_packet_status(PacketType::LrFhss, &LORA_STATUS).is_none());
+}
+
+#[test]
+fn lora_rx_stats() {
+    let rsp = [0x02, 0x00, 0x01, 0x2C, 0x00, 0x07, 0x00, 0x02, 0x01, 0x40, 0x00, 0x03];
+    let stats = decode_rx_stats(PacketType::Lora, &rsp).expect("Stats");
+    assert_eq!(stats.pkt_rx, 300);
+    assert_eq!(stats.crc_error, 7);
+    assert_eq!(stats.len_error, 2);
+}
+
+#[test]
+fn irq() {
+    let (_, intr) = decode_irq(&IRQ_CRC_ERROR).expect("Irq");
+    assert!(intr.rx_done());
+    assert!(intr.crc_error());
+    assert!(decode_irq(&IRQ_CRC_ERROR[..4]).is_none());
+}
+
+#[test]
+fn capture_with_crc() {
+    let frame = RxCapture::new(PacketType::Lora, &LORA_STATUS, &LORA_FIFO)
+        .with_irq(&IRQ_RX_DONE)
+        .with_crc(CrcFormat::lora())
+        .decode()
+        .expect("Decode");
+    assert_eq!(frame.payload, b"Hello");
+    assert_eq!(frame.crc.map(|c| c.received), Some(0x8196));
+    assert!(frame.is_valid());
+}
+
+#[test]
+fn capture_crc_format_without_crc_in_fifo() {
+    // CRC format set but the FIFO holds only the payload: nothing is split
+    let frame = RxCapture::new(PacketType::Lora, &LORA_STATUS, &LORA_FIFO[..5])
+        .with_crc(CrcFormat::lora())
+        .decode()
+        .expect("Decode");
+    assert_eq!(frame.payload, b"Hello");
+    assert!(frame.crc.is_none());
+    assert!(frame.is_valid());
+}
+
+#[test]
+fn capture_errors() {
+    let mut fifo = LORA_FIFO;
+    fifo[6] ^= 0x01;
+    let frame = RxCapture::new(PacketType::Lora, &LORA_STATUS, &fifo)
+        .with_crc(CrcFormat::lora())
+        .decode()
+        .expect("Decode");
+    assert!(!frame.is_valid());
+    let frame = RxCapture::new(PacketType::Lora, &LORA_STATUS, &LORA_FIFO[..5])
+        .with_irq(&IRQ_CRC_ERROR)
+        .decode()
+        .expect("Decode");
+    assert!(!frame.is_valid());
+    // FIFO matching neither the payload nor the payload with CRC
+    let res = RxCapture::new(PacketType::Lora, &LORA_STATUS, &LORA_FIFO[..6]).with_crc(CrcFormat::lora()).decode();
+    assert_eq!(res.err(), Some(RxDecodeError::LengthMismatch));
+    let res = RxCapture::new(PacketType::Lora, &LORA_STATUS, &LORA_FIFO).decode();
+    assert_eq!(res.err(), Some(RxDecodeError::LengthMismatch));
+    let res = RxCapture::new(PacketType::Lora, &LORA_STATUS[..4], &LORA_FIFO).decode();
+    assert_eq!(res.err(), Some(RxDecodeError::ResponseTooShort));
+}
+
+#[test]
+fn fifo_packets() {
+    let dump = [1, 2, 3, 4, 5, 6, 7, 8];
+    let mut packets = FifoPackets::new(&dump, &[3, 2, 4]);
+    assert_eq!(packets.next(), Some(&dump[..3]));
+    assert_eq!(packets.next(), Some(&dump[3..5]));
+    // Dump too short for the last packet
+    assert_eq!(packets.next(), None);
+    assert_eq!(packets.remaining(), &dump[5..]);
+}
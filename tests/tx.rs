@@ -0,0 +1,220 @@
+//! Transmissions waiting for TxDone, checked on the mock bus
+
+mod common;
+
+use common::{run, MockLr2021};
+use embassy_time::Duration;
+use lr2021::Lr2021;
+use lr2021::Lr2021Error;
+use lr2021::beacon::{Beacon, BeaconTx, DutyCycleBudget};
+use lr2021::ble::BleMode;
+use lr2021::cad_wake::{CadWake, CadWakeTx, WakeSeq};
+use lr2021::dtm::{DtmPayload, DTM_PAYLOAD_MAX};
+use lr2021::harness::{DualHarness, RadioTestHarness};
+use lr2021::lora::{LoraBw, LoraModulationParams, LoraPacketParams, Sf};
+use lr2021::mock::{MockBus, MockCapture, MockReply};
+use lr2021::multilisten::LoraSlot;
+use lr2021::radio::PacketType;
+use lr2021::repeater::{RepeatOutcome, Repeater, RepeaterCfg};
+use lr2021::replay::RemoteRecording;
+use lr2021::status::IRQ_MASK_TX_DONE;
+
+/// GetStatus response with TxDone: the flag stays set on the chip until cleared
+const STATUS_TX_DONE: [&[u8]; 1] = [&[0x04, 0x00, 0x00, 0x08, 0x00, 0x00]];
+/// Opcode of SetTx
+const OP_SET_TX: [u8; 2] = [0x02, 0x0D];
+/// Opcode of ClearIrq
+const OP_CLEAR_IRQ: [u8; 2] = [0x01, 0x16];
+/// Opcode of SetRx
+const OP_SET_RX: [u8; 2] = [0x02, 0x0C];
+
+/// Configure a LoRa SF7/125kHz link with a 4 bytes payload
+async fn lora_setup(lr2021: &mut MockLr2021<'_, '_>) {
+    let modulation = LoraModulationParams::basic(Sf::Sf7, LoraBw::Bw125);
+    lr2021.set_packet_type(PacketType::Lora).await.expect("SetPacketType");
+    lr2021.set_lora_modulation(&modulation).await.expect("SetLoraModulation");
+    lr2021.set_lora_packet(&LoraPacketParams::basic(4, &modulation)).await.expect("SetLoraPacket");
+}
+
+/// Number of SetTx sent, checking that TxDone was cleared before each of them
+fn nb_tx_after_clear(cap: &MockCapture) -> usize {
+    let mut cleared = false;
+    let mut nb_tx = 0;
+    for cmd in cap.commands() {
+        if cmd.starts_with(&OP_CLEAR_IRQ) {
+            let irqs = u32::from_be_bytes([cmd[2], cmd[3], cmd[4], cmd[5]]);
+            cleared |= irqs & IRQ_MASK_TX_DONE != 0;
+        } else if cmd.starts_with(&OP_SET_TX) {
+            assert!(cleared, "SetTx #{nb_tx} sent without clearing TxDone");
+            cleared = false;
+            nb_tx += 1;
+        }
+    }
+    nb_tx
+}
+
+#[test]
+fn cad_wake_train_clears_tx_done() {
+    let replies = [MockReply::new(0x0100, &STATUS_TX_DONE)];
+    let mut capture = [0u8; 2048];
+    let bus = MockBus::new(&mut capture, &replies);
+    let mut lr2021 = Lr2021::new(bus.pin(), bus.pin(), bus.spi(), bus.nss());
+    let wake = CadWake::new(Duration::from_millis(100), 2, WakeSeq::PacketTrain);
+    let modulation = LoraModulationParams::basic(Sf::Sf7, LoraBw::Bw125);
+    let nb_packets = wake.nb_packets(&modulation, &LoraPacketParams::basic(4, &modulation));
+    assert!(nb_packets > 1);
+    let res = run(async {
+        lora_setup(&mut lr2021).await;
+        let mut budget = DutyCycleBudget::new(1000, Duration::from_secs(3600));
+        lr2021.cad_wake_send(&wake, b"wake", &mut budget).await
+    });
+    assert!(matches!(res, Ok(CadWakeTx::Sent(_))));
+    assert_eq!(nb_tx_after_clear(&bus.capture()), nb_packets as usize);
+}
+
+#[test]
+fn cad_wake_long_preamble_restores_packet() {
+    let replies = [MockReply::new(0x0100, &STATUS_TX_DONE)];
+    let mut capture = [0u8; 1024];
+    let bus = MockBus::new(&mut capture, &replies);
+    let mut lr2021 = Lr2021::new(bus.pin(), bus.pin(), bus.spi(), bus.nss());
+    let wake = CadWake::new(Duration::from_millis(100), 2, WakeSeq::LongPreamble);
+    run(async {
+        lora_setup(&mut lr2021).await;
+        let mut budget = DutyCycleBudget::new(1000, Duration::from_secs(3600));
+        lr2021.cad_wake_send(&wake, b"wake", &mut budget).await.expect("CadWake");
+    });
+    let cap = bus.capture();
+    assert_eq!(nb_tx_after_clear(&cap), 1);
+    // Packet parameters restored only once TxDone was seen and cleared
+    let cmds: Vec<&[u8]> = cap.commands().collect();
+    let tx = cmds.iter().position(|c| c.starts_with(&OP_SET_TX)).expect("SetTx");
+    let status = tx + cmds[tx..].iter().position(|c| c == &[0x01, 0x00]).expect("GetStatus");
+    assert!(cmds[status + 1].starts_with(&OP_CLEAR_IRQ));
+    assert!(cmds[status + 2].starts_with(&[0x02, 0x21]), "{:02x?}", cmds[status + 2]);
+}
+
+#[test]
+fn dtm_tx_waits_each_packet() {
+    let replies = [MockReply::new(0x0100, &STATUS_TX_DONE)];
+    let mut capture = [0u8; 2048];
+    let bus = MockBus::new(&mut capture, &replies);
+    let mut lr2021 = Lr2021::new(bus.pin(), bus.pin(), bus.spi(), bus.nss());
+    let nb = run(lr2021.dtm_tx(19, BleMode::Le1mb, DtmPayload::Prbs9, 37, 3)).expect("DtmTx");
+    assert_eq!(nb, 3);
+    assert_eq!(nb_tx_after_clear(&bus.capture()), 3);
+}
+
+#[test]
+fn dtm_tx_rejects_long_payload() {
+    let mut capture = [0u8; 256];
+    let bus = MockBus::new(&mut capture, &[]);
+    let mut lr2021 = Lr2021::new(bus.pin(), bus.pin(), bus.spi(), bus.nss());
+    let res = run(lr2021.dtm_tx(19, BleMode::Le1mb, DtmPayload::Prbs9, DTM_PAYLOAD_MAX + 1, 1));
+    assert!(matches!(res, Err(Lr2021Error::InvalidSize)));
+    // Radio not reconfigured
+    assert_eq!(bus.capture().commands().count(), 0);
+}
+
+#[test]
+fn beacon_waits_each_tx_done() {
+    let replies = [MockReply::new(0x0100, &STATUS_TX_DONE)];
+    let mut capture = [0u8; 2048];
+    let bus = MockBus::new(&mut capture, &replies);
+    let mut lr2021 = Lr2021::new(bus.pin(), bus.pin(), bus.spi(), bus.nss());
+    let beacon = Beacon::new(b"beac", Duration::from_secs(1));
+    run(async {
+        lora_setup(&mut lr2021).await;
+        let mut budget = DutyCycleBudget::new(1000, Duration::from_secs(3600));
+        let mut airtime = Duration::from_ticks(0);
+        for _ in 0..3 {
+            match lr2021.beacon_send(&beacon, &mut budget, airtime).await.expect("BeaconSend") {
+                BeaconTx::Sent(t) => airtime = t,
+                BeaconTx::Skipped => panic!("Beacon skipped"),
+            }
+        }
+    });
+    assert_eq!(nb_tx_after_clear(&bus.capture()), 3);
+}
+
+#[test]
+fn repeater_rearms_rx_after_tx_done() {
+    // Each service call sees an RxDone then the TxDone of the forwarded packet
+    const STATUS_RX_TX: [&[u8]; 4] = [
+        &[0x04, 0x00, 0x00, 0x04, 0x00, 0x00],
+        &[0x04, 0x00, 0x00, 0x08, 0x00, 0x00],
+        &[0x04, 0x00, 0x00, 0x04, 0x00, 0x00],
+        &[0x04, 0x00, 0x00, 0x08, 0x00, 0x00],
+    ];
+    const PKT_LEN: [&[u8]; 1] = [&[0x04, 0x00, 0x00, 0x04]];
+    let replies = [MockReply::new(0x0100, &STATUS_RX_TX), MockReply::new(0x0212, &PKT_LEN)];
+    let mut capture = [0u8; 4096];
+    let bus = MockBus::new(&mut capture, &replies);
+    let mut lr2021 = Lr2021::new(bus.pin(), bus.pin(), bus.spi(), bus.nss());
+    let modulation = LoraModulationParams::basic(Sf::Sf7, LoraBw::Bw125);
+    let packet = LoraPacketParams::basic(4, &modulation);
+    let rx = LoraSlot::new(868_100_000, modulation, packet);
+    let tx = LoraSlot::new(869_525_000, modulation, packet);
+    let mut repeater: Repeater = Repeater::new(RepeaterCfg::new(rx, tx, Duration::from_millis(0)));
+    run(async {
+        lr2021.repeater_start(&repeater).await.expect("RepeaterStart");
+        for _ in 0..2 {
+            let outcome = lr2021.repeater_service(&mut repeater).await.expect("RepeaterService");
+            assert_eq!(outcome, RepeatOutcome::Forwarded(4));
+        }
+    });
+    let cap = bus.capture();
+    assert_eq!(nb_tx_after_clear(&cap), 2);
+    // After each forwarded packet, RX is re-armed only once TxDone was seen and cleared
+    let mut tx_pending = false;
+    let mut nb_rx = 0;
+    for cmd in cap.commands() {
+        if cmd.starts_with(&OP_SET_TX) {
+            tx_pending = true;
+        } else if cmd.starts_with(&OP_CLEAR_IRQ) {
+            let irqs = u32::from_be_bytes([cmd[2], cmd[3], cmd[4], cmd[5]]);
+            tx_pending &= irqs & IRQ_MASK_TX_DONE == 0;
+        } else if cmd.starts_with(&OP_SET_RX) {
+            assert!(!tx_pending, "SetRx sent before TxDone was cleared");
+            nb_rx += 1;
+        }
+    }
+    assert_eq!(nb_rx, 3);
+    assert_eq!(replies[0].nb_used(), 4);
+}
+
+#[test]
+fn remote_replay_waits_each_repeat() {
+    let replies = [MockReply::new(0x0100, &STATUS_TX_DONE)];
+    let mut capture = [0u8; 4096];
+    let bus = MockBus::new(&mut capture, &replies);
+    let mut lr2021 = Lr2021::new(bus.pin(), bus.pin(), bus.spi(), bus.nss());
+    // Short/long pulses sampled at 100us
+    let rec = RemoteRecording::from_samples(&[0xF0, 0xFF, 0x00, 0xF0, 0x0F, 0x00], 100).expect("Recording");
+    run(lr2021.remote_replay(&rec, 3, Duration::from_millis(10))).expect("RemoteReplay");
+    assert_eq!(nb_tx_after_clear(&bus.capture()), 3);
+}
+
+#[test]
+fn harness_tx_waits_each_tx_done() {
+    let replies_a = [MockReply::new(0x0100, &STATUS_TX_DONE)];
+    let replies_b = [MockReply::new(0x0100, &STATUS_TX_DONE)];
+    let mut capture_a = [0u8; 2048];
+    let mut capture_b = [0u8; 2048];
+    let bus_a = MockBus::new(&mut capture_a, &replies_a);
+    let bus_b = MockBus::new(&mut capture_b, &replies_b);
+    let mut lr2021_a = Lr2021::new(bus_a.pin(), bus_a.pin(), bus_a.spi(), bus_a.nss());
+    let mut lr2021_b = Lr2021::new(bus_b.pin(), bus_b.pin(), bus_b.spi(), bus_b.nss());
+    let mut harness = DualHarness::new(&mut lr2021_a, &mut lr2021_b);
+    run(async {
+        for _ in 0..2 {
+            harness.tx(b"ping").await.expect("Tx");
+        }
+        harness.swap_roles();
+        for _ in 0..3 {
+            harness.tx(b"pong").await.expect("Tx");
+        }
+    });
+    assert_eq!(nb_tx_after_clear(&bus_a.capture()), 2);
+    assert_eq!(nb_tx_after_clear(&bus_b.capture()), 3);
+}